@@ -0,0 +1,185 @@
+use {
+    construct_vault_sol::{
+        state::{TimeBasis, VaultKind},
+        DEVNET_MINT,
+    },
+    solana_sdk::pubkey::Pubkey,
+    std::{env, path::PathBuf, str::FromStr},
+};
+
+pub const USAGE: &str = "\
+Usage: construct-vault [OPTIONS] <COMMAND>
+
+Commands:
+  init --amount <TOKENS> [--referrer <PUBKEY>] [--auto-renew | --cooldown | --receipts] [--slots]
+                          Open a vault locking <TOKENS> whole tokens; --cooldown
+                          opens one that unlocks on request after a cooldown,
+                          --receipts mints you transferable receipts for the lock,
+                          and --slots times the lock in slots instead of seconds
+  deposit --amount <UNITS>
+                          Add <UNITS> base units to the vault and restart the lock
+  withdraw                Release the vault, paying the early-withdrawal fee if due
+  claim                   Release a vault whose lock has run out
+  auto-renew --on|--off   Roll the lock over at maturity, or stop doing so
+  request-unlock          Start the cooldown of a cooldown vault
+  cancel-unlock           Lock a cooldown vault again, dropping the cooldown
+  crank                   Release every matured vault to its owner, earning the crank tip
+  receipts                Turn the vault into transferable receipt tokens
+  redeem [--owner <PUBKEY>]
+                          Burn your receipts for a matured vault and take its tokens
+  sync-badges [--owner <PUBKEY>]
+                          Mint or burn an owner's tier badges to match their vault
+  status [--owner <PUBKEY>]
+                          Show a vault and check that a withdrawal can go through
+  quote-fee [--owner <PUBKEY>]
+                          Show what a withdrawal would cost right now
+  list [--owner <PUBKEY>] List vaults, optionally only those of one owner
+  stats                   Show protocol totals, summed across the stats shards
+  history [--owner <PUBKEY>]
+                          Show the last actions recorded in a vault's activity log
+
+Options:
+  -u, --url <URL>             RPC URL [env: CONSTRUCT_VAULT_URL] [default: http://127.0.0.1:8899]
+  -k, --keypair <PATH>        Signing keypair [default: ~/.config/solana/id.json]
+  -p, --program-id <PUBKEY>   Vault program [env: CONSTRUCT_VAULT_PROGRAM_ID]
+      --mint <PUBKEY>         KUZA or registered mint [default: devnet mint]
+      --sol                   Use the native SOL vault, locking lamports instead of tokens
+  -h, --help                  Print this help
+
+--owner defaults to the keypair's public key. With --sol, amounts are in lamports.
+";
+
+pub struct Options {
+    pub url: String,
+    pub keypair: PathBuf,
+    pub program_id: Pubkey,
+    pub mint: Pubkey,
+}
+
+pub enum Command {
+    Init {
+        amount: u64,
+        referrer: Option<Pubkey>,
+        auto_renew: bool,
+        kind: VaultKind,
+        time_basis: TimeBasis,
+        receipts: bool,
+    },
+    Deposit { amount: u64 },
+    Withdraw,
+    Claim,
+    AutoRenew { enabled: bool },
+    RequestUnlock,
+    CancelUnlock,
+    Crank,
+    Receipts,
+    Redeem { owner: Option<Pubkey> },
+    SyncBadges { owner: Option<Pubkey> },
+    Status { owner: Option<Pubkey> },
+    QuoteFee { owner: Option<Pubkey> },
+    List { owner: Option<Pubkey> },
+    Stats,
+    History { owner: Option<Pubkey> },
+}
+
+fn parse_pubkey(flag: &str, value: &str) -> Result<Pubkey, String> {
+    Pubkey::from_str(value).map_err(|_| format!("invalid public key for {}: {}", flag, value))
+}
+
+fn parse_amount(value: &str) -> Result<u64, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid amount: {}", value))
+}
+
+fn default_keypair() -> PathBuf {
+    let home = env::var("HOME").unwrap_or_else(|_| ".".to_string());
+    PathBuf::from(home).join(".config/solana/id.json")
+}
+
+/// Parses the arguments after the program name. Options may come before or
+/// after the command. Returns `None` when help was asked for.
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Option<(Options, Command)>, String> {
+    let mut args = args.into_iter();
+
+    let mut url = env::var("CONSTRUCT_VAULT_URL").ok();
+    let mut keypair = None;
+    let mut program_id = env::var("CONSTRUCT_VAULT_PROGRAM_ID").ok();
+    let mut mint = None;
+    let mut amount = None;
+    let mut referrer = None;
+    let mut owner = None;
+    let mut auto_renew = None;
+    let mut kind = VaultKind::Fixed;
+    let mut time_basis = TimeBasis::UnixSeconds;
+    let mut receipts = false;
+    let mut command = None;
+
+    while let Some(arg) = args.next() {
+        let mut value = |flag: &str| args.next().ok_or_else(|| format!("{} needs a value", flag));
+
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-u" | "--url" => url = Some(value(&arg)?),
+            "-k" | "--keypair" => keypair = Some(PathBuf::from(value(&arg)?)),
+            "-p" | "--program-id" => program_id = Some(value(&arg)?),
+            "--mint" => mint = Some(parse_pubkey(&arg, &value(&arg)?)?),
+            "--sol" => mint = Some(spl_token::native_mint::id()),
+            "--amount" => amount = Some(parse_amount(&value(&arg)?)?),
+            "--referrer" => referrer = Some(parse_pubkey(&arg, &value(&arg)?)?),
+            "--owner" => owner = Some(parse_pubkey(&arg, &value(&arg)?)?),
+            "--auto-renew" | "--on" => auto_renew = Some(true),
+            "--off" => auto_renew = Some(false),
+            "--cooldown" => kind = VaultKind::Cooldown,
+            "--slots" => time_basis = TimeBasis::Slots,
+            "--receipts" => receipts = true,
+            flag if flag.starts_with('-') => return Err(format!("unknown option: {}", flag)),
+            name if command.is_none() => command = Some(name.to_string()),
+            extra => return Err(format!("unexpected argument: {}", extra)),
+        }
+    }
+
+    let command = match command.as_deref() {
+        Some("init") => Command::Init {
+            amount: amount.ok_or("init needs --amount")?,
+            referrer,
+            auto_renew: auto_renew.unwrap_or(false),
+            kind,
+            time_basis,
+            receipts,
+        },
+        Some("deposit") => Command::Deposit {
+            amount: amount.ok_or("deposit needs --amount")?,
+        },
+        Some("withdraw") => Command::Withdraw,
+        Some("claim") => Command::Claim,
+        Some("auto-renew") => Command::AutoRenew {
+            enabled: auto_renew.ok_or("auto-renew needs --on or --off")?,
+        },
+        Some("request-unlock") => Command::RequestUnlock,
+        Some("cancel-unlock") => Command::CancelUnlock,
+        Some("crank") => Command::Crank,
+        Some("receipts") => Command::Receipts,
+        Some("redeem") => Command::Redeem { owner },
+        Some("sync-badges") => Command::SyncBadges { owner },
+        Some("status") => Command::Status { owner },
+        Some("quote-fee") => Command::QuoteFee { owner },
+        Some("list") => Command::List { owner },
+        Some("stats") => Command::Stats,
+        Some("history") => Command::History { owner },
+        Some(name) => return Err(format!("unknown command: {}", name)),
+        None => return Ok(None),
+    };
+
+    let program_id = program_id
+        .ok_or("no program id; pass --program-id or set CONSTRUCT_VAULT_PROGRAM_ID")?;
+
+    let options = Options {
+        url: url.unwrap_or_else(|| "http://127.0.0.1:8899".to_string()),
+        keypair: keypair.unwrap_or_else(default_keypair),
+        program_id: parse_pubkey("--program-id", &program_id)?,
+        mint: mint.unwrap_or(DEVNET_MINT),
+    };
+
+    Ok(Some((options, command)))
+}
@@ -0,0 +1,882 @@
+use {
+    crate::args::{Command, Options},
+    borsh::BorshDeserialize,
+    construct_vault_sol::{
+        client::{
+            activity_address, cancel_unlock_ix, claim_ix, crank_release_ix, deposit_ix, deposit_with_receipts_ix,
+            enable_receipts_ix, deposit_sol_ix, find_vault_address, initialize_sol_with_time_basis_ix,
+            initialize_with_receipts_ix, initialize_with_time_basis_ix, receipt_mint_address, redeem_ix,
+            request_unlock_ix, set_auto_renew_ix, sync_badges_ix, withdraw_ix, withdraw_sol_ix,
+            withdraw_with_receipts_ix,
+        },
+        fee::{apply_discount, early_withdrawal_fee, referral_cut, split_fee},
+        instruction::{is_kuza_mint, kuza_mint_config},
+        state::{
+            ActivityKind, ActivityLog, Config, FeeExemption, Loyalty, MintConfig, ProtocolStats, Referral,
+            StatsShard, TimeBasis, Vault, VaultKind, VaultStatus,
+        },
+    },
+    solana_account_decoder_client_types::UiAccountEncoding,
+    solana_rpc_client::rpc_client::RpcClient,
+    solana_rpc_client_api::{
+        client_error::{Error as ClientError, ErrorKind},
+        config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+        filter::{Memcmp, RpcFilterType},
+        request::{RpcError, RpcResponseErrorData},
+    },
+    solana_sdk::{
+        account::{from_account, Account},
+        clock::Clock,
+        commitment_config::CommitmentConfig,
+        instruction::Instruction,
+        pubkey::Pubkey,
+        signature::{read_keypair_file, Keypair, Signer},
+        sysvar,
+        transaction::Transaction,
+    },
+    spl_associated_token_account::get_associated_token_address,
+    spl_token::native_mint,
+};
+
+const DAY: i64 = 86_400;
+
+// Enough for the signature fee of a single-signer transaction
+const TRANSACTION_FEE_LAMPORTS: u64 = 5_000;
+
+// Vaults per CrankRelease transaction; each adds up to six accounts
+const CRANK_BATCH: usize = 4;
+
+type CliResult<T> = Result<T, String>;
+
+/// What a withdrawal would cost at a given time, mirroring `withdraw`.
+struct Quote {
+    early: bool,
+    base_fee: u64,
+    discount_bps: u16,
+    exempt: bool,
+    fee: u64,
+    referrer_cut: Option<(Pubkey, u64)>,
+    recipients: Vec<(Pubkey, u64)>,
+}
+
+struct Context {
+    client: RpcClient,
+    options: Options,
+}
+
+impl Context {
+    fn keypair(&self) -> CliResult<Keypair> {
+        read_keypair_file(&self.options.keypair)
+            .map_err(|error| format!("failed to read keypair {}: {}", self.options.keypair.display(), error))
+    }
+
+    fn owner(&self, owner: Option<Pubkey>) -> CliResult<Pubkey> {
+        match owner {
+            Some(owner) => Ok(owner),
+            None => Ok(self.keypair()?.pubkey()),
+        }
+    }
+
+    fn account(&self, address: &Pubkey) -> CliResult<Option<Account>> {
+        self.client
+            .get_account_with_commitment(address, self.client.commitment())
+            .map(|response| response.value)
+            .map_err(describe_error)
+    }
+
+    fn config(&self) -> CliResult<Config> {
+        let (config_pda, _) = Config::find_address(&self.options.program_id);
+        let account = self
+            .account(&config_pda)?
+            .ok_or("program config not found; has InitializeConfig been run?")?;
+
+        Config::try_from_slice(&account.data).map_err(|_| "config account is malformed".to_string())
+    }
+
+    /// Terms of the selected mint: built in for KUZA, registered otherwise.
+    fn mint_config(&self, config: &Config) -> CliResult<MintConfig> {
+        let mint = &self.options.mint;
+        if is_kuza_mint(mint) {
+            return Ok(kuza_mint_config(mint, config));
+        }
+
+        let (mint_config_pda, _) = MintConfig::find_address(mint, &self.options.program_id);
+        let account = self
+            .account(&mint_config_pda)?
+            .ok_or_else(|| format!("mint {} is not registered", mint))?;
+
+        MintConfig::try_from_slice(&account.data).map_err(|_| "mint config account is malformed".to_string())
+    }
+
+    /// Whether the selected mint is the native mint, whose vaults hold SOL.
+    fn is_sol(&self) -> bool {
+        self.options.mint == native_mint::id()
+    }
+
+    fn fee_recipients(&self, config: &Config) -> Vec<Pubkey> {
+        config.active_fee_shares().iter().map(|share| share.recipient).collect()
+    }
+
+    /// The cluster's clock, which is what the program prices fees against.
+    /// Each vault reads it in its own time basis.
+    fn clock(&self) -> CliResult<Clock> {
+        let account = self.account(&sysvar::clock::id())?.ok_or("clock sysvar not found")?;
+        from_account(&account).ok_or_else(|| "clock sysvar is malformed".to_string())
+    }
+
+    fn vault(&self, owner: &Pubkey) -> CliResult<Option<(Pubkey, Vault)>> {
+        let (vault_pda, _) = find_vault_address(&self.options.program_id, owner, &self.options.mint);
+
+        match self.account(&vault_pda)? {
+            Some(account) => {
+                let vault = Vault::unpack(&account.data)
+                    .map_err(|error| format!("vault {} can't be decoded: {}", vault_pda, error))?;
+                Ok(Some((vault_pda, vault)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Every vault of the program, or only `owner`'s, with its layout version.
+    fn vaults(&self, owner: Option<Pubkey>) -> CliResult<Vec<(Pubkey, u8, Vault)>> {
+        // Current vaults carry the discriminator; legacy ones are identified by size
+        let mut current = vec![
+            RpcFilterType::DataSize(Vault::LEN as u64),
+            RpcFilterType::Memcmp(Memcmp::new_raw_bytes(0, Vault::DISCRIMINATOR.to_vec())),
+        ];
+        let mut legacy = vec![RpcFilterType::DataSize(Vault::LEGACY_LEN as u64)];
+
+        if let Some(owner) = owner {
+            current.push(RpcFilterType::Memcmp(Memcmp::new_raw_bytes(Vault::HEADER_LEN, owner.to_bytes().to_vec())));
+            legacy.push(RpcFilterType::Memcmp(Memcmp::new_raw_bytes(0, owner.to_bytes().to_vec())));
+        }
+
+        let mut vaults = Vec::new();
+        for filters in [current, legacy] {
+            let config = RpcProgramAccountsConfig {
+                filters: Some(filters),
+                account_config: RpcAccountInfoConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    ..RpcAccountInfoConfig::default()
+                },
+                ..RpcProgramAccountsConfig::default()
+            };
+
+            let accounts = self
+                .client
+                .get_program_accounts_with_config(&self.options.program_id, config)
+                .map_err(describe_error)?;
+
+            for (address, account) in accounts {
+                let version = Vault::version(&account.data).unwrap_or(0);
+                if let Ok(vault) = Vault::unpack(&account.data) {
+                    vaults.push((address, version, vault));
+                }
+            }
+        }
+
+        Ok(vaults)
+    }
+
+    fn referrer(&self, owner: &Pubkey) -> CliResult<Option<Pubkey>> {
+        let (referral_pda, _) = Referral::find_address(owner, &self.options.program_id);
+
+        match self.account(&referral_pda)? {
+            Some(account) => Referral::try_from_slice(&account.data)
+                .map(|referral| Some(referral.referrer))
+                .map_err(|_| "referral record is malformed".to_string()),
+            None => Ok(None),
+        }
+    }
+
+    fn send(&self, instruction: Instruction, payer: &Keypair) -> CliResult<()> {
+        let blockhash = self.client.get_latest_blockhash().map_err(describe_error)?;
+        let transaction =
+            Transaction::new_signed_with_payer(&[instruction], Some(&payer.pubkey()), &[payer], blockhash);
+
+        let signature = self
+            .client
+            .send_and_confirm_transaction(&transaction)
+            .map_err(describe_error)?;
+
+        println!("Signature: {}", signature);
+        Ok(())
+    }
+
+    fn quote(&self, owner: &Pubkey, vault: &Vault, config: &Config, clock: &Clock) -> CliResult<Quote> {
+        if vault.kind() == Ok(VaultKind::Cooldown) {
+            return Err("cooldown vaults can't be withdrawn; run request-unlock, then claim".to_string());
+        }
+
+        let now = vault.now(clock).map_err(|error| error.to_string())?;
+        // Priced in seconds, as `withdraw` does, on the running term, which
+        // auto-renew rolls forward
+        let time_basis = vault.time_basis().map_err(|error| error.to_string())?;
+        let to_seconds = |units| time_basis.to_seconds(units, config.slots_per_day);
+        let lock_duration = to_seconds(u64::from(vault.lock_duration));
+        let time_locked = to_seconds(now.saturating_sub(vault.term_start(now)));
+
+        // A vault still locked after maturing is withdrawn early, as in `withdraw`
+        let mut vault = *vault;
+        vault.mature(now).map_err(|error| error.to_string())?;
+        let early = vault.status() == Ok(VaultStatus::Locked);
+        let max_fee = self.mint_config(config)?.max_early_withdrawal_fee_lamports;
+        let base_fee = early_withdrawal_fee(time_locked, lock_duration, max_fee).map_err(|error| error.to_string())?;
+
+        let (loyalty_pda, _) = Loyalty::find_address(owner, &self.options.program_id);
+        let completed_locks = match self.account(&loyalty_pda)? {
+            Some(account) => Loyalty::try_from_slice(&account.data)
+                .map_err(|_| "loyalty account is malformed".to_string())?
+                .completed_locks,
+            None => 0,
+        };
+        let discount_bps = config.loyalty_discount_bps(completed_locks);
+
+        let (exemption_pda, _) = FeeExemption::find_address(owner, &self.options.program_id);
+        let exempt = match self.account(&exemption_pda)? {
+            Some(account) => FeeExemption::deserialize(&mut &account.data[..])
+                .map_err(|_| "fee exemption account is malformed".to_string())?
+                .is_active(clock.unix_timestamp),
+            None => false,
+        };
+
+        let fee = if exempt { 0 } else { apply_discount(base_fee, discount_bps) };
+
+        let referrer_cut = match self.referrer(owner)? {
+            Some(referrer) if early => Some((referrer, referral_cut(fee, config.referral_bps))),
+            _ => None,
+        };
+        let to_recipients = fee - referrer_cut.map_or(0, |(_, cut)| cut);
+
+        let recipients = if early {
+            let shares = config.active_fee_shares();
+            let portions = split_fee(to_recipients, shares, config.dust_recipient as usize);
+            shares.iter().map(|share| share.recipient).zip(portions).collect()
+        } else {
+            vec![]
+        };
+
+        Ok(Quote {
+            early,
+            base_fee,
+            discount_bps,
+            exempt,
+            fee,
+            referrer_cut,
+            recipients,
+        })
+    }
+}
+
+fn describe_error(error: ClientError) -> String {
+    if let ErrorKind::RpcError(RpcError::RpcResponseError {
+        message,
+        data: RpcResponseErrorData::SendTransactionPreflightFailure(result),
+        ..
+    }) = error.kind()
+    {
+        let mut description = message.clone();
+        for log in result.logs.iter().flatten() {
+            description.push_str("\n  ");
+            description.push_str(log);
+        }
+        return description;
+    }
+
+    error.to_string()
+}
+
+fn sol(lamports: u64) -> String {
+    format!("{}.{:09} SOL", lamports / 1_000_000_000, lamports % 1_000_000_000)
+}
+
+fn describe_time(timestamp: i64, now: i64) -> String {
+    format!("{} {}", timestamp, describe_delta(timestamp - now))
+}
+
+fn describe_delta(seconds: i64) -> String {
+    let (days, hours) = (seconds.abs() / DAY, seconds.abs() % DAY / 3_600);
+
+    if seconds >= 0 {
+        format!("(in {}d {}h)", days, hours)
+    } else {
+        format!("({}d {}h ago)", days, hours)
+    }
+}
+
+/// `timestamp` in `vault`'s time basis, relative to `clock`. Slots are shown
+/// with the time they come to at `slots_per_day`.
+fn describe_vault_time(vault: &Vault, timestamp: u64, clock: &Clock, slots_per_day: u64) -> String {
+    match vault.time_basis() {
+        Ok(TimeBasis::Slots) => {
+            let seconds = |slots| TimeBasis::Slots.to_seconds(slots, slots_per_day) as i64;
+            let delta = seconds(timestamp.saturating_sub(clock.slot)) - seconds(clock.slot.saturating_sub(timestamp));
+            format!("slot {} {}", timestamp, describe_delta(delta))
+        }
+        _ => describe_time(timestamp as i64, clock.unix_timestamp),
+    }
+}
+
+fn describe_time_basis(vault: &Vault) -> String {
+    match vault.time_basis() {
+        Ok(TimeBasis::UnixSeconds) => "unix seconds".to_string(),
+        Ok(TimeBasis::Slots) => "slots".to_string(),
+        Err(_) => format!("unknown ({})", vault.time_basis),
+    }
+}
+
+fn describe_status(vault: &Vault) -> String {
+    match vault.status() {
+        Ok(status) => format!("{:?}", status),
+        Err(_) => format!("unknown ({})", vault.status),
+    }
+}
+
+fn describe_activity(kind: u8) -> String {
+    match ActivityKind::try_from(kind) {
+        Ok(kind) => format!("{:?}", kind),
+        Err(_) => format!("unknown ({})", kind),
+    }
+}
+
+fn print_quote(quote: &Quote) {
+    if !quote.early {
+        println!("Lock has run its course; withdrawing is free");
+        return;
+    }
+
+    println!("Early-withdrawal fee:  {}", sol(quote.base_fee));
+    if quote.exempt {
+        println!("Fee exemption:         active, nothing is charged");
+    } else {
+        println!("Loyalty discount:      {} bps", quote.discount_bps);
+    }
+    println!("Fee charged:           {}", sol(quote.fee));
+
+    if let Some((referrer, cut)) = quote.referrer_cut {
+        println!("  to referrer {}: {}", referrer, sol(cut));
+    }
+    for (recipient, portion) in &quote.recipients {
+        println!("  to {}: {}", recipient, sol(*portion));
+    }
+}
+
+fn init(
+    ctx: &Context,
+    amount: u64,
+    referrer: Option<Pubkey>,
+    auto_renew: bool,
+    kind: VaultKind,
+    time_basis: TimeBasis,
+    receipts: bool,
+) -> CliResult<()> {
+    let payer = ctx.keypair()?;
+    let config = ctx.config()?;
+
+    let recipients = ctx.fee_recipients(&config);
+    let instruction = if receipts {
+        if ctx.is_sol() {
+            return Err("SOL vaults can't take receipts".to_string());
+        }
+        if auto_renew || kind != VaultKind::Fixed {
+            return Err("only fixed vaults that don't auto-renew take receipts".to_string());
+        }
+
+        initialize_with_receipts_ix(
+            &ctx.options.program_id,
+            &payer.pubkey(),
+            &ctx.options.mint,
+            amount,
+            &recipients,
+            referrer,
+            time_basis,
+        )
+    } else if ctx.is_sol() {
+        initialize_sol_with_time_basis_ix(
+            &ctx.options.program_id,
+            &payer.pubkey(),
+            amount,
+            &recipients,
+            referrer,
+            auto_renew,
+            kind,
+            time_basis,
+        )
+    } else {
+        initialize_with_time_basis_ix(
+            &ctx.options.program_id,
+            &payer.pubkey(),
+            &ctx.options.mint,
+            amount,
+            &recipients,
+            referrer,
+            auto_renew,
+            kind,
+            time_basis,
+        )
+    };
+    ctx.send(instruction, &payer)?;
+
+    let (vault_pda, _) = find_vault_address(&ctx.options.program_id, &payer.pubkey(), &ctx.options.mint);
+    if ctx.is_sol() {
+        println!("Vault {} locks {}", vault_pda, sol(amount));
+    } else {
+        println!("Vault {} locks {} tokens", vault_pda, amount);
+    }
+    Ok(())
+}
+
+fn deposit(ctx: &Context, amount: u64) -> CliResult<()> {
+    let payer = ctx.keypair()?;
+    let has_receipts = ctx.vault(&payer.pubkey())?.is_some_and(|(_, vault)| vault.has_receipts());
+
+    if ctx.is_sol() {
+        let instruction = deposit_sol_ix(&ctx.options.program_id, &payer.pubkey(), amount);
+        ctx.send(instruction, &payer)?;
+
+        println!("Deposited {}; the lock has restarted", sol(amount));
+    } else if has_receipts {
+        let instruction =
+            deposit_with_receipts_ix(&ctx.options.program_id, &payer.pubkey(), &ctx.options.mint, amount);
+        ctx.send(instruction, &payer)?;
+
+        println!("Deposited {} base units and minted as many receipts", amount);
+    } else {
+        let instruction = deposit_ix(&ctx.options.program_id, &payer.pubkey(), &ctx.options.mint, amount);
+        ctx.send(instruction, &payer)?;
+
+        println!("Deposited {} base units; the lock has restarted", amount);
+    }
+    Ok(())
+}
+
+fn withdraw(ctx: &Context) -> CliResult<()> {
+    let payer = ctx.keypair()?;
+    let owner = payer.pubkey();
+    let config = ctx.config()?;
+
+    let recipients = ctx.fee_recipients(&config);
+    let has_receipts = ctx.vault(&owner)?.is_some_and(|(_, vault)| vault.has_receipts());
+    let (program_id, mint, referrer) = (&ctx.options.program_id, &ctx.options.mint, ctx.referrer(&owner)?);
+    let instruction = if ctx.is_sol() {
+        withdraw_sol_ix(program_id, &owner, &recipients, referrer)
+    } else if has_receipts {
+        withdraw_with_receipts_ix(program_id, &owner, mint, &recipients, referrer)
+    } else {
+        withdraw_ix(program_id, &owner, mint, &recipients, referrer)
+    };
+    ctx.send(instruction, &payer)
+        .map_err(|error| format!("{}\n\nRun `construct-vault status` to see why.", error))?;
+
+    println!("Vault released");
+    Ok(())
+}
+
+fn claim(ctx: &Context) -> CliResult<()> {
+    // WithdrawSol charges nothing once the lock has run out
+    if ctx.is_sol() {
+        return withdraw(ctx);
+    }
+
+    let payer = ctx.keypair()?;
+
+    let instruction = claim_ix(&ctx.options.program_id, &payer.pubkey(), &ctx.options.mint);
+    ctx.send(instruction, &payer)
+        .map_err(|error| format!("{}\n\nRun `construct-vault status` to see why.", error))?;
+
+    println!("Vault claimed");
+    Ok(())
+}
+
+fn auto_renew(ctx: &Context, enabled: bool) -> CliResult<()> {
+    let payer = ctx.keypair()?;
+
+    let instruction = set_auto_renew_ix(&ctx.options.program_id, &payer.pubkey(), &ctx.options.mint, enabled);
+    ctx.send(instruction, &payer)?;
+
+    if enabled {
+        println!("Auto-renew on; the lock rolls over each time it runs out");
+    } else {
+        println!("Auto-renew off; the vault matures when the current term ends");
+    }
+    Ok(())
+}
+
+fn request_unlock(ctx: &Context) -> CliResult<()> {
+    let payer = ctx.keypair()?;
+    let cooldown = ctx.config()?.cooldown_duration as i64;
+
+    let instruction = request_unlock_ix(&ctx.options.program_id, &payer.pubkey(), &ctx.options.mint);
+    ctx.send(instruction, &payer)?;
+
+    println!("Unlock requested; claim in {}d {}h", cooldown / DAY, cooldown % DAY / 3_600);
+    Ok(())
+}
+
+fn cancel_unlock(ctx: &Context) -> CliResult<()> {
+    let payer = ctx.keypair()?;
+
+    let instruction = cancel_unlock_ix(&ctx.options.program_id, &payer.pubkey(), &ctx.options.mint);
+    ctx.send(instruction, &payer)?;
+
+    println!("Unlock cancelled; the vault is locked again");
+    Ok(())
+}
+
+fn crank(ctx: &Context) -> CliResult<()> {
+    if ctx.is_sol() {
+        return Err("SOL vaults aren't cranked; their owners withdraw them".to_string());
+    }
+
+    let payer = ctx.keypair()?;
+    let clock = ctx.clock()?;
+
+    // Only current vaults of the selected mint without receipts that have
+    // matured and whose owner can receive the tokens
+    let registered_mint = (!is_kuza_mint(&ctx.options.mint)).then_some(ctx.options.mint);
+    let mut owners = Vec::new();
+    for (address, version, mut vault) in ctx.vaults(None)? {
+        if version != Vault::VERSION
+            || vault.registered_mint() != registered_mint
+            || vault.has_receipts()
+            || vault.now(&clock).and_then(|now| vault.mature(now)).is_err()
+            || vault.status() != Ok(VaultStatus::Matured)
+        {
+            continue;
+        }
+
+        let vault_ata = get_associated_token_address(&address, &ctx.options.mint);
+        let owner_ata = get_associated_token_address(&vault.owner, &ctx.options.mint);
+        if ctx.account(&vault_ata)?.is_none() || ctx.account(&owner_ata)?.is_none() {
+            println!("Skipping vault {}: token account missing", address);
+            continue;
+        }
+
+        owners.push(vault.owner);
+    }
+
+    for batch in owners.chunks(CRANK_BATCH) {
+        let instruction = crank_release_ix(&ctx.options.program_id, &payer.pubkey(), &ctx.options.mint, batch);
+        ctx.send(instruction, &payer)?;
+    }
+
+    println!("Released {} matured vault(s)", owners.len());
+    Ok(())
+}
+
+fn receipts(ctx: &Context) -> CliResult<()> {
+    let payer = ctx.keypair()?;
+
+    let instruction = enable_receipts_ix(&ctx.options.program_id, &payer.pubkey(), &ctx.options.mint);
+    ctx.send(instruction, &payer)?;
+
+    let (receipt_mint, _) = receipt_mint_address(&ctx.options.program_id, &payer.pubkey(), &ctx.options.mint);
+    println!("Receipts minted from {}; whoever holds them redeems the vault at maturity", receipt_mint);
+    Ok(())
+}
+
+fn redeem(ctx: &Context, owner: Option<Pubkey>) -> CliResult<()> {
+    let payer = ctx.keypair()?;
+    let owner = ctx.owner(owner)?;
+
+    let instruction = redeem_ix(&ctx.options.program_id, &payer.pubkey(), &owner, &ctx.options.mint);
+    ctx.send(instruction, &payer)
+        .map_err(|error| format!("{}\n\nRun `construct-vault status --owner {}` to see why.", error, owner))?;
+
+    println!("Receipts redeemed; the vault's tokens are in your token account");
+    Ok(())
+}
+
+fn sync_badges(ctx: &Context, owner: Option<Pubkey>) -> CliResult<()> {
+    let payer = ctx.keypair()?;
+    let owner = ctx.owner(owner)?;
+
+    let instruction = sync_badges_ix(&ctx.options.program_id, &payer.pubkey(), &owner, &ctx.options.mint);
+    ctx.send(instruction, &payer)?;
+
+    println!("Badges of {} match their vault", owner);
+    Ok(())
+}
+
+fn status(ctx: &Context, owner: Option<Pubkey>) -> CliResult<()> {
+    let owner = ctx.owner(owner)?;
+    let clock = ctx.clock()?;
+    let config = ctx.config()?;
+
+    let (vault_pda, vault) = ctx
+        .vault(&owner)?
+        .ok_or_else(|| format!("{} has no vault", owner))?;
+    let version = Vault::version(&ctx.account(&vault_pda)?.unwrap_or_default().data).unwrap_or(0);
+    let now = vault.now(&clock).map_err(|error| error.to_string())?;
+    let describe = |timestamp| describe_vault_time(&vault, timestamp, &clock, config.slots_per_day);
+
+    let deposit_timestamp = u64::from(vault.deposit_timestamp);
+    let unlock_timestamp = vault.unlock_timestamp(now);
+    let amount_locked = u64::from(vault.amount_locked);
+    let vault_ata = get_associated_token_address(&vault_pda, &ctx.options.mint);
+    let owner_ata = get_associated_token_address(&owner, &ctx.options.mint);
+
+    println!("Vault:           {}", vault_pda);
+    println!("Owner:           {}", vault.owner);
+    println!("Layout version:  {}", version);
+    println!("Amount locked:   {}", amount_locked);
+    println!("Status:          {}", describe_status(&vault));
+    println!("Time basis:      {}", describe_time_basis(&vault));
+    println!("Status since:    {}", describe(u64::from(vault.status_timestamp)));
+    println!("Deposited at:    {}", describe(deposit_timestamp));
+    match (vault.kind(), vault.status()) {
+        (Ok(VaultKind::Cooldown), Ok(VaultStatus::Unlocking)) => {
+            println!("Kind:            cooldown");
+            println!("Claimable at:    {}", describe(vault.cooldown_ends_at()));
+        }
+        (Ok(VaultKind::Cooldown), _) => {
+            println!("Kind:            cooldown");
+            println!("Unlocks at:      after request-unlock and the cooldown");
+        }
+        _ => {
+            println!("Kind:            fixed");
+            println!("Unlocks at:      {}", describe(unlock_timestamp));
+            println!("Auto-renew:      {}", if vault.auto_renews() { "on" } else { "off" });
+        }
+    }
+    if vault.has_receipts() {
+        let (receipt_mint, _) = Vault::find_receipt_mint(&vault_pda, &ctx.options.program_id);
+        println!("Receipt mint:    {}", receipt_mint);
+    }
+    println!("Counts for tier: {}", if vault.counts_toward_tier(now) { "yes" } else { "no" });
+
+    let mint_config = ctx.mint_config(&config)?;
+    if mint_config.access_tier_count > 0 {
+        let tier_amount = vault.tier_amount(now);
+        let reached = mint_config
+            .active_access_tiers()
+            .iter()
+            .filter(|tier| tier_amount >= tier.min_amount_locked)
+            .count();
+        println!("Access tiers:    {} of {} reached", reached, mint_config.access_tier_count);
+    }
+
+    let vault_balance = if vault.is_native() {
+        // The vault's rent stays behind; only what's above it can be released
+        let lamports = ctx.account(&vault_pda)?.map_or(0, |account| account.lamports);
+        let balance = lamports.saturating_sub(u64::from(vault.rent_lamports));
+        println!("Vault balance:   {} above its rent", sol(balance));
+        Some(balance)
+    } else {
+        match ctx.client.get_token_account_balance(&vault_ata) {
+            Ok(balance) => {
+                println!("Vault balance:   {} base units", balance.amount);
+                balance.amount.parse::<u64>().ok()
+            }
+            Err(_) => {
+                println!("Vault balance:   token account {} not found", vault_ata);
+                None
+            }
+        }
+    };
+
+    let mut problems = Vec::new();
+
+    if !vault.status().is_ok_and(|status| status.can_transition_to(VaultStatus::Released)) {
+        problems.push(format!("vault is {}, so there is nothing to withdraw", describe_status(&vault)));
+    }
+
+    if version != Vault::VERSION {
+        problems.push("vault uses a legacy layout; run MigrateVault".to_string());
+    } else if vault.own_address(&ctx.options.program_id) != Ok(vault_pda) {
+        problems.push("vault has no stored bump; run MigrateVault".to_string());
+    }
+
+    match vault_balance {
+        Some(balance) if vault.is_native() && balance < amount_locked => problems.push(format!(
+            "vault holds {} above its rent but {} is recorded as locked",
+            sol(balance),
+            sol(amount_locked)
+        )),
+        Some(balance) if balance < amount_locked => problems.push(format!(
+            "vault token account holds {} base units but {} are recorded as locked",
+            balance, amount_locked
+        )),
+        None => problems.push(format!("vault token account {} is missing", vault_ata)),
+        _ => {}
+    }
+
+    if !vault.is_native() && ctx.account(&owner_ata)?.is_none() {
+        problems.push(format!("owner has no token account {} to receive the tokens", owner_ata));
+    }
+
+    // A withdrawal burns a receipt per base unit locked from the owner
+    if vault.has_receipts() {
+        let (receipt_mint, _) = Vault::find_receipt_mint(&vault_pda, &ctx.options.program_id);
+        let receipt_account = get_associated_token_address(&vault.owner, &receipt_mint);
+        let receipts = ctx
+            .client
+            .get_token_account_balance(&receipt_account)
+            .ok()
+            .and_then(|balance| balance.amount.parse::<u64>().ok())
+            .unwrap_or(0);
+
+        if receipts < amount_locked {
+            problems.push(format!(
+                "owner holds {} of the {} receipts a withdrawal burns; holders redeem the rest at maturity",
+                receipts, amount_locked
+            ));
+        }
+    }
+
+    println!();
+    let fee = if vault.kind() == Ok(VaultKind::Cooldown) {
+        problems.push("cooldown vaults can't be withdrawn; run request-unlock, then claim".to_string());
+        0
+    } else {
+        let quote = ctx.quote(&owner, &vault, &config, &clock)?;
+        print_quote(&quote);
+        quote.fee
+    };
+
+    // A SOL vault pays its fee out of the released lamports
+    let fee = if vault.is_native() { 0 } else { fee };
+
+    let lamports = ctx.client.get_balance(&owner).map_err(describe_error)?;
+    if lamports < fee + TRANSACTION_FEE_LAMPORTS {
+        problems.push(format!(
+            "owner holds {} but the withdrawal needs {} plus the transaction fee",
+            sol(lamports),
+            sol(fee)
+        ));
+    }
+
+    println!();
+    if problems.is_empty() {
+        println!("Withdrawal checks passed");
+    } else {
+        println!("Withdrawal would fail:");
+        for problem in problems {
+            println!("  - {}", problem);
+        }
+    }
+
+    Ok(())
+}
+
+fn quote_fee(ctx: &Context, owner: Option<Pubkey>) -> CliResult<()> {
+    let owner = ctx.owner(owner)?;
+    let clock = ctx.clock()?;
+    let config = ctx.config()?;
+
+    let (_, vault) = ctx
+        .vault(&owner)?
+        .ok_or_else(|| format!("{} has no vault", owner))?;
+
+    print_quote(&ctx.quote(&owner, &vault, &config, &clock)?);
+    Ok(())
+}
+
+fn list(ctx: &Context, owner: Option<Pubkey>) -> CliResult<()> {
+    let clock = ctx.clock()?;
+    let slots_per_day = ctx.config()?.slots_per_day;
+    let mut vaults = ctx.vaults(owner)?;
+
+    vaults.sort_by_key(|(_, _, vault)| vault.owner.to_string());
+
+    println!("{:<44}  {:<44}  {:>3}  {:>20}  {:<13}  UNLOCKS AT", "VAULT", "OWNER", "VER", "AMOUNT", "STATUS");
+    for (address, version, vault) in &vaults {
+        let now = vault.now(&clock).map_err(|error| error.to_string())?;
+        let unlocks_at = vault.unlock_timestamp(now);
+        println!(
+            "{:<44}  {:<44}  {:>3}  {:>20}  {:<13}  {}",
+            address.to_string(),
+            vault.owner.to_string(),
+            version,
+            u64::from(vault.amount_locked),
+            describe_status(vault),
+            describe_vault_time(vault, unlocks_at, &clock, slots_per_day)
+        );
+    }
+    println!("{} vault(s)", vaults.len());
+
+    Ok(())
+}
+
+fn stats(ctx: &Context) -> CliResult<()> {
+    let addresses = StatsShard::all_addresses(&ctx.options.program_id);
+    let accounts = ctx.client.get_multiple_accounts(&addresses).map_err(describe_error)?;
+
+    let mut shards = Vec::new();
+    for (address, account) in addresses.iter().zip(accounts) {
+        if let Some(account) = account {
+            let shard = StatsShard::try_from_slice(&account.data)
+                .map_err(|_| format!("stats shard {} is malformed", address))?;
+            shards.push(shard);
+        }
+    }
+
+    let totals = ProtocolStats::sum(&shards);
+    println!("KUZA locked:       {}", totals.kuza_locked);
+    println!("Open vaults:       {}", totals.open_vaults);
+    println!("Lifetime deposits: {}", totals.lifetime_deposits);
+    println!("Lifetime fees:     {}", sol(totals.lifetime_fees_lamports));
+    println!("From {} of {} shard(s)", shards.len(), StatsShard::SHARD_COUNT);
+
+    Ok(())
+}
+
+fn history(ctx: &Context, owner: Option<Pubkey>) -> CliResult<()> {
+    let owner = ctx.owner(owner)?;
+    let now = ctx.clock()?.unix_timestamp;
+
+    let (vault_pda, vault) = ctx
+        .vault(&owner)?
+        .ok_or_else(|| format!("{} has no vault", owner))?;
+
+    let log_address = activity_address(&ctx.options.program_id, &vault_pda);
+    let account = ctx.account(&log_address)?.ok_or_else(|| {
+        format!("vault {} has no activity log yet; its next deposit or withdrawal creates it", vault_pda)
+    })?;
+    let log = ActivityLog::load(&account.data).map_err(|_| format!("activity log {} is malformed", log_address))?;
+
+    let entries = log.entries();
+    let unit = if vault.is_native() { "LAMPORTS" } else { "AMOUNT" };
+
+    println!("{:<32}  {:<14}  {:>20}  FEE", "TIME", "ACTION", unit);
+    for entry in &entries {
+        println!(
+            "{:<32}  {:<14}  {:>20}  {}",
+            describe_time(u64::from(entry.timestamp) as i64, now),
+            describe_activity(entry.kind),
+            u64::from(entry.amount),
+            sol(u64::from(entry.fee_lamports))
+        );
+    }
+    println!("{} of {} action(s) kept", entries.len(), u64::from(log.total));
+
+    Ok(())
+}
+
+pub fn run(options: Options, command: Command) -> CliResult<()> {
+    let ctx = Context {
+        client: RpcClient::new_with_commitment(options.url.clone(), CommitmentConfig::confirmed()),
+        options,
+    };
+
+    match command {
+        Command::Init { amount, referrer, auto_renew: renew, kind, time_basis, receipts } => {
+            init(&ctx, amount, referrer, renew, kind, time_basis, receipts)
+        }
+        Command::Deposit { amount } => deposit(&ctx, amount),
+        Command::Withdraw => withdraw(&ctx),
+        Command::Claim => claim(&ctx),
+        Command::AutoRenew { enabled } => auto_renew(&ctx, enabled),
+        Command::RequestUnlock => request_unlock(&ctx),
+        Command::CancelUnlock => cancel_unlock(&ctx),
+        Command::Crank => crank(&ctx),
+        Command::Receipts => receipts(&ctx),
+        Command::Redeem { owner } => redeem(&ctx, owner),
+        Command::SyncBadges { owner } => sync_badges(&ctx, owner),
+        Command::Status { owner } => status(&ctx, owner),
+        Command::QuoteFee { owner } => quote_fee(&ctx, owner),
+        Command::List { owner } => list(&ctx, owner),
+        Command::Stats => stats(&ctx),
+        Command::History { owner } => history(&ctx, owner),
+    }
+}
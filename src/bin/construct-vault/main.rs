@@ -0,0 +1,30 @@
+//! `construct-vault`: open, fund and release vaults, and inspect them when a
+//! withdrawal goes wrong. Run with `--help` for the commands.
+
+#[cfg(not(target_os = "solana"))]
+mod args;
+#[cfg(not(target_os = "solana"))]
+mod commands;
+
+#[cfg(not(target_os = "solana"))]
+fn main() {
+    use std::process::exit;
+
+    match args::parse(std::env::args().skip(1)) {
+        Ok(None) => print!("{}", args::USAGE),
+        Ok(Some((options, command))) => {
+            if let Err(error) = commands::run(options, command) {
+                eprintln!("error: {}", error);
+                exit(1);
+            }
+        }
+        Err(error) => {
+            eprintln!("error: {}\n\n{}", error, args::USAGE);
+            exit(2);
+        }
+    }
+}
+
+// The program itself is the library; there is nothing to run on-chain
+#[cfg(target_os = "solana")]
+fn main() {}
@@ -0,0 +1,806 @@
+//! Instruction builders for off-chain clients.
+//!
+//! Each builder takes the keys a caller knows, derives the PDAs and ATAs the
+//! handler expects, and lists the accounts in the order the handler reads
+//! them. Account order is described per variant in `VaultInstruction`.
+
+use {
+    crate::{
+        instruction::is_kuza_mint,
+        processor::VaultInstruction,
+        state::{
+            AccessTier, ActivityLog, Config, FeeExemption, FeeExemptionReason, FeeShare, Loyalty, LoyaltyTier,
+            MintConfig, Referral, ReferrerStats, StatsShard, TimeBasis, Treasury, Vault, VaultKind,
+        },
+    },
+    solana_program::{
+        bpf_loader_upgradeable,
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+        system_program, sysvar,
+    },
+    spl_associated_token_account::{get_associated_token_address, get_associated_token_address_with_program_id},
+    spl_token::native_mint,
+};
+
+pub fn initialize_config_ix(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    fee_shares: Vec<FeeShare>,
+    dust_recipient: u8,
+) -> Instruction {
+    let (config_pda, _) = Config::find_address(program_id);
+
+    Instruction::new_with_borsh(
+        *program_id,
+        &VaultInstruction::InitializeConfig {
+            fee_shares,
+            dust_recipient,
+        },
+        vec![
+            AccountMeta::new(*admin, true),
+            AccountMeta::new(config_pda, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(bpf_loader_upgradeable::get_program_data_address(program_id), false),
+        ],
+    )
+}
+
+pub fn set_fee_shares_ix(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    fee_shares: Vec<FeeShare>,
+    dust_recipient: u8,
+) -> Instruction {
+    let (config_pda, _) = Config::find_address(program_id);
+
+    Instruction::new_with_borsh(
+        *program_id,
+        &VaultInstruction::SetFeeShares {
+            fee_shares,
+            dust_recipient,
+        },
+        vec![
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new(config_pda, false),
+        ],
+    )
+}
+
+pub fn set_referral_share_ix(program_id: &Pubkey, admin: &Pubkey, referral_bps: u16) -> Instruction {
+    let (config_pda, _) = Config::find_address(program_id);
+
+    Instruction::new_with_borsh(
+        *program_id,
+        &VaultInstruction::SetReferralShare { referral_bps },
+        vec![
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new(config_pda, false),
+        ],
+    )
+}
+
+pub fn set_loyalty_tiers_ix(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    loyalty_tiers: Vec<LoyaltyTier>,
+) -> Instruction {
+    let (config_pda, _) = Config::find_address(program_id);
+
+    Instruction::new_with_borsh(
+        *program_id,
+        &VaultInstruction::SetLoyaltyTiers { loyalty_tiers },
+        vec![
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new(config_pda, false),
+        ],
+    )
+}
+
+/// Replaces the access tier table, creating badge mints for new tiers.
+pub fn set_access_tiers_ix(program_id: &Pubkey, admin: &Pubkey, access_tiers: Vec<AccessTier>) -> Instruction {
+    let (config_pda, _) = Config::find_address(program_id);
+
+    let mut accounts = vec![
+        AccountMeta::new(*admin, true),
+        AccountMeta::new(config_pda, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(spl_token_2022::id(), false),
+    ];
+    accounts.extend(
+        (0..Config::MAX_ACCESS_TIERS as u8)
+            .map(|index| AccountMeta::new(AccessTier::find_badge_mint(None, index, program_id).0, false)),
+    );
+
+    Instruction::new_with_borsh(*program_id, &VaultInstruction::SetAccessTiers { access_tiers }, accounts)
+}
+
+/// Registers `mint` or replaces its terms, creating badge mints for new
+/// tiers.
+#[allow(clippy::too_many_arguments)]
+pub fn set_mint_config_ix(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    mint: &Pubkey,
+    enabled: bool,
+    lock_duration: u64,
+    initialization_fee_lamports: u64,
+    max_early_withdrawal_fee_lamports: u64,
+    max_early_withdrawal_fee_tokens: u64,
+    access_tiers: Vec<AccessTier>,
+) -> Instruction {
+    let (config_pda, _) = Config::find_address(program_id);
+
+    let mut accounts = vec![
+        AccountMeta::new(*admin, true),
+        AccountMeta::new_readonly(config_pda, false),
+        AccountMeta::new(MintConfig::find_address(mint, program_id).0, false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(spl_token_2022::id(), false),
+    ];
+    accounts.extend(
+        (0..Config::MAX_ACCESS_TIERS as u8)
+            .map(|index| AccountMeta::new(AccessTier::find_badge_mint(Some(mint), index, program_id).0, false)),
+    );
+
+    Instruction::new_with_borsh(
+        *program_id,
+        &VaultInstruction::SetMintConfig {
+            enabled,
+            lock_duration,
+            initialization_fee_lamports,
+            max_early_withdrawal_fee_lamports,
+            max_early_withdrawal_fee_tokens,
+            access_tiers,
+        },
+        accounts,
+    )
+}
+
+/// Address of the activity log of the vault at `vault`.
+pub fn activity_address(program_id: &Pubkey, vault: &Pubkey) -> Pubkey {
+    ActivityLog::find_address(vault, program_id).0
+}
+
+/// Address of the stats shard `owner`'s vaults are counted in.
+pub fn stats_address(program_id: &Pubkey, owner: &Pubkey) -> Pubkey {
+    StatsShard::find_address(StatsShard::shard_for(owner), program_id).0
+}
+
+/// Address of `owner`'s vault for `mint`. KUZA vaults are seeded by the
+/// owner alone, vaults of registered mints by the owner and the mint.
+pub fn find_vault_address(program_id: &Pubkey, owner: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    if is_kuza_mint(mint) {
+        Vault::find_address(owner, program_id)
+    } else {
+        Vault::find_mint_address(owner, mint, program_id)
+    }
+}
+
+/// The mint whose badges a vault of `mint` earns: `None` for the KUZA badges.
+fn registered_mint(mint: &Pubkey) -> Option<&Pubkey> {
+    (!is_kuza_mint(mint)).then_some(mint)
+}
+
+/// The badge group for `owner`'s vault of `mint`: the config, the Token-2022
+/// program and each tier's badge mint with `owner`'s badge account. Builders
+/// always append it so their instructions work whether or not access tiers
+/// are configured.
+fn badge_metas(program_id: &Pubkey, owner: &Pubkey, mint: &Pubkey) -> Vec<AccountMeta> {
+    let mut accounts = vec![
+        AccountMeta::new_readonly(Config::find_address(program_id).0, false),
+        AccountMeta::new_readonly(spl_token_2022::id(), false),
+    ];
+
+    for index in 0..Config::MAX_ACCESS_TIERS as u8 {
+        let (badge_mint, _) = AccessTier::find_badge_mint(registered_mint(mint), index, program_id);
+        accounts.extend([
+            AccountMeta::new(badge_mint, false),
+            AccountMeta::new(badge_account_address(owner, &badge_mint), false),
+        ]);
+    }
+
+    accounts
+}
+
+/// Address of `owner`'s account for the badge `badge_mint`.
+pub fn badge_account_address(owner: &Pubkey, badge_mint: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(owner, badge_mint, &spl_token_2022::id())
+}
+
+/// Mints or burns `owner`'s tier badges for `mint` to match their vault of
+/// it, with `payer` covering any badge account that has to be created.
+pub fn sync_badges_ix(program_id: &Pubkey, payer: &Pubkey, owner: &Pubkey, mint: &Pubkey) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*payer, true),
+        AccountMeta::new_readonly(*owner, false),
+        AccountMeta::new_readonly(find_vault_address(program_id, owner, mint).0, false),
+        AccountMeta::new_readonly(MintConfig::find_address(mint, program_id).0, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+    ];
+    accounts.extend(badge_metas(program_id, owner, mint));
+
+    Instruction::new_with_borsh(*program_id, &VaultInstruction::SyncBadges, accounts)
+}
+
+pub fn set_fee_exemption_ix(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    wallet: Pubkey,
+    expires_at: Option<i64>,
+    reason: FeeExemptionReason,
+) -> Instruction {
+    let (config_pda, _) = Config::find_address(program_id);
+
+    Instruction::new_with_borsh(
+        *program_id,
+        &VaultInstruction::SetFeeExemption { wallet, expires_at, reason },
+        vec![
+            AccountMeta::new(*admin, true),
+            AccountMeta::new_readonly(config_pda, false),
+            AccountMeta::new(FeeExemption::find_address(&wallet, program_id).0, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+pub fn remove_fee_exemption_ix(program_id: &Pubkey, admin: &Pubkey, wallet: Pubkey) -> Instruction {
+    let (config_pda, _) = Config::find_address(program_id);
+
+    Instruction::new_with_borsh(
+        *program_id,
+        &VaultInstruction::RemoveFeeExemption { wallet },
+        vec![
+            AccountMeta::new(*admin, true),
+            AccountMeta::new_readonly(config_pda, false),
+            AccountMeta::new(FeeExemption::find_address(&wallet, program_id).0, false),
+        ],
+    )
+}
+
+/// The referrer and their stats account, as read after the referral record.
+fn referrer_metas(program_id: &Pubkey, referrer: &Pubkey) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new(*referrer, false),
+        AccountMeta::new(ReferrerStats::find_address(referrer, program_id).0, false),
+    ]
+}
+
+/// Opens `owner`'s vault with `amount` whole tokens of `mint`. `fee_recipients`
+/// must match the config's fee shares, in order. With `auto_renew` the lock
+/// rolls over at maturity instead of ending; `kind` picks a fixed lock or a
+/// cooldown vault. The lock is timed in unix seconds.
+#[allow(clippy::too_many_arguments)]
+pub fn initialize_ix(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+    fee_recipients: &[Pubkey],
+    referrer: Option<Pubkey>,
+    auto_renew: bool,
+    kind: VaultKind,
+) -> Instruction {
+    let basis = TimeBasis::UnixSeconds;
+    initialize_with_time_basis_ix(program_id, owner, mint, amount, fee_recipients, referrer, auto_renew, kind, basis)
+}
+
+/// Like [`initialize_ix`], with the lock timed in `time_basis`.
+#[allow(clippy::too_many_arguments)]
+pub fn initialize_with_time_basis_ix(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+    fee_recipients: &[Pubkey],
+    referrer: Option<Pubkey>,
+    auto_renew: bool,
+    kind: VaultKind,
+    time_basis: TimeBasis,
+) -> Instruction {
+    let mut accounts = initialize_metas(program_id, owner, mint, fee_recipients, referrer);
+    accounts.extend(badge_metas(program_id, owner, mint));
+
+    Instruction::new_with_borsh(
+        *program_id,
+        &VaultInstruction::Initialize { amount, referrer, auto_renew, kind, time_basis, receipts: false },
+        accounts,
+    )
+}
+
+/// Opens `owner`'s fixed vault with `amount` whole tokens of `mint`, timed in
+/// `time_basis`, and mints `owner` one receipt per base unit locked into
+/// their receipt account. The vault doesn't auto-renew.
+pub fn initialize_with_receipts_ix(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+    fee_recipients: &[Pubkey],
+    referrer: Option<Pubkey>,
+    time_basis: TimeBasis,
+) -> Instruction {
+    let mut accounts = initialize_metas(program_id, owner, mint, fee_recipients, referrer);
+    accounts.extend(receipt_metas(program_id, owner, owner, mint));
+    accounts.extend(badge_metas(program_id, owner, mint));
+
+    let kind = VaultKind::Fixed;
+    Instruction::new_with_borsh(
+        *program_id,
+        &VaultInstruction::Initialize { amount, referrer, auto_renew: false, kind, time_basis, receipts: true },
+        accounts,
+    )
+}
+
+/// The accounts of `Initialize` up to the referrer's.
+fn initialize_metas(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    fee_recipients: &[Pubkey],
+    referrer: Option<Pubkey>,
+) -> Vec<AccountMeta> {
+    let (vault_pda, _) = find_vault_address(program_id, owner, mint);
+    let (config_pda, _) = Config::find_address(program_id);
+
+    let mut accounts = vec![
+        AccountMeta::new(*owner, true),
+        AccountMeta::new(vault_pda, false),
+        AccountMeta::new(get_associated_token_address(&vault_pda, mint), false),
+        AccountMeta::new(get_associated_token_address(owner, mint), false),
+        AccountMeta::new_readonly(config_pda, false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new(Loyalty::find_address(owner, program_id).0, false),
+        AccountMeta::new_readonly(FeeExemption::find_address(owner, program_id).0, false),
+        AccountMeta::new_readonly(MintConfig::find_address(mint, program_id).0, false),
+        AccountMeta::new(stats_address(program_id, owner), false),
+        AccountMeta::new(activity_address(program_id, &vault_pda), false),
+    ];
+    accounts.extend(fee_recipients.iter().map(|key| AccountMeta::new(*key, false)));
+
+    if let Some(referrer) = referrer {
+        accounts.push(AccountMeta::new(Referral::find_address(owner, program_id).0, false));
+        accounts.extend(referrer_metas(program_id, &referrer));
+    }
+
+    accounts
+}
+
+/// Adds `amount` base units of `mint` to `owner`'s vault.
+pub fn deposit_ix(program_id: &Pubkey, owner: &Pubkey, mint: &Pubkey, amount: u64) -> Instruction {
+    let mut accounts = deposit_metas(program_id, owner, mint);
+    accounts.extend(badge_metas(program_id, owner, mint));
+
+    Instruction::new_with_borsh(*program_id, &VaultInstruction::Deposit { amount }, accounts)
+}
+
+/// Like [`deposit_ix`], for a vault with receipts: the receipts for `amount`
+/// go to `owner`'s receipt account.
+pub fn deposit_with_receipts_ix(program_id: &Pubkey, owner: &Pubkey, mint: &Pubkey, amount: u64) -> Instruction {
+    let mut accounts = deposit_metas(program_id, owner, mint);
+    accounts.extend(receipt_metas(program_id, owner, owner, mint));
+    accounts.extend(badge_metas(program_id, owner, mint));
+
+    Instruction::new_with_borsh(*program_id, &VaultInstruction::Deposit { amount }, accounts)
+}
+
+/// The fixed accounts of `Deposit`.
+fn deposit_metas(program_id: &Pubkey, owner: &Pubkey, mint: &Pubkey) -> Vec<AccountMeta> {
+    let (vault_pda, _) = find_vault_address(program_id, owner, mint);
+    let (config_pda, _) = Config::find_address(program_id);
+
+    vec![
+        AccountMeta::new(*owner, true),
+        AccountMeta::new(vault_pda, false),
+        AccountMeta::new(get_associated_token_address(&vault_pda, mint), false),
+        AccountMeta::new(get_associated_token_address(owner, mint), false),
+        AccountMeta::new_readonly(config_pda, false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(MintConfig::find_address(mint, program_id).0, false),
+        AccountMeta::new(stats_address(program_id, owner), false),
+        AccountMeta::new(activity_address(program_id, &vault_pda), false),
+    ]
+}
+
+/// The receipt mint of `owner`'s vault of `mint` and `holder`'s receipt account.
+fn receipt_metas(program_id: &Pubkey, owner: &Pubkey, holder: &Pubkey, mint: &Pubkey) -> Vec<AccountMeta> {
+    let (receipt_mint, _) = receipt_mint_address(program_id, owner, mint);
+
+    vec![
+        AccountMeta::new(receipt_mint, false),
+        AccountMeta::new(get_associated_token_address(holder, &receipt_mint), false),
+    ]
+}
+
+/// Address of the receipt mint of `owner`'s vault of `mint`.
+pub fn receipt_mint_address(program_id: &Pubkey, owner: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    let (vault_pda, _) = find_vault_address(program_id, owner, mint);
+    Vault::find_receipt_mint(&vault_pda, program_id)
+}
+
+/// Releases `owner`'s vault. `referrer` must be the referrer recorded at
+/// initialization, if any, so an early withdrawal can pay their share.
+pub fn withdraw_ix(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    fee_recipients: &[Pubkey],
+    referrer: Option<Pubkey>,
+) -> Instruction {
+    let mut accounts = withdraw_metas(program_id, owner, mint, fee_recipients, referrer);
+    accounts.extend(badge_metas(program_id, owner, mint));
+
+    Instruction::new_with_borsh(*program_id, &VaultInstruction::Withdraw, accounts)
+}
+
+/// Like [`withdraw_ix`], for a vault with receipts: the receipts for the
+/// whole lock are burned from `owner`'s receipt account.
+pub fn withdraw_with_receipts_ix(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    fee_recipients: &[Pubkey],
+    referrer: Option<Pubkey>,
+) -> Instruction {
+    let mut accounts = withdraw_metas(program_id, owner, mint, fee_recipients, referrer);
+    accounts.extend(receipt_metas(program_id, owner, owner, mint));
+    accounts.extend(badge_metas(program_id, owner, mint));
+
+    Instruction::new_with_borsh(*program_id, &VaultInstruction::Withdraw, accounts)
+}
+
+/// The accounts of `Withdraw` up to the referrer's.
+fn withdraw_metas(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    fee_recipients: &[Pubkey],
+    referrer: Option<Pubkey>,
+) -> Vec<AccountMeta> {
+    let (vault_pda, _) = find_vault_address(program_id, owner, mint);
+    let (config_pda, _) = Config::find_address(program_id);
+
+    let mut accounts = vec![
+        AccountMeta::new(*owner, true),
+        AccountMeta::new(vault_pda, false),
+        AccountMeta::new(get_associated_token_address(&vault_pda, mint), false),
+        AccountMeta::new(get_associated_token_address(owner, mint), false),
+        AccountMeta::new_readonly(config_pda, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new(Loyalty::find_address(owner, program_id).0, false),
+        AccountMeta::new_readonly(FeeExemption::find_address(owner, program_id).0, false),
+        AccountMeta::new_readonly(MintConfig::find_address(mint, program_id).0, false),
+        AccountMeta::new(stats_address(program_id, owner), false),
+        AccountMeta::new(activity_address(program_id, &vault_pda), false),
+        AccountMeta::new_readonly(*mint, false),
+    ];
+    accounts.extend(fee_recipients.iter().map(|key| AccountMeta::new(*key, false)));
+    accounts.extend(
+        fee_recipients
+            .iter()
+            .map(|key| AccountMeta::new(get_associated_token_address(key, mint), false)),
+    );
+    accounts.push(AccountMeta::new_readonly(Referral::find_address(owner, program_id).0, false));
+
+    if let Some(referrer) = referrer {
+        accounts.extend(referrer_metas(program_id, &referrer));
+    }
+
+    accounts
+}
+
+/// Migrates `owner`'s vault of `mint` to the current layout, with `payer`
+/// covering rent.
+pub fn migrate_vault_ix(program_id: &Pubkey, payer: &Pubkey, owner: &Pubkey, mint: &Pubkey) -> Instruction {
+    let (vault_pda, _) = find_vault_address(program_id, owner, mint);
+
+    Instruction::new_with_borsh(
+        *program_id,
+        &VaultInstruction::MigrateVault,
+        vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(vault_pda, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(get_associated_token_address(&vault_pda, mint), false),
+        ],
+    )
+}
+
+/// Claims `owner`'s matured vault into their token account for `mint`.
+pub fn claim_ix(program_id: &Pubkey, owner: &Pubkey, mint: &Pubkey) -> Instruction {
+    let (vault_pda, _) = find_vault_address(program_id, owner, mint);
+
+    let mut accounts = vec![
+        AccountMeta::new(*owner, true),
+        AccountMeta::new(vault_pda, false),
+        AccountMeta::new(get_associated_token_address(&vault_pda, mint), false),
+        AccountMeta::new(get_associated_token_address(owner, mint), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new(stats_address(program_id, owner), false),
+        AccountMeta::new(activity_address(program_id, &vault_pda), false),
+        AccountMeta::new_readonly(Config::find_address(program_id).0, false),
+        AccountMeta::new(Loyalty::find_address(owner, program_id).0, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(MintConfig::find_address(mint, program_id).0, false),
+    ];
+    accounts.extend(badge_metas(program_id, owner, mint));
+
+    Instruction::new_with_borsh(*program_id, &VaultInstruction::Claim, accounts)
+}
+
+pub fn set_crank_tip_ix(program_id: &Pubkey, admin: &Pubkey, crank_tip_lamports: u64) -> Instruction {
+    let (config_pda, _) = Config::find_address(program_id);
+
+    Instruction::new_with_borsh(
+        *program_id,
+        &VaultInstruction::SetCrankTip { crank_tip_lamports },
+        vec![
+            AccountMeta::new(*admin, true),
+            AccountMeta::new_readonly(config_pda, false),
+            AccountMeta::new(Treasury::find_address(program_id).0, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Releases the matured vaults of `owners` into their token accounts for
+/// `mint`, tipping `cranker` from the treasury.
+pub fn crank_release_ix(program_id: &Pubkey, cranker: &Pubkey, mint: &Pubkey, owners: &[Pubkey]) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*cranker, true),
+        AccountMeta::new(Treasury::find_address(program_id).0, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(Config::find_address(program_id).0, false),
+    ];
+
+    for owner in owners {
+        let (vault_pda, _) = find_vault_address(program_id, owner, mint);
+        accounts.push(AccountMeta::new(vault_pda, false));
+        accounts.push(AccountMeta::new(get_associated_token_address(&vault_pda, mint), false));
+        accounts.push(AccountMeta::new(get_associated_token_address(owner, mint), false));
+        accounts.push(AccountMeta::new(stats_address(program_id, owner), false));
+        accounts.push(AccountMeta::new(activity_address(program_id, &vault_pda), false));
+        accounts.push(AccountMeta::new(Loyalty::find_address(owner, program_id).0, false));
+    }
+
+    Instruction::new_with_borsh(*program_id, &VaultInstruction::CrankRelease, accounts)
+}
+
+/// Turns auto-renew on or off for `owner`'s vault of `mint`.
+pub fn set_auto_renew_ix(program_id: &Pubkey, owner: &Pubkey, mint: &Pubkey, auto_renew: bool) -> Instruction {
+    let (vault_pda, _) = find_vault_address(program_id, owner, mint);
+
+    Instruction::new_with_borsh(
+        *program_id,
+        &VaultInstruction::SetAutoRenew { auto_renew },
+        vec![
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new(vault_pda, false),
+            AccountMeta::new(activity_address(program_id, &vault_pda), false),
+        ],
+    )
+}
+
+pub fn set_cooldown_ix(program_id: &Pubkey, admin: &Pubkey, cooldown_duration: u64) -> Instruction {
+    let (config_pda, _) = Config::find_address(program_id);
+
+    Instruction::new_with_borsh(
+        *program_id,
+        &VaultInstruction::SetCooldown { cooldown_duration },
+        vec![
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new(config_pda, false),
+        ],
+    )
+}
+
+pub fn set_slots_per_day_ix(program_id: &Pubkey, admin: &Pubkey, slots_per_day: u64) -> Instruction {
+    let (config_pda, _) = Config::find_address(program_id);
+
+    Instruction::new_with_borsh(
+        *program_id,
+        &VaultInstruction::SetSlotsPerDay { slots_per_day },
+        vec![
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new(config_pda, false),
+        ],
+    )
+}
+
+/// Starts the cooldown of `owner`'s cooldown vault of `mint`.
+pub fn request_unlock_ix(program_id: &Pubkey, owner: &Pubkey, mint: &Pubkey) -> Instruction {
+    let (vault_pda, _) = find_vault_address(program_id, owner, mint);
+    let (config_pda, _) = Config::find_address(program_id);
+
+    Instruction::new_with_borsh(
+        *program_id,
+        &VaultInstruction::RequestUnlock,
+        vec![
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new(vault_pda, false),
+            AccountMeta::new_readonly(config_pda, false),
+            AccountMeta::new(activity_address(program_id, &vault_pda), false),
+        ],
+    )
+}
+
+/// Locks `owner`'s unlocking cooldown vault of `mint` again.
+pub fn cancel_unlock_ix(program_id: &Pubkey, owner: &Pubkey, mint: &Pubkey) -> Instruction {
+    let (vault_pda, _) = find_vault_address(program_id, owner, mint);
+
+    Instruction::new_with_borsh(
+        *program_id,
+        &VaultInstruction::CancelUnlock,
+        vec![
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new(vault_pda, false),
+            AccountMeta::new(activity_address(program_id, &vault_pda), false),
+        ],
+    )
+}
+
+/// Turns `owner`'s position in their vault of `mint` into receipt tokens in
+/// their receipt account.
+pub fn enable_receipts_ix(program_id: &Pubkey, owner: &Pubkey, mint: &Pubkey) -> Instruction {
+    let (vault_pda, _) = find_vault_address(program_id, owner, mint);
+
+    let mut accounts = vec![
+        AccountMeta::new(*owner, true),
+        AccountMeta::new(vault_pda, false),
+    ];
+    accounts.extend(receipt_metas(program_id, owner, owner, mint));
+    accounts.extend([
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        AccountMeta::new(activity_address(program_id, &vault_pda), false),
+        AccountMeta::new_readonly(*mint, false),
+    ]);
+
+    Instruction::new_with_borsh(*program_id, &VaultInstruction::EnableReceipts, accounts)
+}
+
+/// Burns `holder`'s receipts for `owner`'s matured vault and releases it into
+/// `holder`'s token account for `mint`.
+pub fn redeem_ix(program_id: &Pubkey, holder: &Pubkey, owner: &Pubkey, mint: &Pubkey) -> Instruction {
+    let (vault_pda, _) = find_vault_address(program_id, owner, mint);
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*holder, true),
+        AccountMeta::new(vault_pda, false),
+        AccountMeta::new(get_associated_token_address(&vault_pda, mint), false),
+    ];
+    accounts.extend(receipt_metas(program_id, owner, holder, mint));
+    accounts.extend([
+        AccountMeta::new(get_associated_token_address(holder, mint), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new(stats_address(program_id, owner), false),
+        AccountMeta::new(activity_address(program_id, &vault_pda), false),
+    ]);
+
+    Instruction::new_with_borsh(*program_id, &VaultInstruction::Redeem, accounts)
+}
+
+/// Opens `owner`'s native SOL vault with `amount` lamports. `fee_recipients`
+/// must match the config's fee shares, in order. The vault and its badges
+/// are those of the native mint, so the other builders reach the vault with
+/// `spl_token::native_mint::id()` as the mint. The lock is timed in unix
+/// seconds.
+pub fn initialize_sol_ix(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    amount: u64,
+    fee_recipients: &[Pubkey],
+    referrer: Option<Pubkey>,
+    auto_renew: bool,
+    kind: VaultKind,
+) -> Instruction {
+    let basis = TimeBasis::UnixSeconds;
+    initialize_sol_with_time_basis_ix(program_id, owner, amount, fee_recipients, referrer, auto_renew, kind, basis)
+}
+
+/// Like [`initialize_sol_ix`], with the lock timed in `time_basis`.
+#[allow(clippy::too_many_arguments)]
+pub fn initialize_sol_with_time_basis_ix(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    amount: u64,
+    fee_recipients: &[Pubkey],
+    referrer: Option<Pubkey>,
+    auto_renew: bool,
+    kind: VaultKind,
+    time_basis: TimeBasis,
+) -> Instruction {
+    let mint = native_mint::id();
+    let (vault_pda, _) = find_vault_address(program_id, owner, &mint);
+
+    let mut accounts = vec![
+        AccountMeta::new(*owner, true),
+        AccountMeta::new(vault_pda, false),
+        AccountMeta::new_readonly(Config::find_address(program_id).0, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        AccountMeta::new(Loyalty::find_address(owner, program_id).0, false),
+        AccountMeta::new_readonly(FeeExemption::find_address(owner, program_id).0, false),
+        AccountMeta::new_readonly(MintConfig::find_address(&mint, program_id).0, false),
+        AccountMeta::new(stats_address(program_id, owner), false),
+        AccountMeta::new(activity_address(program_id, &vault_pda), false),
+    ];
+    accounts.extend(fee_recipients.iter().map(|key| AccountMeta::new(*key, false)));
+
+    if let Some(referrer) = referrer {
+        accounts.push(AccountMeta::new(Referral::find_address(owner, program_id).0, false));
+        accounts.extend(referrer_metas(program_id, &referrer));
+    }
+    accounts.extend(badge_metas(program_id, owner, &mint));
+
+    Instruction::new_with_borsh(
+        *program_id,
+        &VaultInstruction::InitializeSol { amount, referrer, auto_renew, kind, time_basis },
+        accounts,
+    )
+}
+
+/// Adds `amount` lamports to `owner`'s SOL vault.
+pub fn deposit_sol_ix(program_id: &Pubkey, owner: &Pubkey, amount: u64) -> Instruction {
+    let mint = native_mint::id();
+    let (vault_pda, _) = find_vault_address(program_id, owner, &mint);
+
+    let mut accounts = vec![
+        AccountMeta::new(*owner, true),
+        AccountMeta::new(vault_pda, false),
+        AccountMeta::new_readonly(Config::find_address(program_id).0, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        AccountMeta::new_readonly(MintConfig::find_address(&mint, program_id).0, false),
+        AccountMeta::new(stats_address(program_id, owner), false),
+        AccountMeta::new(activity_address(program_id, &vault_pda), false),
+    ];
+    accounts.extend(badge_metas(program_id, owner, &mint));
+
+    Instruction::new_with_borsh(*program_id, &VaultInstruction::DepositSol { amount }, accounts)
+}
+
+/// Releases `owner`'s SOL vault. `referrer` must be the referrer recorded at
+/// initialization, if any, so an early withdrawal can pay their share.
+pub fn withdraw_sol_ix(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    fee_recipients: &[Pubkey],
+    referrer: Option<Pubkey>,
+) -> Instruction {
+    let mint = native_mint::id();
+    let (vault_pda, _) = find_vault_address(program_id, owner, &mint);
+
+    let mut accounts = vec![
+        AccountMeta::new(*owner, true),
+        AccountMeta::new(vault_pda, false),
+        AccountMeta::new_readonly(Config::find_address(program_id).0, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new(Loyalty::find_address(owner, program_id).0, false),
+        AccountMeta::new_readonly(FeeExemption::find_address(owner, program_id).0, false),
+        AccountMeta::new_readonly(MintConfig::find_address(&mint, program_id).0, false),
+        AccountMeta::new(stats_address(program_id, owner), false),
+        AccountMeta::new(activity_address(program_id, &vault_pda), false),
+    ];
+    accounts.extend(fee_recipients.iter().map(|key| AccountMeta::new(*key, false)));
+    accounts.push(AccountMeta::new_readonly(Referral::find_address(owner, program_id).0, false));
+
+    if let Some(referrer) = referrer {
+        accounts.extend(referrer_metas(program_id, &referrer));
+    }
+    accounts.extend(badge_metas(program_id, owner, &mint));
+
+    Instruction::new_with_borsh(*program_id, &VaultInstruction::WithdrawSol, accounts)
+}
@@ -0,0 +1,102 @@
+use solana_program::program_error::ProgramError;
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VaultError {
+    #[error("Fee shares are empty, exceed the maximum, or do not sum to 10,000 bps")]
+    InvalidFeeSplit,
+    #[error("A fee recipient account does not match the configured recipient")]
+    FeeRecipientMismatch,
+    #[error("The signer is not the configured admin")]
+    Unauthorized,
+    #[error("A vault owner named themselves as referrer")]
+    SelfReferral,
+    #[error("The referrer account does not match the recorded referral")]
+    ReferrerMismatch,
+    #[error("A basis-point value is above 10,000")]
+    InvalidBps,
+    #[error("Loyalty tiers are unsorted, too many, or discount more than 100%")]
+    InvalidLoyaltyTiers,
+    #[error("The vault uses an older layout and must be migrated first")]
+    VaultNeedsMigration,
+    #[error("The vault's layout version is newer than this program understands")]
+    UnsupportedVaultVersion,
+    #[error("An account the instruction writes to was passed read-only")]
+    AccountNotWritable,
+    #[error("The token mint is neither a KUZA mint nor a registered mint")]
+    InvalidMint,
+    #[error("The vault account is not owned by this program")]
+    VaultNotProgramOwned,
+    #[error("The vault account is not the owner's vault address")]
+    VaultAddressMismatch,
+    #[error("The vault token account is not the vault's associated token account")]
+    InvalidVaultTokenAccount,
+    #[error("The user token account is not the owner's or holds a different mint")]
+    InvalidUserTokenAccount,
+    #[error("The vault's status does not allow this instruction")]
+    InvalidStatusTransition,
+    #[error("The vault's lock has not run out yet")]
+    LockNotExpired,
+    #[error("The instruction does not apply to this kind of vault")]
+    WrongVaultKind,
+    #[error("The vault's unlock cooldown has not passed yet")]
+    CooldownActive,
+    #[error("The vault's position is held as receipt tokens; redeem them instead")]
+    ReceiptsIssued,
+    #[error("The receipt mint is not the vault's receipt mint")]
+    InvalidReceiptMint,
+    #[error("Access tiers are unsorted, too many, or start at zero")]
+    InvalidAccessTiers,
+    #[error("A badge mint or badge account is not at the expected address")]
+    InvalidBadgeAccount,
+    #[error("The mint is registered but not taking new locks")]
+    MintDisabled,
+    #[error("A registered mint's terms need a non-zero lock duration")]
+    InvalidMintConfig,
+    #[error("Native SOL vaults take only the SOL instructions, and token vaults only the token ones")]
+    WrongVaultAsset,
+    #[error("A day must last at least one slot")]
+    InvalidSlotsPerDay,
+    #[error("An amount, count or time overflowed")]
+    ArithmeticOverflow,
+}
+
+impl VaultError {
+    /// Every error, in code order. Used to publish the codes in the IDL.
+    pub const ALL: &'static [VaultError] = &[
+        VaultError::InvalidFeeSplit,
+        VaultError::FeeRecipientMismatch,
+        VaultError::Unauthorized,
+        VaultError::SelfReferral,
+        VaultError::ReferrerMismatch,
+        VaultError::InvalidBps,
+        VaultError::InvalidLoyaltyTiers,
+        VaultError::VaultNeedsMigration,
+        VaultError::UnsupportedVaultVersion,
+        VaultError::AccountNotWritable,
+        VaultError::InvalidMint,
+        VaultError::VaultNotProgramOwned,
+        VaultError::VaultAddressMismatch,
+        VaultError::InvalidVaultTokenAccount,
+        VaultError::InvalidUserTokenAccount,
+        VaultError::InvalidStatusTransition,
+        VaultError::LockNotExpired,
+        VaultError::WrongVaultKind,
+        VaultError::CooldownActive,
+        VaultError::ReceiptsIssued,
+        VaultError::InvalidReceiptMint,
+        VaultError::InvalidAccessTiers,
+        VaultError::InvalidBadgeAccount,
+        VaultError::MintDisabled,
+        VaultError::InvalidMintConfig,
+        VaultError::WrongVaultAsset,
+        VaultError::InvalidSlotsPerDay,
+        VaultError::ArithmeticOverflow,
+    ];
+}
+
+impl From<VaultError> for ProgramError {
+    fn from(e: VaultError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
@@ -0,0 +1,221 @@
+use {
+    crate::{
+        error::VaultError,
+        math::CheckedMath,
+        state::{Config, FeeShare, LoyaltyTier},
+        utils::transfer_lamports,
+    },
+    solana_program::{
+        account_info::AccountInfo,
+        entrypoint::ProgramResult,
+        msg,
+        program::invoke,
+        program_error::ProgramError,
+        program_pack::Pack,
+        system_instruction,
+    },
+    spl_associated_token_account::get_associated_token_address,
+    spl_token::{instruction as token_instruction, state::Mint},
+};
+
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// Checks that a fee split is usable: 1..=MAX recipients, weights summing to
+/// exactly 10,000 bps and a dust recipient that points at one of them.
+pub fn validate_fee_shares(shares: &[FeeShare], dust_recipient: u8) -> Result<(), VaultError> {
+    if shares.is_empty() || shares.len() > Config::MAX_FEE_RECIPIENTS {
+        return Err(VaultError::InvalidFeeSplit);
+    }
+
+    if dust_recipient as usize >= shares.len() {
+        return Err(VaultError::InvalidFeeSplit);
+    }
+
+    let total_bps: u64 = shares.iter().map(|share| share.bps as u64).sum();
+    if total_bps != BPS_DENOMINATOR {
+        return Err(VaultError::InvalidFeeSplit);
+    }
+
+    Ok(())
+}
+
+/// Checks that loyalty tiers fit in the config, are strictly ascending by
+/// `min_completed_locks` and never discount more than the whole fee.
+pub fn validate_loyalty_tiers(tiers: &[LoyaltyTier]) -> Result<(), VaultError> {
+    if tiers.len() > Config::MAX_LOYALTY_TIERS {
+        return Err(VaultError::InvalidLoyaltyTiers);
+    }
+
+    if tiers.iter().any(|tier| tier.discount_bps as u64 > BPS_DENOMINATOR) {
+        return Err(VaultError::InvalidLoyaltyTiers);
+    }
+
+    if tiers.windows(2).any(|pair| pair[0].min_completed_locks >= pair[1].min_completed_locks) {
+        return Err(VaultError::InvalidLoyaltyTiers);
+    }
+
+    Ok(())
+}
+
+/// Fee for leaving a lock early, before any discount or exemption: 75% of
+/// `max_fee_lamports` on day 0, falling linearly to nothing once the lock has
+/// run. Only whole days count, and the fee is rounded down.
+pub fn early_withdrawal_fee(time_locked: u64, lock_duration: u64, max_fee_lamports: u64) -> Result<u64, ProgramError> {
+    let time_elapsed_in_days = time_locked / 86400;
+    let duration_in_days = lock_duration / 86400;
+
+    if time_elapsed_in_days >= duration_in_days {
+        return Ok(0);
+    }
+
+    // 3/4 of the maximum, scaled by the share of whole days still to run
+    let days_left = duration_in_days.try_sub(time_elapsed_in_days)? as u128;
+    let fee = (max_fee_lamports as u128).try_mul(3)?.try_mul(days_left)? / (duration_in_days as u128).try_mul(4)?;
+
+    u64::try_from(fee).map_err(|_| VaultError::ArithmeticOverflow.into())
+}
+
+/// Splits `amount` by basis points. Each portion is rounded down and whatever
+/// is left over goes to `dust_recipient`, so for a split that passes
+/// [`validate_fee_shares`] the portions always sum to `amount`.
+pub fn split_fee(amount: u64, shares: &[FeeShare], dust_recipient: usize) -> Vec<u64> {
+    let mut portions: Vec<u64> = shares
+        .iter()
+        .map(|share| (amount as u128 * share.bps as u128 / BPS_DENOMINATOR as u128) as u64)
+        .collect();
+
+    let distributed = portions.iter().fold(0u64, |total, portion| total.saturating_add(*portion));
+    if let Some(dust) = portions.get_mut(dust_recipient) {
+        *dust = dust.saturating_add(amount.saturating_sub(distributed));
+    }
+
+    portions
+}
+
+/// The part of `amount` owed to a referrer at `referral_bps`, rounded down.
+pub fn referral_cut(amount: u64, referral_bps: u16) -> u64 {
+    (amount as u128 * referral_bps as u128 / BPS_DENOMINATOR as u128) as u64
+}
+
+/// `amount` after a `discount_bps` reduction, rounded down.
+pub fn apply_discount(amount: u64, discount_bps: u16) -> u64 {
+    let discount = (amount as u128 * discount_bps as u128 / BPS_DENOMINATOR as u128) as u64;
+    amount.saturating_sub(discount)
+}
+
+// Pairs each configured recipient account with its portion of `amount`,
+// checking the accounts against the config
+fn sol_portions<'c, 'a>(
+    recipients: &'c [AccountInfo<'a>],
+    config: &Config,
+    amount: u64,
+) -> Result<Vec<(&'c AccountInfo<'a>, u64)>, ProgramError> {
+    let shares = config.active_fee_shares();
+
+    if recipients.len() < shares.len() {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    let portions = split_fee(amount, shares, config.dust_recipient as usize);
+
+    shares
+        .iter()
+        .zip(recipients)
+        .zip(portions)
+        .map(|((share, recipient), portion)| {
+            if *recipient.key != share.recipient {
+                msg!("Fee recipient {} does not match config", recipient.key);
+                return Err(VaultError::FeeRecipientMismatch.into());
+            }
+            Ok((recipient, portion))
+        })
+        .collect()
+}
+
+/// Transfers a SOL fee from `payer` to the configured recipients. `recipients`
+/// must list the recipient accounts in the same order as the config.
+pub fn distribute_sol<'a>(
+    payer: &AccountInfo<'a>,
+    recipients: &[AccountInfo<'a>],
+    system_program: &AccountInfo<'a>,
+    config: &Config,
+    amount: u64,
+) -> ProgramResult {
+    for (recipient, portion) in sol_portions(recipients, config, amount)? {
+        if portion == 0 {
+            continue;
+        }
+
+        invoke(
+            &system_instruction::transfer(payer.key, recipient.key, portion),
+            &[payer.clone(), recipient.clone(), system_program.clone()],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Like [`distribute_sol`], paying the fee out of a program-owned account
+/// such as a native SOL vault, whose lamports the system program can't move.
+pub fn distribute_lamports<'a>(
+    source: &AccountInfo<'a>,
+    recipients: &[AccountInfo<'a>],
+    config: &Config,
+    amount: u64,
+) -> ProgramResult {
+    for (recipient, portion) in sol_portions(recipients, config, amount)? {
+        transfer_lamports(source, recipient, portion)?;
+    }
+
+    Ok(())
+}
+
+/// Transfers a token fee of `mint` from `source`, signed by `authority`, to
+/// the configured recipients' associated token accounts, split like
+/// [`distribute_sol`]. `recipient_token_accounts` must list those accounts in
+/// the same order as the config.
+pub fn distribute_tokens<'a>(
+    source: &AccountInfo<'a>,
+    authority: &AccountInfo<'a>,
+    mint: &AccountInfo<'a>,
+    recipient_token_accounts: &[AccountInfo<'a>],
+    token_program: &AccountInfo<'a>,
+    config: &Config,
+    amount: u64,
+) -> ProgramResult {
+    let shares = config.active_fee_shares();
+
+    if recipient_token_accounts.len() < shares.len() {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    let decimals = Mint::unpack(&mint.data.borrow())?.decimals;
+    let portions = split_fee(amount, shares, config.dust_recipient as usize);
+
+    for ((share, recipient), portion) in shares.iter().zip(recipient_token_accounts).zip(portions) {
+        if *recipient.key != get_associated_token_address(&share.recipient, mint.key) {
+            msg!("Fee token account {} does not match config", recipient.key);
+            return Err(VaultError::FeeRecipientMismatch.into());
+        }
+
+        if portion == 0 {
+            continue;
+        }
+
+        invoke(
+            &token_instruction::transfer_checked(
+                token_program.key,
+                source.key,
+                mint.key,
+                recipient.key,
+                authority.key,
+                &[],
+                portion,
+                decimals,
+            )?,
+            &[source.clone(), mint.clone(), recipient.clone(), authority.clone(), token_program.clone()],
+        )?;
+    }
+
+    Ok(())
+}
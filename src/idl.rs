@@ -0,0 +1,300 @@
+//! Machine-readable interface description.
+//!
+//! `VaultInstruction` is declared through `vault_instructions!`, which takes an
+//! `#[accounts(...)]` annotation on every variant listing the accounts its
+//! handler reads, in order. Each account is tagged `signer` and/or `writable`;
+//! accounts tagged `remaining` come after the fixed ones and may be absent or
+//! repeated. The annotations feed the JSON IDL built by `generate`, which is
+//! checked in at `idl/construct_vault_sol.json`. The generator needs
+//! `serde_json`, so it is only built with the `cli` feature.
+
+macro_rules! vault_instructions {
+    (
+        $(#[$enum_meta:meta])*
+        $vis:vis enum $name:ident {
+            $(
+                $(#[doc = $doc:literal])*
+                #[accounts($($account:ident : $($flag:ident)*),* $(,)?)]
+                $variant:ident $({ $($arg:ident : $ty:ty),* $(,)? })?
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$enum_meta])*
+        $vis enum $name {
+            $(
+                $(#[doc = $doc])*
+                $variant $({ $($arg: $ty),* })?
+            ),*
+        }
+
+        #[cfg(all(feature = "cli", not(target_os = "solana")))]
+        impl $name {
+            /// Instruction descriptions in Borsh discriminant order.
+            pub fn idl_instructions() -> Vec<$crate::idl::IdlInstruction> {
+                vec![
+                    $(
+                        $crate::idl::IdlInstruction {
+                            name: stringify!($variant),
+                            docs: vec![$($doc.trim()),*],
+                            args: vec![$($(
+                                $crate::idl::IdlField::new(stringify!($arg), stringify!($ty))
+                            ),*)?],
+                            accounts: vec![$(
+                                $crate::idl::IdlAccount::new(stringify!($account), &[$(stringify!($flag)),*])
+                            ),*],
+                        }
+                    ),*
+                ]
+            }
+        }
+    };
+}
+
+#[cfg(all(feature = "cli", not(target_os = "solana")))]
+pub use generator::*;
+
+#[cfg(all(feature = "cli", not(target_os = "solana")))]
+mod generator {
+    use {
+        crate::{error::VaultError, processor::VaultInstruction, state::{ActivityEntry, ActivityLog, Vault}},
+        serde_json::{json, Value},
+    };
+
+    pub struct IdlField {
+        pub name: &'static str,
+        pub ty: String,
+    }
+
+    impl IdlField {
+        pub fn new(name: &'static str, ty: &str) -> Self {
+            // `stringify!` spaces out generics, e.g. `Option < Pubkey >`
+            Self {
+                name,
+                ty: ty.split_whitespace().collect(),
+            }
+        }
+    }
+
+    pub struct IdlAccount {
+        pub name: &'static str,
+        pub signer: bool,
+        pub writable: bool,
+        pub remaining: bool,
+    }
+
+    impl IdlAccount {
+        pub fn new(name: &'static str, flags: &[&str]) -> Self {
+            for flag in flags {
+                assert!(
+                    matches!(*flag, "signer" | "writable" | "remaining"),
+                    "unknown account flag `{}` on `{}`",
+                    flag,
+                    name
+                );
+            }
+
+            Self {
+                name,
+                signer: flags.contains(&"signer"),
+                writable: flags.contains(&"writable"),
+                remaining: flags.contains(&"remaining"),
+            }
+        }
+    }
+
+    pub struct IdlInstruction {
+        pub name: &'static str,
+        pub docs: Vec<&'static str>,
+        pub args: Vec<IdlField>,
+        pub accounts: Vec<IdlAccount>,
+    }
+
+    impl IdlInstruction {
+        /// Accounts the handler always reads, before any remaining accounts.
+        pub fn fixed_accounts(&self) -> impl Iterator<Item = &IdlAccount> {
+            self.accounts.iter().filter(|account| !account.remaining)
+        }
+    }
+
+    fn field(name: &str, ty: &str, offset: usize, size: usize) -> Value {
+        json!({ "name": name, "type": ty, "offset": offset, "size": size })
+    }
+
+    fn vault_layout() -> Value {
+        let mut offset = 0;
+        let mut fields = Vec::new();
+
+        for (name, ty, size) in [
+            ("discriminator", "[u8;8]", 8),
+            ("version", "u8", 1),
+            ("owner", "Pubkey", 32),
+            ("lock_duration", "u64", 8),
+            ("amount_locked", "u64", 8),
+            ("deposit_timestamp", "u64", 8),
+            ("status", "VaultStatus", 1),
+            ("bump", "u8", 1),
+            ("status_timestamp", "u64", 8),
+            ("auto_renew", "bool", 1),
+            ("kind", "VaultKind", 1),
+            ("receipts", "bool", 1),
+            ("receipt_bump", "u8", 1),
+            ("mint", "Pubkey", 32),
+            ("rent_lamports", "u64", 8),
+            ("counted", "bool", 1),
+            ("time_basis", "TimeBasis", 1),
+            ("exemption_bump", "u8", 1),
+            ("referral_bump", "u8", 1),
+            ("reserved", "[u8;8]", Vault::RESERVED_LEN),
+        ] {
+            fields.push(field(name, ty, offset, size));
+            offset += size;
+        }
+
+        json!({
+            "name": "Vault",
+            "size": Vault::LEN,
+            "discriminator": Vault::DISCRIMINATOR,
+            "version": Vault::VERSION,
+            "seeds": [Vault::SEED_PREFIX, "owner"],
+            "registered_mint_seeds": [Vault::SEED_PREFIX, "owner", "mint"],
+            "fields": fields,
+        })
+    }
+
+    fn activity_log_layout() -> Value {
+        let entries_size = ActivityLog::CAPACITY * ActivityEntry::LEN;
+        let fields = vec![
+            field("vault", "Pubkey", 0, 32),
+            field("bump", "u8", 32, 1),
+            field("total", "u64", 33, 8),
+            field("entries", &format!("[ActivityEntry;{}]", ActivityLog::CAPACITY), 41, entries_size),
+        ];
+
+        json!({
+            "name": "ActivityLog",
+            "size": ActivityLog::LEN,
+            "seeds": [ActivityLog::SEED_PREFIX, "vault"],
+            "fields": fields,
+        })
+    }
+
+    fn types() -> Value {
+        json!([
+            {
+                "name": "FeeShare",
+                "kind": "struct",
+                "fields": [
+                    { "name": "recipient", "type": "Pubkey" },
+                    { "name": "bps", "type": "u16" },
+                ],
+            },
+            {
+                "name": "LoyaltyTier",
+                "kind": "struct",
+                "fields": [
+                    { "name": "min_completed_locks", "type": "u32" },
+                    { "name": "discount_bps", "type": "u16" },
+                ],
+            },
+            {
+                "name": "AccessTier",
+                "kind": "struct",
+                "fields": [
+                    { "name": "min_amount_locked", "type": "u64" },
+                ],
+            },
+            {
+                "name": "FeeExemptionReason",
+                "kind": "enum",
+                "variants": ["MarketMaker", "TeamMultisig", "PartnerProtocol", "Other"],
+            },
+            {
+                "name": "VaultStatus",
+                "kind": "enum",
+                "variants": ["Uninitialized", "Locked", "Matured", "Unlocking", "Released"],
+            },
+            {
+                "name": "VaultKind",
+                "kind": "enum",
+                "variants": ["Fixed", "Cooldown"],
+            },
+            {
+                "name": "TimeBasis",
+                "kind": "enum",
+                "variants": ["UnixSeconds", "Slots"],
+            },
+            {
+                "name": "ActivityEntry",
+                "kind": "struct",
+                "fields": [
+                    { "name": "timestamp", "type": "u64" },
+                    { "name": "kind", "type": "ActivityKind" },
+                    { "name": "amount", "type": "u64" },
+                    { "name": "fee_lamports", "type": "u64" },
+                ],
+            },
+            {
+                "name": "ActivityKind",
+                "kind": "enum",
+                "variants": [
+                    "Initialize",
+                    "Deposit",
+                    "Withdraw",
+                    "Claim",
+                    "Crank",
+                    "Redeem",
+                    "AutoRenewOn",
+                    "AutoRenewOff",
+                    "RequestUnlock",
+                    "CancelUnlock",
+                    "EnableReceipts",
+                ],
+            },
+        ])
+    }
+
+    /// Builds the full IDL document.
+    pub fn generate() -> Value {
+        let instructions: Vec<Value> = VaultInstruction::idl_instructions()
+            .iter()
+            .enumerate()
+            .map(|(discriminant, instruction)| {
+                json!({
+                    "name": instruction.name,
+                    "discriminant": discriminant,
+                    "docs": instruction.docs,
+                    "args": instruction.args.iter().map(|arg| json!({
+                        "name": arg.name,
+                        "type": arg.ty,
+                    })).collect::<Vec<_>>(),
+                    "accounts": instruction.accounts.iter().map(|account| json!({
+                        "name": account.name,
+                        "signer": account.signer,
+                        "writable": account.writable,
+                        "remaining": account.remaining,
+                    })).collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+
+        let errors: Vec<Value> = VaultError::ALL
+            .iter()
+            .map(|error| {
+                json!({
+                    "code": *error as u32,
+                    "name": format!("{:?}", error),
+                    "msg": error.to_string(),
+                })
+            })
+            .collect();
+
+        json!({
+            "name": env!("CARGO_PKG_NAME"),
+            "version": env!("CARGO_PKG_VERSION"),
+            "instructions": instructions,
+            "accounts": [vault_layout(), activity_log_layout()],
+            "types": types(),
+            "errors": errors,
+        })
+    }
+}
@@ -0,0 +1,102 @@
+use {
+    crate::{
+        math::unix_seconds,
+        state::{ActivityEntry, ActivityKind, ActivityLog},
+        utils::create_pda_account,
+    },
+    solana_program::{
+        account_info::AccountInfo,
+        clock::Clock,
+        entrypoint::ProgramResult,
+        msg,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        sysvar::Sysvar,
+    },
+};
+
+/// Creates the activity log of the vault at `vault_account` at `payer`'s
+/// expense, unless it already exists.
+pub fn create_activity_log<'a>(
+    program_id: &Pubkey,
+    payer: &AccountInfo<'a>,
+    vault_account: &AccountInfo<'a>,
+    activity_account: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+) -> ProgramResult {
+    if !activity_account.data_is_empty() {
+        return Ok(());
+    }
+
+    let bump = check_activity_address(program_id, vault_account.key, activity_account)?;
+
+    create_pda_account(
+        payer,
+        activity_account,
+        system_program,
+        program_id,
+        ActivityLog::LEN,
+        &[ActivityLog::SEED_PREFIX.as_bytes(), vault_account.key.as_ref(), &[bump]],
+    )?;
+
+    let mut data = activity_account.data.borrow_mut();
+    let log = ActivityLog::load_mut(&mut data)?;
+    log.vault = *vault_account.key;
+    log.bump = bump;
+
+    Ok(())
+}
+
+/// Appends an action to the vault's activity log. Vaults opened before the
+/// logs existed get theirs at their next deposit or withdrawal; until then
+/// the account is only checked to be the right address and nothing is
+/// recorded.
+pub fn record_activity(
+    program_id: &Pubkey,
+    vault_account: &AccountInfo,
+    activity_account: &AccountInfo,
+    kind: ActivityKind,
+    amount: u64,
+    fee_lamports: u64,
+) -> ProgramResult {
+    if activity_account.data_is_empty() {
+        check_activity_address(program_id, vault_account.key, activity_account)?;
+        return Ok(());
+    }
+
+    if activity_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let mut data = activity_account.data.borrow_mut();
+    let log = ActivityLog::load_mut(&mut data)?;
+
+    let expected = Pubkey::create_program_address(
+        &[ActivityLog::SEED_PREFIX.as_bytes(), vault_account.key.as_ref(), &[log.bump]],
+        program_id,
+    )?;
+
+    if expected != *activity_account.key || log.vault != *vault_account.key {
+        msg!("Activity account is not the log of vault {}", vault_account.key);
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    // Entries are in unix seconds whatever the vault's time basis
+    let now = unix_seconds(Clock::get()?.unix_timestamp);
+    log.push(ActivityEntry::new(kind, amount, fee_lamports, now));
+    Ok(())
+}
+
+fn check_activity_address(
+    program_id: &Pubkey,
+    vault: &Pubkey,
+    activity_account: &AccountInfo,
+) -> Result<u8, ProgramError> {
+    let (activity_pda, bump) = ActivityLog::find_address(vault, program_id);
+    if activity_pda != *activity_account.key {
+        msg!("Activity account is not the log of vault {}", vault);
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    Ok(bump)
+}
@@ -0,0 +1,83 @@
+use {
+    crate::{
+        error::VaultError,
+        instruction::{load_vault, record_activity},
+        state::{ActivityKind, Vault, VaultKind, VaultStatus},
+        utils::{check_signer, check_writable},
+    },
+    solana_program::{
+        account_info::AccountInfo,
+        clock::Clock,
+        entrypoint::ProgramResult,
+        msg,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        sysvar::Sysvar,
+    },
+};
+
+/// Accounts for `SetAutoRenew`. The activity account is the vault's
+/// activity log.
+pub struct SetAutoRenewAccounts<'a, 'b> {
+    pub owner: &'b AccountInfo<'a>,
+    pub vault_account: &'b AccountInfo<'a>,
+    pub activity_account: &'b AccountInfo<'a>,
+}
+
+impl<'a, 'b> SetAutoRenewAccounts<'a, 'b> {
+    pub fn try_from(program_id: &Pubkey, accounts: &'b [AccountInfo<'a>]) -> Result<Self, ProgramError> {
+        let [owner, vault_account, activity_account, ..] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        check_signer(owner)?;
+        check_writable(vault_account)?;
+        check_writable(activity_account)?;
+
+        load_vault(program_id, owner.key, vault_account)?;
+
+        Ok(Self {
+            owner,
+            vault_account,
+            activity_account,
+        })
+    }
+}
+
+/// Turns auto-renew on or off. A vault that already matured is locked again
+/// for a new term starting now. Switching off fixes the deposit time to the
+/// start of the running term, so the vault matures when that term ends
+/// rather than at once.
+pub fn set_auto_renew(program_id: &Pubkey, accounts: SetAutoRenewAccounts, auto_renew: bool) -> ProgramResult {
+    msg!("Setting auto-renew to {}", auto_renew);
+
+    let mut vault_data = accounts.vault_account.data.borrow_mut();
+    let vault = Vault::load_mut(&mut vault_data)?;
+    let now = vault.now(&Clock::get()?)?;
+
+    if auto_renew {
+        vault.check_kind(VaultKind::Fixed)?;
+
+        // Holders bought a position that ends; the owner can't extend it
+        if vault.has_receipts() {
+            return Err(VaultError::ReceiptsIssued.into());
+        }
+
+        vault.mature(now)?;
+
+        if vault.status()? == VaultStatus::Matured {
+            vault.deposit_timestamp = now.into();
+            vault.transition(VaultStatus::Locked, now)?;
+        }
+    } else {
+        vault.roll_forward(now);
+    }
+
+    vault.auto_renew = auto_renew as u8;
+
+    msg!("Vault unlocks at {}", vault.unlock_timestamp(now));
+    drop(vault_data);
+
+    let kind = if auto_renew { ActivityKind::AutoRenewOn } else { ActivityKind::AutoRenewOff };
+    record_activity(program_id, accounts.vault_account, accounts.activity_account, kind, 0, 0)
+}
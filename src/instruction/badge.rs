@@ -0,0 +1,505 @@
+use {
+    crate::{
+        error::VaultError,
+        instruction::{
+            load_config, load_mint_config, load_registered_mint_config, load_vault,
+            UpdateConfigAccounts,
+        },
+        state::{AccessTier, Config, MintConfig, Vault},
+        utils::{check_program, check_signer, check_writable, create_pda_account},
+    },
+    borsh::BorshSerialize,
+    solana_program::{
+        account_info::AccountInfo,
+        clock::Clock,
+        entrypoint::ProgramResult,
+        msg,
+        program::{invoke, invoke_signed},
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        system_program,
+        sysvar::Sysvar,
+    },
+    spl_associated_token_account::{
+        get_associated_token_address_with_program_id, instruction::create_associated_token_account_idempotent,
+    },
+    spl_token_2022::{
+        extension::{ExtensionType, StateWithExtensions},
+        instruction as token_instruction,
+        state::{Account as BadgeAccount, Mint as BadgeMint},
+    },
+};
+
+/// Accounts in the badge group: the config, the Token-2022 program, then a
+/// (badge mint, owner's badge account) pair for every possible tier.
+pub const BADGE_ACCOUNTS_LEN: usize = 2 + 2 * Config::MAX_ACCESS_TIERS;
+
+/// Fails unless the tiers fit the config and rise strictly from above zero.
+pub fn validate_access_tiers(access_tiers: &[AccessTier]) -> ProgramResult {
+    if access_tiers.len() > Config::MAX_ACCESS_TIERS {
+        return Err(VaultError::InvalidAccessTiers.into());
+    }
+
+    let mut previous = 0;
+    for tier in access_tiers {
+        if tier.min_amount_locked <= previous {
+            return Err(VaultError::InvalidAccessTiers.into());
+        }
+        previous = tier.min_amount_locked;
+    }
+
+    Ok(())
+}
+
+/// The badge group, read from the end of an instruction's accounts so it
+/// can follow any variable-length remaining accounts.
+pub struct BadgeAccounts<'a, 'b> {
+    pub config_account: &'b AccountInfo<'a>,
+    pub token_program: &'b AccountInfo<'a>,
+    pub badges: &'b [AccountInfo<'a>],
+    pub config: Config,
+}
+
+/// Accounts before the badge group, and the group if there is one.
+type SplitAccounts<'a, 'b> = (&'b [AccountInfo<'a>], Option<BadgeAccounts<'a, 'b>>);
+
+impl<'a, 'b> BadgeAccounts<'a, 'b> {
+    /// Splits the badge group off the end of `accounts`. There is no group
+    /// when `accounts` is too short to end in one.
+    pub fn from_tail(
+        program_id: &Pubkey,
+        accounts: &'b [AccountInfo<'a>],
+    ) -> Result<SplitAccounts<'a, 'b>, ProgramError> {
+        let Some(start) = accounts.len().checked_sub(BADGE_ACCOUNTS_LEN) else {
+            return Ok((accounts, None));
+        };
+
+        let (rest, [config_account, token_program, badges @ ..]) = accounts.split_at(start) else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        check_program(token_program, &spl_token_2022::id())?;
+        let config = load_config(program_id, config_account)?;
+
+        Ok((
+            rest,
+            Some(Self {
+                config_account,
+                token_program,
+                badges,
+                config,
+            }),
+        ))
+    }
+
+    /// Like [`BadgeAccounts::from_tail`], but the group may only be left out
+    /// while the mint has no access tiers.
+    pub fn require_from_tail(
+        program_id: &Pubkey,
+        mint_config: &MintConfig,
+        accounts: &'b [AccountInfo<'a>],
+    ) -> Result<SplitAccounts<'a, 'b>, ProgramError> {
+        let (rest, badges) = Self::from_tail(program_id, accounts)?;
+
+        if badges.is_none() && mint_config.access_tier_count > 0 {
+            msg!("Access tiers are configured, so the badge accounts are required");
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+
+        Ok((rest, badges))
+    }
+}
+
+/// Accounts for creating badge accounts the owner doesn't have yet.
+pub struct BadgePayer<'a, 'b> {
+    pub payer: &'b AccountInfo<'a>,
+    pub system_program: &'b AccountInfo<'a>,
+    pub associated_token_program: &'b AccountInfo<'a>,
+}
+
+fn config_seeds(config: &Config) -> [&[u8]; 2] {
+    [Config::SEED_PREFIX.as_bytes(), std::slice::from_ref(&config.bump)]
+}
+
+/// Brings `owner`'s badges for a mint in line with `tier_amount`: they hold
+/// one badge of every tier in `tiers` whose minimum it meets and none of the
+/// others, including tiers the admin has since removed. `registered_mint`
+/// picks the registered mint's badges over the KUZA ones, and `badge_bumps`
+/// are the bumps of its badge mints. Badges are minted and burned by the
+/// config PDA, the mints' authority and permanent delegate.
+#[allow(clippy::too_many_arguments)]
+pub fn sync_badges<'a>(
+    program_id: &Pubkey,
+    badges: &BadgeAccounts<'a, '_>,
+    owner: &AccountInfo<'a>,
+    registered_mint: Option<&Pubkey>,
+    tiers: &[AccessTier],
+    badge_bumps: &[u8; Config::MAX_ACCESS_TIERS],
+    tier_amount: u64,
+    payer: Option<&BadgePayer<'a, '_>>,
+) -> ProgramResult {
+    let (config_account, token_program, config) = (badges.config_account, badges.token_program, &badges.config);
+
+    for (index, (pair, bump)) in badges.badges.chunks_exact(2).zip(badge_bumps).enumerate() {
+        let (badge_mint, badge_account) = (&pair[0], &pair[1]);
+
+        // A tier that was never configured has no mint, so nothing to hold
+        if *bump == 0 {
+            continue;
+        }
+
+        if AccessTier::create_badge_mint(registered_mint, index as u8, *bump, program_id)? != *badge_mint.key
+            || get_associated_token_address_with_program_id(owner.key, badge_mint.key, &spl_token_2022::id())
+                != *badge_account.key
+        {
+            msg!("Badge accounts for tier {} are not at the expected addresses", index);
+            return Err(VaultError::InvalidBadgeAccount.into());
+        }
+
+        let held = if badge_account.data_is_empty() {
+            0
+        } else {
+            StateWithExtensions::<BadgeAccount>::unpack(&badge_account.data.borrow())?.base.amount
+        };
+
+        let earned = tiers
+            .get(index)
+            .is_some_and(|tier| tier_amount >= tier.min_amount_locked);
+
+        if earned && held == 0 {
+            check_writable(badge_mint)?;
+            check_writable(badge_account)?;
+
+            if badge_account.data_is_empty() {
+                let Some(payer) = payer else {
+                    msg!("Owner has no badge account for tier {}", index);
+                    return Err(VaultError::InvalidBadgeAccount.into());
+                };
+
+                invoke(
+                    &create_associated_token_account_idempotent(
+                        payer.payer.key,
+                        owner.key,
+                        badge_mint.key,
+                        token_program.key,
+                    ),
+                    &[
+                        payer.payer.clone(),
+                        badge_account.clone(),
+                        owner.clone(),
+                        badge_mint.clone(),
+                        payer.system_program.clone(),
+                        token_program.clone(),
+                        payer.associated_token_program.clone(),
+                    ],
+                )?;
+            }
+
+            msg!("Minting the tier {} badge", index);
+            invoke_signed(
+                &token_instruction::mint_to(
+                    token_program.key,
+                    badge_mint.key,
+                    badge_account.key,
+                    config_account.key,
+                    &[],
+                    1,
+                )?,
+                &[
+                    badge_mint.clone(),
+                    badge_account.clone(),
+                    config_account.clone(),
+                    token_program.clone(),
+                ],
+                &[&config_seeds(config)],
+            )?;
+        } else if !earned && held > 0 {
+            check_writable(badge_mint)?;
+            check_writable(badge_account)?;
+
+            msg!("Burning the tier {} badge", index);
+            invoke_signed(
+                &token_instruction::burn(
+                    token_program.key,
+                    badge_account.key,
+                    badge_mint.key,
+                    config_account.key,
+                    &[],
+                    held,
+                )?,
+                &[
+                    badge_account.clone(),
+                    badge_mint.clone(),
+                    config_account.clone(),
+                    token_program.clone(),
+                ],
+                &[&config_seeds(config)],
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Accounts for `SetAccessTiers`. The badge mints follow in tier order, one
+/// for every possible tier; missing ones are created for the new tiers.
+pub struct SetAccessTiersAccounts<'a, 'b> {
+    pub update: UpdateConfigAccounts<'a, 'b>,
+    pub system_program: &'b AccountInfo<'a>,
+    pub token_program: &'b AccountInfo<'a>,
+    pub badge_mints: &'b [AccountInfo<'a>],
+}
+
+impl<'a, 'b> SetAccessTiersAccounts<'a, 'b> {
+    pub fn try_from(program_id: &Pubkey, accounts: &'b [AccountInfo<'a>]) -> Result<Self, ProgramError> {
+        let [admin, _config, system_program, token_program, badge_mints @ ..] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        if badge_mints.len() < Config::MAX_ACCESS_TIERS {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+        let badge_mints = &badge_mints[..Config::MAX_ACCESS_TIERS];
+
+        check_signer(admin)?;
+
+        for account in std::iter::once(admin).chain(badge_mints) {
+            check_writable(account)?;
+        }
+
+        check_program(system_program, &system_program::id())?;
+        check_program(token_program, &spl_token_2022::id())?;
+
+        Ok(Self {
+            update: UpdateConfigAccounts::try_from(program_id, accounts)?,
+            system_program,
+            token_program,
+            badge_mints,
+        })
+    }
+}
+
+/// Creates the badge mints of the first `tier_count` tiers that don't have
+/// one yet, for a registered mint or, without `registered_mint`, for KUZA,
+/// and records their bumps in `badge_bumps`. This is the only place a badge
+/// mint's address is searched for. Badge mints have no decimals and can't be
+/// transferred; the config PDA is mint authority and permanent delegate so
+/// the program can burn a badge without the holder.
+#[allow(clippy::too_many_arguments)]
+pub fn create_badge_mints<'a>(
+    program_id: &Pubkey,
+    registered_mint: Option<&Pubkey>,
+    tier_count: usize,
+    badge_bumps: &mut [u8; Config::MAX_ACCESS_TIERS],
+    admin: &AccountInfo<'a>,
+    badge_mints: &[AccountInfo<'a>],
+    config_account: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+) -> ProgramResult {
+    for (index, badge_mint) in badge_mints.iter().enumerate().take(tier_count) {
+        if badge_bumps[index] != 0 {
+            continue;
+        }
+
+        let (badge_pda, bump) = AccessTier::find_badge_mint(registered_mint, index as u8, program_id);
+        if badge_pda != *badge_mint.key {
+            msg!("Mint {} is not the tier {} badge mint", badge_mint.key, index);
+            return Err(VaultError::InvalidBadgeAccount.into());
+        }
+
+        if badge_mint.data_is_empty() {
+            let index = [index as u8];
+            let bump = [bump];
+            let mut seeds = vec![AccessTier::BADGE_SEED_PREFIX.as_bytes()];
+            seeds.extend(registered_mint.map(|mint| mint.as_ref()));
+            seeds.extend([&index[..], &bump[..]]);
+
+            create_badge_mint(admin, badge_mint, config_account, system_program, token_program, &seeds)?;
+        }
+
+        badge_bumps[index] = bump;
+    }
+
+    Ok(())
+}
+
+fn create_badge_mint<'a>(
+    admin: &AccountInfo<'a>,
+    badge_mint: &AccountInfo<'a>,
+    config_account: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+    seeds: &[&[u8]],
+) -> ProgramResult {
+    let space = ExtensionType::try_calculate_account_len::<BadgeMint>(&[
+        ExtensionType::NonTransferable,
+        ExtensionType::PermanentDelegate,
+    ])?;
+
+    create_pda_account(
+        admin,
+        badge_mint,
+        system_program,
+        &spl_token_2022::id(),
+        space,
+        seeds,
+    )?;
+
+    // Extensions have to be initialized before the mint itself
+    invoke(
+        &token_instruction::initialize_non_transferable_mint(token_program.key, badge_mint.key)?,
+        &[badge_mint.clone(), token_program.clone()],
+    )?;
+
+    invoke(
+        &token_instruction::initialize_permanent_delegate(token_program.key, badge_mint.key, config_account.key)?,
+        &[badge_mint.clone(), token_program.clone()],
+    )?;
+
+    invoke(
+        &token_instruction::initialize_mint2(token_program.key, badge_mint.key, config_account.key, None, 0)?,
+        &[badge_mint.clone(), token_program.clone()],
+    )?;
+
+    msg!("Created badge mint {}", badge_mint.key);
+    Ok(())
+}
+
+/// Replaces the access tier table and creates the badge mints of tiers
+/// that don't have one yet. Owners' badges follow on their next vault
+/// instruction or `SyncBadges`.
+pub fn set_access_tiers(
+    program_id: &Pubkey,
+    accounts: SetAccessTiersAccounts,
+    access_tiers: Vec<AccessTier>,
+) -> ProgramResult {
+    msg!("Updating the access tiers");
+
+    validate_access_tiers(&access_tiers)?;
+
+    let SetAccessTiersAccounts {
+        update: UpdateConfigAccounts {
+            admin,
+            config_account,
+            mut config,
+        },
+        system_program,
+        token_program,
+        badge_mints,
+    } = accounts;
+
+    create_badge_mints(
+        program_id,
+        None,
+        access_tiers.len(),
+        &mut config.badge_bumps,
+        admin,
+        badge_mints,
+        config_account,
+        system_program,
+        token_program,
+    )?;
+
+    config.access_tiers = [AccessTier::default(); Config::MAX_ACCESS_TIERS];
+    config.access_tiers[..access_tiers.len()].copy_from_slice(&access_tiers);
+    config.access_tier_count = access_tiers.len() as u8;
+    config.serialize(&mut &mut config_account.data.borrow_mut()[..])?;
+
+    msg!("Access table set to {} tiers", config.access_tier_count);
+    Ok(())
+}
+
+/// Accounts for `SyncBadges`. The badge group follows the fixed accounts.
+pub struct SyncBadgesAccounts<'a, 'b> {
+    pub payer: BadgePayer<'a, 'b>,
+    pub owner: &'b AccountInfo<'a>,
+    pub vault_account: &'b AccountInfo<'a>,
+    pub mint_config_account: &'b AccountInfo<'a>,
+    pub badges: BadgeAccounts<'a, 'b>,
+}
+
+impl<'a, 'b> SyncBadgesAccounts<'a, 'b> {
+    pub fn try_from(program_id: &Pubkey, accounts: &'b [AccountInfo<'a>]) -> Result<Self, ProgramError> {
+        let [
+            payer,
+            owner,
+            vault_account,
+            mint_config_account,
+            system_program,
+            associated_token_program,
+            remaining_accounts @ ..,
+        ] = accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        check_signer(payer)?;
+        check_writable(payer)?;
+
+        check_program(system_program, &system_program::id())?;
+        check_program(associated_token_program, &spl_associated_token_account::id())?;
+
+        let (_, badges) = BadgeAccounts::from_tail(program_id, remaining_accounts)?;
+        let badges = badges.ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+        Ok(Self {
+            payer: BadgePayer {
+                payer,
+                system_program,
+                associated_token_program,
+            },
+            owner,
+            vault_account,
+            mint_config_account,
+            badges,
+        })
+    }
+}
+
+/// Brings the owner's badges in line with their vault. Anyone may send it,
+/// and it is how badges follow changes no owner instruction sees: a lock
+/// running out, an unlock request, receipts, or a new tier table. The mint
+/// config is only read for a vault of a registered mint. An owner without a
+/// vault holds no badges.
+pub fn sync_badges_for_owner(program_id: &Pubkey, accounts: SyncBadgesAccounts) -> ProgramResult {
+    msg!("Syncing tier badges");
+
+    let SyncBadgesAccounts {
+        payer,
+        owner,
+        vault_account,
+        mint_config_account,
+        badges,
+    } = accounts;
+
+    // `None` for KUZA, whose tiers are the config's
+    let (mint_config, tier_amount) = if vault_account.data_is_empty() {
+        if Vault::find_address(owner.key, program_id).0 == *vault_account.key {
+            (None, 0)
+        } else {
+            let mint_config = load_registered_mint_config(program_id, mint_config_account)?;
+            if Vault::find_mint_address(owner.key, &mint_config.mint, program_id).0 != *vault_account.key {
+                return Err(ProgramError::InvalidSeeds);
+            }
+            (Some(mint_config), 0)
+        }
+    } else {
+        let vault = load_vault(program_id, owner.key, vault_account)?;
+        let now = vault.now(&Clock::get()?)?;
+        let mint_config = vault
+            .registered_mint()
+            .map(|mint| load_mint_config(program_id, &badges.config, &mint, mint_config_account))
+            .transpose()?;
+        (mint_config, vault.tier_amount(now))
+    };
+    msg!("Amount toward tiers: {}", tier_amount);
+
+    let registered_mint = mint_config.as_ref().map(|mint_config| mint_config.mint);
+    let (tiers, badge_bumps) = match &mint_config {
+        Some(mint_config) => (mint_config.active_access_tiers(), &mint_config.badge_bumps),
+        None => (badges.config.active_access_tiers(), &badges.config.badge_bumps),
+    };
+
+    sync_badges(program_id, &badges, owner, registered_mint.as_ref(), tiers, badge_bumps, tier_amount, Some(&payer))
+}
@@ -0,0 +1,181 @@
+use {
+    crate::{
+        error::VaultError,
+        instruction::{
+            check_user_token_account, check_vault_token_account, credit_completed_lock, load_config, load_mint_config,
+            load_or_create_loyalty, load_vault, record_activity, release, save_loyalty, sync_badges, BadgeAccounts,
+        },
+        math::elapsed,
+        state::{ActivityKind, Config, MintConfig, Vault, VaultKind, VaultStatus},
+        utils::{check_program, check_signer, check_writable},
+    },
+    solana_program::{
+        account_info::AccountInfo,
+        clock::Clock,
+        entrypoint::ProgramResult,
+        msg,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        system_program,
+        sysvar::Sysvar,
+    },
+};
+
+/// Accounts for `Claim`. The destination must be the owner's token account
+/// for the vault's mint, the stats account the shard the owner's vaults are
+/// counted in, and the activity account the vault's activity log. The owner
+/// pays for their loyalty record if they don't have one yet. The badge
+/// group may follow, with the mint config of the vault's mint holding the
+/// badge mints' bumps; without it the owner's badges wait for `SyncBadges`.
+pub struct ClaimAccounts<'a, 'b> {
+    pub owner: &'b AccountInfo<'a>,
+    pub vault_account: &'b AccountInfo<'a>,
+    pub vault_ata: &'b AccountInfo<'a>,
+    pub destination: &'b AccountInfo<'a>,
+    pub token_program: &'b AccountInfo<'a>,
+    pub stats_account: &'b AccountInfo<'a>,
+    pub activity_account: &'b AccountInfo<'a>,
+    pub loyalty_account: &'b AccountInfo<'a>,
+    pub system_program: &'b AccountInfo<'a>,
+    pub badges: Option<(BadgeAccounts<'a, 'b>, MintConfig)>,
+    pub config: Config,
+    pub vault: Vault,
+}
+
+impl<'a, 'b> ClaimAccounts<'a, 'b> {
+    pub fn try_from(program_id: &Pubkey, accounts: &'b [AccountInfo<'a>]) -> Result<Self, ProgramError> {
+        let [
+            owner,
+            vault_account,
+            vault_ata,
+            destination,
+            token_program,
+            stats_account,
+            activity_account,
+            config_account,
+            loyalty_account,
+            system_program,
+            mint_config_account,
+            remaining_accounts @ ..,
+        ] = accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        check_signer(owner)?;
+
+        let writable = [owner, vault_account, vault_ata, destination, stats_account, activity_account, loyalty_account];
+        for account in writable {
+            check_writable(account)?;
+        }
+
+        check_program(token_program, &spl_token::id())?;
+        check_program(system_program, &system_program::id())?;
+
+        let config = load_config(program_id, config_account)?;
+
+        let vault = load_vault(program_id, owner.key, vault_account)?;
+        let mint = check_vault_token_account(vault_account, &vault, vault_ata)?;
+        check_user_token_account(destination, owner.key, &mint)?;
+
+        let (_, badges) = BadgeAccounts::from_tail(program_id, remaining_accounts)?;
+        let badges = match badges {
+            Some(badges) => {
+                let mint_config = load_mint_config(program_id, &badges.config, &mint, mint_config_account)?;
+                Some((badges, mint_config))
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            owner,
+            vault_account,
+            vault_ata,
+            destination,
+            token_program,
+            stats_account,
+            activity_account,
+            loyalty_account,
+            system_program,
+            badges,
+            config,
+            vault,
+        })
+    }
+}
+
+/// Fails unless the vault can be taken out without a fee at `now`: it has
+/// matured, or it is a cooldown vault whose cooldown has passed.
+pub fn check_claimable(vault: &mut Vault, now: u64) -> ProgramResult {
+    vault.mature(now)?;
+
+    match vault.status()? {
+        VaultStatus::Matured => {}
+        VaultStatus::Locked if vault.kind()? == VaultKind::Cooldown => {
+            msg!("Cooldown vaults need RequestUnlock before they can be claimed");
+            return Err(VaultError::LockNotExpired.into());
+        }
+        VaultStatus::Locked => {
+            msg!("Vault unlocks at {}", vault.unlock_timestamp(now));
+            return Err(VaultError::LockNotExpired.into());
+        }
+        VaultStatus::Unlocking if now < vault.cooldown_ends_at() => {
+            msg!("Vault can be claimed from {}", vault.cooldown_ends_at());
+            return Err(VaultError::CooldownActive.into());
+        }
+        VaultStatus::Unlocking => {}
+        _ => vault.check_transition(VaultStatus::Released)?,
+    }
+
+    Ok(())
+}
+
+/// Releases a matured vault, or a cooldown vault whose cooldown has passed,
+/// to its owner and credits the completed lock to their loyalty record.
+/// Unlike `withdraw` there is no fee to pay, so no fee or referral accounts
+/// are involved.
+pub fn claim(program_id: &Pubkey, accounts: ClaimAccounts) -> ProgramResult {
+    msg!("Claiming a matured vault");
+
+    let ClaimAccounts {
+        owner,
+        vault_account,
+        vault_ata,
+        destination,
+        token_program,
+        stats_account,
+        activity_account,
+        loyalty_account,
+        system_program,
+        badges,
+        config,
+        mut vault,
+    } = accounts;
+
+    if vault.has_receipts() {
+        msg!("Receipt holders take this vault out with Redeem");
+        return Err(VaultError::ReceiptsIssued.into());
+    }
+
+    let now = vault.now(&Clock::get()?)?;
+    check_claimable(&mut vault, now)?;
+
+    release(program_id, vault_account, vault_ata, destination, token_program, stats_account, now)?;
+
+    // Loyalty counts seconds, whatever the vault's time basis
+    let time_locked = elapsed(now, u64::from(vault.deposit_timestamp));
+    let time_locked = vault.time_basis()?.to_seconds(time_locked, config.slots_per_day);
+    let mut loyalty = load_or_create_loyalty(program_id, owner, loyalty_account, system_program)?;
+    credit_completed_lock(&mut loyalty, time_locked)?;
+    save_loyalty(&loyalty, loyalty_account)?;
+
+    let amount = u64::from(vault.amount_locked);
+    record_activity(program_id, vault_account, activity_account, ActivityKind::Claim, amount, 0)?;
+
+    if let Some((badges, mint_config)) = badges {
+        let badge_bumps = &mint_config.badge_bumps;
+        sync_badges(program_id, &badges, owner, vault.registered_mint().as_ref(), &[], badge_bumps, 0, None)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,261 @@
+use {
+    crate::{
+        error::VaultError,
+        fee::{validate_fee_shares, validate_loyalty_tiers, BPS_DENOMINATOR},
+        state::{Config, FeeShare, LoyaltyTier},
+        utils::{check_program, check_signer, check_writable, create_pda_account},
+    },
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::AccountInfo,
+        bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+        entrypoint::ProgramResult,
+        msg,
+        program_error::ProgramError,
+        program_utils::limited_deserialize,
+        pubkey::Pubkey,
+        system_program,
+    },
+};
+
+/// Reads the config account and checks it is the program's config PDA.
+pub fn load_config(program_id: &Pubkey, config_account: &AccountInfo) -> Result<Config, ProgramError> {
+    if config_account.owner != program_id {
+        msg!("Config account is not owned by the program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let config = Config::try_from_slice(&config_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    let expected = Pubkey::create_program_address(
+        &[Config::SEED_PREFIX.as_bytes(), &[config.bump]],
+        program_id,
+    )?;
+
+    if expected != *config_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    Ok(config)
+}
+
+fn apply_fee_shares(config: &mut Config, fee_shares: &[FeeShare], dust_recipient: u8) -> ProgramResult {
+    validate_fee_shares(fee_shares, dust_recipient)?;
+
+    config.fee_shares = [FeeShare::default(); Config::MAX_FEE_RECIPIENTS];
+    config.fee_shares[..fee_shares.len()].copy_from_slice(fee_shares);
+    config.fee_share_count = fee_shares.len() as u8;
+    config.dust_recipient = dust_recipient;
+
+    Ok(())
+}
+
+/// Fails unless `admin` is the upgrade authority recorded in the program's
+/// ProgramData account, so only whoever deployed the program can claim it.
+fn check_upgrade_authority(program_id: &Pubkey, admin: &AccountInfo, program_data: &AccountInfo) -> ProgramResult {
+    if *program_data.key != bpf_loader_upgradeable::get_program_data_address(program_id)
+        || *program_data.owner != bpf_loader_upgradeable::id()
+    {
+        msg!("Account {} is not the program's ProgramData account", program_data.key);
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let metadata_len = UpgradeableLoaderState::size_of_programdata_metadata() as u64;
+    let upgrade_authority = match limited_deserialize(&program_data.data.borrow(), metadata_len) {
+        Ok(UpgradeableLoaderState::ProgramData { upgrade_authority_address, .. }) => upgrade_authority_address,
+        _ => return Err(ProgramError::InvalidAccountData),
+    };
+
+    if upgrade_authority != Some(*admin.key) {
+        msg!("{} is not the program's upgrade authority", admin.key);
+        return Err(VaultError::Unauthorized.into());
+    }
+
+    Ok(())
+}
+
+/// Accounts for `InitializeConfig`. The admin must be the program's upgrade
+/// authority, read from its ProgramData account.
+pub struct InitializeConfigAccounts<'a, 'b> {
+    pub admin: &'b AccountInfo<'a>,
+    pub config_account: &'b AccountInfo<'a>,
+    pub system_program: &'b AccountInfo<'a>,
+    pub config_bump: u8,
+}
+
+impl<'a, 'b> InitializeConfigAccounts<'a, 'b> {
+    pub fn try_from(program_id: &Pubkey, accounts: &'b [AccountInfo<'a>]) -> Result<Self, ProgramError> {
+        let [admin, config_account, system_program, program_data, ..] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        check_signer(admin)?;
+        check_writable(admin)?;
+        check_writable(config_account)?;
+        check_program(system_program, &system_program::id())?;
+        check_upgrade_authority(program_id, admin, program_data)?;
+
+        let (config_pda, config_bump) = Config::find_address(program_id);
+
+        if config_pda != *config_account.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        if !config_account.data_is_empty() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        Ok(Self {
+            admin,
+            config_account,
+            system_program,
+            config_bump,
+        })
+    }
+}
+
+/// Creates the config with the program's upgrade authority as admin.
+pub fn initialize_config(
+    program_id: &Pubkey,
+    accounts: InitializeConfigAccounts,
+    fee_shares: Vec<FeeShare>,
+    dust_recipient: u8,
+) -> ProgramResult {
+    msg!("Initializing the program config");
+
+    let bump = accounts.config_bump;
+
+    let mut config = Config::new(*accounts.admin.key, bump);
+    apply_fee_shares(&mut config, &fee_shares, dust_recipient)?;
+
+    create_pda_account(
+        accounts.admin,
+        accounts.config_account,
+        accounts.system_program,
+        program_id,
+        Config::LEN,
+        &[Config::SEED_PREFIX.as_bytes(), &[bump]],
+    )?;
+
+    config.serialize(&mut &mut accounts.config_account.data.borrow_mut()[..])?;
+
+    msg!("Config initialized with {} fee recipients", config.fee_share_count);
+    Ok(())
+}
+
+/// Loads the config and checks that `admin` signed and is the configured admin.
+pub fn load_config_as_admin(
+    program_id: &Pubkey,
+    admin: &AccountInfo,
+    config_account: &AccountInfo,
+) -> Result<Config, ProgramError> {
+    check_signer(admin)?;
+
+    let config = load_config(program_id, config_account)?;
+
+    if config.admin != *admin.key {
+        return Err(VaultError::Unauthorized.into());
+    }
+
+    Ok(config)
+}
+
+/// Accounts for the admin instructions that rewrite the config:
+/// `SetFeeShares`, `SetReferralShare`, `SetLoyaltyTiers` and `SetCooldown`.
+pub struct UpdateConfigAccounts<'a, 'b> {
+    pub admin: &'b AccountInfo<'a>,
+    pub config_account: &'b AccountInfo<'a>,
+    pub config: Config,
+}
+
+impl<'a, 'b> UpdateConfigAccounts<'a, 'b> {
+    pub fn try_from(program_id: &Pubkey, accounts: &'b [AccountInfo<'a>]) -> Result<Self, ProgramError> {
+        let [admin, config_account, ..] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        check_signer(admin)?;
+        check_writable(config_account)?;
+
+        let config = load_config_as_admin(program_id, admin, config_account)?;
+
+        Ok(Self {
+            admin,
+            config_account,
+            config,
+        })
+    }
+
+    fn save(&self) -> ProgramResult {
+        self.config.serialize(&mut &mut self.config_account.data.borrow_mut()[..])?;
+        Ok(())
+    }
+}
+
+pub fn set_fee_shares(
+    mut accounts: UpdateConfigAccounts,
+    fee_shares: Vec<FeeShare>,
+    dust_recipient: u8,
+) -> ProgramResult {
+    msg!("Updating the fee split");
+
+    apply_fee_shares(&mut accounts.config, &fee_shares, dust_recipient)?;
+    accounts.save()?;
+
+    msg!("Fee split updated to {} recipients", accounts.config.fee_share_count);
+    Ok(())
+}
+
+pub fn set_referral_share(mut accounts: UpdateConfigAccounts, referral_bps: u16) -> ProgramResult {
+    msg!("Updating the referral share");
+
+    if referral_bps as u64 > BPS_DENOMINATOR {
+        return Err(VaultError::InvalidBps.into());
+    }
+
+    accounts.config.referral_bps = referral_bps;
+    accounts.save()?;
+
+    msg!("Referral share set to {} bps", referral_bps);
+    Ok(())
+}
+
+pub fn set_loyalty_tiers(mut accounts: UpdateConfigAccounts, loyalty_tiers: Vec<LoyaltyTier>) -> ProgramResult {
+    msg!("Updating the loyalty discount table");
+
+    validate_loyalty_tiers(&loyalty_tiers)?;
+
+    let config = &mut accounts.config;
+    config.loyalty_tiers = [LoyaltyTier::default(); Config::MAX_LOYALTY_TIERS];
+    config.loyalty_tiers[..loyalty_tiers.len()].copy_from_slice(&loyalty_tiers);
+    config.loyalty_tier_count = loyalty_tiers.len() as u8;
+    accounts.save()?;
+
+    msg!("Loyalty table set to {} tiers", accounts.config.loyalty_tier_count);
+    Ok(())
+}
+
+pub fn set_cooldown(mut accounts: UpdateConfigAccounts, cooldown_duration: u64) -> ProgramResult {
+    msg!("Updating the unlock cooldown");
+
+    accounts.config.cooldown_duration = cooldown_duration;
+    accounts.save()?;
+
+    msg!("Unlock cooldown set to {} seconds", cooldown_duration);
+    Ok(())
+}
+
+pub fn set_slots_per_day(mut accounts: UpdateConfigAccounts, slots_per_day: u64) -> ProgramResult {
+    msg!("Updating the slot rate of slot-based vaults");
+
+    if slots_per_day == 0 {
+        return Err(VaultError::InvalidSlotsPerDay.into());
+    }
+
+    accounts.config.slots_per_day = slots_per_day;
+    accounts.save()?;
+
+    msg!("Slots per day set to {}", slots_per_day);
+    Ok(())
+}
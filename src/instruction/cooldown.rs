@@ -0,0 +1,117 @@
+use {
+    crate::{
+        error::VaultError,
+        instruction::{load_config, load_vault, record_activity},
+        state::{ActivityKind, Config, Vault, VaultKind, VaultStatus},
+        utils::{check_signer, check_writable},
+    },
+    solana_program::{
+        account_info::AccountInfo,
+        clock::Clock,
+        entrypoint::ProgramResult,
+        msg,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        sysvar::Sysvar,
+    },
+};
+
+/// Accounts for `RequestUnlock`. The activity account is the vault's
+/// activity log.
+pub struct RequestUnlockAccounts<'a, 'b> {
+    pub owner: &'b AccountInfo<'a>,
+    pub vault_account: &'b AccountInfo<'a>,
+    pub activity_account: &'b AccountInfo<'a>,
+    pub config: Config,
+}
+
+impl<'a, 'b> RequestUnlockAccounts<'a, 'b> {
+    pub fn try_from(program_id: &Pubkey, accounts: &'b [AccountInfo<'a>]) -> Result<Self, ProgramError> {
+        let [owner, vault_account, config_account, activity_account, ..] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        check_signer(owner)?;
+        check_writable(vault_account)?;
+        check_writable(activity_account)?;
+
+        let config = load_config(program_id, config_account)?;
+        load_vault(program_id, owner.key, vault_account)?.check_kind(VaultKind::Cooldown)?;
+
+        Ok(Self {
+            owner,
+            vault_account,
+            activity_account,
+            config,
+        })
+    }
+}
+
+/// Starts the cooldown of a locked cooldown vault. The cooldown length is
+/// taken from the config now, so later config changes don't move it.
+pub fn request_unlock(program_id: &Pubkey, accounts: RequestUnlockAccounts) -> ProgramResult {
+    msg!("Requesting unlock");
+
+    let mut vault_data = accounts.vault_account.data.borrow_mut();
+    let vault = Vault::load_mut(&mut vault_data)?;
+    let now = vault.now(&Clock::get()?)?;
+
+    vault.transition(VaultStatus::Unlocking, now)?;
+    let config = &accounts.config;
+    vault.lock_duration = vault.time_basis()?.from_seconds(config.cooldown_duration, config.slots_per_day).into();
+
+    msg!("Vault can be claimed from {}", vault.cooldown_ends_at());
+    drop(vault_data);
+
+    let kind = ActivityKind::RequestUnlock;
+    record_activity(program_id, accounts.vault_account, accounts.activity_account, kind, 0, 0)
+}
+
+/// Accounts for `CancelUnlock`. The activity account is the vault's
+/// activity log.
+pub struct CancelUnlockAccounts<'a, 'b> {
+    pub owner: &'b AccountInfo<'a>,
+    pub vault_account: &'b AccountInfo<'a>,
+    pub activity_account: &'b AccountInfo<'a>,
+}
+
+impl<'a, 'b> CancelUnlockAccounts<'a, 'b> {
+    pub fn try_from(program_id: &Pubkey, accounts: &'b [AccountInfo<'a>]) -> Result<Self, ProgramError> {
+        let [owner, vault_account, activity_account, ..] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        check_signer(owner)?;
+        check_writable(vault_account)?;
+        check_writable(activity_account)?;
+
+        load_vault(program_id, owner.key, vault_account)?.check_kind(VaultKind::Cooldown)?;
+
+        Ok(Self {
+            owner,
+            vault_account,
+            activity_account,
+        })
+    }
+}
+
+/// Locks an unlocking cooldown vault again, dropping the pending cooldown.
+pub fn cancel_unlock(program_id: &Pubkey, accounts: CancelUnlockAccounts) -> ProgramResult {
+    msg!("Cancelling unlock");
+
+    let mut vault_data = accounts.vault_account.data.borrow_mut();
+    let vault = Vault::load_mut(&mut vault_data)?;
+    let now = vault.now(&Clock::get()?)?;
+
+    if vault.status()? != VaultStatus::Unlocking {
+        msg!("Vault is {:?}, not unlocking", vault.status()?);
+        return Err(VaultError::InvalidStatusTransition.into());
+    }
+
+    vault.transition(VaultStatus::Locked, now)?;
+    vault.lock_duration = VaultKind::Cooldown.lock_duration().into();
+    drop(vault_data);
+
+    let kind = ActivityKind::CancelUnlock;
+    record_activity(program_id, accounts.vault_account, accounts.activity_account, kind, 0, 0)
+}
@@ -0,0 +1,281 @@
+use {
+    crate::{
+        error::VaultError,
+        instruction::{
+            check_user_token_account, check_vault_token_account, credit_completed_lock, load_config,
+            load_config_as_admin, load_loyalty, load_vault, record_activity, release, save_loyalty,
+        },
+        math::{elapsed, CheckedMath},
+        state::{ActivityKind, Config, Treasury, Vault, VaultStatus},
+        utils::{check_owner, check_program, check_signer, check_writable, create_pda_account, transfer_lamports},
+    },
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::AccountInfo,
+        clock::Clock,
+        entrypoint::ProgramResult,
+        msg,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        system_program,
+        sysvar::{rent::Rent, Sysvar},
+    },
+    spl_associated_token_account::get_associated_token_address,
+};
+
+/// Reads the treasury account and checks it is the program's treasury PDA.
+pub fn load_treasury(program_id: &Pubkey, treasury_account: &AccountInfo) -> Result<Treasury, ProgramError> {
+    if treasury_account.owner != program_id {
+        msg!("Treasury account is not owned by the program; has SetCrankTip been run?");
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    let treasury = Treasury::try_from_slice(&treasury_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    let expected = Pubkey::create_program_address(
+        &[Treasury::SEED_PREFIX.as_bytes(), &[treasury.bump]],
+        program_id,
+    )?;
+
+    if expected != *treasury_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    Ok(treasury)
+}
+
+/// Accounts for `SetCrankTip`.
+pub struct SetCrankTipAccounts<'a, 'b> {
+    pub admin: &'b AccountInfo<'a>,
+    pub config_account: &'b AccountInfo<'a>,
+    pub treasury_account: &'b AccountInfo<'a>,
+    pub system_program: &'b AccountInfo<'a>,
+    pub treasury_bump: u8,
+}
+
+impl<'a, 'b> SetCrankTipAccounts<'a, 'b> {
+    pub fn try_from(program_id: &Pubkey, accounts: &'b [AccountInfo<'a>]) -> Result<Self, ProgramError> {
+        let [admin, config_account, treasury_account, system_program, ..] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        check_signer(admin)?;
+        check_writable(admin)?;
+        check_writable(treasury_account)?;
+        check_program(system_program, &system_program::id())?;
+
+        load_config_as_admin(program_id, admin, config_account)?;
+
+        let (treasury_pda, treasury_bump) = Treasury::find_address(program_id);
+        if treasury_pda != *treasury_account.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        Ok(Self {
+            admin,
+            config_account,
+            treasury_account,
+            system_program,
+            treasury_bump,
+        })
+    }
+}
+
+/// Sets the tip paid per released vault, creating the treasury on first use.
+pub fn set_crank_tip(program_id: &Pubkey, accounts: SetCrankTipAccounts, crank_tip_lamports: u64) -> ProgramResult {
+    msg!("Setting crank tip to {} lamports", crank_tip_lamports);
+
+    let bump = accounts.treasury_bump;
+
+    if accounts.treasury_account.data_is_empty() {
+        create_pda_account(
+            accounts.admin,
+            accounts.treasury_account,
+            accounts.system_program,
+            program_id,
+            Treasury::LEN,
+            &[Treasury::SEED_PREFIX.as_bytes(), &[bump]],
+        )?;
+    }
+
+    Treasury { crank_tip_lamports, bump }.serialize(&mut &mut accounts.treasury_account.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+/// One vault handed to `CrankRelease`, with the token account its balance
+/// is returned to, the stats shard its owner's vaults are counted in, its
+/// activity log and its owner's loyalty record.
+pub struct CrankTarget<'a, 'b> {
+    pub vault_account: &'b AccountInfo<'a>,
+    pub vault_ata: &'b AccountInfo<'a>,
+    pub owner_ata: &'b AccountInfo<'a>,
+    pub stats_account: &'b AccountInfo<'a>,
+    pub activity_account: &'b AccountInfo<'a>,
+    pub loyalty_account: &'b AccountInfo<'a>,
+    pub owner: Pubkey,
+}
+
+/// Accounts for `CrankRelease`. Every owner token account must be the vault
+/// owner's associated token account for the vault's mint. A native SOL
+/// vault has no token accounts to check; it is skipped when released.
+pub struct CrankReleaseAccounts<'a, 'b> {
+    pub cranker: &'b AccountInfo<'a>,
+    pub treasury_account: &'b AccountInfo<'a>,
+    pub token_program: &'b AccountInfo<'a>,
+    pub targets: Vec<CrankTarget<'a, 'b>>,
+    pub treasury: Treasury,
+    pub config: Config,
+}
+
+impl<'a, 'b> CrankReleaseAccounts<'a, 'b> {
+    pub fn try_from(program_id: &Pubkey, accounts: &'b [AccountInfo<'a>]) -> Result<Self, ProgramError> {
+        let [cranker, treasury_account, token_program, config_account, remaining @ ..] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        check_signer(cranker)?;
+        check_writable(cranker)?;
+        check_writable(treasury_account)?;
+        check_program(token_program, &spl_token::id())?;
+
+        let treasury = load_treasury(program_id, treasury_account)?;
+        let config = load_config(program_id, config_account)?;
+
+        // Vaults come as (vault, vault token account, owner token account, stats shard, activity log,
+        // loyalty record)
+        if remaining.len() % 6 != 0 {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+
+        let targets = remaining
+            .chunks_exact(6)
+            .map(|target| {
+                let (vault_account, vault_ata, owner_ata, stats_account, activity_account, loyalty_account) =
+                    (&target[0], &target[1], &target[2], &target[3], &target[4], &target[5]);
+
+                for account in [vault_account, vault_ata, owner_ata, stats_account, activity_account, loyalty_account] {
+                    check_writable(account)?;
+                }
+
+                // The stored owner is only trusted once the address is checked against it
+                check_owner(vault_account, program_id).map_err(|_| VaultError::VaultNotProgramOwned)?;
+                let owner = Vault::load(&vault_account.data.borrow())?.owner;
+                let vault = load_vault(program_id, &owner, vault_account)?;
+
+                if !vault.is_native() {
+                    let mint = check_vault_token_account(vault_account, &vault, vault_ata)?;
+                    check_user_token_account(owner_ata, &owner, &mint)?;
+
+                    if get_associated_token_address(&owner, &mint) != *owner_ata.key {
+                        msg!("Token account {} is not {}'s associated token account", owner_ata.key, owner);
+                        return Err(VaultError::InvalidUserTokenAccount.into());
+                    }
+                }
+
+                Ok(CrankTarget {
+                    vault_account,
+                    vault_ata,
+                    owner_ata,
+                    stats_account,
+                    activity_account,
+                    loyalty_account,
+                    owner,
+                })
+            })
+            .collect::<Result<_, ProgramError>>()?;
+
+        Ok(Self {
+            cranker,
+            treasury_account,
+            token_program,
+            targets,
+            treasury,
+            config,
+        })
+    }
+}
+
+/// Releases every matured vault in the batch to its owner and tips the
+/// cranker from the treasury. Vaults that are still locked or were already
+/// released are skipped, so a batch doesn't fail because an owner claimed
+/// first, and so are vaults with receipts and native SOL vaults, which
+/// their owners take out with `WithdrawSol`. Each release is credited to
+/// the owner's loyalty record as a completed lock; an owner without a record
+/// yet gets it at their next `Initialize` or `Withdraw`, since the crank
+/// has no one to charge for it. The tip is capped at what the treasury holds
+/// above its rent.
+pub fn crank_release(program_id: &Pubkey, accounts: CrankReleaseAccounts) -> ProgramResult {
+    msg!("Cranking {} vaults", accounts.targets.len());
+
+    let CrankReleaseAccounts {
+        cranker,
+        treasury_account,
+        token_program,
+        targets,
+        treasury,
+        config,
+    } = accounts;
+
+    let clock = Clock::get()?;
+    let mut released = 0u64;
+
+    for target in targets {
+        // Read afresh, since an earlier entry in the batch may be the same vault
+        let mut vault = *Vault::load(&target.vault_account.data.borrow())?;
+        let now = vault.now(&clock)?;
+        vault.mature(now)?;
+
+        // SOL vaults have no token account to release into
+        if vault.is_native() {
+            msg!("Skipping vault {}: holds native SOL", target.vault_account.key);
+            continue;
+        }
+
+        // The owner may not hold the receipts, so only a holder can redeem
+        if vault.has_receipts() {
+            msg!("Skipping vault {}: held as receipts", target.vault_account.key);
+            continue;
+        }
+
+        if vault.status()? != VaultStatus::Matured {
+            msg!("Skipping vault {}: {:?}", target.vault_account.key, vault.status()?);
+            continue;
+        }
+
+        release(
+            program_id,
+            target.vault_account,
+            target.vault_ata,
+            target.owner_ata,
+            token_program,
+            target.stats_account,
+            now,
+        )?;
+
+        if let Some(mut loyalty) = load_loyalty(program_id, &target.owner, target.loyalty_account)? {
+            let time_locked = elapsed(now, u64::from(vault.deposit_timestamp));
+            credit_completed_lock(&mut loyalty, vault.time_basis()?.to_seconds(time_locked, config.slots_per_day))?;
+            save_loyalty(&loyalty, target.loyalty_account)?;
+        } else {
+            msg!("Owner {} has no loyalty record to credit", target.owner);
+        }
+
+        let (vault_account, activity_account) = (target.vault_account, target.activity_account);
+        let amount = u64::from(vault.amount_locked);
+        record_activity(program_id, vault_account, activity_account, ActivityKind::Crank, amount, 0)?;
+        released = released.try_add(1)?;
+    }
+
+    let reserve = Rent::get()?.minimum_balance(treasury_account.data_len());
+    let available = treasury_account.lamports().saturating_sub(reserve);
+    let tip = treasury.crank_tip_lamports.saturating_mul(released).min(available);
+
+    if tip > 0 {
+        transfer_lamports(treasury_account, cranker, tip)?;
+    }
+
+    msg!("Released {} vaults; tipped {} lamports", released, tip);
+    Ok(())
+}
@@ -1,8 +1,17 @@
 use {
-    crate::state::Vault, 
-    borsh::{BorshDeserialize, BorshSerialize}, 
+    crate::{
+        error::VaultError,
+        instruction::{
+            check_mint_enabled, check_receipt_mint, check_user_token_account, check_vault_token_account,
+            create_activity_log, load_config, load_mint_config, load_or_create_stats, load_vault, mint_receipts,
+            record_activity, save_stats, sync_badges, BadgeAccounts, BadgePayer, ReceiptAccounts,
+        },
+        math::CheckedMath,
+        state::{ActivityKind, Config, MintConfig, Vault, VaultStatus},
+        utils::{check_program, check_signer, check_writable},
+    },
     solana_program::{
-        account_info::{next_account_info, AccountInfo},
+        account_info::AccountInfo,
         clock::Clock,
         entrypoint::ProgramResult,
         msg,
@@ -11,66 +20,149 @@ use {
         pubkey::Pubkey,
         sysvar::Sysvar,
         system_program,
-    }, spl_token::instruction as token_instruction
+    },
+    spl_token::instruction as token_instruction,
 };
 
-pub fn deposit(
-    program_id: &Pubkey, 
-    accounts: &[AccountInfo], 
-    amount: u64
-) -> ProgramResult {
-    msg!("Depositing funds into the vault");
-
-    let account_info_iter = &mut accounts.iter();
-
-    let initializer = next_account_info(account_info_iter)?;
-    let vault_account = next_account_info(account_info_iter)?;
-    let vault_ata = next_account_info(account_info_iter)?;
-    let user_token_account = next_account_info(account_info_iter)?;
-    let _ = next_account_info(account_info_iter)?;
-    let _ = next_account_info(account_info_iter)?;
-    let system_program = next_account_info(account_info_iter)?;
-    let token_program = next_account_info(account_info_iter)?;
-    let _ = next_account_info(account_info_iter)?;
-    let _ = next_account_info(account_info_iter)?;
-
-    msg!("Successfully retrieved all accounts");
-    
-    if !initializer.is_signer {
-        return Err(ProgramError::MissingRequiredSignature);
-    }
-
-    if *token_program.key != spl_token::id() {
-        return Err(ProgramError::IncorrectProgramId);
-    }
-    
-    if *system_program.key != system_program::id() {
-        return Err(ProgramError::IncorrectProgramId);
-    }
-
-    // Derive PDA for vault
-    let (pda, _) = Pubkey::find_program_address(
-        &[
-            Vault::SEED_PREFIX.as_bytes(),
-            initializer.key.as_ref(),
-        ],
-        program_id
-    );
-
-    if pda != *vault_account.key {
-        return Err(ProgramError::InvalidSeeds);
+/// Accounts for `Deposit`. The rent sysvar is part of the instruction but
+/// not used, and the mint config is only read for a registered mint. The
+/// stats account is the shard the owner's vaults are counted in, and the
+/// activity account the vault's activity log, created if the vault predates
+/// the logs. A vault
+/// with receipts also takes its receipt mint and the owner's receipt account,
+/// and the badge group comes last once the mint has access tiers.
+pub struct DepositAccounts<'a, 'b> {
+    pub initializer: &'b AccountInfo<'a>,
+    pub vault_account: &'b AccountInfo<'a>,
+    pub vault_ata: &'b AccountInfo<'a>,
+    pub user_token_account: &'b AccountInfo<'a>,
+    pub system_program: &'b AccountInfo<'a>,
+    pub token_program: &'b AccountInfo<'a>,
+    pub associated_token_program: &'b AccountInfo<'a>,
+    pub stats_account: &'b AccountInfo<'a>,
+    pub activity_account: &'b AccountInfo<'a>,
+    pub receipts: Option<ReceiptAccounts<'a, 'b>>,
+    pub badges: Option<BadgeAccounts<'a, 'b>>,
+    pub config: Config,
+    pub mint_config: MintConfig,
+    pub vault: Vault,
+}
+
+impl<'a, 'b> DepositAccounts<'a, 'b> {
+    pub fn try_from(program_id: &Pubkey, accounts: &'b [AccountInfo<'a>]) -> Result<Self, ProgramError> {
+        let [
+            initializer,
+            vault_account,
+            vault_ata,
+            user_token_account,
+            config_account,
+            token_mint,
+            system_program,
+            token_program,
+            associated_token_program,
+            _rent_sysvar,
+            mint_config_account,
+            stats_account,
+            activity_account,
+            remaining_accounts @ ..,
+        ] = accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        check_signer(initializer)?;
+
+        // The owner pays for any badge account the deposit earns them
+        for account in [initializer, vault_account, vault_ata, user_token_account, stats_account, activity_account] {
+            check_writable(account)?;
+        }
+
+        check_program(token_program, &spl_token::id())?;
+        check_program(system_program, &system_program::id())?;
+        check_program(associated_token_program, &spl_associated_token_account::id())?;
+
+        let vault = load_vault(program_id, initializer.key, vault_account)?;
+
+        let mint = check_vault_token_account(vault_account, &vault, vault_ata)?;
+
+        if *token_mint.key != mint {
+            msg!("The vault holds {}, not {}", mint, token_mint.key);
+            return Err(VaultError::InvalidMint.into());
+        }
+
+        check_user_token_account(user_token_account, initializer.key, &mint)?;
+
+        let config = load_config(program_id, config_account)?;
+        let mint_config = load_mint_config(program_id, &config, &mint, mint_config_account)?;
+        check_mint_enabled(&mint_config)?;
+
+        let (receipt_accounts, badges) =
+            BadgeAccounts::require_from_tail(program_id, &mint_config, remaining_accounts)?;
+
+        let receipts = if vault.has_receipts() {
+            let receipts = ReceiptAccounts::from_slice(receipt_accounts)?;
+            check_writable(receipts.receipt_mint)?;
+            check_writable(receipts.receipt_account)?;
+            check_receipt_mint(program_id, vault_account, &vault, receipts.receipt_mint)?;
+            Some(receipts)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            initializer,
+            vault_account,
+            vault_ata,
+            user_token_account,
+            system_program,
+            token_program,
+            associated_token_program,
+            stats_account,
+            activity_account,
+            receipts,
+            badges,
+            config,
+            mint_config,
+            vault,
+        })
     }
+}
 
+pub fn deposit(program_id: &Pubkey, accounts: DepositAccounts, amount: u64) -> ProgramResult {
+    msg!("Depositing funds into the vault");
 
-    if *vault_ata.owner != spl_token::id() {
-        msg!("Vault ATA is not owned by the vault");
-        return Err(ProgramError::InvalidAccountData);
+    let DepositAccounts {
+        initializer,
+        vault_account,
+        vault_ata,
+        user_token_account,
+        system_program,
+        token_program,
+        associated_token_program,
+        stats_account,
+        activity_account,
+        receipts,
+        badges,
+        config,
+        mint_config,
+        mut vault,
+    } = accounts;
+
+    // Check the status up front so a rejected deposit never moves tokens
+    let time_basis = vault.time_basis()?;
+    let now = time_basis.now(&Clock::get()?);
+    vault.mature(now)?;
+    vault.check_transition(VaultStatus::Locked)?;
+
+    // Receipt holders bought into the running term, so adding to a vault
+    // with receipts out joins that term instead of restarting it
+    let joins_term = vault.has_receipts() && vault.status()? != VaultStatus::Released;
+
+    if joins_term && vault.status()? != VaultStatus::Locked {
+        msg!("Receipt holders can redeem this vault; it can't be locked again under them");
+        return Err(VaultError::ReceiptsIssued.into());
     }
 
-    let mut vault_data = vault_account.data.borrow_mut();
-    let mut vault = Vault::deserialize(&mut &vault_data[..])?;
-
-
     msg!("Depositing {} tokens", amount);
 
     let transfer_instruction = token_instruction::transfer(
@@ -93,16 +185,55 @@ pub fn deposit(
         ],
     )?;
 
-    vault.amount_locked += amount;
-    vault.deposit_timestamp = Clock::get()?.unix_timestamp as u64;
-    vault.is_locked = true;
-    vault.lock_duration = Vault::LOCK_DURATION;
+    if let Some(receipts) = receipts {
+        mint_receipts(
+            vault_account,
+            &vault,
+            receipts.receipt_mint,
+            receipts.receipt_account,
+            token_program,
+            amount,
+        )?;
+    }
+
+    let mut stats = load_or_create_stats(program_id, initializer.key, initializer, stats_account, system_program)?;
+
+    // Borrow the vault only once the CPIs have returned
+    let mut vault_data = vault_account.data.borrow_mut();
+    let vault = Vault::load_mut(&mut vault_data)?;
+
+    stats.count_lock(vault, amount)?;
+    save_stats(&stats, stats_account)?;
 
-    // Serialize back into the same borrowed data
-    vault.serialize(&mut &mut vault_data[..])?;
+    vault.amount_locked = u64::from(vault.amount_locked).try_add(amount)?.into();
+    if !joins_term {
+        vault.deposit_timestamp = now.into();
+        let lock_duration = mint_config.lock_duration_for(vault.kind()?);
+        vault.lock_duration = time_basis.from_seconds(lock_duration, config.slots_per_day).into();
+    }
+    vault.transition(VaultStatus::Locked, now)?;
 
     msg!("Successfully deposited {} tokens and updated the vault", amount);
 
     msg!("Vault data: {:?}", vault);
+
+    let tier_amount = vault.tier_amount(now);
+    let registered_mint = vault.registered_mint();
+    drop(vault_data);
+
+    create_activity_log(program_id, initializer, vault_account, activity_account, system_program)?;
+    record_activity(program_id, vault_account, activity_account, ActivityKind::Deposit, amount, 0)?;
+
+    if let Some(badges) = badges {
+        let payer = BadgePayer {
+            payer: initializer,
+            system_program,
+            associated_token_program,
+        };
+        let (tiers, badge_bumps) = (mint_config.active_access_tiers(), &mint_config.badge_bumps);
+        let mint = registered_mint.as_ref();
+        sync_badges(program_id, &badges, initializer, mint, tiers, badge_bumps, tier_amount, Some(&payer))?;
+    }
+
     Ok(())
 }
\ No newline at end of file
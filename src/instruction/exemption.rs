@@ -0,0 +1,178 @@
+use {
+    crate::{
+        instruction::load_config_as_admin,
+        state::{FeeExemption, FeeExemptionReason},
+        utils::{check_program, check_signer, check_writable, close_account, create_pda_account},
+    },
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::AccountInfo,
+        clock::Clock,
+        entrypoint::ProgramResult,
+        msg,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        system_program,
+        sysvar::Sysvar,
+    },
+};
+
+/// Whether `wallet` currently holds a fee exemption. The exemption account is
+/// always the wallet's exemption PDA, checked against `exemption_bump`; when
+/// it is empty the wallet pays fees.
+pub fn is_fee_exempt(
+    program_id: &Pubkey,
+    wallet: &Pubkey,
+    exemption_bump: u8,
+    exemption_account: &AccountInfo,
+) -> Result<bool, ProgramError> {
+    if FeeExemption::create_address(wallet, exemption_bump, program_id)? != *exemption_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    if exemption_account.data_is_empty() {
+        return Ok(false);
+    }
+
+    if exemption_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    // `expires_at` is an Option, so a record without expiry doesn't fill the account
+    let exemption = FeeExemption::deserialize(&mut &exemption_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    let exempt = exemption.is_active(Clock::get()?.unix_timestamp);
+    if exempt {
+        msg!("Wallet {} is fee exempt ({:?})", wallet, exemption.reason);
+    }
+
+    Ok(exempt)
+}
+
+/// Accounts for `SetFeeExemption`. The exemption account must be `wallet`'s
+/// exemption PDA.
+pub struct SetFeeExemptionAccounts<'a, 'b> {
+    pub admin: &'b AccountInfo<'a>,
+    pub config_account: &'b AccountInfo<'a>,
+    pub exemption_account: &'b AccountInfo<'a>,
+    pub system_program: &'b AccountInfo<'a>,
+    pub exemption_bump: u8,
+}
+
+impl<'a, 'b> SetFeeExemptionAccounts<'a, 'b> {
+    pub fn try_from(
+        program_id: &Pubkey,
+        accounts: &'b [AccountInfo<'a>],
+        wallet: &Pubkey,
+    ) -> Result<Self, ProgramError> {
+        let [admin, config_account, exemption_account, system_program, ..] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        check_signer(admin)?;
+        check_writable(admin)?;
+        check_writable(exemption_account)?;
+        check_program(system_program, &system_program::id())?;
+
+        load_config_as_admin(program_id, admin, config_account)?;
+
+        let (exemption_pda, exemption_bump) = FeeExemption::find_address(wallet, program_id);
+        if exemption_pda != *exemption_account.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        Ok(Self {
+            admin,
+            config_account,
+            exemption_account,
+            system_program,
+            exemption_bump,
+        })
+    }
+}
+
+/// Grants or updates a wallet's fee exemption.
+pub fn set_fee_exemption(
+    program_id: &Pubkey,
+    accounts: SetFeeExemptionAccounts,
+    wallet: Pubkey,
+    expires_at: Option<i64>,
+    reason: FeeExemptionReason,
+) -> ProgramResult {
+    msg!("Setting fee exemption for {}", wallet);
+
+    let bump = accounts.exemption_bump;
+
+    if accounts.exemption_account.data_is_empty() {
+        create_pda_account(
+            accounts.admin,
+            accounts.exemption_account,
+            accounts.system_program,
+            program_id,
+            FeeExemption::LEN,
+            &[FeeExemption::SEED_PREFIX.as_bytes(), wallet.as_ref(), &[bump]],
+        )?;
+    }
+
+    FeeExemption {
+        wallet,
+        expires_at,
+        reason,
+        bump,
+    }
+    .serialize(&mut &mut accounts.exemption_account.data.borrow_mut()[..])?;
+
+    msg!("Fee exemption for {} expires at {:?}", wallet, expires_at);
+    Ok(())
+}
+
+/// Accounts for `RemoveFeeExemption`. The exemption account must be `wallet`'s
+/// existing exemption record.
+pub struct RemoveFeeExemptionAccounts<'a, 'b> {
+    pub admin: &'b AccountInfo<'a>,
+    pub config_account: &'b AccountInfo<'a>,
+    pub exemption_account: &'b AccountInfo<'a>,
+}
+
+impl<'a, 'b> RemoveFeeExemptionAccounts<'a, 'b> {
+    pub fn try_from(
+        program_id: &Pubkey,
+        accounts: &'b [AccountInfo<'a>],
+        wallet: &Pubkey,
+    ) -> Result<Self, ProgramError> {
+        let [admin, config_account, exemption_account, ..] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        check_signer(admin)?;
+        check_writable(admin)?;
+        check_writable(exemption_account)?;
+
+        load_config_as_admin(program_id, admin, config_account)?;
+
+        if exemption_account.owner != program_id {
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        let exemption = FeeExemption::deserialize(&mut &exemption_account.data.borrow()[..])
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+
+        if FeeExemption::create_address(wallet, exemption.bump, program_id)? != *exemption_account.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        Ok(Self {
+            admin,
+            config_account,
+            exemption_account,
+        })
+    }
+}
+
+/// Revokes a wallet's fee exemption and returns the rent to the admin.
+pub fn remove_fee_exemption(accounts: RemoveFeeExemptionAccounts, wallet: Pubkey) -> ProgramResult {
+    msg!("Removing fee exemption for {}", wallet);
+
+    close_account(accounts.exemption_account, accounts.admin)
+}
@@ -1,113 +1,254 @@
 use {
-    crate::state::Vault, 
-    borsh::BorshSerialize, 
+    crate::{
+        error::VaultError,
+        fee::{apply_discount, distribute_sol, referral_cut},
+        instruction::{
+            check_mint_enabled, check_user_token_account, is_fee_exempt, is_kuza_mint, load_config,
+            create_activity_log, create_receipt_mint, load_mint_config, load_or_create_loyalty, load_or_create_stats,
+            mint_receipts, pay_referrer, record_activity, record_referral, save_loyalty, save_stats, sync_badges,
+            BadgeAccounts, BadgePayer, ReceiptAccounts, ReferralAccounts,
+        },
+        math::{to_base_units, CheckedMath},
+        state::{ActivityKind, Config, MintConfig, TimeBasis, Vault, VaultKind, VaultStatus},
+        utils::{check_program, check_signer, check_writable},
+    },
     solana_program::{
-        account_info::{next_account_info, AccountInfo}, 
+        account_info::AccountInfo, 
         entrypoint::ProgramResult, 
         msg,
         clock::Clock, 
         program::{invoke, invoke_signed}, 
         program_error::ProgramError, 
+        program_pack::Pack,
         pubkey::Pubkey, 
         system_instruction, 
         sysvar::{rent::Rent, Sysvar},
         system_program,
     }, 
-    spl_associated_token_account::instruction::create_associated_token_account, 
-    spl_token::instruction as token_instruction
+    spl_associated_token_account::{
+        get_associated_token_address, instruction::create_associated_token_account,
+    },
+    spl_token::{instruction as token_instruction, state::Mint},
 };
 
-const MAINNET_MINT: Pubkey = Pubkey::from_str_const("3PKZCeF6RVw6sAGqCV5BGCATE1gu3bPceWXhfasapXVS");
-const DEVNET_MINT: Pubkey = Pubkey::from_str_const("AQYzQ3ZS9tXjhYMuVQ8tGoZMVV5DSuucaJB16mzXic9d");
-const MAINNET_FEE_RECEIVER: Pubkey = Pubkey::from_str_const("5zaUUZWoXaWt2Ht5NNQZuQXyfaQKDLyQoESn6BXvVzBd");
-const DEVNET_FEE_RECEIVER: Pubkey = Pubkey::from_str_const("8jHMkdtKK4CCn4ep6Hponmk1ik7ofUNS9bX9qSuiRcN5");
+pub const MAINNET_MINT: Pubkey = Pubkey::from_str_const("3PKZCeF6RVw6sAGqCV5BGCATE1gu3bPceWXhfasapXVS");
+pub const DEVNET_MINT: Pubkey = Pubkey::from_str_const("AQYzQ3ZS9tXjhYMuVQ8tGoZMVV5DSuucaJB16mzXic9d");
 
-fn get_token_mint(token_mint: &AccountInfo) -> Result<Pubkey, ProgramError> {
-    if *token_mint.key == MAINNET_MINT {
-        Ok(MAINNET_MINT)
-    } else if *token_mint.key == DEVNET_MINT {
-        Ok(DEVNET_MINT)
-    } else {
-        Err(ProgramError::InvalidAccountData)
-    }
-}
+pub const INITIALIZATION_FEE_LAMPORTS: u64 = 1000000000 / 10;
 
-fn get_fee_receiver(fee_receiver: &AccountInfo) -> Result<Pubkey, ProgramError> {
-    if *fee_receiver.key == MAINNET_FEE_RECEIVER {
-        Ok(MAINNET_FEE_RECEIVER)
-    } else if *fee_receiver.key == DEVNET_FEE_RECEIVER {
-        Ok(DEVNET_FEE_RECEIVER)
-    } else {
-        Err(ProgramError::InvalidAccountData)
-    }
+/// Accounts for `Initialize`. The token mint is a KUZA mint or an enabled
+/// registered mint, whose mint config must be passed, and the stats account is
+/// the shard the owner's vaults are counted in. The activity account is the
+/// new vault's activity log, created here. Remaining accounts are
+/// the fee recipients, in config order, followed by the referral record,
+/// referrer and referrer stats when a referrer is given, the receipt mint and
+/// the owner's receipt account when the vault takes receipts, and last the
+/// badge group once the mint has access tiers.
+pub struct InitializeAccounts<'a, 'b> {
+    pub initializer: &'b AccountInfo<'a>,
+    pub vault_account: &'b AccountInfo<'a>,
+    pub vault_ata: &'b AccountInfo<'a>,
+    pub user_token_account: &'b AccountInfo<'a>,
+    pub token_mint: &'b AccountInfo<'a>,
+    pub system_program: &'b AccountInfo<'a>,
+    pub token_program: &'b AccountInfo<'a>,
+    pub associated_token_program: &'b AccountInfo<'a>,
+    pub rent_sysvar: &'b AccountInfo<'a>,
+    pub loyalty_account: &'b AccountInfo<'a>,
+    pub exemption_account: &'b AccountInfo<'a>,
+    pub stats_account: &'b AccountInfo<'a>,
+    pub activity_account: &'b AccountInfo<'a>,
+    pub fee_recipients: &'b [AccountInfo<'a>],
+    pub referral_accounts: &'b [AccountInfo<'a>],
+    pub badges: Option<BadgeAccounts<'a, 'b>>,
+    pub config: Config,
+    pub mint_config: MintConfig,
+    pub registered_mint: Option<Pubkey>,
+    pub decimals: u8,
+    pub vault_bump: u8,
 }
-// From the client side we must calculate the amount of lamports needed to transfer to the vault.
-// The client side we actively monitor how much is needed to satisfy the threshold needed for a user to access the features.
-pub fn initialize(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -> ProgramResult {
-    let account_info_iter = &mut accounts.iter();
-
-    // Get all necessary accounts (aligned with deposit's requirements)
-    let initializer = next_account_info(account_info_iter)?;
-    let vault_account = next_account_info(account_info_iter)?;
-    let vault_ata = next_account_info(account_info_iter)?;
-    let user_token_account = next_account_info(account_info_iter)?;
-    let fee_receiver = next_account_info(account_info_iter)?;
-    let token_mint = next_account_info(account_info_iter)?;
-    let system_program = next_account_info(account_info_iter)?;
-    let token_program = next_account_info(account_info_iter)?;
-    let associated_token_program = next_account_info(account_info_iter)?;
-    let rent_sysvar = next_account_info(account_info_iter)?;
 
-    msg!("User wants to initialize the vault with {} tokens", amount);
+impl<'a, 'b> InitializeAccounts<'a, 'b> {
+    pub fn try_from(program_id: &Pubkey, accounts: &'b [AccountInfo<'a>]) -> Result<Self, ProgramError> {
+        let [
+            initializer,
+            vault_account,
+            vault_ata,
+            user_token_account,
+            config_account,
+            token_mint,
+            system_program,
+            token_program,
+            associated_token_program,
+            rent_sysvar,
+            loyalty_account,
+            exemption_account,
+            mint_config_account,
+            stats_account,
+            activity_account,
+            remaining_accounts @ ..,
+        ] = accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
 
-    // Verify initializer signed the transaction
-    if !initializer.is_signer {
-        return Err(ProgramError::MissingRequiredSignature);
-    }
+        check_signer(initializer)?;
 
-    if *token_program.key != spl_token::id() {
-        return Err(ProgramError::IncorrectProgramId);
-    }
-    
-    if *system_program.key != system_program::id() {
-        return Err(ProgramError::IncorrectProgramId);
-    }
-    
-    if *associated_token_program.key != spl_associated_token_account::id() {
-        return Err(ProgramError::IncorrectProgramId);
-    }
+        let writable = [initializer, vault_account, vault_ata, user_token_account, loyalty_account, stats_account];
+        for account in writable.into_iter().chain([activity_account]) {
+            check_writable(account)?;
+        }
 
-    let fee_receiver_key = get_fee_receiver(&fee_receiver).map_err(|_| ProgramError::InvalidAccountData)?;
+        check_program(token_program, &spl_token::id())?;
+        check_program(system_program, &system_program::id())?;
+        check_program(associated_token_program, &spl_associated_token_account::id())?;
 
-    let token_mint_key = get_token_mint(&token_mint).map_err(|_| ProgramError::InvalidAccountData)?;
+        let config = load_config(program_id, config_account)?;
+        let mint_config = load_mint_config(program_id, &config, token_mint.key, mint_config_account)?;
+        check_mint_enabled(&mint_config)?;
 
-    if *token_mint.key != token_mint_key {
-        return Err(ProgramError::InvalidAccountData);
-    }
+        let (remaining_accounts, badges) =
+            BadgeAccounts::require_from_tail(program_id, &mint_config, remaining_accounts)?;
+
+        let (fee_recipients, referral_accounts) = remaining_accounts
+            .split_at((config.fee_share_count as usize).min(remaining_accounts.len()));
+
+        let mint = *token_mint.key;
+
+        // Native SOL is locked in the vault itself, through InitializeSol
+        if mint == spl_token::native_mint::id() {
+            msg!("Lock SOL with InitializeSol");
+            return Err(VaultError::WrongVaultAsset.into());
+        }
+
+        if *token_mint.owner != spl_token::id() {
+            return Err(VaultError::InvalidMint.into());
+        }
+        let decimals = Mint::unpack(&token_mint.data.borrow()).map_err(|_| VaultError::InvalidMint)?.decimals;
+
+        // KUZA vaults keep the owner-only address they always had
+        let registered_mint = (!is_kuza_mint(&mint)).then_some(mint);
 
-    if *fee_receiver.key != fee_receiver_key {
-        return Err(ProgramError::InvalidAccountData);
+        // The canonical bump is stored so later instructions can skip the search
+        let (vault_pda, vault_bump) = match registered_mint {
+            Some(mint) => Vault::find_mint_address(initializer.key, &mint, program_id),
+            None => Vault::find_address(initializer.key, program_id),
+        };
+
+        if vault_pda != *vault_account.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        if !vault_account.data_is_empty() {
+            msg!("Vault {} already exists", vault_account.key);
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        if get_associated_token_address(vault_account.key, &mint) != *vault_ata.key {
+            msg!("Vault token account is not the vault's associated token account");
+            return Err(VaultError::InvalidVaultTokenAccount.into());
+        }
+
+        check_user_token_account(user_token_account, initializer.key, &mint)?;
+
+        Ok(Self {
+            initializer,
+            vault_account,
+            vault_ata,
+            user_token_account,
+            token_mint,
+            system_program,
+            token_program,
+            associated_token_program,
+            rent_sysvar,
+            loyalty_account,
+            exemption_account,
+            stats_account,
+            activity_account,
+            fee_recipients,
+            referral_accounts,
+            badges,
+            config,
+            mint_config,
+            registered_mint,
+            decimals,
+            vault_bump,
+        })
     }
+}
 
-    // Derive PDA for vault
-    let (vault_pda, bump) = Pubkey::find_program_address(
-        &[
-            Vault::SEED_PREFIX.as_bytes(),
-            initializer.key.as_ref(),
-        ],
-        program_id
-    );
+// From the client side we must calculate the amount of lamports needed to transfer to the vault.
+// The client side we actively monitor how much is needed to satisfy the threshold needed for a user to access the features.
+#[allow(clippy::too_many_arguments)]
+pub fn initialize(
+    program_id: &Pubkey,
+    accounts: InitializeAccounts,
+    amount: u64,
+    referrer: Option<Pubkey>,
+    auto_renew: bool,
+    kind: VaultKind,
+    time_basis: TimeBasis,
+    receipts: bool,
+) -> ProgramResult {
+    let InitializeAccounts {
+        initializer,
+        vault_account,
+        vault_ata,
+        user_token_account,
+        token_mint,
+        system_program,
+        token_program,
+        associated_token_program,
+        rent_sysvar,
+        loyalty_account,
+        exemption_account,
+        stats_account,
+        activity_account,
+        fee_recipients,
+        referral_accounts,
+        badges,
+        config,
+        mint_config,
+        registered_mint,
+        decimals,
+        vault_bump: bump,
+    } = accounts;
+
+    msg!("User wants to initialize the vault with {} tokens", amount);
 
-    // Verify derived PDA matches the vault account passed in
-    if vault_pda != *vault_account.key {
-        return Err(ProgramError::InvalidSeeds);
+    // A cooldown vault has no term to roll over
+    if auto_renew && kind != VaultKind::Fixed {
+        return Err(VaultError::WrongVaultKind.into());
     }
 
+    // Receipt holders are owed a term that ends, as with EnableReceipts
+    let (referral_accounts, receipts) = if receipts {
+        if kind != VaultKind::Fixed {
+            return Err(VaultError::WrongVaultKind.into());
+        }
+
+        if auto_renew {
+            msg!("Vaults with receipts can't auto-renew");
+            return Err(VaultError::ReceiptsIssued.into());
+        }
+
+        let (referral_accounts, receipts) = ReceiptAccounts::from_tail(referral_accounts)?;
+        let receipt_bump = receipts.check_new(program_id, initializer.key, vault_account)?;
+        (referral_accounts, Some((receipts, receipt_bump)))
+    } else {
+        (referral_accounts, None)
+    };
+
     // Calculate space and rent
     let vault_size = Vault::LEN;
     let rent = Rent::get()?;
     let rent_lamports = rent.minimum_balance(vault_size);
 
+    let bump_seed = [bump];
+    let mut vault_seeds = vec![Vault::SEED_PREFIX.as_bytes(), initializer.key.as_ref()];
+    vault_seeds.extend(registered_mint.as_ref().map(|mint| mint.as_ref()));
+    vault_seeds.push(&bump_seed);
+
     // Create vault account
     invoke_signed(
         &system_instruction::create_account(
@@ -122,11 +263,7 @@ pub fn initialize(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) ->
             vault_account.clone(),
             system_program.clone(),
         ],
-        &[&[
-            Vault::SEED_PREFIX.as_bytes(),
-            initializer.key.as_ref(),
-            &[bump],
-        ]],
+        &[&vault_seeds],
     )?;
 
     // Create ATA for vault
@@ -149,6 +286,9 @@ pub fn initialize(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) ->
         ],
     )?;
 
+    // `amount` is in whole tokens; the vault records base units like deposits do
+    let base_units = to_base_units(amount, decimals)?;
+
     // Transfer tokens directly here instead of calling deposit
     let transfer_instruction = token_instruction::transfer(
         token_program.key,
@@ -156,7 +296,7 @@ pub fn initialize(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) ->
         vault_ata.key,
         initializer.key,
         &[initializer.key],
-        amount * 1000000000,
+        base_units,
     )?;
 
     invoke(
@@ -170,30 +310,148 @@ pub fn initialize(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) ->
         ],
     )?;
 
-    // Transfer SOL fee
-    let sol_transfer_instruction = system_instruction::transfer(
-        initializer.key,
-        fee_receiver.key,
-        1000000000 / 10 // (10%)
-    );
-
-    invoke(
-        &sol_transfer_instruction,
-        &[
-            initializer.clone(),
-            fee_receiver.clone(),
-            system_program.clone(),
-        ],
+    let owner_bumps = Vault::find_owner_bumps(initializer.key, program_id);
+    let fee_lamports = charge_initialization_fee(
+        program_id,
+        initializer,
+        loyalty_account,
+        exemption_account,
+        fee_recipients,
+        referral_accounts,
+        system_program,
+        &config,
+        mint_config.initialization_fee_lamports,
+        referrer,
+        owner_bumps,
     )?;
 
-    let mut vault = Vault::new(*initializer.key, amount);
+    let mut stats = load_or_create_stats(program_id, initializer.key, initializer, stats_account, system_program)?;
+    stats.count_fee(fee_lamports)?;
+
+    let clock = Clock::get()?;
+    let now = time_basis.now(&clock);
     let mut vault_data = vault_account.data.borrow_mut();
-    vault.deposit_timestamp = Clock::get()?.unix_timestamp as u64;
-    vault.is_locked = true;
-    vault.lock_duration = Vault::LOCK_DURATION;
-    vault.serialize(&mut &mut vault_data[..])?;
+    let vault = Vault::init(&mut vault_data)?;
+    vault.mint = registered_mint.unwrap_or_default();
+    stats.count_lock(vault, base_units)?;
+    save_stats(&stats, stats_account)?;
+
+    vault.owner = *initializer.key;
+    vault.bump = bump;
+    (vault.exemption_bump, vault.referral_bump) = owner_bumps;
+    vault.amount_locked = base_units.into();
+    vault.deposit_timestamp = now.into();
+    vault.lock_duration = time_basis.from_seconds(mint_config.lock_duration_for(kind), config.slots_per_day).into();
+    vault.auto_renew = auto_renew as u8;
+    vault.kind = kind as u8;
+    vault.time_basis = time_basis as u8;
+    if let Some((_, receipt_bump)) = receipts {
+        vault.receipts = 1;
+        vault.receipt_bump = receipt_bump;
+    }
+    vault.transition(VaultStatus::Locked, now)?;
+
+    let tier_amount = vault.tier_amount(now);
+    let vault = *vault;
+    drop(vault_data);
+
+    if let Some((receipts, receipt_bump)) = receipts {
+        create_receipt_mint(
+            initializer,
+            vault_account,
+            &receipts,
+            system_program,
+            token_program,
+            associated_token_program,
+            decimals,
+            receipt_bump,
+        )?;
+
+        let (receipt_mint, receipt_account) = (receipts.receipt_mint, receipts.receipt_account);
+        mint_receipts(vault_account, &vault, receipt_mint, receipt_account, token_program, base_units)?;
+    }
 
+    create_activity_log(program_id, initializer, vault_account, activity_account, system_program)?;
+    record_activity(program_id, vault_account, activity_account, ActivityKind::Initialize, base_units, fee_lamports)?;
+
+    if let Some(badges) = badges {
+        let payer = BadgePayer {
+            payer: initializer,
+            system_program,
+            associated_token_program,
+        };
+        let (tiers, badge_bumps) = (mint_config.active_access_tiers(), &mint_config.badge_bumps);
+        let mint = registered_mint.as_ref();
+        sync_badges(program_id, &badges, initializer, mint, tiers, badge_bumps, tier_amount, Some(&payer))?;
+    }
 
     msg!("Vault initialized successfully with {} tokens", amount);
     Ok(())
 }
+
+/// Charges `initializer` the initialization fee of a new vault: `fee_lamports`
+/// after the owner's loyalty discount, or nothing for an exempt wallet. The
+/// referrer, if given, is recorded and paid their share and the rest is split
+/// across the fee recipients. `owner_bumps` are the bumps of the owner's fee
+/// exemption and referral PDAs from [`Vault::find_owner_bumps`]. Returns the
+/// fee charged, referrer's share included. Shared by `Initialize` and
+/// `InitializeSol`.
+#[allow(clippy::too_many_arguments)]
+pub fn charge_initialization_fee<'a>(
+    program_id: &Pubkey,
+    initializer: &AccountInfo<'a>,
+    loyalty_account: &AccountInfo<'a>,
+    exemption_account: &AccountInfo<'a>,
+    fee_recipients: &[AccountInfo<'a>],
+    referral_accounts: &[AccountInfo<'a>],
+    system_program: &AccountInfo<'a>,
+    config: &Config,
+    fee_lamports: u64,
+    referrer: Option<Pubkey>,
+    (exemption_bump, referral_bump): (u8, u8),
+) -> Result<u64, ProgramError> {
+    // Discount the fee by the owner's loyalty level
+    let loyalty = load_or_create_loyalty(program_id, initializer, loyalty_account, system_program)?;
+    save_loyalty(&loyalty, loyalty_account)?;
+
+    let discount_bps = config.loyalty_discount_bps(loyalty.completed_locks);
+    msg!("Loyalty discount: {} bps", discount_bps);
+
+    // Exempt wallets pay nothing, so the referrer's share is zero too
+    let fee_lamports = if is_fee_exempt(program_id, initializer.key, exemption_bump, exemption_account)? {
+        0
+    } else {
+        apply_discount(fee_lamports, discount_bps)
+    };
+
+    // Pay the referrer their share first; the rest of the fee is split
+    let mut split_lamports = fee_lamports;
+
+    if let Some(referrer) = referrer {
+        let (referral_account, referral_accounts) = referral_accounts
+            .split_first()
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+        let referral = ReferralAccounts::from_slice(referral_accounts)?;
+
+        if *referral.referrer.key != referrer {
+            return Err(VaultError::ReferrerMismatch.into());
+        }
+
+        record_referral(program_id, initializer, referral_bump, referral_account, &referral, system_program)?;
+
+        let cut = referral_cut(fee_lamports, config.referral_bps);
+        pay_referrer(program_id, initializer, &referrer, &referral, system_program, cut)?;
+        split_lamports = split_lamports.try_sub(cut)?;
+    }
+
+    // Transfer SOL fee, split across the configured recipients
+    distribute_sol(
+        initializer,
+        fee_recipients,
+        system_program,
+        config,
+        split_lamports,
+    )?;
+
+    Ok(fee_lamports)
+}
@@ -0,0 +1,83 @@
+use {
+    crate::{math::CheckedMath, state::Loyalty, utils::create_pda_account},
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::AccountInfo,
+        entrypoint::ProgramResult,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+    },
+};
+
+/// Reads the owner's loyalty record, creating an empty one on first use so
+/// vaults opened before loyalty tracking existed still work.
+pub fn load_or_create_loyalty<'a>(
+    program_id: &Pubkey,
+    owner: &AccountInfo<'a>,
+    loyalty_account: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+) -> Result<Loyalty, ProgramError> {
+    if let Some(loyalty) = load_loyalty(program_id, owner.key, loyalty_account)? {
+        return Ok(loyalty);
+    }
+
+    let (_, bump) = Loyalty::find_address(owner.key, program_id);
+    create_pda_account(
+        owner,
+        loyalty_account,
+        system_program,
+        program_id,
+        Loyalty::LEN,
+        &[Loyalty::SEED_PREFIX.as_bytes(), owner.key.as_ref(), &[bump]],
+    )?;
+
+    Ok(Loyalty::new(*owner.key, bump))
+}
+
+/// Reads `owner`'s loyalty record, or `None` if it hasn't been created yet.
+/// Either way the account must be the owner's loyalty PDA.
+pub fn load_loyalty(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    loyalty_account: &AccountInfo,
+) -> Result<Option<Loyalty>, ProgramError> {
+    if loyalty_account.data_is_empty() {
+        let (loyalty_pda, _) = Loyalty::find_address(owner, program_id);
+        if loyalty_pda != *loyalty_account.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        return Ok(None);
+    }
+
+    if loyalty_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let loyalty = Loyalty::try_from_slice(&loyalty_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    let expected = Pubkey::create_program_address(
+        &[Loyalty::SEED_PREFIX.as_bytes(), owner.as_ref(), &[loyalty.bump]],
+        program_id,
+    )?;
+
+    if expected != *loyalty_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    Ok(Some(loyalty))
+}
+
+/// Credits a lock that ran its full term, `time_locked` seconds of it, to an
+/// owner's loyalty record. Used by the exits that charge no fee.
+pub fn credit_completed_lock(loyalty: &mut Loyalty, time_locked: u64) -> ProgramResult {
+    loyalty.total_time_locked = loyalty.total_time_locked.try_add(time_locked)?;
+    loyalty.completed_locks = loyalty.completed_locks.try_add(1)?;
+    Ok(())
+}
+
+pub fn save_loyalty(loyalty: &Loyalty, loyalty_account: &AccountInfo) -> ProgramResult {
+    loyalty.serialize(&mut &mut loyalty_account.data.borrow_mut()[..])?;
+    Ok(())
+}
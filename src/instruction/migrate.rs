@@ -0,0 +1,147 @@
+use {
+    crate::{
+        instruction::check_vault_token_account,
+        state::Vault,
+        utils::{check_owner, check_program, check_signer, check_writable},
+    },
+    solana_program::{
+        account_info::AccountInfo,
+        entrypoint::ProgramResult,
+        msg,
+        program::invoke,
+        program_error::ProgramError,
+        program_pack::Pack,
+        pubkey::Pubkey,
+        system_instruction,
+        system_program,
+        sysvar::{rent::Rent, Sysvar},
+    },
+    spl_token::state::Account as TokenAccount,
+};
+
+/// Accounts for `MigrateVault`. The vault may be in any supported layout;
+/// the vault token account is only read when the vault is rewritten.
+pub struct MigrateVaultAccounts<'a, 'b> {
+    pub payer: &'b AccountInfo<'a>,
+    pub vault_account: &'b AccountInfo<'a>,
+    pub system_program: &'b AccountInfo<'a>,
+    pub vault_ata: &'b AccountInfo<'a>,
+    pub vault: Vault,
+    pub version: u8,
+    pub vault_bump: u8,
+}
+
+impl<'a, 'b> MigrateVaultAccounts<'a, 'b> {
+    pub fn try_from(program_id: &Pubkey, accounts: &'b [AccountInfo<'a>]) -> Result<Self, ProgramError> {
+        let [payer, vault_account, system_program, vault_ata, ..] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        check_signer(payer)?;
+        check_writable(payer)?;
+        check_writable(vault_account)?;
+        check_program(system_program, &system_program::id())?;
+        check_owner(vault_account, program_id)?;
+
+        let version = Vault::version(&vault_account.data.borrow())?;
+        let vault = Vault::unpack(&vault_account.data.borrow())?;
+
+        // Legacy vaults never recorded their bump, so search for it
+        let (pda, vault_bump) = vault.find_own_address(program_id);
+
+        if pda != *vault_account.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        Ok(Self {
+            payer,
+            vault_account,
+            system_program,
+            vault_ata,
+            vault,
+            version,
+            vault_bump,
+        })
+    }
+}
+
+/// Rewrites a vault in the current layout. Anyone may pay for the migration;
+/// the vault's contents are carried over apart from the bump, which legacy
+/// vaults never recorded, and `amount_locked`, which they recorded in whole
+/// tokens and is taken from the vault token account's balance instead. The
+/// bumps of the owner's exemption and referral PDAs are recorded too.
+/// Current-layout vaults only have their bump and amount fixed if the bump
+/// is missing, and their owner's bumps recorded if they are.
+pub fn migrate_vault(program_id: &Pubkey, accounts: MigrateVaultAccounts) -> ProgramResult {
+    msg!("Migrating vault to layout version {}", Vault::VERSION);
+
+    let MigrateVaultAccounts {
+        payer,
+        vault_account,
+        system_program,
+        vault_ata,
+        mut vault,
+        version,
+        vault_bump: bump,
+    } = accounts;
+
+    if version == Vault::VERSION {
+        let missing_owner_bumps = vault.exemption_bump == 0 || vault.referral_bump == 0;
+        if vault.bump == bump && !missing_owner_bumps {
+            msg!("Vault is already at version {}", version);
+            return Ok(());
+        }
+
+        // Vaults initialized before the bump was stored hold 0 there, and
+        // predate locks recorded in base units
+        let amount_locked = if vault.bump != bump {
+            Some(vault_balance(vault_account, &vault, vault_ata)?)
+        } else {
+            None
+        };
+
+        let mut data = vault_account.data.borrow_mut();
+        let stored = Vault::load_mut(&mut data)?;
+
+        if let Some(amount_locked) = amount_locked {
+            msg!("Recording canonical bump {} for vault", bump);
+            stored.bump = bump;
+            stored.amount_locked = amount_locked.into();
+        }
+
+        msg!("Recording the owner's exemption and referral bumps");
+        (stored.exemption_bump, stored.referral_bump) = Vault::find_owner_bumps(&vault.owner, program_id);
+        return Ok(());
+    }
+
+    // Top up rent for the larger account before growing it
+    let required_lamports = Rent::get()?.minimum_balance(Vault::LEN);
+    let shortfall = required_lamports.saturating_sub(vault_account.lamports());
+
+    if shortfall > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, vault_account.key, shortfall),
+            &[payer.clone(), vault_account.clone(), system_program.clone()],
+        )?;
+    }
+
+    vault_account.realloc(Vault::LEN, true)?;
+
+    vault.bump = bump;
+    vault.amount_locked = vault_balance(vault_account, &vault, vault_ata)?.into();
+    (vault.exemption_bump, vault.referral_bump) = Vault::find_owner_bumps(&vault.owner, program_id);
+    *Vault::init(&mut vault_account.data.borrow_mut())? = vault;
+
+    msg!("Migrated vault from version {} to {}", version, Vault::VERSION);
+    Ok(())
+}
+
+// Base units held by the vault, which the older layouts' whole-token
+// `amount_locked` stood for
+fn vault_balance(vault_account: &AccountInfo, vault: &Vault, vault_ata: &AccountInfo) -> Result<u64, ProgramError> {
+    check_vault_token_account(vault_account, vault, vault_ata)?;
+    let amount = TokenAccount::unpack(&vault_ata.data.borrow())?.amount;
+
+    msg!("Recording {} base units locked, from {} whole tokens", amount, u64::from(vault.amount_locked));
+    Ok(amount)
+}
@@ -0,0 +1,258 @@
+use {
+    crate::{
+        error::VaultError,
+        instruction::{
+            create_badge_mints, load_config_as_admin, validate_access_tiers, DEVNET_MINT,
+            INITIALIZATION_FEE_LAMPORTS, MAINNET_MINT, MAX_EARLY_WITHDRAWAL_FEE_LAMPORTS,
+        },
+        state::{AccessTier, Config, MintConfig, Vault},
+        utils::{check_program, check_signer, check_writable, create_pda_account},
+    },
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::AccountInfo,
+        entrypoint::ProgramResult,
+        msg,
+        program_error::ProgramError,
+        program_pack::Pack,
+        pubkey::Pubkey,
+        system_program,
+    },
+    spl_token::{native_mint, state::Mint},
+};
+
+/// Whether `mint` is one of the KUZA mints, which are always supported and
+/// never registered.
+pub fn is_kuza_mint(mint: &Pubkey) -> bool {
+    [MAINNET_MINT, DEVNET_MINT].contains(mint)
+}
+
+/// The terms of a KUZA mint: the program's built-in lock and fees, and the
+/// config's access tiers.
+pub fn kuza_mint_config(mint: &Pubkey, config: &Config) -> MintConfig {
+    MintConfig {
+        mint: *mint,
+        enabled: true,
+        lock_duration: Vault::LOCK_DURATION,
+        initialization_fee_lamports: INITIALIZATION_FEE_LAMPORTS,
+        max_early_withdrawal_fee_lamports: MAX_EARLY_WITHDRAWAL_FEE_LAMPORTS,
+        max_early_withdrawal_fee_tokens: 0,
+        access_tiers: config.access_tiers,
+        access_tier_count: config.access_tier_count,
+        badge_bumps: config.badge_bumps,
+        bump: 0,
+    }
+}
+
+/// Reads a registered mint's config, checking that the program owns it and
+/// that it sits at the address derived from its mint and stored bump.
+pub fn load_registered_mint_config(
+    program_id: &Pubkey,
+    mint_config_account: &AccountInfo,
+) -> Result<MintConfig, ProgramError> {
+    if mint_config_account.owner != program_id {
+        msg!("Mint {} is not registered", mint_config_account.key);
+        return Err(VaultError::InvalidMint.into());
+    }
+
+    let mint_config = MintConfig::try_from_slice(&mint_config_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    let expected = Pubkey::create_program_address(
+        &[MintConfig::SEED_PREFIX.as_bytes(), mint_config.mint.as_ref(), &[mint_config.bump]],
+        program_id,
+    )?;
+
+    if expected != *mint_config_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    Ok(mint_config)
+}
+
+/// Terms for vaults of `mint`. KUZA mints use the built-in terms and ignore
+/// the mint config account; any other mint must be registered there.
+pub fn load_mint_config(
+    program_id: &Pubkey,
+    config: &Config,
+    mint: &Pubkey,
+    mint_config_account: &AccountInfo,
+) -> Result<MintConfig, ProgramError> {
+    if is_kuza_mint(mint) {
+        return Ok(kuza_mint_config(mint, config));
+    }
+
+    let mint_config = load_registered_mint_config(program_id, mint_config_account)?;
+
+    if mint_config.mint != *mint {
+        msg!("Mint config {} is for {}, not {}", mint_config_account.key, mint_config.mint, mint);
+        return Err(VaultError::InvalidMint.into());
+    }
+
+    Ok(mint_config)
+}
+
+/// Fails when the admin has switched off new locks of the mint.
+pub fn check_mint_enabled(mint_config: &MintConfig) -> ProgramResult {
+    if !mint_config.enabled {
+        msg!("Mint {} is not taking new locks", mint_config.mint);
+        return Err(VaultError::MintDisabled.into());
+    }
+    Ok(())
+}
+
+/// Accounts for `SetMintConfig`. The mint config must be `token_mint`'s
+/// mint config PDA, and the badge mints the mint's tier badge mints.
+pub struct SetMintConfigAccounts<'a, 'b> {
+    pub admin: &'b AccountInfo<'a>,
+    pub config_account: &'b AccountInfo<'a>,
+    pub mint_config_account: &'b AccountInfo<'a>,
+    pub token_mint: &'b AccountInfo<'a>,
+    pub system_program: &'b AccountInfo<'a>,
+    pub token_program: &'b AccountInfo<'a>,
+    pub badge_mints: &'b [AccountInfo<'a>],
+    pub mint_config_bump: u8,
+}
+
+impl<'a, 'b> SetMintConfigAccounts<'a, 'b> {
+    pub fn try_from(program_id: &Pubkey, accounts: &'b [AccountInfo<'a>]) -> Result<Self, ProgramError> {
+        let [
+            admin,
+            config_account,
+            mint_config_account,
+            token_mint,
+            system_program,
+            token_program,
+            badge_mints @ ..,
+        ] = accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        let badge_mints = badge_mints
+            .get(..Config::MAX_ACCESS_TIERS)
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+        check_signer(admin)?;
+
+        for account in [admin, mint_config_account].into_iter().chain(badge_mints) {
+            check_writable(account)?;
+        }
+
+        check_program(system_program, &system_program::id())?;
+        check_program(token_program, &spl_token_2022::id())?;
+
+        load_config_as_admin(program_id, admin, config_account)?;
+
+        // The KUZA terms are built in
+        if is_kuza_mint(token_mint.key) || *token_mint.owner != spl_token::id() {
+            msg!("{} can't be registered", token_mint.key);
+            return Err(VaultError::InvalidMint.into());
+        }
+
+        Mint::unpack(&token_mint.data.borrow()).map_err(|_| VaultError::InvalidMint)?;
+
+        let (mint_config_pda, mint_config_bump) = MintConfig::find_address(token_mint.key, program_id);
+        if mint_config_pda != *mint_config_account.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        Ok(Self {
+            admin,
+            config_account,
+            mint_config_account,
+            token_mint,
+            system_program,
+            token_program,
+            badge_mints,
+            mint_config_bump,
+        })
+    }
+}
+
+/// Registers a mint or updates its terms, creating the badge mints of any
+/// new tiers. Existing vaults keep the term they locked under.
+#[allow(clippy::too_many_arguments)]
+pub fn set_mint_config(
+    program_id: &Pubkey,
+    accounts: SetMintConfigAccounts,
+    enabled: bool,
+    lock_duration: u64,
+    initialization_fee_lamports: u64,
+    max_early_withdrawal_fee_lamports: u64,
+    max_early_withdrawal_fee_tokens: u64,
+    access_tiers: Vec<AccessTier>,
+) -> ProgramResult {
+    let SetMintConfigAccounts {
+        admin,
+        config_account,
+        mint_config_account,
+        token_mint,
+        system_program,
+        token_program,
+        badge_mints,
+        mint_config_bump: bump,
+    } = accounts;
+
+    msg!("Setting the terms of mint {}", token_mint.key);
+
+    if lock_duration == 0 {
+        msg!("A registered mint needs a lock duration");
+        return Err(VaultError::InvalidMintConfig.into());
+    }
+
+    // A SOL vault's fee is already in lamports
+    if max_early_withdrawal_fee_tokens > 0 && *token_mint.key == native_mint::id() {
+        msg!("The native mint takes no token fee");
+        return Err(VaultError::InvalidMintConfig.into());
+    }
+
+    validate_access_tiers(&access_tiers)?;
+
+    // Bumps of badge mints created for earlier tiers carry over
+    let mut badge_bumps = [0; Config::MAX_ACCESS_TIERS];
+    if mint_config_account.data_is_empty() {
+        create_pda_account(
+            admin,
+            mint_config_account,
+            system_program,
+            program_id,
+            MintConfig::LEN,
+            &[MintConfig::SEED_PREFIX.as_bytes(), token_mint.key.as_ref(), &[bump]],
+        )?;
+    } else {
+        badge_bumps = MintConfig::try_from_slice(&mint_config_account.data.borrow())?.badge_bumps;
+    }
+
+    create_badge_mints(
+        program_id,
+        Some(token_mint.key),
+        access_tiers.len(),
+        &mut badge_bumps,
+        admin,
+        badge_mints,
+        config_account,
+        system_program,
+        token_program,
+    )?;
+
+    let mut tiers = [AccessTier::default(); Config::MAX_ACCESS_TIERS];
+    tiers[..access_tiers.len()].copy_from_slice(&access_tiers);
+
+    MintConfig {
+        mint: *token_mint.key,
+        enabled,
+        lock_duration,
+        initialization_fee_lamports,
+        max_early_withdrawal_fee_lamports,
+        max_early_withdrawal_fee_tokens,
+        access_tiers: tiers,
+        access_tier_count: access_tiers.len() as u8,
+        badge_bumps,
+        bump,
+    }
+    .serialize(&mut &mut mint_config_account.data.borrow_mut()[..])?;
+
+    msg!("Mint {} is {}", token_mint.key, if enabled { "enabled" } else { "disabled" });
+    Ok(())
+}
@@ -1,11 +1,43 @@
+pub mod activity;
+pub mod auto_renew;
+pub mod badge;
+pub mod claim;
+pub mod config;
+pub mod cooldown;
+pub mod crank;
 pub mod deposit;
+pub mod exemption;
 pub mod extend;
 pub mod initialize;
+pub mod loyalty;
+pub mod migrate;
+pub mod mint_config;
+pub mod receipt;
+pub mod referral;
 pub mod release;
+pub mod sol;
+pub mod stats;
+pub mod vault;
 pub mod withdraw;
 
+pub use activity::*;
+pub use auto_renew::*;
+pub use badge::*;
+pub use claim::*;
+pub use config::*;
+pub use cooldown::*;
+pub use crank::*;
 pub use deposit::*;
+pub use exemption::*;
 pub use extend::*;
 pub use initialize::*;
+pub use loyalty::*;
+pub use migrate::*;
+pub use mint_config::*;
+pub use receipt::*;
+pub use referral::*;
 pub use release::*;
+pub use sol::*;
+pub use stats::*;
+pub use vault::*;
 pub use withdraw::*;
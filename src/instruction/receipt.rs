@@ -0,0 +1,467 @@
+use {
+    crate::{
+        error::VaultError,
+        instruction::{
+            check_claimable, check_user_token_account, check_vault_token_account, create_activity_log, is_kuza_mint,
+            load_vault, record_activity, release,
+        },
+        state::{ActivityKind, Vault, VaultKind},
+        utils::{check_owner, check_program, check_signer, check_writable, create_pda_account},
+    },
+    solana_program::{
+        account_info::AccountInfo,
+        clock::Clock,
+        entrypoint::ProgramResult,
+        msg,
+        program::{invoke, invoke_signed},
+        program_error::ProgramError,
+        program_pack::Pack,
+        pubkey::Pubkey,
+        system_program,
+        sysvar::Sysvar,
+    },
+    spl_associated_token_account::{
+        get_associated_token_address, instruction::create_associated_token_account_idempotent,
+    },
+    spl_token::{instruction as token_instruction, state::Mint},
+};
+
+/// Fails unless the vault has receipts and `receipt_mint` is its receipt
+/// mint, derived from the bump stored when receipts were enabled.
+pub fn check_receipt_mint(
+    program_id: &Pubkey,
+    vault_account: &AccountInfo,
+    vault: &Vault,
+    receipt_mint: &AccountInfo,
+) -> ProgramResult {
+    let expected = Pubkey::create_program_address(
+        &[Vault::RECEIPT_SEED_PREFIX.as_bytes(), vault_account.key.as_ref(), &[vault.receipt_bump]],
+        program_id,
+    )
+    .ok();
+
+    if !vault.has_receipts() || expected != Some(*receipt_mint.key) {
+        msg!("Mint {} is not the receipt mint of vault {}", receipt_mint.key, vault_account.key);
+        return Err(VaultError::InvalidReceiptMint.into());
+    }
+
+    Ok(())
+}
+
+/// Mints `amount` receipts to `destination`, signed by the vault as the
+/// receipt mint's authority.
+pub fn mint_receipts<'a>(
+    vault_account: &AccountInfo<'a>,
+    vault: &Vault,
+    receipt_mint: &AccountInfo<'a>,
+    destination: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+    amount: u64,
+) -> ProgramResult {
+    msg!("Minting {} receipts", amount);
+
+    invoke_signed(
+        &token_instruction::mint_to(
+            token_program.key,
+            receipt_mint.key,
+            destination.key,
+            vault_account.key,
+            &[],
+            amount,
+        )?,
+        &[
+            receipt_mint.clone(),
+            destination.clone(),
+            vault_account.clone(),
+            token_program.clone(),
+        ],
+        &[&vault.signer_seeds()],
+    )
+}
+
+/// Burns `amount` receipts from `receipt_account`, signed by `holder`. The
+/// burn fails if the account holds fewer.
+pub fn burn_receipts<'a>(
+    holder: &AccountInfo<'a>,
+    receipt_mint: &AccountInfo<'a>,
+    receipt_account: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+    amount: u64,
+) -> ProgramResult {
+    msg!("Burning {} receipts", amount);
+
+    invoke(
+        &token_instruction::burn(
+            token_program.key,
+            receipt_account.key,
+            receipt_mint.key,
+            holder.key,
+            &[],
+            amount,
+        )?,
+        &[
+            receipt_account.clone(),
+            receipt_mint.clone(),
+            holder.clone(),
+            token_program.clone(),
+        ],
+    )
+}
+
+/// The receipt mint and the receipt token account that come just before the
+/// badge group when the vault has receipts.
+pub struct ReceiptAccounts<'a, 'b> {
+    pub receipt_mint: &'b AccountInfo<'a>,
+    pub receipt_account: &'b AccountInfo<'a>,
+}
+
+impl<'a, 'b> ReceiptAccounts<'a, 'b> {
+    pub fn from_slice(accounts: &'b [AccountInfo<'a>]) -> Result<Self, ProgramError> {
+        match accounts {
+            [receipt_mint, receipt_account, ..] => Ok(Self {
+                receipt_mint,
+                receipt_account,
+            }),
+            _ => Err(ProgramError::NotEnoughAccountKeys),
+        }
+    }
+
+    /// Splits the receipt accounts off the end of `accounts`, returning the
+    /// accounts before them.
+    pub fn from_tail(accounts: &'b [AccountInfo<'a>]) -> Result<(&'b [AccountInfo<'a>], Self), ProgramError> {
+        match accounts {
+            [rest @ .., receipt_mint, receipt_account] => Ok((
+                rest,
+                Self {
+                    receipt_mint,
+                    receipt_account,
+                },
+            )),
+            _ => Err(ProgramError::NotEnoughAccountKeys),
+        }
+    }
+
+    /// Checks the accounts of a vault whose receipts are being created:
+    /// the receipt mint must be the vault's receipt mint PDA and the receipt
+    /// account `owner`'s associated token account for it. Returns the
+    /// receipt mint's bump.
+    pub fn check_new(
+        &self,
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        vault_account: &AccountInfo,
+    ) -> Result<u8, ProgramError> {
+        check_writable(self.receipt_mint)?;
+        check_writable(self.receipt_account)?;
+
+        let (receipt_pda, receipt_bump) = Vault::find_receipt_mint(vault_account.key, program_id);
+        if receipt_pda != *self.receipt_mint.key {
+            msg!("Mint {} is not the receipt mint of vault {}", self.receipt_mint.key, vault_account.key);
+            return Err(VaultError::InvalidReceiptMint.into());
+        }
+
+        if get_associated_token_address(owner, self.receipt_mint.key) != *self.receipt_account.key {
+            msg!("Receipt account is not the owner's associated token account");
+            return Err(VaultError::InvalidUserTokenAccount.into());
+        }
+
+        Ok(receipt_bump)
+    }
+}
+
+/// Creates the receipt mint of `vault_account`, with the vault as mint
+/// authority and `decimals` decimals, and `owner`'s receipt account if it is
+/// missing, all at `owner`'s expense. Shared by `Initialize` and
+/// `EnableReceipts`.
+#[allow(clippy::too_many_arguments)]
+pub fn create_receipt_mint<'a>(
+    owner: &AccountInfo<'a>,
+    vault_account: &AccountInfo<'a>,
+    receipts: &ReceiptAccounts<'a, '_>,
+    system_program: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+    associated_token_program: &AccountInfo<'a>,
+    decimals: u8,
+    receipt_bump: u8,
+) -> ProgramResult {
+    let (receipt_mint, receipt_account) = (receipts.receipt_mint, receipts.receipt_account);
+
+    create_pda_account(
+        owner,
+        receipt_mint,
+        system_program,
+        &spl_token::id(),
+        Mint::LEN,
+        &[Vault::RECEIPT_SEED_PREFIX.as_bytes(), vault_account.key.as_ref(), &[receipt_bump]],
+    )?;
+
+    invoke(
+        &token_instruction::initialize_mint2(
+            token_program.key,
+            receipt_mint.key,
+            vault_account.key,
+            None,
+            decimals,
+        )?,
+        &[receipt_mint.clone(), token_program.clone()],
+    )?;
+
+    invoke(
+        &create_associated_token_account_idempotent(owner.key, owner.key, receipt_mint.key, token_program.key),
+        &[
+            owner.clone(),
+            receipt_account.clone(),
+            owner.clone(),
+            receipt_mint.clone(),
+            system_program.clone(),
+            token_program.clone(),
+            associated_token_program.clone(),
+        ],
+    )
+}
+
+/// Accounts for `EnableReceipts`. The receipt account must be the owner's
+/// associated token account for the receipt mint; it is created if missing,
+/// as is the vault's activity log. `mint` is the mint the vault locks, whose
+/// decimals the receipts take.
+pub struct EnableReceiptsAccounts<'a, 'b> {
+    pub owner: &'b AccountInfo<'a>,
+    pub vault_account: &'b AccountInfo<'a>,
+    pub receipts: ReceiptAccounts<'a, 'b>,
+    pub system_program: &'b AccountInfo<'a>,
+    pub token_program: &'b AccountInfo<'a>,
+    pub associated_token_program: &'b AccountInfo<'a>,
+    pub activity_account: &'b AccountInfo<'a>,
+    pub mint: &'b AccountInfo<'a>,
+    pub vault: Vault,
+    pub receipt_bump: u8,
+}
+
+impl<'a, 'b> EnableReceiptsAccounts<'a, 'b> {
+    pub fn try_from(program_id: &Pubkey, accounts: &'b [AccountInfo<'a>]) -> Result<Self, ProgramError> {
+        let [
+            owner,
+            vault_account,
+            receipt_mint,
+            receipt_account,
+            system_program,
+            token_program,
+            associated_token_program,
+            activity_account,
+            mint,
+            ..,
+        ] = accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        check_signer(owner)?;
+
+        for account in [owner, vault_account, activity_account] {
+            check_writable(account)?;
+        }
+
+        check_program(system_program, &system_program::id())?;
+        check_program(token_program, &spl_token::id())?;
+        check_program(associated_token_program, &spl_associated_token_account::id())?;
+
+        let vault = load_vault(program_id, owner.key, vault_account)?;
+
+        let receipts = ReceiptAccounts {
+            receipt_mint,
+            receipt_account,
+        };
+        let receipt_bump = receipts.check_new(program_id, owner.key, vault_account)?;
+
+        let mint_matches = match vault.registered_mint() {
+            Some(registered) => *mint.key == registered,
+            None => is_kuza_mint(mint.key),
+        };
+
+        if !mint_matches || *mint.owner != spl_token::id() {
+            msg!("Mint {} is not the mint of vault {}", mint.key, vault_account.key);
+            return Err(VaultError::InvalidMint.into());
+        }
+
+        Ok(Self {
+            owner,
+            vault_account,
+            receipts,
+            system_program,
+            token_program,
+            associated_token_program,
+            activity_account,
+            mint,
+            vault,
+            receipt_bump,
+        })
+    }
+}
+
+/// Creates the vault's receipt mint, with the vault as mint authority, and
+/// mints the owner one receipt per base unit locked, so the receipts take the
+/// locked mint's decimals. From then on whoever
+/// holds the receipts takes the tokens out through `redeem`, and the owner's
+/// own exits are closed. Only fixed vaults take receipts, and enabling them
+/// switches auto-renew off so the position has an end a holder can rely on.
+pub fn enable_receipts(program_id: &Pubkey, accounts: EnableReceiptsAccounts) -> ProgramResult {
+    msg!("Enabling receipts");
+
+    let EnableReceiptsAccounts {
+        owner,
+        vault_account,
+        receipts,
+        system_program,
+        token_program,
+        associated_token_program,
+        activity_account,
+        mint,
+        vault,
+        receipt_bump,
+    } = accounts;
+
+    vault.check_kind(VaultKind::Fixed)?;
+
+    // Receipts stand for tokens held in the vault's token account
+    if vault.is_native() {
+        return Err(VaultError::WrongVaultAsset.into());
+    }
+
+    if vault.has_receipts() {
+        msg!("Vault {} already has receipts", vault_account.key);
+        return Err(VaultError::ReceiptsIssued.into());
+    }
+
+    let decimals = Mint::unpack(&mint.data.borrow()).map_err(|_| VaultError::InvalidMint)?.decimals;
+
+    create_receipt_mint(
+        owner,
+        vault_account,
+        &receipts,
+        system_program,
+        token_program,
+        associated_token_program,
+        decimals,
+        receipt_bump,
+    )?;
+
+    let amount_locked = u64::from(vault.amount_locked);
+    let ReceiptAccounts {
+        receipt_mint,
+        receipt_account,
+    } = receipts;
+    mint_receipts(vault_account, &vault, receipt_mint, receipt_account, token_program, amount_locked)?;
+
+    let now = vault.now(&Clock::get()?)?;
+    let mut vault_data = vault_account.data.borrow_mut();
+    let vault = Vault::load_mut(&mut vault_data)?;
+
+    vault.roll_forward(now);
+    vault.auto_renew = 0;
+    vault.receipts = 1;
+    vault.receipt_bump = receipt_bump;
+    drop(vault_data);
+
+    create_activity_log(program_id, owner, vault_account, activity_account, system_program)?;
+    record_activity(program_id, vault_account, activity_account, ActivityKind::EnableReceipts, amount_locked, 0)?;
+
+    msg!("Receipts for vault {} are minted by {}", vault_account.key, receipt_mint.key);
+    Ok(())
+}
+
+/// Accounts for `Redeem`. Any receipt holder may send it; the destination
+/// must be the holder's token account for the vault's mint, the stats
+/// account the shard the vault owner's vaults are counted in, and the
+/// activity account the vault's activity log.
+pub struct RedeemAccounts<'a, 'b> {
+    pub holder: &'b AccountInfo<'a>,
+    pub vault_account: &'b AccountInfo<'a>,
+    pub vault_ata: &'b AccountInfo<'a>,
+    pub receipt_mint: &'b AccountInfo<'a>,
+    pub receipt_account: &'b AccountInfo<'a>,
+    pub destination: &'b AccountInfo<'a>,
+    pub token_program: &'b AccountInfo<'a>,
+    pub stats_account: &'b AccountInfo<'a>,
+    pub activity_account: &'b AccountInfo<'a>,
+    pub vault: Vault,
+}
+
+impl<'a, 'b> RedeemAccounts<'a, 'b> {
+    pub fn try_from(program_id: &Pubkey, accounts: &'b [AccountInfo<'a>]) -> Result<Self, ProgramError> {
+        let [
+            holder,
+            vault_account,
+            vault_ata,
+            receipt_mint,
+            receipt_account,
+            destination,
+            token_program,
+            stats_account,
+            activity_account,
+            ..,
+        ] = accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        check_signer(holder)?;
+
+        let writable = [vault_account, vault_ata, receipt_mint, receipt_account, destination, stats_account];
+        for account in writable.into_iter().chain([activity_account]) {
+            check_writable(account)?;
+        }
+
+        check_program(token_program, &spl_token::id())?;
+
+        // The stored owner is only trusted once the address is checked against it
+        check_owner(vault_account, program_id).map_err(|_| VaultError::VaultNotProgramOwned)?;
+        let owner = Vault::load(&vault_account.data.borrow())?.owner;
+        let vault = load_vault(program_id, &owner, vault_account)?;
+
+        let mint = check_vault_token_account(vault_account, &vault, vault_ata)?;
+        check_user_token_account(destination, holder.key, &mint)?;
+        check_receipt_mint(program_id, vault_account, &vault, receipt_mint)?;
+
+        Ok(Self {
+            holder,
+            vault_account,
+            vault_ata,
+            receipt_mint,
+            receipt_account,
+            destination,
+            token_program,
+            stats_account,
+            activity_account,
+            vault,
+        })
+    }
+}
+
+/// Burns one receipt per base unit locked from the holder's receipt account
+/// and releases the matured vault to the holder. The holder needs every
+/// outstanding receipt; the burn fails if they hold fewer.
+pub fn redeem(program_id: &Pubkey, accounts: RedeemAccounts) -> ProgramResult {
+    msg!("Redeeming receipts");
+
+    let RedeemAccounts {
+        holder,
+        vault_account,
+        vault_ata,
+        receipt_mint,
+        receipt_account,
+        destination,
+        token_program,
+        stats_account,
+        activity_account,
+        mut vault,
+    } = accounts;
+
+    let now = vault.now(&Clock::get()?)?;
+    check_claimable(&mut vault, now)?;
+
+    let amount_locked = u64::from(vault.amount_locked);
+    burn_receipts(holder, receipt_mint, receipt_account, token_program, amount_locked)?;
+
+    release(program_id, vault_account, vault_ata, destination, token_program, stats_account, now)?;
+    record_activity(program_id, vault_account, activity_account, ActivityKind::Redeem, amount_locked, 0)
+}
@@ -0,0 +1,190 @@
+use {
+    crate::{
+        error::VaultError,
+        math::CheckedMath,
+        state::{Referral, ReferrerStats},
+        utils::create_pda_account,
+    },
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::AccountInfo,
+        entrypoint::ProgramResult,
+        msg,
+        program::invoke,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        system_instruction,
+    },
+};
+
+/// Accounts that follow the fee recipients when a referral is involved.
+pub struct ReferralAccounts<'a, 'b> {
+    pub referrer: &'b AccountInfo<'a>,
+    pub stats: &'b AccountInfo<'a>,
+}
+
+impl<'a, 'b> ReferralAccounts<'a, 'b> {
+    pub fn from_slice(accounts: &'b [AccountInfo<'a>]) -> Result<Self, ProgramError> {
+        match accounts {
+            [referrer, stats, ..] => Ok(Self { referrer, stats }),
+            _ => Err(ProgramError::NotEnoughAccountKeys),
+        }
+    }
+}
+
+/// Creates the owner's referral record at `referral_bump` and bumps the
+/// referrer's stats, creating the stats account on the referrer's first
+/// referral. An owner referred before keeps their record: another vault
+/// naming the same referrer reuses it without counting a new referral, and
+/// naming anyone else fails with `ReferrerMismatch`.
+pub fn record_referral<'a>(
+    program_id: &Pubkey,
+    owner: &AccountInfo<'a>,
+    referral_bump: u8,
+    referral_account: &AccountInfo<'a>,
+    referral: &ReferralAccounts<'a, '_>,
+    system_program: &AccountInfo<'a>,
+) -> ProgramResult {
+    if referral.referrer.key == owner.key {
+        return Err(VaultError::SelfReferral.into());
+    }
+
+    let (stats_pda, stats_bump) = ReferrerStats::find_address(referral.referrer.key, program_id);
+    if stats_pda != *referral.stats.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    if let Some(recorded) = load_referral(program_id, owner.key, referral_bump, referral_account)? {
+        if recorded.referrer != *referral.referrer.key {
+            msg!("{} was referred by {}", owner.key, recorded.referrer);
+            return Err(VaultError::ReferrerMismatch.into());
+        }
+
+        return Ok(());
+    }
+
+    create_pda_account(
+        owner,
+        referral_account,
+        system_program,
+        program_id,
+        Referral::LEN,
+        &[Referral::SEED_PREFIX.as_bytes(), owner.key.as_ref(), &[referral_bump]],
+    )?;
+
+    Referral {
+        owner: *owner.key,
+        referrer: *referral.referrer.key,
+        bump: referral_bump,
+    }
+    .serialize(&mut &mut referral_account.data.borrow_mut()[..])?;
+
+    let mut stats = if referral.stats.data_is_empty() {
+        create_pda_account(
+            owner,
+            referral.stats,
+            system_program,
+            program_id,
+            ReferrerStats::LEN,
+            &[ReferrerStats::SEED_PREFIX.as_bytes(), referral.referrer.key.as_ref(), &[stats_bump]],
+        )?;
+
+        ReferrerStats {
+            referrer: *referral.referrer.key,
+            referral_count: 0,
+            lamports_earned: 0,
+            bump: stats_bump,
+        }
+    } else {
+        load_referrer_stats(program_id, referral.stats)?
+    };
+
+    stats.referral_count = stats.referral_count.try_add(1)?;
+    stats.serialize(&mut &mut referral.stats.data.borrow_mut()[..])?;
+
+    msg!("Recorded referral of {} by {}", owner.key, referral.referrer.key);
+    Ok(())
+}
+
+/// Returns the owner's referral record, or `None` if they were not referred.
+/// The record address is always checked against `referral_bump` so a
+/// referred owner can't dodge the referrer's share by passing some other
+/// empty account.
+pub fn load_referral(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    referral_bump: u8,
+    referral_account: &AccountInfo,
+) -> Result<Option<Referral>, ProgramError> {
+    if Referral::create_address(owner, referral_bump, program_id)? != *referral_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    if referral_account.data_is_empty() {
+        return Ok(None);
+    }
+
+    if referral_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let referral = Referral::try_from_slice(&referral_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    Ok(Some(referral))
+}
+
+fn load_referrer_stats(program_id: &Pubkey, stats_account: &AccountInfo) -> Result<ReferrerStats, ProgramError> {
+    if stats_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    ReferrerStats::try_from_slice(&stats_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)
+}
+
+/// Sends `amount` lamports from `payer` to the referrer and records it in
+/// their stats.
+pub fn pay_referrer<'a>(
+    program_id: &Pubkey,
+    payer: &AccountInfo<'a>,
+    referrer_key: &Pubkey,
+    referral: &ReferralAccounts<'a, '_>,
+    system_program: &AccountInfo<'a>,
+    amount: u64,
+) -> ProgramResult {
+    credit_referrer(program_id, referrer_key, referral, amount)?;
+
+    if amount > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, referral.referrer.key, amount),
+            &[payer.clone(), referral.referrer.clone(), system_program.clone()],
+        )?;
+    }
+
+    msg!("Paid {} lamports to referrer {}", amount, referrer_key);
+    Ok(())
+}
+
+/// Checks the referrer accounts against `referrer_key` and adds `amount` to
+/// their stats. The caller moves the lamports.
+pub fn credit_referrer(
+    program_id: &Pubkey,
+    referrer_key: &Pubkey,
+    referral: &ReferralAccounts,
+    amount: u64,
+) -> ProgramResult {
+    if referral.referrer.key != referrer_key {
+        return Err(VaultError::ReferrerMismatch.into());
+    }
+
+    let mut stats = load_referrer_stats(program_id, referral.stats)?;
+    if stats.referrer != *referrer_key {
+        return Err(VaultError::ReferrerMismatch.into());
+    }
+
+    stats.lamports_earned = stats.lamports_earned.try_add(amount)?;
+    stats.serialize(&mut &mut referral.stats.data.borrow_mut()[..])?;
+
+    Ok(())
+}
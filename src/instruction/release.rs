@@ -1,48 +1,47 @@
 use solana_program::{
-    account_info::{next_account_info, AccountInfo},
+    account_info::AccountInfo,
     entrypoint::ProgramResult,
     msg,
     program::invoke_signed,
-    pubkey::Pubkey
+    pubkey::Pubkey,
+};
+use crate::{
+    instruction::record_release,
+    state::{Vault, VaultStatus},
 };
-use crate::state::Vault;
 use spl_token::instruction as token_instruction;
-use borsh::BorshSerialize;
 
-pub fn release(
-    program_id: &Pubkey, 
-    accounts: &[AccountInfo],
-    vault: &mut Vault
+/// Transfers the vault's tokens to `destination`, takes it out of its owner's
+/// stats shard and marks it released. The caller must already have checked
+/// the vault address against its bump.
+pub fn release<'a>(
+    program_id: &Pubkey,
+    vault_account: &AccountInfo<'a>,
+    vault_ata: &AccountInfo<'a>,
+    destination: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+    stats_account: &AccountInfo<'a>,
+    now: u64,
 ) -> ProgramResult {
     msg!("Releasing tokens from the vault");
 
-    let account_info_iter = &mut accounts.iter();
-
-    let user = next_account_info(account_info_iter)?;
-    let vault_account = next_account_info(account_info_iter)?;
-    let vault_ata = next_account_info(account_info_iter)?;
-    let user_token_account = next_account_info(account_info_iter)?;
-    let _ = next_account_info(account_info_iter)?;
-    let _ = next_account_info(account_info_iter)?;
-    let token_program = next_account_info(account_info_iter)?;
-
-    let (pda, bump) = Pubkey::find_program_address(
-        &[
-            Vault::SEED_PREFIX.as_bytes(),
-            user.key.as_ref(),
-        ],
-        program_id
-    );
+    let vault = {
+        let vault_data = vault_account.data.borrow();
+        let vault = *Vault::load(&vault_data)?;
+        vault.check_transition(VaultStatus::Released)?;
+        vault
+    };
+    let amount_locked = u64::from(vault.amount_locked);
 
-    msg!("Releasing {} tokens", vault.amount_locked);
+    msg!("Releasing {} tokens", amount_locked);
 
     let transfer_instruction = token_instruction::transfer(
         token_program.key,
-        &vault_ata.key,
-        &user_token_account.key,
-        &pda,
-        &[&pda],
-        vault.amount_locked,
+        vault_ata.key,
+        destination.key,
+        vault_account.key,
+        &[vault_account.key],
+        amount_locked,
     )?;
 
     msg!("Signing the transfer");
@@ -51,24 +50,24 @@ pub fn release(
         &transfer_instruction,
         &[
             vault_ata.clone(),
-            user_token_account.clone(),
+            destination.clone(),
             vault_account.clone(),
             token_program.clone(),
         ],
-        &[&[
-            Vault::SEED_PREFIX.as_bytes(),
-            user.key.as_ref(),
-            &[bump]
-        ]],
+        &[&vault.signer_seeds()],
     )?;
 
-    vault.amount_locked = 0;
-    vault.is_locked = false;
-    vault.deposit_timestamp = 0;
-    vault.lock_duration = 0;
+    record_release(program_id, stats_account, &vault)?;
 
     let mut vault_data = vault_account.data.borrow_mut();
-    vault.serialize(&mut &mut vault_data[..])?;
-    
-    Ok(())
+    mark_released(Vault::load_mut(&mut vault_data)?, now)
+}
+
+/// Clears the lock of a vault whose balance has just been paid out and moves
+/// it to `Released`.
+pub fn mark_released(vault: &mut Vault, now: u64) -> ProgramResult {
+    vault.amount_locked = 0.into();
+    vault.deposit_timestamp = 0.into();
+    vault.lock_duration = 0.into();
+    vault.transition(VaultStatus::Released, now)
 }
\ No newline at end of file
@@ -0,0 +1,555 @@
+use {
+    crate::{
+        error::VaultError,
+        fee::{distribute_lamports, referral_cut},
+        instruction::{
+            charge_initialization_fee, check_claimable, check_mint_enabled, create_activity_log, credit_referrer,
+            load_config, load_mint_config, load_or_create_stats, load_referral, load_vault, mark_released,
+            record_activity, save_stats, settle_exit, sync_badges, BadgeAccounts, BadgePayer, ReferralAccounts,
+        },
+        math::CheckedMath,
+        state::{ActivityKind, Config, MintConfig, TimeBasis, Vault, VaultKind, VaultStatus},
+        utils::{check_program, check_signer, check_writable, create_pda_account, transfer_lamports},
+    },
+    solana_program::{
+        account_info::AccountInfo,
+        clock::Clock,
+        entrypoint::ProgramResult,
+        msg,
+        program::invoke,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        system_instruction,
+        system_program,
+        sysvar::Sysvar,
+    },
+    spl_token::native_mint,
+};
+
+/// Reads `owner`'s native SOL vault, rejecting token vaults.
+pub fn load_sol_vault(program_id: &Pubkey, owner: &Pubkey, vault_account: &AccountInfo) -> Result<Vault, ProgramError> {
+    let vault = load_vault(program_id, owner, vault_account)?;
+
+    if !vault.is_native() {
+        msg!("Vault {} holds tokens; use the token instructions", vault_account.key);
+        return Err(VaultError::WrongVaultAsset.into());
+    }
+
+    Ok(vault)
+}
+
+/// Moves `amount` lamports from `payer` into the vault.
+fn lock_lamports<'a>(
+    payer: &AccountInfo<'a>,
+    vault_account: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    amount: u64,
+) -> ProgramResult {
+    invoke(
+        &system_instruction::transfer(payer.key, vault_account.key, amount),
+        &[payer.clone(), vault_account.clone(), system_program.clone()],
+    )
+}
+
+/// Accounts for `InitializeSol`. The mint config is the native mint's, which
+/// must be registered and enabled, the stats account is the shard the owner's
+/// vaults are counted in, and the activity account is the new vault's
+/// activity log. Remaining accounts are as for `Initialize`:
+/// the fee recipients, the referral accounts when a referrer is given, and
+/// the badge group once SOL has access tiers.
+pub struct InitializeSolAccounts<'a, 'b> {
+    pub initializer: &'b AccountInfo<'a>,
+    pub vault_account: &'b AccountInfo<'a>,
+    pub system_program: &'b AccountInfo<'a>,
+    pub associated_token_program: &'b AccountInfo<'a>,
+    pub loyalty_account: &'b AccountInfo<'a>,
+    pub exemption_account: &'b AccountInfo<'a>,
+    pub stats_account: &'b AccountInfo<'a>,
+    pub activity_account: &'b AccountInfo<'a>,
+    pub fee_recipients: &'b [AccountInfo<'a>],
+    pub referral_accounts: &'b [AccountInfo<'a>],
+    pub badges: Option<BadgeAccounts<'a, 'b>>,
+    pub config: Config,
+    pub mint_config: MintConfig,
+    pub vault_bump: u8,
+}
+
+impl<'a, 'b> InitializeSolAccounts<'a, 'b> {
+    pub fn try_from(program_id: &Pubkey, accounts: &'b [AccountInfo<'a>]) -> Result<Self, ProgramError> {
+        let [
+            initializer,
+            vault_account,
+            config_account,
+            system_program,
+            associated_token_program,
+            loyalty_account,
+            exemption_account,
+            mint_config_account,
+            stats_account,
+            activity_account,
+            remaining_accounts @ ..,
+        ] = accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        check_signer(initializer)?;
+
+        for account in [initializer, vault_account, loyalty_account, stats_account, activity_account] {
+            check_writable(account)?;
+        }
+
+        check_program(system_program, &system_program::id())?;
+        check_program(associated_token_program, &spl_associated_token_account::id())?;
+
+        let config = load_config(program_id, config_account)?;
+        let mint_config = load_mint_config(program_id, &config, &native_mint::id(), mint_config_account)?;
+        check_mint_enabled(&mint_config)?;
+
+        let (remaining_accounts, badges) =
+            BadgeAccounts::require_from_tail(program_id, &mint_config, remaining_accounts)?;
+
+        let (fee_recipients, referral_accounts) = remaining_accounts
+            .split_at((config.fee_share_count as usize).min(remaining_accounts.len()));
+
+        let (vault_pda, vault_bump) = Vault::find_mint_address(initializer.key, &native_mint::id(), program_id);
+
+        if vault_pda != *vault_account.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        if !vault_account.data_is_empty() {
+            msg!("Vault {} already exists", vault_account.key);
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        Ok(Self {
+            initializer,
+            vault_account,
+            system_program,
+            associated_token_program,
+            loyalty_account,
+            exemption_account,
+            stats_account,
+            activity_account,
+            fee_recipients,
+            referral_accounts,
+            badges,
+            config,
+            mint_config,
+            vault_bump,
+        })
+    }
+}
+
+/// Creates the owner's native SOL vault and locks `amount` lamports in it,
+/// on top of the rent the vault account needs, which the owner also pays.
+/// The fee, lock and tiers are the native mint's.
+pub fn initialize_sol(
+    program_id: &Pubkey,
+    accounts: InitializeSolAccounts,
+    amount: u64,
+    referrer: Option<Pubkey>,
+    auto_renew: bool,
+    kind: VaultKind,
+    time_basis: TimeBasis,
+) -> ProgramResult {
+    let InitializeSolAccounts {
+        initializer,
+        vault_account,
+        system_program,
+        associated_token_program,
+        loyalty_account,
+        exemption_account,
+        stats_account,
+        activity_account,
+        fee_recipients,
+        referral_accounts,
+        badges,
+        config,
+        mint_config,
+        vault_bump: bump,
+    } = accounts;
+
+    msg!("User wants to lock {} lamports in a SOL vault", amount);
+
+    // A cooldown vault has no term to roll over
+    if auto_renew && kind != VaultKind::Fixed {
+        return Err(VaultError::WrongVaultKind.into());
+    }
+
+    create_pda_account(
+        initializer,
+        vault_account,
+        system_program,
+        program_id,
+        Vault::LEN,
+        &[Vault::SEED_PREFIX.as_bytes(), initializer.key.as_ref(), native_mint::id().as_ref(), &[bump]],
+    )?;
+
+    // Whatever the account holds now is its rent; the lock comes on top
+    let rent_lamports = vault_account.lamports();
+    lock_lamports(initializer, vault_account, system_program, amount)?;
+
+    let owner_bumps = Vault::find_owner_bumps(initializer.key, program_id);
+    let fee_lamports = charge_initialization_fee(
+        program_id,
+        initializer,
+        loyalty_account,
+        exemption_account,
+        fee_recipients,
+        referral_accounts,
+        system_program,
+        &config,
+        mint_config.initialization_fee_lamports,
+        referrer,
+        owner_bumps,
+    )?;
+
+    let mut stats = load_or_create_stats(program_id, initializer.key, initializer, stats_account, system_program)?;
+    stats.count_fee(fee_lamports)?;
+
+    let now = time_basis.now(&Clock::get()?);
+    let mut vault_data = vault_account.data.borrow_mut();
+    let vault = Vault::init(&mut vault_data)?;
+    vault.mint = native_mint::id();
+    stats.count_lock(vault, amount)?;
+    save_stats(&stats, stats_account)?;
+
+    vault.owner = *initializer.key;
+    vault.bump = bump;
+    (vault.exemption_bump, vault.referral_bump) = owner_bumps;
+    vault.rent_lamports = rent_lamports.into();
+    vault.amount_locked = amount.into();
+    vault.deposit_timestamp = now.into();
+    vault.lock_duration = time_basis.from_seconds(mint_config.lock_duration_for(kind), config.slots_per_day).into();
+    vault.auto_renew = auto_renew as u8;
+    vault.kind = kind as u8;
+    vault.time_basis = time_basis as u8;
+    vault.transition(VaultStatus::Locked, now)?;
+
+    let tier_amount = vault.tier_amount(now);
+    drop(vault_data);
+
+    create_activity_log(program_id, initializer, vault_account, activity_account, system_program)?;
+    record_activity(program_id, vault_account, activity_account, ActivityKind::Initialize, amount, fee_lamports)?;
+
+    if let Some(badges) = badges {
+        let payer = BadgePayer {
+            payer: initializer,
+            system_program,
+            associated_token_program,
+        };
+        let (tiers, badge_bumps) = (mint_config.active_access_tiers(), &mint_config.badge_bumps);
+        let mint = native_mint::id();
+        sync_badges(program_id, &badges, initializer, Some(&mint), tiers, badge_bumps, tier_amount, Some(&payer))?;
+    }
+
+    msg!("SOL vault initialized with {} lamports", amount);
+    Ok(())
+}
+
+/// Accounts for `DepositSol`. The stats account is the shard the owner's
+/// vaults are counted in and the activity account the vault's activity log,
+/// created if the vault predates the logs. The badge group comes last once
+/// SOL has access tiers.
+pub struct DepositSolAccounts<'a, 'b> {
+    pub initializer: &'b AccountInfo<'a>,
+    pub vault_account: &'b AccountInfo<'a>,
+    pub system_program: &'b AccountInfo<'a>,
+    pub associated_token_program: &'b AccountInfo<'a>,
+    pub stats_account: &'b AccountInfo<'a>,
+    pub activity_account: &'b AccountInfo<'a>,
+    pub badges: Option<BadgeAccounts<'a, 'b>>,
+    pub config: Config,
+    pub mint_config: MintConfig,
+    pub vault: Vault,
+}
+
+impl<'a, 'b> DepositSolAccounts<'a, 'b> {
+    pub fn try_from(program_id: &Pubkey, accounts: &'b [AccountInfo<'a>]) -> Result<Self, ProgramError> {
+        let [
+            initializer,
+            vault_account,
+            config_account,
+            system_program,
+            associated_token_program,
+            mint_config_account,
+            stats_account,
+            activity_account,
+            remaining_accounts @ ..,
+        ] = accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        check_signer(initializer)?;
+
+        for account in [initializer, vault_account, stats_account, activity_account] {
+            check_writable(account)?;
+        }
+
+        check_program(system_program, &system_program::id())?;
+        check_program(associated_token_program, &spl_associated_token_account::id())?;
+
+        let vault = load_sol_vault(program_id, initializer.key, vault_account)?;
+
+        let config = load_config(program_id, config_account)?;
+        let mint_config = load_mint_config(program_id, &config, &native_mint::id(), mint_config_account)?;
+        check_mint_enabled(&mint_config)?;
+
+        let (_, badges) = BadgeAccounts::require_from_tail(program_id, &mint_config, remaining_accounts)?;
+
+        Ok(Self {
+            initializer,
+            vault_account,
+            system_program,
+            associated_token_program,
+            stats_account,
+            activity_account,
+            badges,
+            config,
+            mint_config,
+            vault,
+        })
+    }
+}
+
+/// Adds `amount` lamports to the owner's SOL vault and restarts the lock.
+pub fn deposit_sol(program_id: &Pubkey, accounts: DepositSolAccounts, amount: u64) -> ProgramResult {
+    msg!("Depositing {} lamports into the SOL vault", amount);
+
+    let DepositSolAccounts {
+        initializer,
+        vault_account,
+        system_program,
+        associated_token_program,
+        stats_account,
+        activity_account,
+        badges,
+        config,
+        mint_config,
+        mut vault,
+    } = accounts;
+
+    // Check the status up front so a rejected deposit never moves lamports
+    let time_basis = vault.time_basis()?;
+    let now = time_basis.now(&Clock::get()?);
+    vault.mature(now)?;
+    vault.check_transition(VaultStatus::Locked)?;
+
+    lock_lamports(initializer, vault_account, system_program, amount)?;
+
+    let mut stats = load_or_create_stats(program_id, initializer.key, initializer, stats_account, system_program)?;
+
+    let mut vault_data = vault_account.data.borrow_mut();
+    let vault = Vault::load_mut(&mut vault_data)?;
+
+    stats.count_lock(vault, amount)?;
+    save_stats(&stats, stats_account)?;
+
+    vault.amount_locked = u64::from(vault.amount_locked).try_add(amount)?.into();
+    vault.deposit_timestamp = now.into();
+    let lock_duration = mint_config.lock_duration_for(vault.kind()?);
+    vault.lock_duration = time_basis.from_seconds(lock_duration, config.slots_per_day).into();
+    vault.transition(VaultStatus::Locked, now)?;
+
+    let tier_amount = vault.tier_amount(now);
+    drop(vault_data);
+
+    create_activity_log(program_id, initializer, vault_account, activity_account, system_program)?;
+    record_activity(program_id, vault_account, activity_account, ActivityKind::Deposit, amount, 0)?;
+
+    if let Some(badges) = badges {
+        let payer = BadgePayer {
+            payer: initializer,
+            system_program,
+            associated_token_program,
+        };
+        let (tiers, badge_bumps) = (mint_config.active_access_tiers(), &mint_config.badge_bumps);
+        let mint = native_mint::id();
+        sync_badges(program_id, &badges, initializer, Some(&mint), tiers, badge_bumps, tier_amount, Some(&payer))?;
+    }
+
+    Ok(())
+}
+
+/// Accounts for `WithdrawSol`. The stats account is the shard the owner's
+/// vaults are counted in and the activity account the vault's activity log,
+/// created if the vault predates the logs. Remaining accounts are as for `Withdraw`: the
+/// fee recipients, the referral record, the referrer and referrer stats if
+/// one is recorded, and the badge group once SOL has access tiers.
+pub struct WithdrawSolAccounts<'a, 'b> {
+    pub user: &'b AccountInfo<'a>,
+    pub vault_account: &'b AccountInfo<'a>,
+    pub system_program: &'b AccountInfo<'a>,
+    pub loyalty_account: &'b AccountInfo<'a>,
+    pub exemption_account: &'b AccountInfo<'a>,
+    pub stats_account: &'b AccountInfo<'a>,
+    pub activity_account: &'b AccountInfo<'a>,
+    pub fee_recipients: &'b [AccountInfo<'a>],
+    pub referral_accounts: &'b [AccountInfo<'a>],
+    pub badges: Option<BadgeAccounts<'a, 'b>>,
+    pub config: Config,
+    pub mint_config: MintConfig,
+    pub vault: Vault,
+}
+
+impl<'a, 'b> WithdrawSolAccounts<'a, 'b> {
+    pub fn try_from(program_id: &Pubkey, accounts: &'b [AccountInfo<'a>]) -> Result<Self, ProgramError> {
+        let [
+            user,
+            vault_account,
+            config_account,
+            system_program,
+            loyalty_account,
+            exemption_account,
+            mint_config_account,
+            stats_account,
+            activity_account,
+            remaining_accounts @ ..,
+        ] = accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        check_signer(user)?;
+
+        for account in [user, vault_account, loyalty_account, stats_account, activity_account] {
+            check_writable(account)?;
+        }
+
+        check_program(system_program, &system_program::id())?;
+
+        let config = load_config(program_id, config_account)?;
+        let vault = load_sol_vault(program_id, user.key, vault_account)?;
+        let mint_config = load_mint_config(program_id, &config, &native_mint::id(), mint_config_account)?;
+
+        let (remaining_accounts, badges) =
+            BadgeAccounts::require_from_tail(program_id, &mint_config, remaining_accounts)?;
+
+        let (fee_recipients, referral_accounts) = remaining_accounts
+            .split_at((config.fee_share_count as usize).min(remaining_accounts.len()));
+
+        Ok(Self {
+            user,
+            vault_account,
+            system_program,
+            loyalty_account,
+            exemption_account,
+            stats_account,
+            activity_account,
+            fee_recipients,
+            referral_accounts,
+            badges,
+            config,
+            mint_config,
+            vault,
+        })
+    }
+}
+
+/// Releases the owner's SOL vault, leaving its rent behind. A fixed vault
+/// still in its lock pays the early-withdrawal fee out of the released
+/// lamports; once the lock has run out, or a cooldown vault's cooldown has
+/// passed, everything locked goes back to the owner.
+pub fn withdraw_sol(program_id: &Pubkey, accounts: WithdrawSolAccounts) -> ProgramResult {
+    msg!("Withdrawing from the SOL vault");
+
+    let WithdrawSolAccounts {
+        user,
+        vault_account,
+        system_program,
+        loyalty_account,
+        exemption_account,
+        stats_account,
+        activity_account,
+        fee_recipients,
+        referral_accounts,
+        badges,
+        config,
+        mint_config,
+        mut vault,
+    } = accounts;
+
+    let now = vault.now(&Clock::get()?)?;
+
+    vault.mature(now)?;
+    let is_early = vault.kind()? == VaultKind::Fixed && vault.status()? == VaultStatus::Locked;
+
+    if !is_early {
+        check_claimable(&mut vault, now)?;
+    }
+    vault.check_transition(VaultStatus::Released)?;
+
+    let amount_locked = u64::from(vault.amount_locked);
+
+    // Lamports sent to the vault outside the program stay with it
+    if vault_account.lamports() < u64::from(vault.rent_lamports).saturating_add(amount_locked) {
+        msg!("Vault {} holds less than its rent and locked lamports", vault_account.key);
+        return Err(ProgramError::InsufficientFunds);
+    }
+
+    // The native mint charges no token fee
+    let (fee_lamports, _) = settle_exit(
+        program_id,
+        user,
+        loyalty_account,
+        exemption_account,
+        system_program,
+        &config,
+        &mint_config,
+        &vault,
+        now,
+        is_early,
+    )?;
+
+    // The fee comes out of the lock, so it can't be more than the lock
+    let fee_lamports = fee_lamports.min(amount_locked);
+
+    let mut stats = load_or_create_stats(program_id, user.key, user, stats_account, system_program)?;
+    stats.count_fee(fee_lamports)?;
+    stats.count_release(&vault);
+    save_stats(&stats, stats_account)?;
+
+    create_activity_log(program_id, user, vault_account, activity_account, system_program)?;
+
+    if is_early {
+        let (referral_account, referral_accounts) = referral_accounts
+            .split_first()
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+        let mut fee_lamports = fee_lamports;
+
+        let referral_bump = vault.referral_bump(program_id);
+        if let Some(recorded) = load_referral(program_id, user.key, referral_bump, referral_account)? {
+            let referral = ReferralAccounts::from_slice(referral_accounts)?;
+            let cut = referral_cut(fee_lamports, config.referral_bps);
+            credit_referrer(program_id, &recorded.referrer, &referral, cut)?;
+            transfer_lamports(vault_account, referral.referrer, cut)?;
+            fee_lamports = fee_lamports.try_sub(cut)?;
+        }
+
+        msg!("Paying the fee out of the vault");
+        distribute_lamports(vault_account, fee_recipients, &config, fee_lamports)?;
+    }
+
+    let released = amount_locked.try_sub(fee_lamports)?;
+    msg!("Releasing {} lamports", released);
+    transfer_lamports(vault_account, user, released)?;
+
+    let mut vault_data = vault_account.data.borrow_mut();
+    mark_released(Vault::load_mut(&mut vault_data)?, now)?;
+    drop(vault_data);
+
+    let kind = ActivityKind::Withdraw;
+    record_activity(program_id, vault_account, activity_account, kind, amount_locked, fee_lamports)?;
+
+    // A released vault counts toward no tier
+    if let Some(badges) = badges {
+        let badge_bumps = &mint_config.badge_bumps;
+        sync_badges(program_id, &badges, user, Some(&native_mint::id()), &[], badge_bumps, 0, None)?;
+    }
+
+    Ok(())
+}
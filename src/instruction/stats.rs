@@ -0,0 +1,95 @@
+use {
+    crate::{
+        state::{StatsShard, Vault},
+        utils::create_pda_account,
+    },
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::AccountInfo,
+        entrypoint::ProgramResult,
+        msg,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+    },
+};
+
+/// Reads the stats shard `owner`'s vaults are counted in, creating it at
+/// `payer`'s expense the first time anyone in the shard locks.
+pub fn load_or_create_stats<'a>(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    payer: &AccountInfo<'a>,
+    stats_account: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+) -> Result<StatsShard, ProgramError> {
+    if !stats_account.data_is_empty() {
+        return load_stats(program_id, owner, stats_account);
+    }
+
+    let shard = StatsShard::shard_for(owner);
+    let (stats_pda, bump) = StatsShard::find_address(shard, program_id);
+    if stats_pda != *stats_account.key {
+        msg!("Stats account is not shard {}", shard);
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    create_pda_account(
+        payer,
+        stats_account,
+        system_program,
+        program_id,
+        StatsShard::LEN,
+        &[StatsShard::SEED_PREFIX.as_bytes(), &[shard], &[bump]],
+    )?;
+
+    Ok(StatsShard {
+        shard,
+        bump,
+        ..StatsShard::default()
+    })
+}
+
+/// Reads an existing stats shard and checks it is the one `owner`'s vaults
+/// are counted in.
+pub fn load_stats(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    stats_account: &AccountInfo,
+) -> Result<StatsShard, ProgramError> {
+    if stats_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let stats = StatsShard::try_from_slice(&stats_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    let expected = Pubkey::create_program_address(
+        &[StatsShard::SEED_PREFIX.as_bytes(), &[stats.shard], &[stats.bump]],
+        program_id,
+    )?;
+
+    if expected != *stats_account.key || stats.shard != StatsShard::shard_for(owner) {
+        msg!("Stats account is not shard {}", StatsShard::shard_for(owner));
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    Ok(stats)
+}
+
+pub fn save_stats(stats: &StatsShard, stats_account: &AccountInfo) -> ProgramResult {
+    stats.serialize(&mut &mut stats_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+/// Takes `vault` out of its owner's stats shard as it is released, before
+/// its lock is cleared. The shard is only read for a vault the stats have
+/// seen, so releasing a vault locked before the shards existed needs none.
+pub fn record_release(program_id: &Pubkey, stats_account: &AccountInfo, vault: &Vault) -> ProgramResult {
+    if !vault.is_counted() {
+        return Ok(());
+    }
+
+    let mut stats = load_stats(program_id, &vault.owner, stats_account)?;
+    stats.count_release(vault);
+    save_stats(&stats, stats_account)
+}
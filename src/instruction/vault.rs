@@ -0,0 +1,89 @@
+use {
+    crate::{
+        error::VaultError,
+        instruction::is_kuza_mint,
+        state::Vault,
+    },
+    solana_program::{
+        account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError,
+        program_pack::Pack, pubkey::Pubkey,
+    },
+    spl_associated_token_account::get_associated_token_address,
+    spl_token::state::Account as TokenAccount,
+};
+
+/// Reads `owner`'s vault, checking that the program owns it and that it sits
+/// at the address derived from its stored mint and bump.
+pub fn load_vault(program_id: &Pubkey, owner: &Pubkey, vault_account: &AccountInfo) -> Result<Vault, ProgramError> {
+    if vault_account.owner != program_id {
+        msg!("Vault {} is owned by {}", vault_account.key, vault_account.owner);
+        return Err(VaultError::VaultNotProgramOwned.into());
+    }
+
+    let vault = *Vault::load(&vault_account.data.borrow())?;
+
+    // A bump that doesn't derive a valid address is a mismatch too
+    let expected = vault.own_address(program_id).ok();
+
+    if vault.owner != *owner || expected != Some(*vault_account.key) {
+        msg!("Vault {} does not belong to {}", vault_account.key, owner);
+        return Err(VaultError::VaultAddressMismatch.into());
+    }
+
+    Ok(vault)
+}
+
+/// Checks that `vault_ata` is the vault's associated token account for its
+/// mint and returns that mint: the registered mint the vault was opened for,
+/// or either KUZA mint. Native SOL vaults have no token account.
+pub fn check_vault_token_account(
+    vault_account: &AccountInfo,
+    vault: &Vault,
+    vault_ata: &AccountInfo,
+) -> Result<Pubkey, ProgramError> {
+    if vault.is_native() {
+        msg!("Vault {} holds native SOL; use the SOL instructions", vault_account.key);
+        return Err(VaultError::WrongVaultAsset.into());
+    }
+
+    if *vault_ata.owner != spl_token::id() {
+        msg!("Vault token account {} is not a token account", vault_ata.key);
+        return Err(VaultError::InvalidVaultTokenAccount.into());
+    }
+
+    let token_account = TokenAccount::unpack(&vault_ata.data.borrow())
+        .map_err(|_| VaultError::InvalidVaultTokenAccount)?;
+
+    let mint_matches = match vault.registered_mint() {
+        Some(mint) => token_account.mint == mint,
+        None => is_kuza_mint(&token_account.mint),
+    };
+
+    if token_account.owner != *vault_account.key
+        || !mint_matches
+        || get_associated_token_address(vault_account.key, &token_account.mint) != *vault_ata.key
+    {
+        msg!("Vault token account {} is not the vault's token account", vault_ata.key);
+        return Err(VaultError::InvalidVaultTokenAccount.into());
+    }
+
+    Ok(token_account.mint)
+}
+
+/// Checks that `user_token_account` is a token account of `owner` holding `mint`.
+pub fn check_user_token_account(user_token_account: &AccountInfo, owner: &Pubkey, mint: &Pubkey) -> ProgramResult {
+    if *user_token_account.owner != spl_token::id() {
+        msg!("User token account {} is not a token account", user_token_account.key);
+        return Err(VaultError::InvalidUserTokenAccount.into());
+    }
+
+    let token_account = TokenAccount::unpack(&user_token_account.data.borrow())
+        .map_err(|_| VaultError::InvalidUserTokenAccount)?;
+
+    if token_account.owner != *owner || token_account.mint != *mint {
+        msg!("User token account {} is not {}'s {} account", user_token_account.key, owner, mint);
+        return Err(VaultError::InvalidUserTokenAccount.into());
+    }
+
+    Ok(())
+}
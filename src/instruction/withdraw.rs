@@ -1,116 +1,347 @@
 use {
-    crate::state::Vault, 
-    borsh::BorshDeserialize, 
+    crate::{
+        error::VaultError,
+        fee::{apply_discount, distribute_sol, distribute_tokens, early_withdrawal_fee, referral_cut},
+        instruction::{
+            burn_receipts, check_receipt_mint, check_user_token_account, check_vault_token_account,
+            create_activity_log, is_fee_exempt, load_config, load_mint_config, load_or_create_loyalty,
+            load_or_create_stats, load_referral, load_vault, pay_referrer, record_activity, release, save_loyalty,
+            save_stats, sync_badges, BadgeAccounts, ReceiptAccounts, ReferralAccounts,
+        },
+        math::{elapsed, CheckedMath},
+        state::{ActivityKind, Config, MintConfig, Vault, VaultKind, VaultStatus},
+        utils::{check_program, check_signer, check_writable},
+    },
     solana_program::{
-        account_info::{next_account_info, AccountInfo},
+        account_info::AccountInfo,
         clock::Clock,
         entrypoint::ProgramResult,
         msg,
-        program::invoke,
         program_error::ProgramError,
         pubkey::Pubkey,
         sysvar::Sysvar,
-        system_instruction
+        system_program,
     },
-    crate::release
 };
 
-const MAINNET_FEE_RECEIVER: Pubkey = Pubkey::from_str_const("5zaUUZWoXaWt2Ht5NNQZuQXyfaQKDLyQoESn6BXvVzBd");
-const DEVNET_FEE_RECEIVER: Pubkey = Pubkey::from_str_const("8jHMkdtKK4CCn4ep6Hponmk1ik7ofUNS9bX9qSuiRcN5");
+pub const MAX_EARLY_WITHDRAWAL_FEE_LAMPORTS: u64 = 5_000_000_000;
 
-fn get_fee_receiver(fee_receiver: &AccountInfo) -> Result<Pubkey, ProgramError> {
-    if *fee_receiver.key == MAINNET_FEE_RECEIVER {
-        Ok(MAINNET_FEE_RECEIVER)
-    } else if *fee_receiver.key == DEVNET_FEE_RECEIVER {
-        Ok(DEVNET_FEE_RECEIVER)
-    } else {
-        Err(ProgramError::InvalidAccountData)
+/// Accounts for `Withdraw`. The mint config is only read for a vault of a
+/// registered mint, which may since have been disabled. The stats account is
+/// the shard the owner's vaults are counted in, and the activity account the
+/// vault's activity log, created if the vault predates the logs. Remaining
+/// accounts are the fee recipients, in config order, then their token
+/// accounts for the vault's mint in the same order, then the referral record,
+/// then the referrer and referrer stats if one is recorded, then the receipt
+/// mint and the owner's receipt account if the vault has receipts, and last
+/// the badge group once the mint has access tiers.
+pub struct WithdrawAccounts<'a, 'b> {
+    pub user: &'b AccountInfo<'a>,
+    pub vault_account: &'b AccountInfo<'a>,
+    pub vault_ata: &'b AccountInfo<'a>,
+    pub user_token_account: &'b AccountInfo<'a>,
+    pub system_program: &'b AccountInfo<'a>,
+    pub token_program: &'b AccountInfo<'a>,
+    pub loyalty_account: &'b AccountInfo<'a>,
+    pub exemption_account: &'b AccountInfo<'a>,
+    pub stats_account: &'b AccountInfo<'a>,
+    pub activity_account: &'b AccountInfo<'a>,
+    pub token_mint: &'b AccountInfo<'a>,
+    pub fee_recipients: &'b [AccountInfo<'a>],
+    pub fee_token_accounts: &'b [AccountInfo<'a>],
+    pub referral_accounts: &'b [AccountInfo<'a>],
+    pub receipts: Option<ReceiptAccounts<'a, 'b>>,
+    pub badges: Option<BadgeAccounts<'a, 'b>>,
+    pub config: Config,
+    pub mint_config: MintConfig,
+    pub vault: Vault,
+}
+
+impl<'a, 'b> WithdrawAccounts<'a, 'b> {
+    pub fn try_from(program_id: &Pubkey, accounts: &'b [AccountInfo<'a>]) -> Result<Self, ProgramError> {
+        let [
+            user,
+            vault_account,
+            vault_ata,
+            user_token_account,
+            config_account,
+            system_program,
+            token_program,
+            loyalty_account,
+            exemption_account,
+            mint_config_account,
+            stats_account,
+            activity_account,
+            token_mint,
+            remaining_accounts @ ..,
+        ] = accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        check_signer(user)?;
+
+        let writable = [user, vault_account, vault_ata, user_token_account, loyalty_account, stats_account];
+        for account in writable.into_iter().chain([activity_account]) {
+            check_writable(account)?;
+        }
+
+        check_program(system_program, &system_program::id())?;
+        check_program(token_program, &spl_token::id())?;
+
+        let config = load_config(program_id, config_account)?;
+
+        let vault = load_vault(program_id, user.key, vault_account)?;
+        let mint = check_vault_token_account(vault_account, &vault, vault_ata)?;
+        check_user_token_account(user_token_account, user.key, &mint)?;
+
+        if *token_mint.key != mint {
+            msg!("Token mint {} is not the vault's mint", token_mint.key);
+            return Err(VaultError::InvalidMint.into());
+        }
+
+        let mint_config = load_mint_config(program_id, &config, &mint, mint_config_account)?;
+
+        let (remaining_accounts, badges) =
+            BadgeAccounts::require_from_tail(program_id, &mint_config, remaining_accounts)?;
+
+        let (remaining_accounts, receipts) = if vault.has_receipts() {
+            let (remaining_accounts, receipts) = ReceiptAccounts::from_tail(remaining_accounts)?;
+            check_writable(receipts.receipt_mint)?;
+            check_writable(receipts.receipt_account)?;
+            check_receipt_mint(program_id, vault_account, &vault, receipts.receipt_mint)?;
+            (remaining_accounts, Some(receipts))
+        } else {
+            (remaining_accounts, None)
+        };
+
+        let fee_share_count = config.fee_share_count as usize;
+        let (fee_recipients, remaining_accounts) =
+            remaining_accounts.split_at(fee_share_count.min(remaining_accounts.len()));
+        let (fee_token_accounts, referral_accounts) =
+            remaining_accounts.split_at(fee_share_count.min(remaining_accounts.len()));
+
+        Ok(Self {
+            user,
+            vault_account,
+            vault_ata,
+            user_token_account,
+            system_program,
+            token_program,
+            loyalty_account,
+            exemption_account,
+            stats_account,
+            activity_account,
+            token_mint,
+            fee_recipients,
+            fee_token_accounts,
+            referral_accounts,
+            receipts,
+            badges,
+            config,
+            mint_config,
+            vault,
+        })
     }
 }
 
-pub fn withdraw(
-    program_id: &Pubkey, 
-    accounts: &[AccountInfo]
-) -> ProgramResult {
+pub fn withdraw(program_id: &Pubkey, accounts: WithdrawAccounts) -> ProgramResult {
     msg!("Withdrawing funds from the vault early");
 
-    let account_info_iter = &mut accounts.iter();
+    let WithdrawAccounts {
+        user,
+        vault_account,
+        vault_ata,
+        user_token_account,
+        system_program,
+        token_program,
+        loyalty_account,
+        exemption_account,
+        stats_account,
+        activity_account,
+        token_mint,
+        fee_recipients,
+        fee_token_accounts,
+        referral_accounts,
+        receipts,
+        badges,
+        config,
+        mint_config,
+        mut vault,
+    } = accounts;
 
-    let user = next_account_info(account_info_iter)?;
-    let vault_account = next_account_info(account_info_iter)?;
-    let vault_ata = next_account_info(account_info_iter)?;
-    let _ = next_account_info(account_info_iter)?;
-    let fee_receiver = next_account_info(account_info_iter)?;
-    let _ = next_account_info(account_info_iter)?;
-    let _ = next_account_info(account_info_iter)?;
+    // Cooldown vaults exit through RequestUnlock and Claim
+    vault.check_kind(VaultKind::Fixed)?;
 
-    if !user.is_signer {
-        return Err(ProgramError::MissingRequiredSignature);
-    }
+    let now = vault.now(&Clock::get()?)?;
 
-    let fee_receiver_key = get_fee_receiver(&fee_receiver).map_err(|_| ProgramError::InvalidAccountData)?;
+    vault.mature(now)?;
+    vault.check_transition(VaultStatus::Released)?;
+    let is_early = vault.status()? == VaultStatus::Locked;
 
-    if *fee_receiver.key != fee_receiver_key {
-        return Err(ProgramError::InvalidAccountData);
+    // Receipt holders own the position, so the owner ends it only by handing
+    // every receipt back
+    if let Some(receipts) = receipts {
+        let amount_locked = u64::from(vault.amount_locked);
+        burn_receipts(user, receipts.receipt_mint, receipts.receipt_account, token_program, amount_locked)?;
     }
 
-    let (pda, _) = Pubkey::find_program_address(
-        &[
-            Vault::SEED_PREFIX.as_bytes(),
-            user.key.as_ref(),
-        ],
-        program_id
-    );
+    let (fee_lamports, fee_tokens) = settle_exit(
+        program_id,
+        user,
+        loyalty_account,
+        exemption_account,
+        system_program,
+        &config,
+        &mint_config,
+        &vault,
+        now,
+        is_early,
+    )?;
+
+    // The token fee comes out of the released tokens, so it can't be more
+    // than the lock
+    let fee_tokens = fee_tokens.min(u64::from(vault.amount_locked));
+
+    let mut stats = load_or_create_stats(program_id, user.key, user, stats_account, system_program)?;
+    stats.count_fee(fee_lamports)?;
+    save_stats(&stats, stats_account)?;
+
+    if is_early {
+        let (referral_account, referral_accounts) = referral_accounts
+            .split_first()
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+        let mut fee_lamports = fee_lamports;
+
+        let referral_bump = vault.referral_bump(program_id);
+        if let Some(recorded) = load_referral(program_id, user.key, referral_bump, referral_account)? {
+            let referral = ReferralAccounts::from_slice(referral_accounts)?;
+            let cut = referral_cut(fee_lamports, config.referral_bps);
+            pay_referrer(program_id, user, &recorded.referrer, &referral, system_program, cut)?;
+            fee_lamports = fee_lamports.try_sub(cut)?;
+        }
 
-    if *vault_account.key != pda {
-        return Err(ProgramError::InvalidAccountData);
+        // Transfer SOL fee, split across the configured recipients
+        msg!("Transferring SOL fee to the fee recipients");
+        distribute_sol(
+            user,
+            fee_recipients,
+            system_program,
+            &config,
+            fee_lamports,
+        )?;
+
+        msg!("Attempting to release tokens from the vault");
+        release(program_id, vault_account, vault_ata, user_token_account, token_program, stats_account, now)?;
+
+        if fee_tokens > 0 {
+            msg!("Transferring token fee to the fee recipients");
+            distribute_tokens(
+                user_token_account,
+                user,
+                token_mint,
+                fee_token_accounts,
+                token_program,
+                &config,
+                fee_tokens,
+            )?;
+        }
+    } else {
+        msg!("Vault is not locked, so it's free to release");
+        release(program_id, vault_account, vault_ata, user_token_account, token_program, stats_account, now)?;
     }
 
-    if *vault_ata.owner != spl_token::id() {
-        msg!("Vault ATA is not owned by the vault");
-        return Err(ProgramError::InvalidAccountData);
+    let amount = u64::from(vault.amount_locked);
+    create_activity_log(program_id, user, vault_account, activity_account, system_program)?;
+    record_activity(program_id, vault_account, activity_account, ActivityKind::Withdraw, amount, fee_lamports)?;
+
+    // A released vault counts toward no tier
+    if let Some(badges) = badges {
+        let badge_bumps = &mint_config.badge_bumps;
+        sync_badges(program_id, &badges, user, vault.registered_mint().as_ref(), &[], badge_bumps, 0, None)?;
     }
 
-    let vault_data = vault_account.data.borrow_mut();
-    let mut vault = Vault::deserialize(&mut &vault_data[..])?;
-    // Drop the borrow here
-    drop(vault_data);
+    Ok(())
+}
+
+/// Records an owner's exit from `vault` at `now` in their loyalty record and
+/// returns the early-withdrawal fees due, in lamports and in the mint's
+/// tokens: the mint's fees for the time into the current term, after the
+/// loyalty discount, or nothing for an exempt wallet or a vault whose lock
+/// has run out. `now` is in the vault's time basis. Shared by `Withdraw` and
+/// `WithdrawSol`.
+#[allow(clippy::too_many_arguments)]
+pub fn settle_exit<'a>(
+    program_id: &Pubkey,
+    user: &AccountInfo<'a>,
+    loyalty_account: &AccountInfo<'a>,
+    exemption_account: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    config: &Config,
+    mint_config: &MintConfig,
+    vault: &Vault,
+    now: u64,
+    is_early: bool,
+) -> Result<(u64, u64), ProgramError> {
+    let deposit_timestamp = u64::from(vault.deposit_timestamp);
+    let lock_duration = u64::from(vault.lock_duration);
+
+    // Times are in the vault's basis; loyalty and the logs count seconds
+    let time_basis = vault.time_basis()?;
+    let to_seconds = |units| time_basis.to_seconds(units, config.slots_per_day);
+
+    // A clock running behind the deposit counts as no time locked
+    let time_locked = elapsed(now, deposit_timestamp);
+
+    // The fee is priced on the current term; auto-renewed terms start over
+    let term_elapsed = elapsed(now, vault.term_start(now));
 
-    let time_elasped_in_days: u64 = (Clock::get()?.unix_timestamp as u64  - vault.deposit_timestamp) / 86400;
+    let time_elasped_in_days: u64 = to_seconds(term_elapsed) / 86400;
     msg!("Time elasped in days: {}", time_elasped_in_days);
 
-    let duration_in_days: u64 = vault.lock_duration / 86400;
+    let duration_in_days: u64 = to_seconds(lock_duration) / 86400;
     msg!("Lock Period Duration In Days: {}", duration_in_days);
 
-    if time_elasped_in_days < duration_in_days {
-        msg!("Vault is still within lock period");
+    let mut loyalty = load_or_create_loyalty(program_id, user, loyalty_account, system_program)?;
+    // The discount is priced on history before this exit
+    let discount_bps = config.loyalty_discount_bps(loyalty.completed_locks);
 
-        let percentage_of_lock_period: f64 = (time_elasped_in_days as f64 / duration_in_days as f64) * 100.0;
-        msg!("Percentage of lock period completed: {}%", percentage_of_lock_period);
+    loyalty.total_time_locked = loyalty.total_time_locked.try_add(to_seconds(time_locked))?;
+    if is_early {
+        loyalty.early_exits = loyalty.early_exits.try_add(1)?;
+    } else {
+        loyalty.completed_locks = loyalty.completed_locks.try_add(1)?;
+    }
+    save_loyalty(&loyalty, loyalty_account)?;
 
-        let fee_percentage: f64 = 0.75 * (1.0 - percentage_of_lock_period / 100.0);
-        msg!("Early withdrawal fee percentage: {}%", fee_percentage * 100.0);
+    if !is_early {
+        return Ok((0, 0));
+    }
 
-        let total_amount_in_lamports: u64 = (5_000_000_000f64 * fee_percentage) as u64;
-        msg!("Total fee in Lamports: {}", total_amount_in_lamports);
+    msg!("Vault is still within lock period");
 
-         // Transfer SOL fee
-         msg!("Transferring SOL fee to the fee receiver");
-        let sol_transfer_instruction = system_instruction::transfer(
-            user.key,
-            fee_receiver.key,
-            total_amount_in_lamports
-        );
+    // The curve falls by whole days, which for a slot vault are
+    // `slots_per_day` slots
+    let fee_for = |max_fee| early_withdrawal_fee(to_seconds(term_elapsed), to_seconds(lock_duration), max_fee);
 
-        msg!("Invoking SOL transfer instruction");
-        invoke(&sol_transfer_instruction, accounts)?;
+    let total_amount_in_lamports = fee_for(mint_config.max_early_withdrawal_fee_lamports)?;
+    let total_amount_in_tokens = fee_for(mint_config.max_early_withdrawal_fee_tokens)?;
+    msg!("Total fee in Lamports: {}, in tokens: {}", total_amount_in_lamports, total_amount_in_tokens);
 
-        msg!("Attempting to release tokens from the vault");
-        release(program_id, accounts, &mut vault)?;
-        return Ok(());
-    } else {
-        msg!("Vault is not locked, so it's free to release");
-        release(program_id, accounts, &mut vault)?;
-        return Ok(());
+    if is_fee_exempt(program_id, user.key, vault.exemption_bump(program_id), exemption_account)? {
+        msg!("Wallet is exempt from fees");
+        return Ok((0, 0));
     }
-}
\ No newline at end of file
+
+    let total_amount_in_lamports = apply_discount(total_amount_in_lamports, discount_bps);
+    let total_amount_in_tokens = apply_discount(total_amount_in_tokens, discount_bps);
+    msg!(
+        "Fee after {} bps loyalty discount: {} lamports, {} tokens",
+        discount_bps,
+        total_amount_in_lamports,
+        total_amount_in_tokens
+    );
+
+    Ok((total_amount_in_lamports, total_amount_in_tokens))
+}
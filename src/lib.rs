@@ -2,9 +2,17 @@ use solana_program::entrypoint;
 
 use processor::process_instruction;
 
+#[cfg(not(target_os = "solana"))]
+pub mod client;
+pub mod error;
+#[macro_use]
+pub mod idl;
+pub mod fee;
+pub mod math;
 pub mod state;
 pub mod instruction;
 pub mod processor;
+pub mod utils;
 
 pub use instruction::*;
 
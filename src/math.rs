@@ -0,0 +1,65 @@
+//! Checked arithmetic for amounts, lamports, counters and times.
+//!
+//! Handlers go through these helpers rather than the bare operators, so an
+//! overflow fails the instruction with [`VaultError::ArithmeticOverflow`]
+//! instead of panicking or wrapping.
+
+use {
+    crate::error::VaultError,
+    solana_program::{msg, program_error::ProgramError},
+};
+
+/// Arithmetic that fails with [`VaultError::ArithmeticOverflow`] when the
+/// result doesn't fit.
+pub trait CheckedMath: Sized {
+    fn try_add(self, rhs: Self) -> Result<Self, ProgramError>;
+
+    fn try_sub(self, rhs: Self) -> Result<Self, ProgramError>;
+
+    fn try_mul(self, rhs: Self) -> Result<Self, ProgramError>;
+}
+
+macro_rules! impl_checked_math {
+    ($($ty:ty),*) => {
+        $(
+            impl CheckedMath for $ty {
+                fn try_add(self, rhs: Self) -> Result<Self, ProgramError> {
+                    self.checked_add(rhs).ok_or_else(overflow)
+                }
+
+                fn try_sub(self, rhs: Self) -> Result<Self, ProgramError> {
+                    self.checked_sub(rhs).ok_or_else(overflow)
+                }
+
+                fn try_mul(self, rhs: Self) -> Result<Self, ProgramError> {
+                    self.checked_mul(rhs).ok_or_else(overflow)
+                }
+            }
+        )*
+    };
+}
+
+impl_checked_math!(u32, u64, u128);
+
+fn overflow() -> ProgramError {
+    msg!("Arithmetic overflow");
+    VaultError::ArithmeticOverflow.into()
+}
+
+/// `tokens` whole tokens in base units of a mint with `decimals`.
+pub fn to_base_units(tokens: u64, decimals: u8) -> Result<u64, ProgramError> {
+    let scale = 10u64.checked_pow(decimals as u32).ok_or_else(overflow)?;
+    tokens.try_mul(scale)
+}
+
+/// Time from `since` to `now`, clamped at zero for a clock running behind.
+pub fn elapsed(now: u64, since: u64) -> u64 {
+    now.saturating_sub(since)
+}
+
+/// A `Clock::unix_timestamp` as unsigned seconds. Validators may skew the
+/// clock, and a timestamp before the epoch reads as zero rather than wrapping
+/// to a far-future time.
+pub fn unix_seconds(timestamp: i64) -> u64 {
+    u64::try_from(timestamp).unwrap_or(0)
+}
@@ -5,7 +5,10 @@ use solana_program::{
 
 use borsh::{BorshDeserialize, BorshSerialize};
 
-use crate::instruction::*;
+use crate::{
+    instruction::*,
+    state::{AccessTier, FeeExemptionReason, FeeShare, LoyaltyTier, TimeBasis, VaultKind},
+};
 
 pub fn process_instruction(
     program_id: &Pubkey,
@@ -17,15 +20,484 @@ pub fn process_instruction(
         .map_err(|_| ProgramError::InvalidInstructionData)?;
 
     match instruction {
-        VaultInstruction::Initialize { amount } => initialize(program_id, accounts, amount),
-        VaultInstruction::Deposit { amount } => deposit(program_id, accounts, amount),
-        VaultInstruction::Withdraw => withdraw(program_id, accounts),
+        VaultInstruction::Initialize { amount, referrer, auto_renew, kind, time_basis, receipts } => {
+            let accounts = InitializeAccounts::try_from(program_id, accounts)?;
+            initialize(program_id, accounts, amount, referrer, auto_renew, kind, time_basis, receipts)
+        }
+        VaultInstruction::Deposit { amount } => {
+            deposit(program_id, DepositAccounts::try_from(program_id, accounts)?, amount)
+        }
+        VaultInstruction::Withdraw => {
+            withdraw(program_id, WithdrawAccounts::try_from(program_id, accounts)?)
+        }
+        VaultInstruction::InitializeConfig { fee_shares, dust_recipient } => {
+            let accounts = InitializeConfigAccounts::try_from(program_id, accounts)?;
+            initialize_config(program_id, accounts, fee_shares, dust_recipient)
+        }
+        VaultInstruction::SetFeeShares { fee_shares, dust_recipient } => {
+            let accounts = UpdateConfigAccounts::try_from(program_id, accounts)?;
+            set_fee_shares(accounts, fee_shares, dust_recipient)
+        }
+        VaultInstruction::SetReferralShare { referral_bps } => {
+            set_referral_share(UpdateConfigAccounts::try_from(program_id, accounts)?, referral_bps)
+        }
+        VaultInstruction::SetLoyaltyTiers { loyalty_tiers } => {
+            set_loyalty_tiers(UpdateConfigAccounts::try_from(program_id, accounts)?, loyalty_tiers)
+        }
+        VaultInstruction::SetFeeExemption { wallet, expires_at, reason } => {
+            let accounts = SetFeeExemptionAccounts::try_from(program_id, accounts, &wallet)?;
+            set_fee_exemption(program_id, accounts, wallet, expires_at, reason)
+        }
+        VaultInstruction::RemoveFeeExemption { wallet } => {
+            let accounts = RemoveFeeExemptionAccounts::try_from(program_id, accounts, &wallet)?;
+            remove_fee_exemption(accounts, wallet)
+        }
+        VaultInstruction::MigrateVault => {
+            migrate_vault(program_id, MigrateVaultAccounts::try_from(program_id, accounts)?)
+        }
+        VaultInstruction::Claim => claim(program_id, ClaimAccounts::try_from(program_id, accounts)?),
+        VaultInstruction::SetCrankTip { crank_tip_lamports } => {
+            let accounts = SetCrankTipAccounts::try_from(program_id, accounts)?;
+            set_crank_tip(program_id, accounts, crank_tip_lamports)
+        }
+        VaultInstruction::CrankRelease => {
+            crank_release(program_id, CrankReleaseAccounts::try_from(program_id, accounts)?)
+        }
+        VaultInstruction::SetAutoRenew { auto_renew } => {
+            set_auto_renew(program_id, SetAutoRenewAccounts::try_from(program_id, accounts)?, auto_renew)
+        }
+        VaultInstruction::SetCooldown { cooldown_duration } => {
+            set_cooldown(UpdateConfigAccounts::try_from(program_id, accounts)?, cooldown_duration)
+        }
+        VaultInstruction::SetSlotsPerDay { slots_per_day } => {
+            set_slots_per_day(UpdateConfigAccounts::try_from(program_id, accounts)?, slots_per_day)
+        }
+        VaultInstruction::RequestUnlock => {
+            request_unlock(program_id, RequestUnlockAccounts::try_from(program_id, accounts)?)
+        }
+        VaultInstruction::CancelUnlock => {
+            cancel_unlock(program_id, CancelUnlockAccounts::try_from(program_id, accounts)?)
+        }
+        VaultInstruction::EnableReceipts => {
+            enable_receipts(program_id, EnableReceiptsAccounts::try_from(program_id, accounts)?)
+        }
+        VaultInstruction::Redeem => redeem(program_id, RedeemAccounts::try_from(program_id, accounts)?),
+        VaultInstruction::SetAccessTiers { access_tiers } => {
+            let accounts = SetAccessTiersAccounts::try_from(program_id, accounts)?;
+            set_access_tiers(program_id, accounts, access_tiers)
+        }
+        VaultInstruction::SyncBadges => {
+            sync_badges_for_owner(program_id, SyncBadgesAccounts::try_from(program_id, accounts)?)
+        }
+        VaultInstruction::SetMintConfig {
+            enabled,
+            lock_duration,
+            initialization_fee_lamports,
+            max_early_withdrawal_fee_lamports,
+            max_early_withdrawal_fee_tokens,
+            access_tiers,
+        } => {
+            let accounts = SetMintConfigAccounts::try_from(program_id, accounts)?;
+            set_mint_config(
+                program_id,
+                accounts,
+                enabled,
+                lock_duration,
+                initialization_fee_lamports,
+                max_early_withdrawal_fee_lamports,
+                max_early_withdrawal_fee_tokens,
+                access_tiers,
+            )
+        }
+        VaultInstruction::InitializeSol { amount, referrer, auto_renew, kind, time_basis } => {
+            let accounts = InitializeSolAccounts::try_from(program_id, accounts)?;
+            initialize_sol(program_id, accounts, amount, referrer, auto_renew, kind, time_basis)
+        }
+        VaultInstruction::DepositSol { amount } => {
+            deposit_sol(program_id, DepositSolAccounts::try_from(program_id, accounts)?, amount)
+        }
+        VaultInstruction::WithdrawSol => {
+            withdraw_sol(program_id, WithdrawSolAccounts::try_from(program_id, accounts)?)
+        }
     }
 }
 
-#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
-pub enum VaultInstruction {
-    Initialize { amount: u64 },
-    Deposit { amount: u64 },
-    Withdraw,
-}
\ No newline at end of file
+vault_instructions! {
+    #[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+    pub enum VaultInstruction {
+        /// Creates the vault and its token account, locks `amount` whole tokens,
+        /// recorded in base units like deposits, and charges the
+        /// initialization fee. The mint is a KUZA mint, whose
+        /// vault is seeded by the owner alone, or an enabled registered mint,
+        /// whose vault is seeded by owner and mint and whose terms come from
+        /// its mint config. `stats` is the protocol stats shard the owner's
+        /// key hashes to, created on first use, and `activity` the new vault's
+        /// activity log. Remaining accounts are the fee recipients in config
+        /// order, then the referral record, referrer and referrer stats when
+        /// `referrer` is set, then the badge group once the mint has access
+        /// tiers. With `auto_renew` the lock rolls into a new term each time
+        /// it runs out; `kind` picks a fixed lock or a cooldown
+        /// vault, which can't auto-renew, and `time_basis` whether the lock
+        /// is timed in unix seconds or slots. With `receipts` the vault's
+        /// receipt mint is created and the owner minted one receipt per base
+        /// unit locked; the receipt mint and the owner's receipt account then
+        /// come just before the badge group. Only fixed vaults that don't
+        /// auto-renew take receipts.
+        #[accounts(
+            initializer: signer writable,
+            vault: writable,
+            vault_ata: writable,
+            user_token_account: writable,
+            config:,
+            token_mint:,
+            system_program:,
+            token_program:,
+            associated_token_program:,
+            rent_sysvar:,
+            loyalty: writable,
+            fee_exemption:,
+            mint_config:,
+            stats: writable,
+            activity: writable,
+            fee_recipients: writable remaining,
+            referral: writable remaining,
+            referrer: writable remaining,
+            referrer_stats: writable remaining,
+            receipt_mint: writable remaining,
+            receipt_account: writable remaining,
+            badge_config: remaining,
+            token_2022_program: remaining,
+            badge_mints: writable remaining,
+            badge_accounts: writable remaining,
+        )]
+        Initialize {
+            amount: u64,
+            referrer: Option<Pubkey>,
+            auto_renew: bool,
+            kind: VaultKind,
+            time_basis: TimeBasis,
+            receipts: bool,
+        },
+        /// Adds `amount` base units to the vault and restarts the lock. The
+        /// rent account is read but not used. A vault with receipts mints the
+        /// owner `amount` receipts and, while they are out, keeps its running
+        /// term. Registered mints must be enabled. The badge group comes last
+        /// once the mint has access tiers.
+        #[accounts(
+            initializer: signer writable,
+            vault: writable,
+            vault_ata: writable,
+            user_token_account: writable,
+            config:,
+            token_mint:,
+            system_program:,
+            token_program:,
+            associated_token_program:,
+            rent_sysvar:,
+            mint_config:,
+            stats: writable,
+            activity: writable,
+            receipt_mint: writable remaining,
+            receipt_account: writable remaining,
+            badge_config: remaining,
+            token_2022_program: remaining,
+            badge_mints: writable remaining,
+            badge_accounts: writable remaining,
+        )]
+        Deposit { amount: u64 },
+        /// Releases the vault to its owner, charging the mint's early-withdrawal
+        /// fee if the lock has not expired. A token fee is paid from the owner's
+        /// token account once the tokens are released. Remaining accounts are
+        /// the fee recipients in config order, their token accounts for the
+        /// vault's mint, the referral record, the referrer and referrer stats if
+        /// a referral is recorded, the receipt mint and the owner's receipt
+        /// account if the vault has receipts, and the badge group once the mint
+        /// has access tiers. A vault with receipts burns one per base unit
+        /// locked from the owner's receipt account, so the owner must hold
+        /// them all.
+        #[accounts(
+            user: signer writable,
+            vault: writable,
+            vault_ata: writable,
+            user_token_account: writable,
+            config:,
+            system_program:,
+            token_program:,
+            loyalty: writable,
+            fee_exemption:,
+            mint_config:,
+            stats: writable,
+            activity: writable,
+            token_mint:,
+            fee_recipients: writable remaining,
+            fee_token_accounts: writable remaining,
+            referral: remaining,
+            referrer: writable remaining,
+            referrer_stats: writable remaining,
+            receipt_mint: writable remaining,
+            receipt_account: writable remaining,
+            badge_config: remaining,
+            token_2022_program: remaining,
+            badge_mints: writable remaining,
+            badge_accounts: writable remaining,
+        )]
+        Withdraw,
+        /// Creates the program config. The signer must be the program's
+        /// upgrade authority and becomes the admin.
+        #[accounts(admin: signer writable, config: writable, system_program:, program_data:)]
+        InitializeConfig { fee_shares: Vec<FeeShare>, dust_recipient: u8 },
+        /// Replaces the fee split. Admin only.
+        #[accounts(admin: signer, config: writable)]
+        SetFeeShares { fee_shares: Vec<FeeShare>, dust_recipient: u8 },
+        /// Sets the referrer's share of every fee. Admin only.
+        #[accounts(admin: signer, config: writable)]
+        SetReferralShare { referral_bps: u16 },
+        /// Replaces the loyalty discount table. Admin only.
+        #[accounts(admin: signer, config: writable)]
+        SetLoyaltyTiers { loyalty_tiers: Vec<LoyaltyTier> },
+        /// Grants or updates a wallet's fee exemption. Admin only.
+        #[accounts(admin: signer writable, config:, fee_exemption: writable, system_program:)]
+        SetFeeExemption { wallet: Pubkey, expires_at: Option<i64>, reason: FeeExemptionReason },
+        /// Revokes a wallet's fee exemption. Admin only.
+        #[accounts(admin: signer writable, config:, fee_exemption: writable)]
+        RemoveFeeExemption { wallet: Pubkey },
+        /// Rewrites a legacy vault in the current layout, topping up its rent.
+        /// The lock is recorded in base units from the vault token account's
+        /// balance, along with the bumps of the owner's exemption and referral
+        /// PDAs. On a current vault it only fills in missing bumps, and the
+        /// lock too if the vault's own bump was missing.
+        #[accounts(payer: signer writable, vault: writable, system_program:, vault_ata:)]
+        MigrateVault,
+        /// Releases a vault whose lock has run out, or a cooldown vault whose
+        /// cooldown has passed, to the owner's token account, and credits the
+        /// completed lock to the owner's loyalty record, creating it at the
+        /// owner's expense if needed. No fee is due, so no fee accounts are
+        /// needed. With the badge group, the owner's
+        /// badges are burned in the same instruction, at the badge mints the
+        /// mint config records bumps for.
+        #[accounts(
+            owner: signer writable,
+            vault: writable,
+            vault_ata: writable,
+            destination: writable,
+            token_program:,
+            stats: writable,
+            activity: writable,
+            config:,
+            loyalty: writable,
+            system_program:,
+            mint_config:,
+            badge_config: remaining,
+            token_2022_program: remaining,
+            badge_mints: writable remaining,
+            badge_accounts: writable remaining,
+        )]
+        Claim,
+        /// Sets the tip paid from the treasury for each vault `CrankRelease`
+        /// releases, creating the treasury on first use. Admin only.
+        #[accounts(admin: signer writable, config:, treasury: writable, system_program:)]
+        SetCrankTip { crank_tip_lamports: u64 },
+        /// Releases matured vaults on their owners' behalf; anyone may send
+        /// it. Remaining accounts are (vault, vault token account, owner's
+        /// associated token account, owner's stats shard, vault's activity
+        /// log, owner's loyalty record) sextuples. Vaults that aren't matured
+        /// are skipped, as are native SOL vaults. Each release is credited
+        /// to the owner's loyalty record if they have one, and the cranker
+        /// is tipped for each one released.
+        #[accounts(
+            cranker: signer writable,
+            treasury: writable,
+            token_program:,
+            config:,
+            vaults: writable remaining,
+            vault_atas: writable remaining,
+            owner_atas: writable remaining,
+            stats_shards: writable remaining,
+            activity_logs: writable remaining,
+            loyalty_records: writable remaining,
+        )]
+        CrankRelease,
+        /// Turns auto-renew on or off for the owner's vault. Turning it on
+        /// relocks a matured vault for a new term; turning it off keeps the
+        /// expiry of the term already running.
+        #[accounts(owner: signer, vault: writable, activity: writable)]
+        SetAutoRenew { auto_renew: bool },
+        /// Sets how long cooldown vaults wait between `RequestUnlock` and
+        /// `Claim`. Admin only.
+        #[accounts(admin: signer, config: writable)]
+        SetCooldown { cooldown_duration: u64 },
+        /// Starts the cooldown of the owner's cooldown vault. From now on the
+        /// vault no longer counts toward tiers or voting.
+        #[accounts(owner: signer, vault: writable, config:, activity: writable)]
+        RequestUnlock,
+        /// Locks an unlocking cooldown vault again.
+        #[accounts(owner: signer, vault: writable, activity: writable)]
+        CancelUnlock,
+        /// Gives a vault opened without receipts its receipt mint, with the
+        /// decimals of the mint the vault locks, and mints the owner one
+        /// receipt per base unit locked. From then on the receipts are the
+        /// position: `Withdraw` burns them from the owner, `Redeem` from any
+        /// holder, `Claim` and the crank leave the vault alone, and it stops
+        /// counting toward tiers. Fixed vaults only; auto-renew is switched
+        /// off.
+        #[accounts(
+            owner: signer writable,
+            vault: writable,
+            receipt_mint: writable,
+            receipt_account: writable,
+            system_program:,
+            token_program:,
+            associated_token_program:,
+            activity: writable,
+            mint:,
+        )]
+        EnableReceipts,
+        /// Burns the vault's receipts from the holder's receipt account and
+        /// releases the matured vault to the holder's token account.
+        #[accounts(
+            holder: signer,
+            vault: writable,
+            vault_ata: writable,
+            receipt_mint: writable,
+            receipt_account: writable,
+            destination: writable,
+            token_program:,
+            stats: writable,
+            activity: writable,
+        )]
+        Redeem,
+        /// Replaces the access tier table, creating the non-transferable
+        /// badge mint of each new tier. Badge mints follow in tier order, one
+        /// per possible tier. Admin only.
+        #[accounts(
+            admin: signer writable,
+            config: writable,
+            system_program:,
+            token_2022_program:,
+            badge_mint_0: writable,
+            badge_mint_1: writable,
+            badge_mint_2: writable,
+            badge_mint_3: writable,
+        )]
+        SetAccessTiers { access_tiers: Vec<AccessTier> },
+        /// Mints or burns the owner's tier badges to match their vault; anyone
+        /// may send it. The badge group is the config, the Token-2022 program
+        /// and a (badge mint, owner's badge account) pair per possible tier,
+        /// for the KUZA badges or the vault's registered mint's.
+        #[accounts(
+            payer: signer writable,
+            owner:,
+            vault:,
+            mint_config:,
+            system_program:,
+            associated_token_program:,
+            badge_config: remaining,
+            token_2022_program: remaining,
+            badge_mints: writable remaining,
+            badge_accounts: writable remaining,
+        )]
+        SyncBadges,
+        /// Registers a mint for vaults or replaces its terms, creating the
+        /// badge mints of new tiers. Disabling a mint stops new locks and
+        /// deposits; its vaults can still be taken out. A token mint may also
+        /// charge its early-withdrawal fee in its own tokens. KUZA mints can't
+        /// be registered. Admin only.
+        #[accounts(
+            admin: signer writable,
+            config:,
+            mint_config: writable,
+            token_mint:,
+            system_program:,
+            token_2022_program:,
+            badge_mint_0: writable,
+            badge_mint_1: writable,
+            badge_mint_2: writable,
+            badge_mint_3: writable,
+        )]
+        SetMintConfig {
+            enabled: bool,
+            lock_duration: u64,
+            initialization_fee_lamports: u64,
+            max_early_withdrawal_fee_lamports: u64,
+            max_early_withdrawal_fee_tokens: u64,
+            access_tiers: Vec<AccessTier>,
+        },
+        /// Creates the owner's native SOL vault, seeded by owner and native
+        /// mint, and locks `amount` lamports in the vault account itself, on
+        /// top of its rent. The native mint must be registered and enabled;
+        /// its mint config sets the fee, lock and tiers. Remaining accounts
+        /// are as for `Initialize`.
+        #[accounts(
+            initializer: signer writable,
+            vault: writable,
+            config:,
+            system_program:,
+            associated_token_program:,
+            loyalty: writable,
+            fee_exemption:,
+            mint_config:,
+            stats: writable,
+            activity: writable,
+            fee_recipients: writable remaining,
+            referral: writable remaining,
+            referrer: writable remaining,
+            referrer_stats: writable remaining,
+            badge_config: remaining,
+            token_2022_program: remaining,
+            badge_mints: writable remaining,
+            badge_accounts: writable remaining,
+        )]
+        InitializeSol {
+            amount: u64,
+            referrer: Option<Pubkey>,
+            auto_renew: bool,
+            kind: VaultKind,
+            time_basis: TimeBasis,
+        },
+        /// Adds `amount` lamports to the owner's SOL vault and restarts the
+        /// lock. The badge group comes last once SOL has access tiers.
+        #[accounts(
+            initializer: signer writable,
+            vault: writable,
+            config:,
+            system_program:,
+            associated_token_program:,
+            mint_config:,
+            stats: writable,
+            activity: writable,
+            badge_config: remaining,
+            token_2022_program: remaining,
+            badge_mints: writable remaining,
+            badge_accounts: writable remaining,
+        )]
+        DepositSol { amount: u64 },
+        /// Releases the owner's SOL vault once its lock has run out or its
+        /// cooldown has passed, or early for a fixed vault, in which case the
+        /// fee is paid out of the released lamports. The vault keeps its rent.
+        /// Remaining accounts are as for `Withdraw`.
+        #[accounts(
+            user: signer writable,
+            vault: writable,
+            config:,
+            system_program:,
+            loyalty: writable,
+            fee_exemption:,
+            mint_config:,
+            stats: writable,
+            activity: writable,
+            fee_recipients: writable remaining,
+            referral: remaining,
+            referrer: writable remaining,
+            referrer_stats: writable remaining,
+            badge_config: remaining,
+            token_2022_program: remaining,
+            badge_mints: writable remaining,
+            badge_accounts: writable remaining,
+        )]
+        WithdrawSol,
+        /// Sets how many slots slot-based vaults take a day to last, used to
+        /// convert lock durations and cooldowns set in seconds. Admin only.
+        #[accounts(admin: signer, config: writable)]
+        SetSlotsPerDay { slots_per_day: u64 },
+    }
+}
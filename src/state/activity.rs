@@ -0,0 +1,147 @@
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use bytemuck::{Pod, Zeroable};
+use spl_pod::primitives::PodU64;
+
+/// What a vault's owner, or someone on their behalf, did to the vault.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ActivityKind {
+    /// The vault was opened; the amount is the first lock and the fee the
+    /// initialization fee.
+    Initialize,
+    /// The amount was added to the lock.
+    Deposit,
+    /// The lock was taken out through `Withdraw` or `WithdrawSol`, paying
+    /// the fee if it was early.
+    Withdraw,
+    /// The lock was taken out fee-free after it ran out.
+    Claim,
+    /// A crank operator released the matured lock to the owner.
+    Crank,
+    /// A receipt holder redeemed the lock.
+    Redeem,
+    /// Auto-renew was switched on, relocking the vault if it had matured.
+    AutoRenewOn,
+    /// Auto-renew was switched off; the running term still holds.
+    AutoRenewOff,
+    /// The cooldown of a cooldown vault was started.
+    RequestUnlock,
+    /// An unlocking cooldown vault was locked again.
+    CancelUnlock,
+    /// The amount was minted as receipts.
+    EnableReceipts,
+}
+
+impl TryFrom<u8> for ActivityKind {
+    type Error = ProgramError;
+
+    fn try_from(value: u8) -> Result<Self, ProgramError> {
+        Ok(match value {
+            0 => ActivityKind::Initialize,
+            1 => ActivityKind::Deposit,
+            2 => ActivityKind::Withdraw,
+            3 => ActivityKind::Claim,
+            4 => ActivityKind::Crank,
+            5 => ActivityKind::Redeem,
+            6 => ActivityKind::AutoRenewOn,
+            7 => ActivityKind::AutoRenewOff,
+            8 => ActivityKind::RequestUnlock,
+            9 => ActivityKind::CancelUnlock,
+            10 => ActivityKind::EnableReceipts,
+            _ => return Err(ProgramError::InvalidAccountData),
+        })
+    }
+}
+
+/// One recorded action. `amount` is in the vault's units, base units or
+/// lamports, and is zero for actions that move nothing; `fee_lamports` is
+/// what the owner paid in fees, referrer's share included.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ActivityEntry {
+    pub timestamp: PodU64,
+    /// An [`ActivityKind`]; read it through [`ActivityEntry::kind`].
+    pub kind: u8,
+    pub amount: PodU64,
+    pub fee_lamports: PodU64,
+}
+
+unsafe impl Zeroable for ActivityEntry {}
+unsafe impl Pod for ActivityEntry {}
+
+impl ActivityEntry {
+    pub const LEN: usize = 8 + 1 + 8 + 8;
+
+    pub fn new(kind: ActivityKind, amount: u64, fee_lamports: u64, now: u64) -> Self {
+        Self {
+            timestamp: now.into(),
+            kind: kind as u8,
+            amount: amount.into(),
+            fee_lamports: fee_lamports.into(),
+        }
+    }
+
+    pub fn kind(&self) -> Result<ActivityKind, ProgramError> {
+        ActivityKind::try_from(self.kind)
+    }
+}
+
+/// A vault's last [`ActivityLog::CAPACITY`] actions, kept in a companion
+/// account next to the vault and overwritten oldest first. Read and written
+/// in place like [`crate::state::Vault`].
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ActivityLog {
+    pub vault: Pubkey,
+    pub bump: u8,
+    /// Actions ever recorded; the next one goes to slot `total % CAPACITY`.
+    pub total: PodU64,
+    pub entries: [ActivityEntry; ActivityLog::CAPACITY],
+}
+
+// Every field is alignment 1, so there is no padding and any bit pattern is
+// a valid log
+const _: () = assert!(ActivityEntry::LEN == std::mem::size_of::<ActivityEntry>());
+const _: () = assert!(ActivityLog::LEN == 32 + 1 + 8 + ActivityLog::CAPACITY * ActivityEntry::LEN);
+const _: () = assert!(std::mem::align_of::<ActivityLog>() == 1);
+
+unsafe impl Zeroable for ActivityLog {}
+unsafe impl Pod for ActivityLog {}
+
+impl ActivityLog {
+    pub const CAPACITY: usize = 16;
+
+    pub const LEN: usize = std::mem::size_of::<ActivityLog>();
+
+    pub const SEED_PREFIX: &'static str = "kuza_activity";
+
+    /// Address of the activity log of the vault at `vault`.
+    pub fn find_address(vault: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[ActivityLog::SEED_PREFIX.as_bytes(), vault.as_ref()], program_id)
+    }
+
+    /// Borrows a log in place.
+    pub fn load(data: &[u8]) -> Result<&Self, ProgramError> {
+        bytemuck::try_from_bytes(data).map_err(|_| ProgramError::InvalidAccountData)
+    }
+
+    /// Mutably borrows a log in place.
+    pub fn load_mut(data: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        bytemuck::try_from_bytes_mut(data).map_err(|_| ProgramError::InvalidAccountData)
+    }
+
+    /// Records `entry`, overwriting the oldest once the log is full.
+    pub fn push(&mut self, entry: ActivityEntry) {
+        let total = u64::from(self.total);
+        self.entries[(total % ActivityLog::CAPACITY as u64) as usize] = entry;
+        self.total = total.saturating_add(1).into();
+    }
+
+    /// The recorded actions still in the log, oldest first.
+    pub fn entries(&self) -> Vec<ActivityEntry> {
+        let total = u64::from(self.total);
+        let start = total.saturating_sub(ActivityLog::CAPACITY as u64);
+
+        (start..total).map(|index| self.entries[(index % ActivityLog::CAPACITY as u64) as usize]).collect()
+    }
+}
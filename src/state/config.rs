@@ -0,0 +1,164 @@
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use borsh::{BorshDeserialize, BorshSerialize};
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default, Clone, Copy, PartialEq)]
+pub struct FeeShare {
+    pub recipient: Pubkey,
+    pub bps: u16,
+}
+
+impl FeeShare {
+    pub const LEN: usize = 32 + 2;
+}
+
+/// Discount applied to both fees once an owner has completed
+/// `min_completed_locks` locks.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default, Clone, Copy, PartialEq)]
+pub struct LoyaltyTier {
+    pub min_completed_locks: u32,
+    pub discount_bps: u16,
+}
+
+impl LoyaltyTier {
+    pub const LEN: usize = 4 + 2;
+}
+
+/// Amount an owner must have locked, in `amount_locked` units, to hold the
+/// tier's badge. Each tier has a non-transferable Token-2022 badge mint at
+/// [`AccessTier::find_badge_mint`], whose bump is kept next to the tier
+/// table once the mint is created.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default, Clone, Copy, PartialEq)]
+pub struct AccessTier {
+    pub min_amount_locked: u64,
+}
+
+impl AccessTier {
+    pub const LEN: usize = 8;
+
+    pub const BADGE_SEED_PREFIX: &'static str = "kuza_badge";
+
+    /// Address of the badge mint of the tier at `index`: in the config's
+    /// table, or in a registered mint's when `registered_mint` is given.
+    pub fn find_badge_mint(registered_mint: Option<&Pubkey>, index: u8, program_id: &Pubkey) -> (Pubkey, u8) {
+        match registered_mint {
+            Some(mint) => Pubkey::find_program_address(
+                &[AccessTier::BADGE_SEED_PREFIX.as_bytes(), mint.as_ref(), &[index]],
+                program_id,
+            ),
+            None => Pubkey::find_program_address(&[AccessTier::BADGE_SEED_PREFIX.as_bytes(), &[index]], program_id),
+        }
+    }
+
+    /// Re-derives the address of a tier's badge mint from its stored bump.
+    pub fn create_badge_mint(
+        registered_mint: Option<&Pubkey>,
+        index: u8,
+        bump: u8,
+        program_id: &Pubkey,
+    ) -> Result<Pubkey, ProgramError> {
+        let (index, bump) = ([index], [bump]);
+        let mut seeds = vec![AccessTier::BADGE_SEED_PREFIX.as_bytes()];
+        seeds.extend(registered_mint.map(|mint| mint.as_ref()));
+        seeds.extend([&index[..], &bump[..]]);
+
+        Pubkey::create_program_address(&seeds, program_id).map_err(|_| ProgramError::InvalidSeeds)
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct Config {
+    pub admin: Pubkey,
+    pub fee_shares: [FeeShare; Config::MAX_FEE_RECIPIENTS],
+    pub fee_share_count: u8,
+    // Index into `fee_shares` that receives the rounding remainder of every split.
+    pub dust_recipient: u8,
+    // Share of every fee paid to the vault's referrer before the split, in bps.
+    pub referral_bps: u16,
+    // Sorted by `min_completed_locks`, ascending.
+    pub loyalty_tiers: [LoyaltyTier; Config::MAX_LOYALTY_TIERS],
+    pub loyalty_tier_count: u8,
+    // Seconds a cooldown vault waits between `RequestUnlock` and `Claim`.
+    pub cooldown_duration: u64,
+    // Sorted by `min_amount_locked`, ascending.
+    pub access_tiers: [AccessTier; Config::MAX_ACCESS_TIERS],
+    pub access_tier_count: u8,
+    // Bump of each tier's badge mint; zero until the mint is created.
+    pub badge_bumps: [u8; Config::MAX_ACCESS_TIERS],
+    // Slots taken to last a day, at which durations set in seconds are
+    // converted for slot-based vaults.
+    pub slots_per_day: u64,
+    pub bump: u8,
+}
+
+impl Config {
+    pub const MAX_FEE_RECIPIENTS: usize = 5;
+
+    pub const MAX_LOYALTY_TIERS: usize = 4;
+
+    pub const MAX_ACCESS_TIERS: usize = 4;
+
+    pub const DEFAULT_COOLDOWN: u64 = 60 * 60 * 24 * 7;
+
+    /// A day at the 400ms slot time the cluster targets.
+    pub const DEFAULT_SLOTS_PER_DAY: u64 = 60 * 60 * 24 * 10 / 4;
+
+    pub const LEN: usize = 32
+        + FeeShare::LEN * Config::MAX_FEE_RECIPIENTS
+        + 1
+        + 1
+        + 2
+        + LoyaltyTier::LEN * Config::MAX_LOYALTY_TIERS
+        + 1
+        + 8
+        + AccessTier::LEN * Config::MAX_ACCESS_TIERS
+        + 1
+        + Config::MAX_ACCESS_TIERS
+        + 8
+        + 1;
+
+    pub const SEED_PREFIX: &'static str = "kuza_config";
+
+    pub fn new(admin: Pubkey, bump: u8) -> Self {
+        Self {
+            admin,
+            fee_shares: [FeeShare::default(); Config::MAX_FEE_RECIPIENTS],
+            fee_share_count: 0,
+            dust_recipient: 0,
+            referral_bps: 0,
+            loyalty_tiers: [LoyaltyTier::default(); Config::MAX_LOYALTY_TIERS],
+            loyalty_tier_count: 0,
+            cooldown_duration: Config::DEFAULT_COOLDOWN,
+            access_tiers: [AccessTier::default(); Config::MAX_ACCESS_TIERS],
+            access_tier_count: 0,
+            badge_bumps: [0; Config::MAX_ACCESS_TIERS],
+            slots_per_day: Config::DEFAULT_SLOTS_PER_DAY,
+            bump,
+        }
+    }
+
+    pub fn find_address(program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Config::SEED_PREFIX.as_bytes()], program_id)
+    }
+
+    pub fn active_fee_shares(&self) -> &[FeeShare] {
+        &self.fee_shares[..(self.fee_share_count as usize).min(Config::MAX_FEE_RECIPIENTS)]
+    }
+
+    pub fn active_loyalty_tiers(&self) -> &[LoyaltyTier] {
+        &self.loyalty_tiers[..(self.loyalty_tier_count as usize).min(Config::MAX_LOYALTY_TIERS)]
+    }
+
+    pub fn active_access_tiers(&self) -> &[AccessTier] {
+        &self.access_tiers[..(self.access_tier_count as usize).min(Config::MAX_ACCESS_TIERS)]
+    }
+
+    /// Discount for an owner with `completed_locks` behind them: the highest
+    /// tier they have reached, or nothing below the first tier.
+    pub fn loyalty_discount_bps(&self, completed_locks: u32) -> u16 {
+        self.active_loyalty_tiers()
+            .iter()
+            .rev()
+            .find(|tier| completed_locks >= tier.min_completed_locks)
+            .map_or(0, |tier| tier.discount_bps)
+    }
+}
@@ -1,8 +1,223 @@
-use solana_program::pubkey::Pubkey;
+use solana_program::{clock::Clock, msg, program_error::ProgramError, pubkey::Pubkey};
 use borsh::{BorshDeserialize, BorshSerialize};
-    
-#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+use bytemuck::{Pod, Zeroable};
+use spl_pod::primitives::PodU64;
+use crate::{
+    error::VaultError,
+    math::{elapsed, unix_seconds},
+    state::{FeeExemption, Referral},
+};
+
+/// Vault account data, read and written in place through [`Vault::load`] and
+/// [`Vault::load_mut`]. Every field has alignment 1, so a view can sit on
+/// account data at any address.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Vault {
+    pub discriminator: [u8; 8],
+    pub version: u8,
+    pub owner: Pubkey,
+    pub lock_duration: PodU64,
+    pub amount_locked: PodU64,
+    pub deposit_timestamp: PodU64,
+    /// A [`VaultStatus`]; read it through [`Vault::status`].
+    pub status: u8,
+    pub bump: u8,
+    /// When the vault last changed status.
+    pub status_timestamp: PodU64,
+    /// Non-zero when the lock rolls into a new term at maturity; read it
+    /// through [`Vault::auto_renews`].
+    pub auto_renew: u8,
+    /// A [`VaultKind`]; read it through [`Vault::kind`].
+    pub kind: u8,
+    /// Non-zero once the position is held as receipt tokens; read it through
+    /// [`Vault::has_receipts`].
+    pub receipts: u8,
+    /// Bump of the vault's receipt mint, valid once receipts are enabled.
+    pub receipt_bump: u8,
+    /// Mint of a vault opened for a registered mint, which sits at an address
+    /// seeded by owner and mint. Zero for KUZA vaults, seeded by owner alone;
+    /// read it through [`Vault::registered_mint`].
+    pub mint: Pubkey,
+    /// Lamports set aside for rent when a native SOL vault was created, kept
+    /// apart from the `amount_locked` lamports it holds. Zero for token vaults.
+    pub rent_lamports: PodU64,
+    /// Non-zero once the vault is tracked in its owner's stats shard. Vaults
+    /// locked before the shards existed join at their next deposit; read it
+    /// through [`Vault::is_counted`].
+    pub counted: u8,
+    /// A [`TimeBasis`]: the unit the lock's timestamps and durations are in.
+    /// Read it through [`Vault::time_basis`].
+    pub time_basis: u8,
+    /// Bumps of the owner's fee exemption and referral PDAs, found once when
+    /// the vault is created so those accounts can be checked without a
+    /// search. Zero in vaults created before they were stored; read them
+    /// through [`Vault::exemption_bump`] and [`Vault::referral_bump`].
+    pub exemption_bump: u8,
+    pub referral_bump: u8,
+    // Zeroed space that later versions can claim without a realloc
+    pub reserved: [u8; Vault::RESERVED_LEN],
+}
+
+// Every field is alignment 1 and the size matches the field sum, so there is
+// no padding and any bit pattern is a valid `Vault`
+const _: () = assert!(Vault::LEN == Vault::HEADER_LEN + Vault::FIELDS_LEN + Vault::RESERVED_LEN);
+const _: () = assert!(std::mem::align_of::<Vault>() == 1);
+
+unsafe impl Zeroable for Vault {}
+unsafe impl Pod for Vault {}
+
+/// Where a vault is in its lifecycle. Instructions move a vault between
+/// statuses only along the transitions [`VaultStatus::can_transition_to`]
+/// allows.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VaultStatus {
+    /// Freshly allocated, before the first lock.
+    Uninitialized,
+    /// Tokens are locked and withdrawing them costs a fee.
+    Locked,
+    /// The lock has run out; the tokens can be taken without a fee.
+    Matured,
+    /// The owner asked to unlock and is waiting out the cooldown.
+    Unlocking,
+    /// The tokens went back to the owner. A deposit locks the vault again.
+    Released,
+}
+
+impl VaultStatus {
+    /// Whether a vault may move from `self` to `next`.
+    pub fn can_transition_to(self, next: VaultStatus) -> bool {
+        use VaultStatus::*;
+
+        matches!(
+            (self, next),
+            (Uninitialized, Locked)
+                // A deposit restarts the lock of any vault not on its way out
+                | (Locked | Matured | Released, Locked)
+                | (Locked, Matured)
+                | (Locked, Unlocking)
+                | (Unlocking, Locked)
+                | (Locked | Matured | Unlocking, Released)
+        )
+    }
+}
+
+/// How a vault's lock ends. Fixed vaults mature `lock_duration` after the
+/// deposit; cooldown vaults stay locked until the owner asks to unlock and
+/// then wait out the configured cooldown.
+#[repr(u8)]
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VaultKind {
+    Fixed,
+    Cooldown,
+}
+
+impl VaultKind {
+    /// Lock length recorded when the vault is locked. A cooldown vault has
+    /// none until an unlock is requested.
+    pub fn lock_duration(self) -> u64 {
+        match self {
+            VaultKind::Fixed => Vault::LOCK_DURATION,
+            VaultKind::Cooldown => 0,
+        }
+    }
+}
+
+impl TryFrom<u8> for VaultKind {
+    type Error = ProgramError;
+
+    fn try_from(value: u8) -> Result<Self, ProgramError> {
+        Ok(match value {
+            0 => VaultKind::Fixed,
+            1 => VaultKind::Cooldown,
+            _ => return Err(ProgramError::InvalidAccountData),
+        })
+    }
+}
+
+/// The clock a vault's lock runs on. Unix-second vaults follow
+/// `Clock::unix_timestamp`, which validators may skew within bounds; slot
+/// vaults follow `Clock::slot`, for integrations that reason in slots. Every
+/// timestamp and duration stored in the vault is in its basis' unit, and
+/// durations configured in seconds are converted at the config's
+/// `slots_per_day`.
+#[repr(u8)]
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TimeBasis {
+    #[default]
+    UnixSeconds,
+    Slots,
+}
+
+impl TimeBasis {
+    pub const SECONDS_PER_DAY: u64 = 60 * 60 * 24;
+
+    /// The current time in this basis' unit. A clock before the epoch reads
+    /// as zero.
+    pub fn now(self, clock: &Clock) -> u64 {
+        match self {
+            TimeBasis::UnixSeconds => unix_seconds(clock.unix_timestamp),
+            TimeBasis::Slots => clock.slot,
+        }
+    }
+
+    /// `seconds` in this basis' unit, saturating at `u64::MAX`.
+    pub fn from_seconds(self, seconds: u64, slots_per_day: u64) -> u64 {
+        match self {
+            TimeBasis::UnixSeconds => seconds,
+            TimeBasis::Slots => {
+                let slots = seconds as u128 * slots_per_day as u128 / TimeBasis::SECONDS_PER_DAY as u128;
+                u64::try_from(slots).unwrap_or(u64::MAX)
+            }
+        }
+    }
+
+    /// `units` of this basis in seconds, saturating at `u64::MAX`. Without a
+    /// slot rate a slot duration reads as zero seconds.
+    pub fn to_seconds(self, units: u64, slots_per_day: u64) -> u64 {
+        match self {
+            TimeBasis::UnixSeconds => units,
+            TimeBasis::Slots => {
+                let seconds = (units as u128 * TimeBasis::SECONDS_PER_DAY as u128)
+                    .checked_div(slots_per_day as u128)
+                    .unwrap_or(0);
+                u64::try_from(seconds).unwrap_or(u64::MAX)
+            }
+        }
+    }
+}
+
+impl TryFrom<u8> for TimeBasis {
+    type Error = ProgramError;
+
+    fn try_from(value: u8) -> Result<Self, ProgramError> {
+        Ok(match value {
+            0 => TimeBasis::UnixSeconds,
+            1 => TimeBasis::Slots,
+            _ => return Err(ProgramError::InvalidAccountData),
+        })
+    }
+}
+
+impl TryFrom<u8> for VaultStatus {
+    type Error = ProgramError;
+
+    fn try_from(value: u8) -> Result<Self, ProgramError> {
+        Ok(match value {
+            0 => VaultStatus::Uninitialized,
+            1 => VaultStatus::Locked,
+            2 => VaultStatus::Matured,
+            3 => VaultStatus::Unlocking,
+            4 => VaultStatus::Released,
+            _ => return Err(ProgramError::InvalidAccountData),
+        })
+    }
+}
+
+/// Version 0 vaults: the bare Borsh field encoding with no header.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct LegacyVault {
     pub owner: Pubkey,
     pub lock_duration: u64,
     pub amount_locked: u64,
@@ -12,20 +227,375 @@ pub struct Vault {
 }
 
 impl Vault {
-    pub const LEN: usize = 32 + 8 + 8 + 8 + 1 + 1;
+    // Account layout, from version 1 on:
+    //   [discriminator: 8][version: 1][fields: FIELDS_LEN][reserved: RESERVED_LEN]
+    // Version 0 accounts are the bare 58-byte field encoding with no header.
+    // Version 1 had the same size, with an `is_locked` bool where `status` now
+    // sits and no status timestamp. `auto_renew` took the first reserved byte,
+    // which reads as off in vaults written before it; `kind` took the next,
+    // which reads as `Fixed`; `receipts` and `receipt_bump` the two after,
+    // which read as no receipts; `mint` the 32 after that, which reads as a
+    // KUZA vault at its owner-only address; `rent_lamports` the 8 after that,
+    // which a token vault never uses; `counted` the byte after that, which
+    // reads as a vault the stats haven't seen yet; `time_basis` the byte after
+    // that, which reads as unix seconds; `exemption_bump` and `referral_bump`
+    // the two after that, which read as not yet found.
+    pub const DISCRIMINATOR: [u8; 8] = *b"kuzavlt\0";
+
+    pub const VERSION: u8 = 2;
+
+    pub const HEADER_LEN: usize = 8 + 1;
+
+    pub const FIELDS_LEN: usize = 32 + 8 + 8 + 8 + 1 + 1 + 8 + 1 + 1 + 1 + 1 + 32 + 8 + 1 + 1 + 1 + 1;
+
+    pub const RESERVED_LEN: usize = 8;
+
+    pub const LEN: usize = std::mem::size_of::<Vault>();
+
+    pub const LEGACY_LEN: usize = 32 + 8 + 8 + 8 + 1 + 1;
 
     pub const LOCK_DURATION: u64 = 60 * 60 * 24 * 30;
 
     pub const SEED_PREFIX: &'static str = "kuza_vault";
 
-    pub fn new(owner: Pubkey, amount_locked: u64) -> Self {
+    pub const RECEIPT_SEED_PREFIX: &'static str = "kuza_receipt";
+
+    /// Address of `owner`'s KUZA vault.
+    pub fn find_address(owner: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Vault::SEED_PREFIX.as_bytes(), owner.as_ref()], program_id)
+    }
+
+    /// Address of `owner`'s vault for the registered mint `mint`.
+    pub fn find_mint_address(owner: &Pubkey, mint: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Vault::SEED_PREFIX.as_bytes(), owner.as_ref(), mint.as_ref()], program_id)
+    }
+
+    /// The vault's canonical address and bump, searched for from its stored
+    /// owner and mint.
+    pub fn find_own_address(&self, program_id: &Pubkey) -> (Pubkey, u8) {
+        match self.registered_mint() {
+            Some(mint) => Vault::find_mint_address(&self.owner, &mint, program_id),
+            None => Vault::find_address(&self.owner, program_id),
+        }
+    }
+
+    /// Seeds the vault signs with: the owner, the mint for a vault of a
+    /// registered mint, and the stored bump.
+    pub fn signer_seeds(&self) -> Vec<&[u8]> {
+        let mut seeds = vec![Vault::SEED_PREFIX.as_bytes(), self.owner.as_ref()];
+        if self.registered_mint().is_some() {
+            seeds.push(self.mint.as_ref());
+        }
+        seeds.push(std::slice::from_ref(&self.bump));
+        seeds
+    }
+
+    /// Re-derives the vault's own address from its stored seeds and bump.
+    pub fn own_address(&self, program_id: &Pubkey) -> Result<Pubkey, ProgramError> {
+        Pubkey::create_program_address(&self.signer_seeds(), program_id).map_err(|_| ProgramError::InvalidSeeds)
+    }
+
+    /// The registered mint the vault was opened for, or `None` for a KUZA
+    /// vault.
+    pub fn registered_mint(&self) -> Option<Pubkey> {
+        (self.mint != Pubkey::default()).then_some(self.mint)
+    }
+
+    /// Whether the vault locks native SOL in its own lamports rather than
+    /// tokens in an associated token account. Such a vault is seeded by the
+    /// owner and the native mint, whose mint config holds its terms.
+    pub fn is_native(&self) -> bool {
+        self.mint == spl_token::native_mint::id()
+    }
+
+    /// Re-derives the vault address from the bump stored at creation, which
+    /// costs a single hash instead of `find_address`'s search.
+    pub fn create_address(owner: &Pubkey, bump: u8, program_id: &Pubkey) -> Result<Pubkey, ProgramError> {
+        Pubkey::create_program_address(&[Vault::SEED_PREFIX.as_bytes(), owner.as_ref(), &[bump]], program_id)
+            .map_err(|_| ProgramError::InvalidSeeds)
+    }
+
+    /// Bump of the owner's fee exemption PDA. Vaults created before it was
+    /// stored search for it until `MigrateVault` records it.
+    pub fn exemption_bump(&self, program_id: &Pubkey) -> u8 {
+        match self.exemption_bump {
+            0 => FeeExemption::find_address(&self.owner, program_id).1,
+            bump => bump,
+        }
+    }
+
+    /// Bump of the owner's referral PDA, like [`Vault::exemption_bump`].
+    pub fn referral_bump(&self, program_id: &Pubkey) -> u8 {
+        match self.referral_bump {
+            0 => Referral::find_address(&self.owner, program_id).1,
+            bump => bump,
+        }
+    }
+
+    /// Searches for the bumps of `owner`'s fee exemption and referral PDAs,
+    /// once, when a vault is created or migrated.
+    pub fn find_owner_bumps(owner: &Pubkey, program_id: &Pubkey) -> (u8, u8) {
+        (FeeExemption::find_address(owner, program_id).1, Referral::find_address(owner, program_id).1)
+    }
+
+    /// Address of the receipt mint of the vault at `vault`.
+    pub fn find_receipt_mint(vault: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Vault::RECEIPT_SEED_PREFIX.as_bytes(), vault.as_ref()], program_id)
+    }
+
+    /// Layout version of the account data: 0 for legacy headerless vaults.
+    pub fn version(data: &[u8]) -> Result<u8, ProgramError> {
+        if data.len() >= Vault::HEADER_LEN && data[..8] == Vault::DISCRIMINATOR {
+            Ok(data[8])
+        } else if data.len() == Vault::LEGACY_LEN {
+            Ok(0)
+        } else {
+            Err(ProgramError::InvalidAccountData)
+        }
+    }
+
+    // Rejects anything but a current-layout vault of exactly `LEN` bytes
+    fn check_current(data: &[u8]) -> Result<(), ProgramError> {
+        match Vault::version(data)? {
+            Vault::VERSION => {}
+            version if version < Vault::VERSION => return Err(VaultError::VaultNeedsMigration.into()),
+            version => {
+                msg!("Unsupported vault layout version {}", version);
+                return Err(VaultError::UnsupportedVaultVersion.into());
+            }
+        }
+
+        if data.len() != Vault::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        Ok(())
+    }
+
+    /// Borrows a current-layout vault in place.
+    pub fn load(data: &[u8]) -> Result<&Self, ProgramError> {
+        Vault::check_current(data)?;
+        bytemuck::try_from_bytes(data).map_err(|_| ProgramError::InvalidAccountData)
+    }
+
+    /// Mutably borrows a current-layout vault in place.
+    pub fn load_mut(data: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        Vault::check_current(data)?;
+        bytemuck::try_from_bytes_mut(data).map_err(|_| ProgramError::InvalidAccountData)
+    }
+
+    /// Zeroes freshly allocated account data and writes the current header.
+    /// `data` must be `Vault::LEN` long.
+    pub fn init(data: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        let vault: &mut Self =
+            bytemuck::try_from_bytes_mut(data).map_err(|_| ProgramError::InvalidAccountData)?;
+
+        *vault = Vault::zeroed();
+        vault.discriminator = Vault::DISCRIMINATOR;
+        vault.version = Vault::VERSION;
+
+        Ok(vault)
+    }
+
+    /// Decodes a copy of a vault of any supported layout version.
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        match Vault::version(data)? {
+            0 => {
+                let legacy = LegacyVault::try_from_slice(data)
+                    .map_err(|_| ProgramError::InvalidAccountData)?;
+                Ok(legacy.into())
+            }
+            1 => {
+                let mut vault: Vault = *bytemuck::try_from_bytes(data)
+                    .map_err(|_| ProgramError::InvalidAccountData)?;
+                let is_locked = vault.status != 0;
+
+                vault.version = Vault::VERSION;
+                vault.status = Vault::status_from_lock(is_locked) as u8;
+                vault.status_timestamp = Vault::status_timestamp_from_lock(is_locked, vault.deposit_timestamp);
+                Ok(vault)
+            }
+            _ => Vault::load(data).copied(),
+        }
+    }
+
+    // Older layouts only knew whether the vault was locked
+    fn status_from_lock(is_locked: bool) -> VaultStatus {
+        if is_locked {
+            VaultStatus::Locked
+        } else {
+            VaultStatus::Released
+        }
+    }
+
+    // A locked vault last changed status when it was deposited into; for a
+    // released one the time was never recorded
+    fn status_timestamp_from_lock(is_locked: bool, deposit_timestamp: PodU64) -> PodU64 {
+        if is_locked {
+            deposit_timestamp
+        } else {
+            0.into()
+        }
+    }
+
+    /// The vault's lifecycle status.
+    pub fn status(&self) -> Result<VaultStatus, ProgramError> {
+        VaultStatus::try_from(self.status)
+    }
+
+    /// Fails unless the vault may move to `next` from its current status.
+    pub fn check_transition(&self, next: VaultStatus) -> Result<(), ProgramError> {
+        let status = self.status()?;
+
+        if !status.can_transition_to(next) {
+            msg!("Vault cannot go from {:?} to {:?}", status, next);
+            return Err(VaultError::InvalidStatusTransition.into());
+        }
+
+        Ok(())
+    }
+
+    /// Moves the vault to `next`, recording `now` as the time of the change.
+    pub fn transition(&mut self, next: VaultStatus, now: u64) -> Result<(), ProgramError> {
+        self.check_transition(next)?;
+
+        self.status = next as u8;
+        self.status_timestamp = now.into();
+        Ok(())
+    }
+
+    /// How the vault's lock ends.
+    pub fn kind(&self) -> Result<VaultKind, ProgramError> {
+        VaultKind::try_from(self.kind)
+    }
+
+    /// Fails unless the vault is of `kind`.
+    pub fn check_kind(&self, kind: VaultKind) -> Result<(), ProgramError> {
+        let actual = self.kind()?;
+
+        if actual != kind {
+            msg!("This instruction needs a {:?} vault, not {:?}", kind, actual);
+            return Err(VaultError::WrongVaultKind.into());
+        }
+
+        Ok(())
+    }
+
+    /// When an unlocking cooldown vault can be claimed: the cooldown is
+    /// recorded in `lock_duration` when the unlock is requested.
+    pub fn cooldown_ends_at(&self) -> u64 {
+        u64::from(self.status_timestamp).saturating_add(u64::from(self.lock_duration))
+    }
+
+    /// Whether the vault counts toward tier access and voting at `now`:
+    /// only a lock that hasn't run out or been asked to end does. A vault
+    /// with receipts never counts, since the owner may have sold the position.
+    pub fn counts_toward_tier(&self, now: u64) -> bool {
+        let mut vault = *self;
+        !self.has_receipts() && vault.mature(now).is_ok() && vault.status() == Ok(VaultStatus::Locked)
+    }
+
+    /// Amount the vault contributes toward access tiers at `now`: all of it
+    /// while it counts, nothing otherwise.
+    pub fn tier_amount(&self, now: u64) -> u64 {
+        if self.counts_toward_tier(now) {
+            u64::from(self.amount_locked)
+        } else {
+            0
+        }
+    }
+
+    /// Whether the position is held as receipt tokens, so whoever holds them
+    /// rather than the owner takes the tokens out.
+    pub fn has_receipts(&self) -> bool {
+        self.receipts != 0
+    }
+
+    /// Whether the vault's lock is tracked in its owner's stats shard.
+    pub fn is_counted(&self) -> bool {
+        self.counted != 0
+    }
+
+    /// The clock the vault's lock runs on.
+    pub fn time_basis(&self) -> Result<TimeBasis, ProgramError> {
+        TimeBasis::try_from(self.time_basis)
+    }
+
+    /// The current time in the vault's basis.
+    pub fn now(&self, clock: &Clock) -> Result<u64, ProgramError> {
+        Ok(self.time_basis()?.now(clock))
+    }
+
+    /// Whether the lock rolls into a new term of the same length at maturity.
+    pub fn auto_renews(&self) -> bool {
+        self.auto_renew != 0
+    }
+
+    /// Start of the lock term running at `now`. An auto-renewing vault's
+    /// terms follow each other from the deposit, so the current one is found
+    /// lazily rather than stored; otherwise the only term starts at the deposit.
+    pub fn term_start(&self, now: u64) -> u64 {
+        let deposit_timestamp = u64::from(self.deposit_timestamp);
+        let lock_duration = u64::from(self.lock_duration);
+
+        if !self.auto_renews() || lock_duration == 0 || now <= deposit_timestamp {
+            return deposit_timestamp;
+        }
+
+        now - elapsed(now, deposit_timestamp) % lock_duration
+    }
+
+    /// When the lock term running at `now` runs out.
+    pub fn unlock_timestamp(&self, now: u64) -> u64 {
+        self.term_start(now).saturating_add(u64::from(self.lock_duration))
+    }
+
+    /// Stores the current term's start as the deposit time, so the vault
+    /// keeps that expiry if auto-renew is switched off.
+    pub fn roll_forward(&mut self, now: u64) {
+        self.deposit_timestamp = self.term_start(now).into();
+    }
+
+    /// Marks a locked vault as matured once its lock has run out. The status
+    /// is only stored when an instruction touches the vault, so anything that
+    /// depends on maturity calls this first. An auto-renewing vault never
+    /// matures; its lock rolls over instead. Neither does a cooldown vault,
+    /// which has no expiry until an unlock is requested.
+    pub fn mature(&mut self, now: u64) -> Result<(), ProgramError> {
+        if self.kind()? == VaultKind::Fixed
+            && self.status()? == VaultStatus::Locked
+            && now >= self.unlock_timestamp(now)
+        {
+            self.transition(VaultStatus::Matured, now)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl From<LegacyVault> for Vault {
+    fn from(legacy: LegacyVault) -> Self {
         Self {
-            owner,
-            lock_duration: 0,
-            amount_locked,
-            deposit_timestamp: 0,
-            is_locked: false,
-            bump: 0,
+            discriminator: Vault::DISCRIMINATOR,
+            version: Vault::VERSION,
+            owner: legacy.owner,
+            lock_duration: legacy.lock_duration.into(),
+            amount_locked: legacy.amount_locked.into(),
+            deposit_timestamp: legacy.deposit_timestamp.into(),
+            status: Vault::status_from_lock(legacy.is_locked) as u8,
+            bump: legacy.bump,
+            status_timestamp: Vault::status_timestamp_from_lock(legacy.is_locked, legacy.deposit_timestamp.into()),
+            auto_renew: 0,
+            kind: VaultKind::Fixed as u8,
+            receipts: 0,
+            receipt_bump: 0,
+            mint: Pubkey::default(),
+            rent_lamports: 0.into(),
+            counted: 0,
+            time_basis: TimeBasis::UnixSeconds as u8,
+            exemption_bump: 0,
+            referral_bump: 0,
+            reserved: [0; Vault::RESERVED_LEN],
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,42 @@
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use borsh::{BorshDeserialize, BorshSerialize};
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq)]
+pub enum FeeExemptionReason {
+    MarketMaker,
+    TeamMultisig,
+    PartnerProtocol,
+    Other,
+}
+
+/// Admin-granted waiver of the initialization and early-withdrawal fees for
+/// a single wallet.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct FeeExemption {
+    pub wallet: Pubkey,
+    // Unix timestamp after which the exemption no longer applies; `None` never expires.
+    pub expires_at: Option<i64>,
+    pub reason: FeeExemptionReason,
+    pub bump: u8,
+}
+
+impl FeeExemption {
+    // Sized for `expires_at: Some(_)`.
+    pub const LEN: usize = 32 + (1 + 8) + 1 + 1;
+
+    pub const SEED_PREFIX: &'static str = "kuza_exemption";
+
+    pub fn find_address(wallet: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[FeeExemption::SEED_PREFIX.as_bytes(), wallet.as_ref()], program_id)
+    }
+
+    /// Re-derives `wallet`'s exemption address from a known bump.
+    pub fn create_address(wallet: &Pubkey, bump: u8, program_id: &Pubkey) -> Result<Pubkey, ProgramError> {
+        Pubkey::create_program_address(&[FeeExemption::SEED_PREFIX.as_bytes(), wallet.as_ref(), &[bump]], program_id)
+            .map_err(|_| ProgramError::InvalidSeeds)
+    }
+
+    pub fn is_active(&self, now: i64) -> bool {
+        self.expires_at.is_none_or(|expires_at| now < expires_at)
+    }
+}
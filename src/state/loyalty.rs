@@ -0,0 +1,32 @@
+use solana_program::pubkey::Pubkey;
+use borsh::{BorshDeserialize, BorshSerialize};
+
+/// Lifetime locking history of a single owner, used to price their fees.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct Loyalty {
+    pub owner: Pubkey,
+    pub completed_locks: u32,
+    pub total_time_locked: u64,
+    pub early_exits: u32,
+    pub bump: u8,
+}
+
+impl Loyalty {
+    pub const LEN: usize = 32 + 4 + 8 + 4 + 1;
+
+    pub const SEED_PREFIX: &'static str = "kuza_loyalty";
+
+    pub fn new(owner: Pubkey, bump: u8) -> Self {
+        Self {
+            owner,
+            completed_locks: 0,
+            total_time_locked: 0,
+            early_exits: 0,
+            bump,
+        }
+    }
+
+    pub fn find_address(owner: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Loyalty::SEED_PREFIX.as_bytes(), owner.as_ref()], program_id)
+    }
+}
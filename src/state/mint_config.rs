@@ -0,0 +1,51 @@
+use solana_program::pubkey::Pubkey;
+use borsh::{BorshDeserialize, BorshSerialize};
+use crate::state::{AccessTier, Config, VaultKind};
+
+/// Terms for vaults of one mint. A registered mint has one at
+/// [`MintConfig::find_address`], written by the admin with `SetMintConfig`.
+/// The KUZA mints aren't registered; their terms are built from the
+/// program's constants and the config's access tiers.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct MintConfig {
+    pub mint: Pubkey,
+    // New vaults and deposits are refused while this is off; existing vaults
+    // can still be taken out.
+    pub enabled: bool,
+    // Term of a fixed vault.
+    pub lock_duration: u64,
+    pub initialization_fee_lamports: u64,
+    // Early-withdrawal fee on day 0 is 75% of this.
+    pub max_early_withdrawal_fee_lamports: u64,
+    // Like the lamport fee, in base units of the mint, charged on top of it.
+    pub max_early_withdrawal_fee_tokens: u64,
+    // Sorted by `min_amount_locked`, ascending.
+    pub access_tiers: [AccessTier; Config::MAX_ACCESS_TIERS],
+    pub access_tier_count: u8,
+    // Bump of each tier's badge mint; zero until the mint is created.
+    pub badge_bumps: [u8; Config::MAX_ACCESS_TIERS],
+    pub bump: u8,
+}
+
+impl MintConfig {
+    pub const LEN: usize = 32 + 1 + 8 + 8 + 8 + 8 + AccessTier::LEN * Config::MAX_ACCESS_TIERS + 1 + Config::MAX_ACCESS_TIERS + 1;
+
+    pub const SEED_PREFIX: &'static str = "kuza_mint";
+
+    pub fn find_address(mint: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[MintConfig::SEED_PREFIX.as_bytes(), mint.as_ref()], program_id)
+    }
+
+    pub fn active_access_tiers(&self) -> &[AccessTier] {
+        &self.access_tiers[..(self.access_tier_count as usize).min(Config::MAX_ACCESS_TIERS)]
+    }
+
+    /// Lock length recorded when a vault of `kind` is locked. A cooldown
+    /// vault has none until an unlock is requested.
+    pub fn lock_duration_for(&self, kind: VaultKind) -> u64 {
+        match kind {
+            VaultKind::Fixed => self.lock_duration,
+            VaultKind::Cooldown => VaultKind::Cooldown.lock_duration(),
+        }
+    }
+}
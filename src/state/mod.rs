@@ -1,2 +1,18 @@
+pub mod activity;
+pub mod config;
 pub mod construct_vault;
-pub use construct_vault::Vault;
\ No newline at end of file
+pub mod exemption;
+pub mod loyalty;
+pub mod mint_config;
+pub mod referral;
+pub mod stats;
+pub mod treasury;
+pub use activity::{ActivityEntry, ActivityKind, ActivityLog};
+pub use config::{AccessTier, Config, FeeShare, LoyaltyTier};
+pub use construct_vault::{LegacyVault, TimeBasis, Vault, VaultKind, VaultStatus};
+pub use exemption::{FeeExemption, FeeExemptionReason};
+pub use loyalty::Loyalty;
+pub use mint_config::MintConfig;
+pub use referral::{Referral, ReferrerStats};
+pub use stats::{ProtocolStats, StatsShard};
+pub use treasury::Treasury;
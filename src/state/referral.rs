@@ -0,0 +1,44 @@
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use borsh::{BorshDeserialize, BorshSerialize};
+
+/// Links a vault owner to the referrer named when their vault was created.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct Referral {
+    pub owner: Pubkey,
+    pub referrer: Pubkey,
+    pub bump: u8,
+}
+
+impl Referral {
+    pub const LEN: usize = 32 + 32 + 1;
+
+    pub const SEED_PREFIX: &'static str = "kuza_referral";
+
+    pub fn find_address(owner: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Referral::SEED_PREFIX.as_bytes(), owner.as_ref()], program_id)
+    }
+
+    /// Re-derives `owner`'s referral address from a known bump.
+    pub fn create_address(owner: &Pubkey, bump: u8, program_id: &Pubkey) -> Result<Pubkey, ProgramError> {
+        Pubkey::create_program_address(&[Referral::SEED_PREFIX.as_bytes(), owner.as_ref(), &[bump]], program_id)
+            .map_err(|_| ProgramError::InvalidSeeds)
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct ReferrerStats {
+    pub referrer: Pubkey,
+    pub referral_count: u64,
+    pub lamports_earned: u64,
+    pub bump: u8,
+}
+
+impl ReferrerStats {
+    pub const LEN: usize = 32 + 8 + 8 + 1;
+
+    pub const SEED_PREFIX: &'static str = "kuza_referrer";
+
+    pub fn find_address(referrer: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[ReferrerStats::SEED_PREFIX.as_bytes(), referrer.as_ref()], program_id)
+    }
+}
@@ -0,0 +1,106 @@
+use solana_program::{hash::hash, program_error::ProgramError, pubkey::Pubkey};
+use borsh::{BorshDeserialize, BorshSerialize};
+use crate::{math::CheckedMath, state::{Vault, VaultStatus}};
+
+/// One slice of the protocol-wide totals. Every owner's vaults are counted in
+/// the shard their key hashes to, so locks by different owners rarely write
+/// the same account; [`ProtocolStats::sum`] adds the shards back up.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default, PartialEq)]
+pub struct StatsShard {
+    pub shard: u8,
+    /// KUZA base units held by open vaults.
+    pub kuza_locked: u64,
+    /// Vaults of any mint that are locked, matured or unlocking.
+    pub open_vaults: u64,
+    /// KUZA base units ever deposited, including at initialization.
+    pub lifetime_deposits: u64,
+    /// Initialization and early-withdrawal fees charged, referrer shares
+    /// included.
+    pub lifetime_fees_lamports: u64,
+    pub bump: u8,
+}
+
+impl StatsShard {
+    pub const LEN: usize = 1 + 8 + 8 + 8 + 8 + 1;
+
+    pub const SEED_PREFIX: &'static str = "kuza_stats";
+
+    pub const SHARD_COUNT: u8 = 8;
+
+    /// The shard `owner`'s vaults are counted in.
+    pub fn shard_for(owner: &Pubkey) -> u8 {
+        hash(owner.as_ref()).to_bytes()[0] % StatsShard::SHARD_COUNT
+    }
+
+    pub fn find_address(shard: u8, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[StatsShard::SEED_PREFIX.as_bytes(), &[shard]], program_id)
+    }
+
+    /// Addresses of every shard, in shard order.
+    pub fn all_addresses(program_id: &Pubkey) -> Vec<Pubkey> {
+        (0..StatsShard::SHARD_COUNT).map(|shard| StatsShard::find_address(shard, program_id).0).collect()
+    }
+
+    /// Counts `amount` newly locked in `vault`, before the vault records it.
+    /// A vault that isn't open in the stats yet, because it is new, was
+    /// released, or was locked before the shards existed, is opened with
+    /// whatever it already holds and marked as counted.
+    pub fn count_lock(&mut self, vault: &mut Vault, amount: u64) -> Result<(), ProgramError> {
+        let is_kuza = vault.registered_mint().is_none();
+
+        if !vault.is_counted() || vault.status()? == VaultStatus::Released {
+            self.open_vaults = self.open_vaults.try_add(1)?;
+            if is_kuza {
+                self.kuza_locked = self.kuza_locked.try_add(u64::from(vault.amount_locked))?;
+            }
+            vault.counted = 1;
+        }
+
+        if is_kuza {
+            self.kuza_locked = self.kuza_locked.try_add(amount)?;
+            self.lifetime_deposits = self.lifetime_deposits.try_add(amount)?;
+        }
+
+        Ok(())
+    }
+
+    /// Takes `vault` out of the open totals as it is released, before its
+    /// lock is cleared. Vaults the stats haven't seen are left out.
+    pub fn count_release(&mut self, vault: &Vault) {
+        if !vault.is_counted() {
+            return;
+        }
+
+        self.open_vaults = self.open_vaults.saturating_sub(1);
+        if vault.registered_mint().is_none() {
+            self.kuza_locked = self.kuza_locked.saturating_sub(u64::from(vault.amount_locked));
+        }
+    }
+
+    pub fn count_fee(&mut self, fee_lamports: u64) -> Result<(), ProgramError> {
+        self.lifetime_fees_lamports = self.lifetime_fees_lamports.try_add(fee_lamports)?;
+        Ok(())
+    }
+}
+
+/// Protocol-wide totals: the sum of every stats shard.
+#[derive(Debug, Default, PartialEq)]
+pub struct ProtocolStats {
+    pub kuza_locked: u64,
+    pub open_vaults: u64,
+    pub lifetime_deposits: u64,
+    pub lifetime_fees_lamports: u64,
+}
+
+impl ProtocolStats {
+    /// Adds up `shards`. Shards nobody has written to yet don't exist and are
+    /// simply left out.
+    pub fn sum<'a>(shards: impl IntoIterator<Item = &'a StatsShard>) -> Self {
+        shards.into_iter().fold(ProtocolStats::default(), |total, shard| ProtocolStats {
+            kuza_locked: total.kuza_locked.saturating_add(shard.kuza_locked),
+            open_vaults: total.open_vaults.saturating_add(shard.open_vaults),
+            lifetime_deposits: total.lifetime_deposits.saturating_add(shard.lifetime_deposits),
+            lifetime_fees_lamports: total.lifetime_fees_lamports.saturating_add(shard.lifetime_fees_lamports),
+        })
+    }
+}
@@ -0,0 +1,22 @@
+use solana_program::pubkey::Pubkey;
+use borsh::{BorshDeserialize, BorshSerialize};
+
+/// Program-owned pot that pays crank operators for releasing matured vaults.
+/// Anyone can fund it with a plain SOL transfer; lamports above its rent
+/// reserve are available for tips.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct Treasury {
+    // Paid to the crank operator for every vault it releases.
+    pub crank_tip_lamports: u64,
+    pub bump: u8,
+}
+
+impl Treasury {
+    pub const LEN: usize = 8 + 1;
+
+    pub const SEED_PREFIX: &'static str = "kuza_treasury";
+
+    pub fn find_address(program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Treasury::SEED_PREFIX.as_bytes()], program_id)
+    }
+}
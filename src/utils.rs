@@ -0,0 +1,93 @@
+use crate::{error::VaultError, math::CheckedMath};
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_instruction,
+    system_program,
+    sysvar::{rent::Rent, Sysvar},
+};
+
+/// Creates a rent-exempt, program-owned account at a PDA, paid for by `payer`.
+pub fn create_pda_account<'a>(
+    payer: &AccountInfo<'a>,
+    new_account: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    program_id: &Pubkey,
+    space: usize,
+    signer_seeds: &[&[u8]],
+) -> ProgramResult {
+    let rent_lamports = Rent::get()?.minimum_balance(space);
+
+    invoke_signed(
+        &system_instruction::create_account(
+            payer.key,
+            new_account.key,
+            rent_lamports,
+            space as u64,
+            program_id,
+        ),
+        &[payer.clone(), new_account.clone(), system_program.clone()],
+        &[signer_seeds],
+    )
+}
+
+/// Closes a program-owned account, sending its lamports to `destination`.
+pub fn close_account<'a>(account: &AccountInfo<'a>, destination: &AccountInfo<'a>) -> ProgramResult {
+    let received = destination.lamports().try_add(account.lamports())?;
+    **destination.try_borrow_mut_lamports()? = received;
+    **account.try_borrow_mut_lamports()? = 0;
+
+    account.realloc(0, false)?;
+    account.assign(&system_program::id());
+
+    Ok(())
+}
+
+/// Moves lamports out of a program-owned account, which the system program
+/// can't debit.
+pub fn transfer_lamports(from: &AccountInfo, to: &AccountInfo, amount: u64) -> ProgramResult {
+    let remaining = from.lamports().checked_sub(amount).ok_or(ProgramError::InsufficientFunds)?;
+    let received = to.lamports().try_add(amount)?;
+
+    **from.try_borrow_mut_lamports()? = remaining;
+    **to.try_borrow_mut_lamports()? = received;
+    Ok(())
+}
+
+/// Fails unless `account` signed the transaction.
+pub fn check_signer(account: &AccountInfo) -> ProgramResult {
+    if !account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    Ok(())
+}
+
+/// Fails unless `account` was passed as writable.
+pub fn check_writable(account: &AccountInfo) -> ProgramResult {
+    if !account.is_writable {
+        msg!("Account {} must be writable", account.key);
+        return Err(VaultError::AccountNotWritable.into());
+    }
+    Ok(())
+}
+
+/// Fails unless `account` is the program `program_id`.
+pub fn check_program(account: &AccountInfo, program_id: &Pubkey) -> ProgramResult {
+    if account.key != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    Ok(())
+}
+
+/// Fails unless `account` is owned by `owner`.
+pub fn check_owner(account: &AccountInfo, owner: &Pubkey) -> ProgramResult {
+    if account.owner != owner {
+        msg!("Account {} is not owned by {}", account.key, owner);
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    Ok(())
+}
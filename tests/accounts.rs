@@ -0,0 +1,319 @@
+mod common;
+
+use common::*;
+use construct_vault_sol::{
+    error::VaultError,
+    state::{Vault, VaultKind},
+};
+use solana_program::{program_option::COption, program_pack::Pack, rent::Rent};
+use solana_sdk::{
+    account::Account,
+    instruction::{Instruction, InstructionError},
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+use spl_associated_token_account::get_associated_token_address;
+use spl_token::state::{Account as TokenAccount, AccountState};
+
+// Account positions shared by the initialize, deposit and withdraw builders
+const VAULT: usize = 1;
+const VAULT_ATA: usize = 2;
+const USER_TOKEN_ACCOUNT: usize = 3;
+const TOKEN_MINT: usize = 5;
+const WITHDRAW_TOKEN_PROGRAM: usize = 6;
+
+/// An SPL token account of `owner` holding `mint`, as an attacker might plant it.
+fn token_account(owner: Pubkey, mint: Pubkey) -> Account {
+    let mut data = vec![0; TokenAccount::LEN];
+    TokenAccount::pack(
+        TokenAccount {
+            mint,
+            owner,
+            amount: 1_000_000_000,
+            delegate: COption::None,
+            state: AccountState::Initialized,
+            is_native: COption::None,
+            delegated_amount: 0,
+            close_authority: COption::None,
+        },
+        &mut data,
+    )
+    .unwrap();
+
+    Account {
+        lamports: Rent::default().minimum_balance(TokenAccount::LEN),
+        data,
+        owner: spl_token::id(),
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+/// A copy of `owner`'s vault account data, owned by `program_owner`.
+async fn vault_copy(ctx: &mut TestContext, owner: &Pubkey, program_owner: Pubkey) -> Account {
+    let (vault_pda, _) = vault_address(&ctx.program_id, owner);
+    let account = ctx.banks_client.get_account(vault_pda).await.unwrap().unwrap();
+
+    Account {
+        owner: program_owner,
+        ..account
+    }
+}
+
+async fn assert_rejected(ctx: &mut TestContext, instruction: Instruction, signers: &[&Keypair], expected: VaultError) {
+    let error = ctx.process(&[instruction], signers).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(expected as u32));
+}
+
+fn initialize_instruction(ctx: &TestContext) -> (Instruction, Pubkey) {
+    let fee_receiver = Pubkey::new_unique();
+    let instruction = initialize_ix(&ctx.program_id, &ctx.payer.pubkey(), &DEVNET_MINT, 1, &[fee_receiver], None, false, VaultKind::Fixed);
+    (instruction, fee_receiver)
+}
+
+#[tokio::test]
+async fn test_initialize_rejects_a_vault_token_account_off_the_ata() {
+    let mut ctx = setup().await;
+    let (mut instruction, fee_receiver) = initialize_instruction(&ctx);
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    ctx.fund_token_account(&ctx.payer.pubkey(), 1_000_000_000).await;
+
+    instruction.accounts[VAULT_ATA].pubkey = Pubkey::new_unique();
+
+    assert_rejected(&mut ctx, instruction, &[], VaultError::InvalidVaultTokenAccount).await;
+}
+
+#[tokio::test]
+async fn test_initialize_rejects_an_unsupported_mint() {
+    let mut ctx = setup().await;
+    let (mut instruction, fee_receiver) = initialize_instruction(&ctx);
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    ctx.fund_token_account(&ctx.payer.pubkey(), 1_000_000_000).await;
+
+    instruction.accounts[TOKEN_MINT].pubkey = Pubkey::new_unique();
+
+    assert_rejected(&mut ctx, instruction, &[], VaultError::InvalidMint).await;
+}
+
+#[tokio::test]
+async fn test_initialize_rejects_another_wallets_token_account() {
+    let mut ctx = setup().await;
+    let (mut instruction, fee_receiver) = initialize_instruction(&ctx);
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    ctx.fund_token_account(&ctx.payer.pubkey(), 1_000_000_000).await;
+
+    // The token program would refuse the transfer anyway; this fails earlier
+    let other = Pubkey::new_unique();
+    instruction.accounts[USER_TOKEN_ACCOUNT].pubkey = ctx.fund_token_account(&other, 1_000_000_000).await;
+
+    assert_rejected(&mut ctx, instruction, &[], VaultError::InvalidUserTokenAccount).await;
+}
+
+#[tokio::test]
+async fn test_deposit_rejects_a_vault_owned_by_another_program() {
+    let mut ctx = setup().await;
+    let fee_receiver = Pubkey::new_unique();
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    ctx.open_vault(None, fee_receiver, 1, false, VaultKind::Fixed).await;
+    let payer = ctx.payer.pubkey();
+
+    // Same data as the real vault, but another program owns it
+    let spoofed = Pubkey::new_unique();
+    let account = vault_copy(&mut ctx, &payer, Pubkey::new_unique()).await;
+    ctx.set_account(&spoofed, account);
+
+    let mut instruction = deposit_ix(&ctx.program_id, &payer, &DEVNET_MINT, 1);
+    instruction.accounts[VAULT].pubkey = spoofed;
+
+    assert_rejected(&mut ctx, instruction, &[], VaultError::VaultNotProgramOwned).await;
+}
+
+#[tokio::test]
+async fn test_deposit_rejects_a_vault_at_another_address() {
+    let mut ctx = setup().await;
+    let fee_receiver = Pubkey::new_unique();
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    ctx.open_vault(None, fee_receiver, 1, false, VaultKind::Fixed).await;
+    let payer = ctx.payer.pubkey();
+
+    // Program-owned and well-formed, but not at the vault PDA
+    let spoofed = Pubkey::new_unique();
+    let program_id = ctx.program_id;
+    let account = vault_copy(&mut ctx, &payer, program_id).await;
+    ctx.set_account(&spoofed, account);
+
+    let mut instruction = deposit_ix(&ctx.program_id, &payer, &DEVNET_MINT, 1);
+    instruction.accounts[VAULT].pubkey = spoofed;
+
+    assert_rejected(&mut ctx, instruction, &[], VaultError::VaultAddressMismatch).await;
+}
+
+#[tokio::test]
+async fn test_deposit_rejects_another_owners_vault() {
+    let mut ctx = setup().await;
+    let fee_receiver = Pubkey::new_unique();
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    ctx.open_vault(None, fee_receiver, 1, false, VaultKind::Fixed).await;
+    let payer = ctx.payer.pubkey();
+
+    // Depositing into someone else's vault would restart their lock
+    let attacker = Keypair::new();
+    let mut instruction = deposit_ix(&ctx.program_id, &attacker.pubkey(), &DEVNET_MINT, 1);
+    let (victim_vault, _) = vault_address(&ctx.program_id, &payer);
+    instruction.accounts[VAULT].pubkey = victim_vault;
+    instruction.accounts[VAULT_ATA].pubkey = get_associated_token_address(&victim_vault, &DEVNET_MINT);
+
+    assert_rejected(&mut ctx, instruction, &[&attacker], VaultError::VaultAddressMismatch).await;
+}
+
+#[tokio::test]
+async fn test_deposit_rejects_an_unsupported_mint() {
+    let mut ctx = setup().await;
+    let fee_receiver = Pubkey::new_unique();
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    ctx.open_vault(None, fee_receiver, 1, false, VaultKind::Fixed).await;
+
+    let mut instruction = deposit_ix(&ctx.program_id, &ctx.payer.pubkey(), &DEVNET_MINT, 1);
+    instruction.accounts[TOKEN_MINT].pubkey = Pubkey::new_unique();
+
+    assert_rejected(&mut ctx, instruction, &[], VaultError::InvalidMint).await;
+}
+
+#[tokio::test]
+async fn test_deposit_rejects_a_vault_token_account_off_the_ata() {
+    let mut ctx = setup().await;
+    let fee_receiver = Pubkey::new_unique();
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    ctx.open_vault(None, fee_receiver, 1, false, VaultKind::Fixed).await;
+    let payer = ctx.payer.pubkey();
+    let (vault_pda, _) = vault_address(&ctx.program_id, &payer);
+
+    // Owned by the vault and holding KUZA, but not its associated account
+    let spoofed = Pubkey::new_unique();
+    ctx.set_account(&spoofed, token_account(vault_pda, DEVNET_MINT));
+
+    let mut instruction = deposit_ix(&ctx.program_id, &payer, &DEVNET_MINT, 1);
+    instruction.accounts[VAULT_ATA].pubkey = spoofed;
+
+    assert_rejected(&mut ctx, instruction, &[], VaultError::InvalidVaultTokenAccount).await;
+}
+
+#[tokio::test]
+async fn test_deposit_rejects_a_token_account_of_another_mint() {
+    let mut ctx = setup().await;
+    let fee_receiver = Pubkey::new_unique();
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    ctx.open_vault(None, fee_receiver, 1, false, VaultKind::Fixed).await;
+    let payer = ctx.payer.pubkey();
+
+    let spoofed = Pubkey::new_unique();
+    ctx.set_account(&spoofed, token_account(payer, Pubkey::new_unique()));
+
+    let mut instruction = deposit_ix(&ctx.program_id, &payer, &DEVNET_MINT, 1);
+    instruction.accounts[USER_TOKEN_ACCOUNT].pubkey = spoofed;
+
+    assert_rejected(&mut ctx, instruction, &[], VaultError::InvalidUserTokenAccount).await;
+}
+
+#[tokio::test]
+async fn test_deposit_rejects_a_readonly_vault() {
+    let mut ctx = setup().await;
+    let fee_receiver = Pubkey::new_unique();
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    ctx.open_vault(None, fee_receiver, 1, false, VaultKind::Fixed).await;
+
+    let mut instruction = deposit_ix(&ctx.program_id, &ctx.payer.pubkey(), &DEVNET_MINT, 1);
+    instruction.accounts[VAULT].is_writable = false;
+
+    assert_rejected(&mut ctx, instruction, &[], VaultError::AccountNotWritable).await;
+}
+
+#[tokio::test]
+async fn test_withdraw_rejects_a_spoofed_token_program() {
+    let mut ctx = setup().await;
+    let fee_receiver = Pubkey::new_unique();
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    ctx.open_vault(None, fee_receiver, 1, false, VaultKind::Fixed).await;
+
+    // The vault signs the release transfer, so it must only ever go to SPL Token
+    let mut instruction = withdraw_ix(&ctx.program_id, &ctx.payer.pubkey(), &DEVNET_MINT, &[fee_receiver], None);
+    instruction.accounts[WITHDRAW_TOKEN_PROGRAM].pubkey = Pubkey::new_unique();
+
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+    assert_eq!(
+        error.unwrap(),
+        TransactionError::InstructionError(0, InstructionError::IncorrectProgramId)
+    );
+}
+
+#[tokio::test]
+async fn test_withdraw_rejects_a_vault_token_account_off_the_ata() {
+    let mut ctx = setup().await;
+    let fee_receiver = Pubkey::new_unique();
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    ctx.open_vault(None, fee_receiver, 1, false, VaultKind::Fixed).await;
+    let payer = ctx.payer.pubkey();
+    let (vault_pda, _) = vault_address(&ctx.program_id, &payer);
+
+    let spoofed = Pubkey::new_unique();
+    ctx.set_account(&spoofed, token_account(vault_pda, DEVNET_MINT));
+
+    let mut instruction = withdraw_ix(&ctx.program_id, &payer, &DEVNET_MINT, &[fee_receiver], None);
+    instruction.accounts[VAULT_ATA].pubkey = spoofed;
+
+    assert_rejected(&mut ctx, instruction, &[], VaultError::InvalidVaultTokenAccount).await;
+}
+
+#[tokio::test]
+async fn test_withdraw_rejects_another_wallets_token_account() {
+    let mut ctx = setup().await;
+    let fee_receiver = Pubkey::new_unique();
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    ctx.open_vault(None, fee_receiver, 1, false, VaultKind::Fixed).await;
+    let payer = ctx.payer.pubkey();
+
+    let other = Pubkey::new_unique();
+    let other_ata = ctx.fund_token_account(&other, 0).await;
+
+    let mut instruction = withdraw_ix(&ctx.program_id, &payer, &DEVNET_MINT, &[fee_receiver], None);
+    instruction.accounts[USER_TOKEN_ACCOUNT].pubkey = other_ata;
+
+    assert_rejected(&mut ctx, instruction, &[], VaultError::InvalidUserTokenAccount).await;
+}
+
+#[tokio::test]
+async fn test_withdraw_rejects_a_token_account_of_another_mint() {
+    let mut ctx = setup().await;
+    let fee_receiver = Pubkey::new_unique();
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    ctx.open_vault(None, fee_receiver, 1, false, VaultKind::Fixed).await;
+    let payer = ctx.payer.pubkey();
+
+    let spoofed = Pubkey::new_unique();
+    ctx.set_account(&spoofed, token_account(payer, Pubkey::new_unique()));
+
+    let mut instruction = withdraw_ix(&ctx.program_id, &payer, &DEVNET_MINT, &[fee_receiver], None);
+    instruction.accounts[USER_TOKEN_ACCOUNT].pubkey = spoofed;
+
+    assert_rejected(&mut ctx, instruction, &[], VaultError::InvalidUserTokenAccount).await;
+}
+
+#[tokio::test]
+async fn test_withdraw_rejects_a_vault_with_a_bad_bump() {
+    let mut ctx = setup().await;
+    let fee_receiver = Pubkey::new_unique();
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    ctx.open_vault(None, fee_receiver, 1, false, VaultKind::Fixed).await;
+    let payer = ctx.payer.pubkey();
+    let (vault_pda, bump) = vault_address(&ctx.program_id, &payer);
+
+    let program_id = ctx.program_id;
+    let mut account = vault_copy(&mut ctx, &payer, program_id).await;
+    Vault::load_mut(&mut account.data).unwrap().bump = bump.wrapping_sub(1);
+    ctx.set_account(&vault_pda, account);
+
+    let instruction = withdraw_ix(&ctx.program_id, &payer, &DEVNET_MINT, &[fee_receiver], None);
+
+    assert_rejected(&mut ctx, instruction, &[], VaultError::VaultAddressMismatch).await;
+}
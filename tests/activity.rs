@@ -0,0 +1,134 @@
+mod common;
+
+use common::*;
+use construct_vault_sol::state::{ActivityEntry, ActivityKind, ActivityLog, Vault, VaultKind};
+use solana_program::{instruction::InstructionError, pubkey::Pubkey};
+use solana_sdk::{
+    account::Account,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+
+async fn activity(ctx: &mut TestContext, owner: &Pubkey) -> Vec<ActivityEntry> {
+    let (vault_pda, _) = vault_address(&ctx.program_id, owner);
+    let address = activity_address(&ctx.program_id, &vault_pda);
+    let account = ctx.banks_client.get_account(address).await.unwrap().unwrap();
+    assert_eq!(account.owner, ctx.program_id);
+
+    ActivityLog::load(&account.data).unwrap().entries()
+}
+
+fn kinds(entries: &[ActivityEntry]) -> Vec<ActivityKind> {
+    entries.iter().map(|entry| entry.kind().unwrap()).collect()
+}
+
+#[tokio::test]
+async fn test_activity_log_records_each_action_with_its_fee() {
+    let mut ctx = setup().await;
+    let fee_receiver = Pubkey::new_unique();
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+
+    let owner = Keypair::new();
+    ctx.open_vault(Some(&owner), fee_receiver, 5, false, VaultKind::Fixed).await;
+    let owner_key = owner.pubkey();
+    let initialization_fee = ctx.lamports(&fee_receiver).await;
+
+    let instructions = [
+        deposit_ix(&ctx.program_id, &owner_key, &DEVNET_MINT, 10),
+        set_auto_renew_ix(&ctx.program_id, &owner_key, &DEVNET_MINT, true),
+        set_auto_renew_ix(&ctx.program_id, &owner_key, &DEVNET_MINT, false),
+        withdraw_ix(&ctx.program_id, &owner_key, &DEVNET_MINT, &[fee_receiver], None),
+    ];
+    ctx.process(&instructions, &[&owner]).await.unwrap();
+    let withdrawal_fee = ctx.lamports(&fee_receiver).await - initialization_fee;
+    assert!(withdrawal_fee > 0);
+
+    let entries = activity(&mut ctx, &owner_key).await;
+    assert_eq!(
+        kinds(&entries),
+        vec![
+            ActivityKind::Initialize,
+            ActivityKind::Deposit,
+            ActivityKind::AutoRenewOn,
+            ActivityKind::AutoRenewOff,
+            ActivityKind::Withdraw,
+        ]
+    );
+
+    let amounts: Vec<(u64, u64)> =
+        entries.iter().map(|entry| (u64::from(entry.amount), u64::from(entry.fee_lamports))).collect();
+    let locked = 5 * TOKEN + 10;
+    assert_eq!(amounts, vec![(5 * TOKEN, initialization_fee), (10, 0), (0, 0), (0, 0), (locked, withdrawal_fee)]);
+    assert!(entries.iter().all(|entry| u64::from(entry.timestamp) > 0));
+}
+
+#[tokio::test]
+async fn test_activity_log_keeps_the_latest_actions() {
+    let mut ctx = setup().await;
+    let fee_receiver = Pubkey::new_unique();
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    let instruction = set_crank_tip_ix(&ctx.program_id, &ctx.payer.pubkey(), 0);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    let owner = Keypair::new();
+    ctx.open_vault(Some(&owner), fee_receiver, 1, false, VaultKind::Fixed).await;
+    let owner_key = owner.pubkey();
+
+    for amount in 1..=20 {
+        let instruction = deposit_ix(&ctx.program_id, &owner_key, &DEVNET_MINT, amount);
+        ctx.process(&[instruction], &[&owner]).await.unwrap();
+    }
+
+    // The crank records the release on the owner's behalf
+    ctx.warp_forward(Vault::LOCK_DURATION as i64).await;
+    let instruction = crank_release_ix(&ctx.program_id, &ctx.payer.pubkey(), &DEVNET_MINT, &[owner_key]);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    let entries = activity(&mut ctx, &owner_key).await;
+    assert_eq!(entries.len(), ActivityLog::CAPACITY);
+
+    // 22 actions in all; the initialization and first five deposits were overwritten
+    let amounts: Vec<u64> = entries.iter().map(|entry| u64::from(entry.amount)).collect();
+    let mut expected: Vec<u64> = (6..=20).collect();
+    expected.push(TOKEN + 210);
+    assert_eq!(amounts, expected);
+
+    let last = entries.last().unwrap();
+    assert_eq!(last.kind().unwrap(), ActivityKind::Crank);
+    assert!(u64::from(last.timestamp) > u64::from(entries[0].timestamp));
+}
+
+#[tokio::test]
+async fn test_vaults_without_a_log_get_one_at_their_next_deposit() {
+    let mut ctx = setup().await;
+    let fee_receiver = Pubkey::new_unique();
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+
+    let owner = Keypair::new();
+    ctx.open_vault(Some(&owner), fee_receiver, 5, false, VaultKind::Fixed).await;
+    let owner_key = owner.pubkey();
+    let (vault_pda, _) = vault_address(&ctx.program_id, &owner_key);
+    let log_address = activity_address(&ctx.program_id, &vault_pda);
+
+    // A vault from before the logs: owner-only actions go through unrecorded
+    ctx.set_account(&log_address, Account::default());
+    let instruction = set_auto_renew_ix(&ctx.program_id, &owner_key, &DEVNET_MINT, true);
+    ctx.process(&[instruction], &[&owner]).await.unwrap();
+    assert!(ctx.banks_client.get_account(log_address).await.unwrap().is_none());
+
+    // But not with some other account standing in for the log
+    let mut instruction = set_auto_renew_ix(&ctx.program_id, &owner_key, &DEVNET_MINT, false);
+    instruction.accounts[2].pubkey = Pubkey::new_unique();
+    let error = ctx.process(&[instruction], &[&owner]).await.unwrap_err();
+    assert_eq!(
+        error.unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidSeeds)
+    );
+
+    let instruction = deposit_ix(&ctx.program_id, &owner_key, &DEVNET_MINT, 3);
+    ctx.process(&[instruction], &[&owner]).await.unwrap();
+
+    let entries = activity(&mut ctx, &owner_key).await;
+    assert_eq!(kinds(&entries), vec![ActivityKind::Deposit]);
+    assert_eq!(u64::from(entries[0].amount), 3);
+}
@@ -0,0 +1,136 @@
+mod common;
+
+use common::*;
+use construct_vault_sol::{
+    error::VaultError,
+    fee::early_withdrawal_fee,
+    state::{Vault, VaultKind, VaultStatus},
+    MAX_EARLY_WITHDRAWAL_FEE_LAMPORTS,
+};
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+const DAY: i64 = 86_400;
+
+#[test]
+fn test_terms_roll_over_from_the_deposit() {
+    let mut data = vec![0; Vault::LEN];
+    let vault = Vault::init(&mut data).unwrap();
+    vault.deposit_timestamp = 1_000.into();
+    vault.lock_duration = Vault::LOCK_DURATION.into();
+    vault.transition(VaultStatus::Locked, 1_000).unwrap();
+
+    let second_term = 1_000 + Vault::LOCK_DURATION;
+    assert_eq!(vault.term_start(second_term + 5), 1_000);
+    assert_eq!(vault.unlock_timestamp(second_term + 5), second_term);
+
+    vault.auto_renew = 1;
+    assert_eq!(vault.term_start(999), 1_000);
+    assert_eq!(vault.term_start(second_term - 1), 1_000);
+    assert_eq!(vault.term_start(second_term), second_term);
+    assert_eq!(vault.unlock_timestamp(second_term + 5), second_term + Vault::LOCK_DURATION);
+
+    vault.mature(second_term * 3).unwrap();
+    assert_eq!(vault.status(), Ok(VaultStatus::Locked));
+
+    // Switching off keeps the running term's expiry
+    vault.roll_forward(second_term + 5);
+    vault.auto_renew = 0;
+    assert_eq!(vault.unlock_timestamp(second_term + 5), second_term + Vault::LOCK_DURATION);
+}
+
+#[tokio::test]
+async fn test_auto_renewing_vault_never_matures() {
+    let mut ctx = setup().await;
+    let payer = ctx.payer.pubkey();
+    let fee_receiver = Pubkey::new_unique();
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    ctx.open_vault(None, fee_receiver, 1, true, VaultKind::Fixed).await;
+    assert!(ctx.vault(&payer).await.auto_renews());
+
+    ctx.warp_forward(Vault::LOCK_DURATION as i64 + DAY).await;
+
+    let instruction = claim_ix(&ctx.program_id, &payer, &DEVNET_MINT);
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::LockNotExpired as u32));
+
+    // Withdrawing a day into the second term is priced as a day into a lock
+    let fees_before = ctx.lamports(&fee_receiver).await;
+    let instruction = withdraw_ix(&ctx.program_id, &payer, &DEVNET_MINT, &[fee_receiver], None);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    let expected = early_withdrawal_fee(DAY as u64, Vault::LOCK_DURATION, MAX_EARLY_WITHDRAWAL_FEE_LAMPORTS).unwrap();
+    assert_eq!(ctx.lamports(&fee_receiver).await - fees_before, expected);
+    assert_eq!(ctx.vault(&payer).await.status(), Ok(VaultStatus::Released));
+}
+
+#[tokio::test]
+async fn test_switching_off_matures_at_the_end_of_the_running_term() {
+    let mut ctx = setup().await;
+    let payer = ctx.payer.pubkey();
+    let fee_receiver = Pubkey::new_unique();
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    ctx.open_vault(None, fee_receiver, 1, true, VaultKind::Fixed).await;
+    let opened_at = ctx.now().await;
+
+    ctx.warp_forward(Vault::LOCK_DURATION as i64 + DAY).await;
+
+    let instruction = set_auto_renew_ix(&ctx.program_id, &payer, &DEVNET_MINT, false);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    let vault = ctx.vault(&payer).await;
+    assert!(!vault.auto_renews());
+    assert_eq!(u64::from(vault.deposit_timestamp), opened_at + Vault::LOCK_DURATION);
+
+    let instruction = claim_ix(&ctx.program_id, &payer, &DEVNET_MINT);
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::LockNotExpired as u32));
+
+    ctx.warp_forward(Vault::LOCK_DURATION as i64 - DAY).await;
+
+    let instruction = claim_ix(&ctx.program_id, &payer, &DEVNET_MINT);
+    ctx.process(&[instruction], &[]).await.unwrap();
+    assert_eq!(ctx.vault(&payer).await.status(), Ok(VaultStatus::Released));
+}
+
+#[tokio::test]
+async fn test_switching_on_relocks_a_matured_vault() {
+    let mut ctx = setup().await;
+    let payer = ctx.payer.pubkey();
+    let fee_receiver = Pubkey::new_unique();
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    ctx.open_vault(None, fee_receiver, 1, false, VaultKind::Fixed).await;
+
+    ctx.warp_forward(Vault::LOCK_DURATION as i64 + DAY).await;
+
+    let instruction = set_auto_renew_ix(&ctx.program_id, &payer, &DEVNET_MINT, true);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    let relocked_at = ctx.now().await;
+    let vault = ctx.vault(&payer).await;
+    assert!(vault.auto_renews());
+    assert_eq!(vault.status(), Ok(VaultStatus::Locked));
+    assert_eq!(u64::from(vault.deposit_timestamp), relocked_at);
+
+    let instruction = claim_ix(&ctx.program_id, &payer, &DEVNET_MINT);
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::LockNotExpired as u32));
+}
+
+#[tokio::test]
+async fn test_only_the_owner_sets_auto_renew() {
+    let mut ctx = setup().await;
+    let payer = ctx.payer.pubkey();
+    let fee_receiver = Pubkey::new_unique();
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    ctx.open_vault(None, fee_receiver, 1, false, VaultKind::Fixed).await;
+
+    let intruder = Keypair::new();
+    let mut instruction = set_auto_renew_ix(&ctx.program_id, &intruder.pubkey(), &DEVNET_MINT, true);
+    instruction.accounts[1].pubkey = vault_address(&ctx.program_id, &payer).0;
+
+    let error = ctx.process(&[instruction], &[&intruder]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::VaultAddressMismatch as u32));
+}
@@ -0,0 +1,197 @@
+mod common;
+
+use common::*;
+use construct_vault_sol::{
+    error::VaultError,
+    state::{AccessTier, Vault, VaultKind},
+};
+use solana_sdk::{
+    instruction::InstructionError,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+use spl_associated_token_account::instruction::create_associated_token_account;
+use spl_token_2022::{
+    extension::{BaseStateWithExtensions, StateWithExtensions},
+    state::{Account as BadgeAccount, Mint as BadgeMint},
+};
+
+/// Tiers at 1 and `TOKEN + 1_000` base units locked, so a one-token vault
+/// reaches only the first, with the config in place and the payer funded.
+async fn setup_tiers(ctx: &mut TestContext) -> Pubkey {
+    let payer = ctx.payer.pubkey();
+    let fee_receiver = Pubkey::new_unique();
+
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    ctx.fund_token_account(&payer, 1_000_000_000_000).await;
+
+    let tiers = vec![AccessTier { min_amount_locked: 1 }, AccessTier { min_amount_locked: TOKEN + 1_000 }];
+    let instruction = set_access_tiers_ix(&ctx.program_id, &payer, tiers);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    fee_receiver
+}
+
+fn badge_mint(ctx: &TestContext, index: u8) -> Pubkey {
+    AccessTier::find_badge_mint(None, index, &ctx.program_id).0
+}
+
+/// The owner's badge balance for each of the two tiers; a missing badge
+/// account counts as none.
+async fn badges(ctx: &mut TestContext, owner: &Pubkey) -> [u64; 2] {
+    let mut balances = [0; 2];
+
+    for (index, balance) in balances.iter_mut().enumerate() {
+        let address = badge_account_address(owner, &badge_mint(ctx, index as u8));
+        if let Some(account) = ctx.banks_client.get_account(address).await.unwrap() {
+            *balance = StateWithExtensions::<BadgeAccount>::unpack(&account.data).unwrap().base.amount;
+        }
+    }
+
+    balances
+}
+
+#[tokio::test]
+async fn test_badge_mints_are_non_transferable_and_program_controlled() {
+    let mut ctx = setup().await;
+    setup_tiers(&mut ctx).await;
+    let (config_pda, _) = construct_vault_sol::state::Config::find_address(&ctx.program_id);
+
+    let account = ctx.banks_client.get_account(badge_mint(&ctx, 0)).await.unwrap().unwrap();
+    assert_eq!(account.owner, spl_token_2022::id());
+
+    let mint = StateWithExtensions::<BadgeMint>::unpack(&account.data).unwrap();
+    assert_eq!(mint.base.decimals, 0);
+    assert_eq!(mint.base.mint_authority, Some(config_pda).into());
+    assert!(mint.get_extension::<spl_token_2022::extension::non_transferable::NonTransferable>().is_ok());
+
+    let delegate = mint
+        .get_extension::<spl_token_2022::extension::permanent_delegate::PermanentDelegate>()
+        .unwrap();
+    assert_eq!(Option::<Pubkey>::from(delegate.delegate), Some(config_pda));
+
+    // Only configured tiers get a mint
+    let unused = ctx.banks_client.get_account(badge_mint(&ctx, 2)).await.unwrap();
+    assert!(unused.is_none());
+}
+
+#[tokio::test]
+async fn test_vault_instructions_mint_and_burn_badges() {
+    let mut ctx = setup().await;
+    let payer = ctx.payer.pubkey();
+    let fee_receiver = setup_tiers(&mut ctx).await;
+
+    let instruction =
+        initialize_ix(&ctx.program_id, &payer, &DEVNET_MINT, 1, &[fee_receiver], None, false, VaultKind::Fixed);
+    ctx.process(&[instruction], &[]).await.unwrap();
+    assert_eq!(badges(&mut ctx, &payer).await, [1, 0]);
+
+    // Crossing the next threshold adds its badge and keeps the first
+    let instruction = deposit_ix(&ctx.program_id, &payer, &DEVNET_MINT, 1_000);
+    ctx.process(&[instruction], &[]).await.unwrap();
+    assert_eq!(badges(&mut ctx, &payer).await, [1, 1]);
+
+    let instruction = withdraw_ix(&ctx.program_id, &payer, &DEVNET_MINT, &[fee_receiver], None);
+    ctx.process(&[instruction], &[]).await.unwrap();
+    assert_eq!(badges(&mut ctx, &payer).await, [0, 0]);
+}
+
+#[tokio::test]
+async fn test_badges_cannot_be_transferred() {
+    let mut ctx = setup().await;
+    let payer = ctx.payer.pubkey();
+    let fee_receiver = setup_tiers(&mut ctx).await;
+
+    let instruction =
+        initialize_ix(&ctx.program_id, &payer, &DEVNET_MINT, 1, &[fee_receiver], None, false, VaultKind::Fixed);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    let other = Pubkey::new_unique();
+    let mint = badge_mint(&ctx, 0);
+    let instructions = [
+        create_associated_token_account(&payer, &other, &mint, &spl_token_2022::id()),
+        spl_token_2022::instruction::transfer_checked(
+            &spl_token_2022::id(),
+            &badge_account_address(&payer, &mint),
+            &mint,
+            &badge_account_address(&other, &mint),
+            &payer,
+            &[],
+            1,
+            0,
+        )
+        .unwrap(),
+    ];
+    let error = ctx.process(&instructions, &[]).await.unwrap_err();
+    assert_eq!(
+        custom_error(error),
+        Some(spl_token_2022::error::TokenError::NonTransferable as u32)
+    );
+    assert_eq!(badges(&mut ctx, &payer).await, [1, 0]);
+}
+
+#[tokio::test]
+async fn test_anyone_can_sync_badges_after_maturity_or_a_tier_change() {
+    let mut ctx = setup().await;
+    let payer = ctx.payer.pubkey();
+    let fee_receiver = setup_tiers(&mut ctx).await;
+    let cranker = Keypair::new();
+
+    let instructions = [
+        initialize_ix(&ctx.program_id, &payer, &DEVNET_MINT, 1, &[fee_receiver], None, false, VaultKind::Fixed),
+        deposit_ix(&ctx.program_id, &payer, &DEVNET_MINT, 1_000),
+        solana_sdk::system_instruction::transfer(&payer, &cranker.pubkey(), 1_000_000_000),
+    ];
+    ctx.process(&instructions, &[]).await.unwrap();
+
+    // Raising the top tier out of reach takes its badge away
+    let tiers = vec![AccessTier { min_amount_locked: 1 }, AccessTier { min_amount_locked: 2 * TOKEN }];
+    let instructions = [
+        set_access_tiers_ix(&ctx.program_id, &payer, tiers),
+        sync_badges_ix(&ctx.program_id, &cranker.pubkey(), &payer, &DEVNET_MINT),
+    ];
+    ctx.process(&instructions, &[&cranker]).await.unwrap();
+    assert_eq!(badges(&mut ctx, &payer).await, [1, 0]);
+
+    // A matured vault counts toward no tier
+    ctx.warp_forward(Vault::LOCK_DURATION as i64).await;
+    let instruction = sync_badges_ix(&ctx.program_id, &cranker.pubkey(), &payer, &DEVNET_MINT);
+    ctx.process(&[instruction], &[&cranker]).await.unwrap();
+    assert_eq!(badges(&mut ctx, &payer).await, [0, 0]);
+}
+
+#[tokio::test]
+async fn test_badge_accounts_are_checked() {
+    let mut ctx = setup().await;
+    let payer = ctx.payer.pubkey();
+    let fee_receiver = setup_tiers(&mut ctx).await;
+
+    // Once tiers are configured the badge group can't be left off
+    let mut instruction =
+        initialize_ix(&ctx.program_id, &payer, &DEVNET_MINT, 1, &[fee_receiver], None, false, VaultKind::Fixed);
+    instruction.accounts.truncate(instruction.accounts.len() - 10);
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+    assert_eq!(
+        error.unwrap(),
+        TransactionError::InstructionError(0, InstructionError::NotEnoughAccountKeys)
+    );
+
+    // Nor can someone else's badge account stand in for the owner's
+    let mut instruction =
+        initialize_ix(&ctx.program_id, &payer, &DEVNET_MINT, 1, &[fee_receiver], None, false, VaultKind::Fixed);
+    let last = instruction.accounts.len() - 1;
+    instruction.accounts[last - 6].pubkey = badge_account_address(&Pubkey::new_unique(), &badge_mint(&ctx, 0));
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::InvalidBadgeAccount as u32));
+
+    let tiers = vec![AccessTier { min_amount_locked: 5 }, AccessTier { min_amount_locked: 5 }];
+    let instruction = set_access_tiers_ix(&ctx.program_id, &payer, tiers);
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::InvalidAccessTiers as u32));
+
+    let stranger = Keypair::new();
+    let instruction = set_access_tiers_ix(&ctx.program_id, &stranger.pubkey(), vec![]);
+    let error = ctx.process(&[instruction], &[&stranger]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::Unauthorized as u32));
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use common::*;
+use construct_vault_sol::{
+    error::VaultError,
+    state::{Vault, VaultKind, VaultStatus},
+};
+use solana_sdk::{pubkey::Pubkey, signature::Signer};
+
+const DAY: i64 = 86_400;
+
+#[tokio::test]
+async fn test_claim_releases_a_matured_vault_without_fees() {
+    let mut ctx = setup().await;
+    let payer = ctx.payer.pubkey();
+    let fee_receiver = Pubkey::new_unique();
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    let user_ata = ctx.open_vault(None, fee_receiver, 1, false, VaultKind::Fixed).await;
+    let fees_before = ctx.lamports(&fee_receiver).await;
+    let balance_before = ctx.token_balance(&user_ata).await;
+    let vault_ata = vault_token_address(&ctx.program_id, &payer);
+    let locked = ctx.token_balance(&vault_ata).await;
+    assert_eq!(locked, TOKEN);
+
+    ctx.warp_forward(Vault::LOCK_DURATION as i64).await;
+
+    let instruction = claim_ix(&ctx.program_id, &payer, &DEVNET_MINT);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    assert_eq!(ctx.token_balance(&user_ata).await, balance_before + locked);
+    assert_eq!(ctx.token_balance(&vault_ata).await, 0);
+    assert_eq!(ctx.lamports(&fee_receiver).await, fees_before);
+
+    let vault = ctx.vault(&payer).await;
+    assert_eq!(vault.status(), Ok(VaultStatus::Released));
+    assert_eq!(u64::from(vault.amount_locked), 0);
+
+    // Claiming again finds nothing to release
+    let instruction = claim_ix(&ctx.program_id, &payer, &DEVNET_MINT);
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::InvalidStatusTransition as u32));
+}
+
+#[tokio::test]
+async fn test_claim_before_expiry_is_rejected() {
+    let mut ctx = setup().await;
+    let payer = ctx.payer.pubkey();
+    let fee_receiver = Pubkey::new_unique();
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    ctx.open_vault(None, fee_receiver, 1, false, VaultKind::Fixed).await;
+
+    ctx.warp_forward(Vault::LOCK_DURATION as i64 - 1).await;
+
+    let instruction = claim_ix(&ctx.program_id, &payer, &DEVNET_MINT);
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::LockNotExpired as u32));
+    assert_eq!(ctx.vault(&payer).await.status(), Ok(VaultStatus::Locked));
+}
+
+#[tokio::test]
+async fn test_clock_behind_the_deposit_does_not_panic() {
+    let mut ctx = setup().await;
+    let payer = ctx.payer.pubkey();
+    let fee_receiver = Pubkey::new_unique();
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    ctx.open_vault(None, fee_receiver, 1, false, VaultKind::Fixed).await;
+
+    ctx.warp_forward(-DAY).await;
+
+    let instruction = claim_ix(&ctx.program_id, &payer, &DEVNET_MINT);
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::LockNotExpired as u32));
+
+    // Withdrawing treats the skew as no time locked and charges the full fee
+    let fees_before = ctx.lamports(&fee_receiver).await;
+    let instruction = withdraw_ix(&ctx.program_id, &payer, &DEVNET_MINT, &[fee_receiver], None);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    assert_eq!(ctx.lamports(&fee_receiver).await - fees_before, 3_750_000_000);
+    assert_eq!(ctx.vault(&payer).await.status(), Ok(VaultStatus::Released));
+}
+
+#[tokio::test]
+async fn test_claim_only_pays_the_owner() {
+    let mut ctx = setup().await;
+    let payer = ctx.payer.pubkey();
+    let fee_receiver = Pubkey::new_unique();
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    ctx.open_vault(None, fee_receiver, 1, false, VaultKind::Fixed).await;
+
+    ctx.warp_forward(Vault::LOCK_DURATION as i64).await;
+
+    let other_ata = ctx.fund_token_account(&Pubkey::new_unique(), 0).await;
+    let mut instruction = claim_ix(&ctx.program_id, &payer, &DEVNET_MINT);
+    instruction.accounts[3].pubkey = other_ata;
+
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::InvalidUserTokenAccount as u32));
+}
@@ -0,0 +1,57 @@
+use std::process::{Command, Output};
+
+fn construct_vault(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_construct-vault"))
+        .args(args)
+        .env_remove("CONSTRUCT_VAULT_URL")
+        .env_remove("CONSTRUCT_VAULT_PROGRAM_ID")
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_help_lists_every_command() {
+    let output = construct_vault(&["--help"]);
+    assert!(output.status.success());
+
+    let usage = String::from_utf8(output.stdout).unwrap();
+    for command in ["init", "deposit", "withdraw", "claim", "auto-renew", "request-unlock", "cancel-unlock", "crank", "receipts", "redeem", "sync-badges", "status", "quote-fee", "list", "stats", "history"] {
+        assert!(usage.contains(command), "usage doesn't mention {}", command);
+    }
+}
+
+#[test]
+fn test_bad_arguments_exit_with_usage() {
+    for (args, message) in [
+        (&["frobnicate"][..], "unknown command: frobnicate"),
+        (&["deposit", "-p", "11111111111111111111111111111111"][..], "deposit needs --amount"),
+        (&["init", "--amount", "ten"][..], "invalid amount: ten"),
+        (&["auto-renew", "-p", "11111111111111111111111111111111"][..], "auto-renew needs --on or --off"),
+        (&["status", "--owner", "not-a-key"][..], "invalid public key for --owner"),
+        (&["status"][..], "no program id"),
+    ] {
+        let output = construct_vault(args);
+        assert_eq!(output.status.code(), Some(2), "{:?}", args);
+
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains(message), "{:?} printed {}", args, stderr);
+        assert!(stderr.contains("Usage:"));
+    }
+}
+
+#[test]
+fn test_rpc_failures_are_reported() {
+    // Nothing listens on port 1, so the first RPC call fails
+    let output = construct_vault(&[
+        "status",
+        "--url",
+        "http://127.0.0.1:1",
+        "--program-id",
+        "11111111111111111111111111111111",
+        "--owner",
+        "11111111111111111111111111111111",
+    ]);
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stderr).unwrap().starts_with("error: "));
+}
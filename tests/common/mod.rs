@@ -0,0 +1,347 @@
+#![allow(dead_code)]
+
+pub use construct_vault_sol::{client::*, DEVNET_MINT};
+
+use construct_vault_sol::{
+    processor,
+    state::{FeeShare, Vault, VaultKind},
+};
+use solana_program::{
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+    clock::Clock,
+    program_option::COption,
+    program_pack::Pack,
+};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    hash::Hash,
+    instruction::{Instruction, InstructionError},
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction,
+    transaction::{Transaction, TransactionError},
+};
+use spl_associated_token_account::{
+    get_associated_token_address, instruction::create_associated_token_account,
+};
+use spl_token::state::Mint;
+
+pub struct TestContext {
+    pub context: ProgramTestContext,
+    pub banks_client: BanksClient,
+    pub payer: Keypair,
+    pub recent_blockhash: Hash,
+    pub program_id: Pubkey,
+    pub mint_authority: Keypair,
+}
+
+/// Base units in one token of the test KUZA mint, which has 9 decimals.
+pub const TOKEN: u64 = 1_000_000_000;
+
+/// Starts a program test with the devnet KUZA mint already in place; it
+/// needs no registration, unlike any other mint.
+pub async fn setup() -> TestContext {
+    setup_with_accounts(Pubkey::new_unique(), vec![]).await
+}
+
+/// Like `setup`, with extra accounts preloaded before the test starts.
+pub async fn setup_with_accounts(program_id: Pubkey, accounts: Vec<(Pubkey, Account)>) -> TestContext {
+    let mint_authority = Keypair::new();
+
+    let mut program_test = ProgramTest::new(
+        "construct_vault_sol",
+        program_id,
+        processor!(processor::process_instruction),
+    );
+
+    let mut mint_data = vec![0; Mint::LEN];
+    Mint::pack(
+        Mint {
+            mint_authority: COption::Some(mint_authority.pubkey()),
+            supply: 0,
+            decimals: 9,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        },
+        &mut mint_data,
+    )
+    .unwrap();
+
+    program_test.add_account(
+        DEVNET_MINT,
+        Account {
+            lamports: 1_000_000_000,
+            data: mint_data,
+            owner: spl_token::id(),
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+
+    for (address, account) in accounts {
+        program_test.add_account(address, account);
+    }
+
+    let context = program_test.start_with_context().await;
+    let banks_client = context.banks_client.clone();
+    let payer = context.payer.insecure_clone();
+    let recent_blockhash = context.last_blockhash;
+
+    let mut ctx = TestContext {
+        context,
+        banks_client,
+        payer,
+        recent_blockhash,
+        program_id,
+        mint_authority,
+    };
+
+    // The payer deployed the program, so it may initialize the config
+    let upgrade_authority = ctx.payer.pubkey();
+    ctx.set_account(&bpf_loader_upgradeable::get_program_data_address(&program_id), program_data(upgrade_authority));
+    ctx
+}
+
+/// A ProgramData account naming `upgrade_authority` as the program's
+/// upgrade authority.
+pub fn program_data(upgrade_authority: Pubkey) -> Account {
+    let state = UpgradeableLoaderState::ProgramData {
+        slot: 0,
+        upgrade_authority_address: Some(upgrade_authority),
+    };
+    Account::new_data(1_000_000_000, &state, &bpf_loader_upgradeable::id()).unwrap()
+}
+
+/// A KUZA token account of `owner` holding `amount` base units, for
+/// preloading the token account of a vault planted with `setup_with_accounts`.
+pub fn kuza_token_account(owner: Pubkey, amount: u64) -> Account {
+    let mut data = vec![0; spl_token::state::Account::LEN];
+    spl_token::state::Account::pack(
+        spl_token::state::Account {
+            mint: DEVNET_MINT,
+            owner,
+            amount,
+            state: spl_token::state::AccountState::Initialized,
+            ..Default::default()
+        },
+        &mut data,
+    )
+    .unwrap();
+
+    Account {
+        lamports: 1_000_000_000,
+        data,
+        owner: spl_token::id(),
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+impl TestContext {
+    pub async fn process(
+        &mut self,
+        instructions: &[Instruction],
+        extra_signers: &[&Keypair],
+    ) -> Result<(), BanksClientError> {
+        let mut signers: Vec<&Keypair> = vec![&self.payer];
+        signers.extend_from_slice(extra_signers);
+
+        self.recent_blockhash = self
+            .banks_client
+            .get_new_latest_blockhash(&self.recent_blockhash)
+            .await
+            .unwrap();
+
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.payer.pubkey()),
+            &signers,
+            self.recent_blockhash,
+        );
+
+        self.banks_client.process_transaction(transaction).await
+    }
+
+    /// Simulates `instructions` and returns the compute units they used,
+    /// failing the test if the simulation errors.
+    pub async fn units_consumed(&mut self, instructions: &[Instruction], extra_signers: &[&Keypair]) -> u64 {
+        let mut signers: Vec<&Keypair> = vec![&self.payer];
+        signers.extend_from_slice(extra_signers);
+
+        self.recent_blockhash = self
+            .banks_client
+            .get_new_latest_blockhash(&self.recent_blockhash)
+            .await
+            .unwrap();
+
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.payer.pubkey()),
+            &signers,
+            self.recent_blockhash,
+        );
+
+        let simulation = self.banks_client.simulate_transaction(transaction).await.unwrap();
+        simulation.result.unwrap().unwrap();
+        simulation.simulation_details.unwrap().units_consumed
+    }
+
+    /// Creates `owner`'s KUZA ATA and mints `amount` base units into it.
+    pub async fn fund_token_account(&mut self, owner: &Pubkey, amount: u64) -> Pubkey {
+        let ata = get_associated_token_address(owner, &DEVNET_MINT);
+
+        let create_ata_ix = create_associated_token_account(
+            &self.payer.pubkey(),
+            owner,
+            &DEVNET_MINT,
+            &spl_token::id(),
+        );
+
+        let mint_to_ix = spl_token::instruction::mint_to(
+            &spl_token::id(),
+            &DEVNET_MINT,
+            &ata,
+            &self.mint_authority.pubkey(),
+            &[&self.mint_authority.pubkey()],
+            amount,
+        )
+        .unwrap();
+
+        let mint_authority = self.mint_authority.insecure_clone();
+        self.process(&[create_ata_ix, mint_to_ix], &[&mint_authority])
+            .await
+            .unwrap();
+
+        ata
+    }
+
+    /// Creates a new mint with the payer as mint authority and mints
+    /// `amount` base units into the payer's account of it.
+    pub async fn create_funded_mint(&mut self, decimals: u8, amount: u64) -> Pubkey {
+        let payer = self.payer.pubkey();
+        let mint = Keypair::new();
+        let rent = self.banks_client.get_rent().await.unwrap().minimum_balance(Mint::LEN);
+
+        let instructions = [
+            system_instruction::create_account(&payer, &mint.pubkey(), rent, Mint::LEN as u64, &spl_token::id()),
+            spl_token::instruction::initialize_mint2(&spl_token::id(), &mint.pubkey(), &payer, None, decimals)
+                .unwrap(),
+            create_associated_token_account(&payer, &payer, &mint.pubkey(), &spl_token::id()),
+            spl_token::instruction::mint_to(
+                &spl_token::id(),
+                &mint.pubkey(),
+                &get_associated_token_address(&payer, &mint.pubkey()),
+                &payer,
+                &[],
+                amount,
+            )
+            .unwrap(),
+        ];
+        self.process(&instructions, &[&mint]).await.unwrap();
+
+        mint.pubkey()
+    }
+
+    pub async fn initialize_config(&mut self, fee_shares: Vec<FeeShare>, dust_recipient: u8) {
+        let instruction = initialize_config_ix(
+            &self.program_id,
+            &self.payer.pubkey(),
+            fee_shares,
+            dust_recipient,
+        );
+
+        self.process(&[instruction], &[]).await.unwrap();
+    }
+
+    /// Opens a KUZA vault of `kind` locking `amount` whole tokens, paying the
+    /// initialization fee to `fee_receiver`. The payer owns it unless `owner`
+    /// is given, which is funded with SOL first. Either way the owner's token
+    /// account is funded with 1,000 tokens, and returned.
+    pub async fn open_vault(
+        &mut self,
+        owner: Option<&Keypair>,
+        fee_receiver: Pubkey,
+        amount: u64,
+        auto_renew: bool,
+        kind: VaultKind,
+    ) -> Pubkey {
+        let owner_key = owner.map_or(self.payer.pubkey(), |owner| owner.pubkey());
+        if owner.is_some() {
+            let fund = system_instruction::transfer(&self.payer.pubkey(), &owner_key, 10_000_000_000);
+            self.process(&[fund], &[]).await.unwrap();
+        }
+        let user_ata = self.fund_token_account(&owner_key, 1_000 * TOKEN).await;
+
+        let instruction =
+            initialize_ix(&self.program_id, &owner_key, &DEVNET_MINT, amount, &[fee_receiver], None, auto_renew, kind);
+        let signers: Vec<&Keypair> = owner.into_iter().collect();
+        self.process(&[instruction], &signers).await.unwrap();
+
+        user_ata
+    }
+
+    /// The clock's unix timestamp.
+    pub async fn now(&mut self) -> u64 {
+        let clock: Clock = self.banks_client.get_sysvar().await.unwrap();
+        clock.unix_timestamp as u64
+    }
+
+    /// Overwrites `address` with `account`, e.g. to plant a spoofed account.
+    pub fn set_account(&mut self, address: &Pubkey, account: Account) {
+        self.context.set_account(address, &account.into());
+    }
+
+    /// Moves the clock's unix timestamp forward by `seconds`.
+    pub async fn warp_forward(&mut self, seconds: i64) {
+        let mut clock: Clock = self.banks_client.get_sysvar().await.unwrap();
+        clock.unix_timestamp += seconds;
+        self.context.set_sysvar(&clock);
+    }
+
+    /// Moves the clock's slot forward by `slots`, leaving its unix timestamp.
+    pub async fn warp_slots(&mut self, slots: u64) {
+        let mut clock: Clock = self.banks_client.get_sysvar().await.unwrap();
+        clock.slot += slots;
+        self.context.set_sysvar(&clock);
+    }
+
+    pub async fn lamports(&mut self, address: &Pubkey) -> u64 {
+        self.banks_client.get_balance(*address).await.unwrap()
+    }
+
+    pub async fn token_balance(&mut self, address: &Pubkey) -> u64 {
+        let account = self.banks_client.get_account(*address).await.unwrap().unwrap();
+        spl_token::state::Account::unpack(&account.data).unwrap().amount
+    }
+
+    pub async fn vault(&mut self, owner: &Pubkey) -> Vault {
+        let (vault_pda, _) = vault_address(&self.program_id, owner);
+        let account = self.banks_client.get_account(vault_pda).await.unwrap().unwrap();
+        Vault::unpack(&account.data).unwrap()
+    }
+}
+
+/// Extracts the custom program error code from a failed transaction.
+pub fn custom_error(error: BanksClientError) -> Option<u32> {
+    match error.unwrap() {
+        TransactionError::InstructionError(_, InstructionError::Custom(code)) => Some(code),
+        _ => None,
+    }
+}
+
+pub fn vault_address(program_id: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Vault::find_address(owner, program_id)
+}
+
+/// The token account holding what `owner`'s KUZA vault has locked.
+pub fn vault_token_address(program_id: &Pubkey, owner: &Pubkey) -> Pubkey {
+    get_associated_token_address(&vault_address(program_id, owner).0, &DEVNET_MINT)
+}
+
+pub fn single_recipient(recipient: Pubkey) -> Vec<FeeShare> {
+    vec![FeeShare {
+        recipient,
+        bps: 10_000,
+    }]
+}
@@ -0,0 +1,355 @@
+//! Compute-unit budgets per instruction. Priority fees are estimated from
+//! these numbers, so raising one should be a deliberate change here.
+//!
+//! The budgets are for the SBF build. The native `processor!` build used by
+//! `cargo test` charges just the token and system CPIs and syscalls, so the
+//! tests are ignored there and run against the program's `.so` with:
+//!
+//! ```bash
+//! cargo test-sbf --test compute_budget -- --ignored
+//! ```
+//!
+//! `cargo test-sbf` sets `SBF_OUT_DIR`, which makes `ProgramTest` load the
+//! SBF build over the native processor.
+
+mod common;
+
+use borsh::BorshSerialize;
+use common::*;
+use construct_vault_sol::state::{AccessTier, Config, FeeExemptionReason, LegacyVault, LoyaltyTier, Vault, VaultKind};
+use solana_program::rent::Rent;
+use solana_sdk::{
+    account::Account,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+const DAY: i64 = 86_400;
+
+const INITIALIZE_CONFIG_BUDGET: u64 = 15_000;
+const SET_FEE_SHARES_BUDGET: u64 = 10_000;
+const SET_REFERRAL_SHARE_BUDGET: u64 = 10_000;
+const SET_LOYALTY_TIERS_BUDGET: u64 = 10_000;
+const SET_FEE_EXEMPTION_BUDGET: u64 = 20_000;
+const REMOVE_FEE_EXEMPTION_BUDGET: u64 = 15_000;
+const INITIALIZE_BUDGET: u64 = 80_000;
+const REFERRED_INITIALIZE_BUDGET: u64 = 100_000;
+const DEPOSIT_BUDGET: u64 = 20_000;
+const WITHDRAW_BUDGET: u64 = 60_000;
+const MIGRATE_VAULT_BUDGET: u64 = 20_000;
+const CLAIM_BUDGET: u64 = 20_000;
+const SET_CRANK_TIP_BUDGET: u64 = 20_000;
+const SET_AUTO_RENEW_BUDGET: u64 = 10_000;
+const SET_COOLDOWN_BUDGET: u64 = 10_000;
+const REQUEST_UNLOCK_BUDGET: u64 = 10_000;
+const CANCEL_UNLOCK_BUDGET: u64 = 10_000;
+// Creates the receipt mint and the owner's receipt account
+const ENABLE_RECEIPTS_BUDGET: u64 = 60_000;
+const REDEEM_BUDGET: u64 = 30_000;
+// Creates a badge mint per tier
+const SET_ACCESS_TIERS_BUDGET: u64 = 40_000;
+// Each badge earned adds an account creation and a mint CPI
+const BADGED_INITIALIZE_BUDGET: u64 = 120_000;
+const BADGED_DEPOSIT_BUDGET: u64 = 50_000;
+const SYNC_BADGES_BUDGET: u64 = 30_000;
+// Creates the mint config and a badge mint per tier
+const SET_MINT_CONFIG_BUDGET: u64 = 40_000;
+// No token CPIs; the lamports move by system transfer or directly
+const INITIALIZE_SOL_BUDGET: u64 = 50_000;
+const DEPOSIT_SOL_BUDGET: u64 = 15_000;
+const WITHDRAW_SOL_BUDGET: u64 = 40_000;
+// For a batch of one vault; each further vault adds a token CPI
+const CRANK_RELEASE_BUDGET: u64 = 25_000;
+
+/// Checks `instruction` against `budget`, then runs it for real so later
+/// steps see its effects.
+async fn assert_within_budget(
+    ctx: &mut TestContext,
+    name: &str,
+    instruction: Instruction,
+    extra_signers: &[&Keypair],
+    budget: u64,
+) {
+    let units = ctx.units_consumed(std::slice::from_ref(&instruction), extra_signers).await;
+    assert!(
+        units <= budget,
+        "{} used {} compute units, over its budget of {}",
+        name,
+        units,
+        budget
+    );
+
+    ctx.process(&[instruction], extra_signers).await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "meters only the SBF build; run under cargo test-sbf"]
+async fn test_admin_instruction_budgets() {
+    let mut ctx = setup().await;
+    let program_id = ctx.program_id;
+    let admin = ctx.payer.pubkey();
+    let fee_receiver = Pubkey::new_unique();
+    let wallet = Pubkey::new_unique();
+
+    let instruction = initialize_config_ix(&program_id, &admin, single_recipient(fee_receiver), 0);
+    assert_within_budget(&mut ctx, "InitializeConfig", instruction, &[], INITIALIZE_CONFIG_BUDGET).await;
+
+    let instruction = set_fee_shares_ix(&program_id, &admin, single_recipient(fee_receiver), 0);
+    assert_within_budget(&mut ctx, "SetFeeShares", instruction, &[], SET_FEE_SHARES_BUDGET).await;
+
+    let instruction = set_referral_share_ix(&program_id, &admin, 2_000);
+    assert_within_budget(&mut ctx, "SetReferralShare", instruction, &[], SET_REFERRAL_SHARE_BUDGET).await;
+
+    let tiers = vec![
+        LoyaltyTier { min_completed_locks: 1, discount_bps: 1_000 },
+        LoyaltyTier { min_completed_locks: 5, discount_bps: 2_500 },
+    ];
+    let instruction = set_loyalty_tiers_ix(&program_id, &admin, tiers);
+    assert_within_budget(&mut ctx, "SetLoyaltyTiers", instruction, &[], SET_LOYALTY_TIERS_BUDGET).await;
+
+    let instruction = set_fee_exemption_ix(
+        &program_id,
+        &admin,
+        wallet,
+        Some(i64::MAX),
+        FeeExemptionReason::PartnerProtocol,
+    );
+    assert_within_budget(&mut ctx, "SetFeeExemption", instruction, &[], SET_FEE_EXEMPTION_BUDGET).await;
+
+    let instruction = remove_fee_exemption_ix(&program_id, &admin, wallet);
+    assert_within_budget(&mut ctx, "RemoveFeeExemption", instruction, &[], REMOVE_FEE_EXEMPTION_BUDGET).await;
+
+    let instruction = set_crank_tip_ix(&program_id, &admin, 1_000_000);
+    assert_within_budget(&mut ctx, "SetCrankTip", instruction, &[], SET_CRANK_TIP_BUDGET).await;
+
+    let instruction = set_cooldown_ix(&program_id, &admin, DAY as u64);
+    assert_within_budget(&mut ctx, "SetCooldown", instruction, &[], SET_COOLDOWN_BUDGET).await;
+}
+
+#[tokio::test]
+#[ignore = "meters only the SBF build; run under cargo test-sbf"]
+async fn test_vault_lifecycle_budgets() {
+    let mut ctx = setup().await;
+    let program_id = ctx.program_id;
+    let payer = ctx.payer.pubkey();
+    let fee_receiver = Pubkey::new_unique();
+
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    ctx.fund_token_account(&payer, 10_000_000_000).await;
+
+    let instruction = initialize_ix(&program_id, &payer, &DEVNET_MINT, 1, &[fee_receiver], None, false, VaultKind::Fixed);
+    assert_within_budget(&mut ctx, "Initialize", instruction, &[], INITIALIZE_BUDGET).await;
+
+    let instruction = deposit_ix(&program_id, &payer, &DEVNET_MINT, 1_000);
+    assert_within_budget(&mut ctx, "Deposit", instruction, &[], DEPOSIT_BUDGET).await;
+
+    let instruction = set_auto_renew_ix(&program_id, &payer, &DEVNET_MINT, true);
+    assert_within_budget(&mut ctx, "SetAutoRenew", instruction, &[], SET_AUTO_RENEW_BUDGET).await;
+    let instruction = set_auto_renew_ix(&program_id, &payer, &DEVNET_MINT, false);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    // Early exit: fee curve, loyalty update and fee distribution
+    let instruction = withdraw_ix(&program_id, &payer, &DEVNET_MINT, &[fee_receiver], None);
+    assert_within_budget(&mut ctx, "Withdraw (early)", instruction, &[], WITHDRAW_BUDGET).await;
+
+    let instruction = deposit_ix(&program_id, &payer, &DEVNET_MINT, 1_000);
+    ctx.process(&[instruction], &[]).await.unwrap();
+    ctx.warp_forward(Vault::LOCK_DURATION as i64).await;
+
+    let instruction = claim_ix(&program_id, &payer, &DEVNET_MINT);
+    assert_within_budget(&mut ctx, "Claim", instruction, &[], CLAIM_BUDGET).await;
+
+    let instruction = set_crank_tip_ix(&program_id, &payer, 1_000_000);
+    ctx.process(&[instruction], &[]).await.unwrap();
+    let instruction = deposit_ix(&program_id, &payer, &DEVNET_MINT, 1_000);
+    ctx.process(&[instruction], &[]).await.unwrap();
+    ctx.warp_forward(Vault::LOCK_DURATION as i64).await;
+
+    let instruction = crank_release_ix(&program_id, &payer, &DEVNET_MINT, &[payer]);
+    assert_within_budget(&mut ctx, "CrankRelease", instruction, &[], CRANK_RELEASE_BUDGET).await;
+}
+
+#[tokio::test]
+#[ignore = "meters only the SBF build; run under cargo test-sbf"]
+async fn test_cooldown_vault_budgets() {
+    let mut ctx = setup().await;
+    let program_id = ctx.program_id;
+    let payer = ctx.payer.pubkey();
+    let fee_receiver = Pubkey::new_unique();
+
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    ctx.fund_token_account(&payer, 1_000_000_000).await;
+
+    let instruction =
+        initialize_ix(&program_id, &payer, &DEVNET_MINT, 1, &[fee_receiver], None, false, VaultKind::Cooldown);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    let instruction = request_unlock_ix(&program_id, &payer, &DEVNET_MINT);
+    assert_within_budget(&mut ctx, "RequestUnlock", instruction, &[], REQUEST_UNLOCK_BUDGET).await;
+
+    let instruction = cancel_unlock_ix(&program_id, &payer, &DEVNET_MINT);
+    assert_within_budget(&mut ctx, "CancelUnlock", instruction, &[], CANCEL_UNLOCK_BUDGET).await;
+
+    let instruction = request_unlock_ix(&program_id, &payer, &DEVNET_MINT);
+    ctx.process(&[instruction], &[]).await.unwrap();
+    ctx.warp_forward(Config::DEFAULT_COOLDOWN as i64).await;
+
+    let instruction = claim_ix(&program_id, &payer, &DEVNET_MINT);
+    assert_within_budget(&mut ctx, "Claim (cooldown)", instruction, &[], CLAIM_BUDGET).await;
+}
+
+#[tokio::test]
+#[ignore = "meters only the SBF build; run under cargo test-sbf"]
+async fn test_receipt_vault_budgets() {
+    let mut ctx = setup().await;
+    let program_id = ctx.program_id;
+    let payer = ctx.payer.pubkey();
+    let fee_receiver = Pubkey::new_unique();
+
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    ctx.fund_token_account(&payer, 1_000_000_000_000).await;
+
+    let instruction =
+        initialize_ix(&program_id, &payer, &DEVNET_MINT, 1, &[fee_receiver], None, false, VaultKind::Fixed);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    let instruction = enable_receipts_ix(&program_id, &payer, &DEVNET_MINT);
+    assert_within_budget(&mut ctx, "EnableReceipts", instruction, &[], ENABLE_RECEIPTS_BUDGET).await;
+
+    let instruction = deposit_with_receipts_ix(&program_id, &payer, &DEVNET_MINT, 1_000);
+    assert_within_budget(&mut ctx, "Deposit (receipts)", instruction, &[], DEPOSIT_BUDGET).await;
+
+    ctx.warp_forward(Vault::LOCK_DURATION as i64).await;
+
+    let instruction = redeem_ix(&program_id, &payer, &payer, &DEVNET_MINT);
+    assert_within_budget(&mut ctx, "Redeem", instruction, &[], REDEEM_BUDGET).await;
+}
+
+#[tokio::test]
+#[ignore = "meters only the SBF build; run under cargo test-sbf"]
+async fn test_badge_budgets() {
+    let mut ctx = setup().await;
+    let program_id = ctx.program_id;
+    let payer = ctx.payer.pubkey();
+    let fee_receiver = Pubkey::new_unique();
+
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    ctx.fund_token_account(&payer, 1_000_000_000_000).await;
+
+    let tiers = vec![AccessTier { min_amount_locked: 1 }, AccessTier { min_amount_locked: TOKEN + 1_000 }];
+    let instruction = set_access_tiers_ix(&program_id, &payer, tiers);
+    assert_within_budget(&mut ctx, "SetAccessTiers", instruction, &[], SET_ACCESS_TIERS_BUDGET).await;
+
+    let instruction =
+        initialize_ix(&program_id, &payer, &DEVNET_MINT, 1, &[fee_receiver], None, false, VaultKind::Fixed);
+    assert_within_budget(&mut ctx, "Initialize (badges)", instruction, &[], BADGED_INITIALIZE_BUDGET).await;
+
+    let instruction = deposit_ix(&program_id, &payer, &DEVNET_MINT, 1_000);
+    assert_within_budget(&mut ctx, "Deposit (badges)", instruction, &[], BADGED_DEPOSIT_BUDGET).await;
+
+    ctx.warp_forward(Vault::LOCK_DURATION as i64).await;
+
+    let instruction = sync_badges_ix(&program_id, &payer, &payer, &DEVNET_MINT);
+    assert_within_budget(&mut ctx, "SyncBadges", instruction, &[], SYNC_BADGES_BUDGET).await;
+}
+
+#[tokio::test]
+#[ignore = "meters only the SBF build; run under cargo test-sbf"]
+async fn test_registered_mint_budgets() {
+    let mut ctx = setup().await;
+    let program_id = ctx.program_id;
+    let payer = ctx.payer.pubkey();
+    let fee_receiver = Pubkey::new_unique();
+
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    let mint = ctx.create_funded_mint(6, 1_000_000_000).await;
+
+    let tiers = vec![AccessTier { min_amount_locked: 1 }, AccessTier { min_amount_locked: 1_000 }];
+    let instruction = set_mint_config_ix(&program_id, &payer, &mint, true, DAY as u64, 0, 0, 0, tiers);
+    assert_within_budget(&mut ctx, "SetMintConfig", instruction, &[], SET_MINT_CONFIG_BUDGET).await;
+
+    let instruction = initialize_ix(&program_id, &payer, &mint, 1, &[fee_receiver], None, false, VaultKind::Fixed);
+    assert_within_budget(&mut ctx, "Initialize (registered)", instruction, &[], BADGED_INITIALIZE_BUDGET).await;
+
+    let instruction = withdraw_ix(&program_id, &payer, &mint, &[fee_receiver], None);
+    assert_within_budget(&mut ctx, "Withdraw (registered)", instruction, &[], WITHDRAW_BUDGET).await;
+}
+
+#[tokio::test]
+#[ignore = "meters only the SBF build; run under cargo test-sbf"]
+async fn test_sol_vault_budgets() {
+    let mut ctx = setup().await;
+    let program_id = ctx.program_id;
+    let payer = ctx.payer.pubkey();
+    let fee_receiver = Pubkey::new_unique();
+
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    let native_mint = spl_token::native_mint::id();
+    let instruction = set_mint_config_ix(&program_id, &payer, &native_mint, true, DAY as u64, 0, 10_000_000, 0, vec![]);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    let instruction =
+        initialize_sol_ix(&program_id, &payer, 1_000_000_000, &[fee_receiver], None, false, VaultKind::Fixed);
+    assert_within_budget(&mut ctx, "InitializeSol", instruction, &[], INITIALIZE_SOL_BUDGET).await;
+
+    let instruction = deposit_sol_ix(&program_id, &payer, 1_000_000);
+    assert_within_budget(&mut ctx, "DepositSol", instruction, &[], DEPOSIT_SOL_BUDGET).await;
+
+    let instruction = withdraw_sol_ix(&program_id, &payer, &[fee_receiver], None);
+    assert_within_budget(&mut ctx, "WithdrawSol", instruction, &[], WITHDRAW_SOL_BUDGET).await;
+}
+
+#[tokio::test]
+#[ignore = "meters only the SBF build; run under cargo test-sbf"]
+async fn test_referred_vault_budgets() {
+    let mut ctx = setup().await;
+    let program_id = ctx.program_id;
+    let payer = ctx.payer.pubkey();
+    let fee_receiver = Pubkey::new_unique();
+    let referrer = Pubkey::new_unique();
+
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    ctx.fund_token_account(&payer, 1_000_000_000).await;
+
+    let instruction = initialize_ix(&program_id, &payer, &DEVNET_MINT, 1, &[fee_receiver], Some(referrer), false, VaultKind::Fixed);
+    assert_within_budget(&mut ctx, "Initialize (referred)", instruction, &[], REFERRED_INITIALIZE_BUDGET).await;
+
+    ctx.warp_forward(Vault::LOCK_DURATION as i64 + DAY).await;
+
+    let instruction = withdraw_ix(&program_id, &payer, &DEVNET_MINT, &[fee_receiver], Some(referrer));
+    assert_within_budget(&mut ctx, "Withdraw (matured)", instruction, &[], WITHDRAW_BUDGET).await;
+}
+
+#[tokio::test]
+#[ignore = "meters only the SBF build; run under cargo test-sbf"]
+async fn test_migrate_vault_budget() {
+    let program_id = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let (vault_pda, _) = vault_address(&program_id, &owner);
+
+    let legacy = LegacyVault {
+        owner,
+        lock_duration: Vault::LOCK_DURATION,
+        amount_locked: 42,
+        deposit_timestamp: 1_700_000_000,
+        is_locked: true,
+        bump: 0,
+    };
+    let mut data = Vec::new();
+    legacy.serialize(&mut data).unwrap();
+
+    let account = Account {
+        lamports: Rent::default().minimum_balance(Vault::LEGACY_LEN),
+        data,
+        owner: program_id,
+        executable: false,
+        rent_epoch: 0,
+    };
+
+    let vault_ata = (vault_token_address(&program_id, &owner), kuza_token_account(vault_pda, 42 * TOKEN));
+    let mut ctx = setup_with_accounts(program_id, vec![(vault_pda, account), vault_ata]).await;
+
+    let instruction = migrate_vault_ix(&program_id, &ctx.payer.pubkey(), &owner, &DEVNET_MINT);
+    assert_within_budget(&mut ctx, "MigrateVault", instruction, &[], MIGRATE_VAULT_BUDGET).await;
+}
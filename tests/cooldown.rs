@@ -0,0 +1,135 @@
+mod common;
+
+use common::*;
+use construct_vault_sol::{
+    error::VaultError,
+    state::{Config, Vault, VaultKind, VaultStatus},
+};
+use solana_sdk::{pubkey::Pubkey, signature::Signer};
+
+const DAY: i64 = 86_400;
+
+#[tokio::test]
+async fn test_cooldown_vault_claims_after_the_cooldown() {
+    let mut ctx = setup().await;
+    let payer = ctx.payer.pubkey();
+    let fee_receiver = Pubkey::new_unique();
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    let user_ata = ctx.open_vault(None, fee_receiver, 1, false, VaultKind::Cooldown).await;
+    let amount_locked = u64::from(ctx.vault(&payer).await.amount_locked);
+
+    // No fixed expiry: the lock holds however long it has been
+    ctx.warp_forward(2 * Vault::LOCK_DURATION as i64).await;
+    let instruction = claim_ix(&ctx.program_id, &payer, &DEVNET_MINT);
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::LockNotExpired as u32));
+    assert!(ctx.vault(&payer).await.counts_toward_tier(ctx.now().await));
+
+    let instruction = set_cooldown_ix(&ctx.program_id, &payer, 3 * DAY as u64);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    let instruction = request_unlock_ix(&ctx.program_id, &payer, &DEVNET_MINT);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    let requested_at = ctx.now().await;
+    let vault = ctx.vault(&payer).await;
+    assert_eq!(vault.status(), Ok(VaultStatus::Unlocking));
+    assert_eq!(vault.cooldown_ends_at(), requested_at + 3 * DAY as u64);
+    assert!(!vault.counts_toward_tier(requested_at));
+
+    // Changing the config doesn't move a cooldown already running
+    let instruction = set_cooldown_ix(&ctx.program_id, &payer, 30 * DAY as u64);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    ctx.warp_forward(3 * DAY - 1).await;
+    let instruction = claim_ix(&ctx.program_id, &payer, &DEVNET_MINT);
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::CooldownActive as u32));
+
+    ctx.warp_forward(1).await;
+    let balance_before = ctx.token_balance(&user_ata).await;
+    let instruction = claim_ix(&ctx.program_id, &payer, &DEVNET_MINT);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    assert_eq!(ctx.token_balance(&user_ata).await, balance_before + amount_locked);
+    assert_eq!(ctx.vault(&payer).await.status(), Ok(VaultStatus::Released));
+}
+
+#[tokio::test]
+async fn test_cancel_unlock_locks_the_vault_again() {
+    let mut ctx = setup().await;
+    let payer = ctx.payer.pubkey();
+    let fee_receiver = Pubkey::new_unique();
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    ctx.open_vault(None, fee_receiver, 1, false, VaultKind::Cooldown).await;
+
+    let instruction = request_unlock_ix(&ctx.program_id, &payer, &DEVNET_MINT);
+    ctx.process(&[instruction], &[]).await.unwrap();
+    assert_eq!(
+        u64::from(ctx.vault(&payer).await.lock_duration),
+        Config::DEFAULT_COOLDOWN
+    );
+
+    let instruction = cancel_unlock_ix(&ctx.program_id, &payer, &DEVNET_MINT);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    let vault = ctx.vault(&payer).await;
+    assert_eq!(vault.status(), Ok(VaultStatus::Locked));
+    assert_eq!(u64::from(vault.lock_duration), 0);
+    assert!(vault.counts_toward_tier(ctx.now().await));
+
+    // The cooldown that was running no longer lets the vault be claimed
+    ctx.warp_forward(Config::DEFAULT_COOLDOWN as i64).await;
+    let instruction = claim_ix(&ctx.program_id, &payer, &DEVNET_MINT);
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::LockNotExpired as u32));
+
+    let instruction = cancel_unlock_ix(&ctx.program_id, &payer, &DEVNET_MINT);
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::InvalidStatusTransition as u32));
+}
+
+#[tokio::test]
+async fn test_cooldown_vaults_skip_the_fixed_lock_instructions() {
+    let mut ctx = setup().await;
+    let payer = ctx.payer.pubkey();
+    let fee_receiver = Pubkey::new_unique();
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    ctx.open_vault(None, fee_receiver, 1, false, VaultKind::Cooldown).await;
+
+    let instruction = withdraw_ix(&ctx.program_id, &payer, &DEVNET_MINT, &[fee_receiver], None);
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::WrongVaultKind as u32));
+
+    let instruction = set_auto_renew_ix(&ctx.program_id, &payer, &DEVNET_MINT, true);
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::WrongVaultKind as u32));
+}
+
+#[tokio::test]
+async fn test_fixed_vaults_cannot_request_unlock() {
+    let mut ctx = setup().await;
+    let payer = ctx.payer.pubkey();
+    let fee_receiver = Pubkey::new_unique();
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    ctx.open_vault(None, fee_receiver, 1, false, VaultKind::Fixed).await;
+
+    let instruction = request_unlock_ix(&ctx.program_id, &payer, &DEVNET_MINT);
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::WrongVaultKind as u32));
+}
+
+#[tokio::test]
+async fn test_cooldown_vaults_cannot_auto_renew() {
+    let mut ctx = setup().await;
+    let payer = ctx.payer.pubkey();
+    let fee_receiver = Pubkey::new_unique();
+
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    ctx.fund_token_account(&payer, 1_000_000_000).await;
+
+    let instruction =
+        initialize_ix(&ctx.program_id, &payer, &DEVNET_MINT, 1, &[fee_receiver], None, true, VaultKind::Cooldown);
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::WrongVaultKind as u32));
+}
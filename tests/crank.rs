@@ -0,0 +1,221 @@
+mod common;
+
+use borsh::BorshDeserialize;
+use common::*;
+use construct_vault_sol::{
+    error::VaultError,
+    state::{Loyalty, Treasury, Vault, VaultKind, VaultStatus},
+};
+use solana_program::instruction::AccountMeta;
+use solana_program::{program_option::COption, program_pack::Pack, rent::Rent};
+use solana_sdk::{
+    account::Account,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction,
+};
+use spl_associated_token_account::get_associated_token_address;
+use spl_token::state::{Account as TokenAccount, AccountState};
+
+const DAY: i64 = 86_400;
+const TIP: u64 = 1_000_000;
+
+/// Sets up the config and a treasury tipping `TIP`, holding `treasury_funds`
+/// above its rent. Returns the fee recipient.
+async fn setup_crank(ctx: &mut TestContext, treasury_funds: u64) -> Pubkey {
+    let admin = ctx.payer.pubkey();
+    let fee_receiver = Pubkey::new_unique();
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+
+    let (treasury, _) = Treasury::find_address(&ctx.program_id);
+    let mut instructions = vec![set_crank_tip_ix(&ctx.program_id, &admin, TIP)];
+    if treasury_funds > 0 {
+        instructions.push(system_instruction::transfer(&admin, &treasury, treasury_funds));
+    }
+    ctx.process(&instructions, &[]).await.unwrap();
+
+    fee_receiver
+}
+
+/// A wallet that sends `CrankRelease`, holding enough SOL to receive tips.
+async fn cranker(ctx: &mut TestContext) -> Keypair {
+    let cranker = Keypair::new();
+    let fund = system_instruction::transfer(&ctx.payer.pubkey(), &cranker.pubkey(), 1_000_000_000);
+    ctx.process(&[fund], &[]).await.unwrap();
+    cranker
+}
+
+#[tokio::test]
+async fn test_crank_releases_matured_vaults_and_tips() {
+    let mut ctx = setup().await;
+    let fee_receiver = setup_crank(&mut ctx, 10 * TIP).await;
+
+    let matured = Keypair::new();
+    ctx.open_vault(Some(&matured), fee_receiver, 1, false, VaultKind::Fixed).await;
+    ctx.warp_forward(DAY).await;
+    let locked = Keypair::new();
+    ctx.open_vault(Some(&locked), fee_receiver, 1, false, VaultKind::Fixed).await;
+    ctx.warp_forward(Vault::LOCK_DURATION as i64 - DAY).await;
+
+    let matured_ata = get_associated_token_address(&matured.pubkey(), &DEVNET_MINT);
+    let balance_before = ctx.token_balance(&matured_ata).await;
+    let vault_ata = vault_token_address(&ctx.program_id, &matured.pubkey());
+    let vault_balance = ctx.token_balance(&vault_ata).await;
+    assert_eq!(vault_balance, TOKEN);
+
+    let cranker = cranker(&mut ctx).await;
+    let lamports_before = ctx.lamports(&cranker.pubkey()).await;
+
+    let owners = [matured.pubkey(), locked.pubkey()];
+    let instruction = crank_release_ix(&ctx.program_id, &cranker.pubkey(), &DEVNET_MINT, &owners);
+    ctx.process(&[instruction], &[&cranker]).await.unwrap();
+
+    assert_eq!(ctx.token_balance(&matured_ata).await, balance_before + vault_balance);
+    assert_eq!(ctx.token_balance(&vault_ata).await, 0);
+    assert_eq!(ctx.vault(&matured.pubkey()).await.status(), Ok(VaultStatus::Released));
+    assert_eq!(ctx.vault(&locked.pubkey()).await.status(), Ok(VaultStatus::Locked));
+
+    // One tip, for the one vault released
+    assert_eq!(ctx.lamports(&cranker.pubkey()).await, lamports_before + TIP);
+
+    // Only the released vault counts as a completed lock
+    for (owner, completed_locks) in [(matured.pubkey(), 1), (locked.pubkey(), 0)] {
+        let (loyalty_pda, _) = Loyalty::find_address(&owner, &ctx.program_id);
+        let account = ctx.banks_client.get_account(loyalty_pda).await.unwrap().unwrap();
+        assert_eq!(Loyalty::try_from_slice(&account.data).unwrap().completed_locks, completed_locks);
+    }
+
+    // Cranking an already-released vault skips it and earns nothing
+    let instruction = crank_release_ix(&ctx.program_id, &cranker.pubkey(), &DEVNET_MINT, &owners[..1]);
+    ctx.process(&[instruction], &[&cranker]).await.unwrap();
+    assert_eq!(ctx.lamports(&cranker.pubkey()).await, lamports_before + TIP);
+}
+
+#[tokio::test]
+async fn test_crank_tip_is_capped_by_the_treasury() {
+    let mut ctx = setup().await;
+    let fee_receiver = setup_crank(&mut ctx, TIP / 2).await;
+
+    let first = Keypair::new();
+    ctx.open_vault(Some(&first), fee_receiver, 1, false, VaultKind::Fixed).await;
+    let second = Keypair::new();
+    ctx.open_vault(Some(&second), fee_receiver, 1, false, VaultKind::Fixed).await;
+    ctx.warp_forward(Vault::LOCK_DURATION as i64).await;
+
+    let cranker = cranker(&mut ctx).await;
+    let lamports_before = ctx.lamports(&cranker.pubkey()).await;
+
+    let owners = [first.pubkey(), second.pubkey()];
+    let instruction = crank_release_ix(&ctx.program_id, &cranker.pubkey(), &DEVNET_MINT, &owners);
+    ctx.process(&[instruction], &[&cranker]).await.unwrap();
+
+    assert_eq!(ctx.vault(&first.pubkey()).await.status(), Ok(VaultStatus::Released));
+    assert_eq!(ctx.vault(&second.pubkey()).await.status(), Ok(VaultStatus::Released));
+    assert_eq!(ctx.lamports(&cranker.pubkey()).await, lamports_before + TIP / 2);
+
+    // The treasury keeps its rent reserve
+    let (treasury, _) = Treasury::find_address(&ctx.program_id);
+    assert_eq!(ctx.lamports(&treasury).await, Rent::default().minimum_balance(Treasury::LEN));
+}
+
+#[tokio::test]
+async fn test_crank_only_pays_the_owners_associated_token_account() {
+    let mut ctx = setup().await;
+    let fee_receiver = setup_crank(&mut ctx, 10 * TIP).await;
+    let owner = Keypair::new();
+    ctx.open_vault(Some(&owner), fee_receiver, 1, false, VaultKind::Fixed).await;
+    ctx.warp_forward(Vault::LOCK_DURATION as i64).await;
+
+    let cranker = cranker(&mut ctx).await;
+    let cranker_ata = ctx.fund_token_account(&cranker.pubkey(), 0).await;
+
+    // Another token account the owner holds, but not their ATA
+    let side_account = Pubkey::new_unique();
+    let mut data = vec![0; TokenAccount::LEN];
+    TokenAccount::pack(
+        TokenAccount {
+            mint: DEVNET_MINT,
+            owner: owner.pubkey(),
+            amount: 0,
+            delegate: COption::None,
+            state: AccountState::Initialized,
+            is_native: COption::None,
+            delegated_amount: 0,
+            close_authority: COption::None,
+        },
+        &mut data,
+    )
+    .unwrap();
+    ctx.set_account(
+        &side_account,
+        Account {
+            lamports: Rent::default().minimum_balance(TokenAccount::LEN),
+            data,
+            owner: spl_token::id(),
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+
+    for destination in [cranker_ata, side_account] {
+        let mut instruction = crank_release_ix(&ctx.program_id, &cranker.pubkey(), &DEVNET_MINT, &[owner.pubkey()]);
+        instruction.accounts[6].pubkey = destination;
+
+        let error = ctx.process(&[instruction], &[&cranker]).await.unwrap_err();
+        assert_eq!(custom_error(error), Some(VaultError::InvalidUserTokenAccount as u32));
+    }
+
+    assert_eq!(ctx.vault(&owner.pubkey()).await.status(), Ok(VaultStatus::Locked));
+}
+
+#[tokio::test]
+async fn test_only_the_admin_sets_the_crank_tip() {
+    let mut ctx = setup().await;
+    setup_crank(&mut ctx, 0).await;
+
+    let intruder = cranker(&mut ctx).await;
+    let instruction = set_crank_tip_ix(&ctx.program_id, &intruder.pubkey(), u64::MAX);
+    let error = ctx.process(&[instruction], &[&intruder]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::Unauthorized as u32));
+}
+
+#[tokio::test]
+async fn test_crank_skips_sol_vaults_in_the_batch() {
+    let mut ctx = setup().await;
+    let fee_receiver = setup_crank(&mut ctx, 10 * TIP).await;
+
+    let owner = Keypair::new();
+    ctx.open_vault(Some(&owner), fee_receiver, 1, false, VaultKind::Fixed).await;
+
+    // A matured SOL vault, which has no token account to release into
+    let native = spl_token::native_mint::id();
+    let sol_owner = Keypair::new();
+    let instructions = [
+        set_mint_config_ix(&ctx.program_id, &ctx.payer.pubkey(), &native, true, Vault::LOCK_DURATION, 0, 0, 0, vec![]),
+        system_instruction::transfer(&ctx.payer.pubkey(), &sol_owner.pubkey(), 10_000_000_000),
+    ];
+    ctx.process(&instructions, &[]).await.unwrap();
+    let instruction =
+        initialize_sol_ix(&ctx.program_id, &sol_owner.pubkey(), TOKEN, &[fee_receiver], None, false, VaultKind::Fixed);
+    ctx.process(&[instruction], &[&sol_owner]).await.unwrap();
+    ctx.warp_forward(Vault::LOCK_DURATION as i64).await;
+
+    let (sol_vault, _) = find_vault_address(&ctx.program_id, &sol_owner.pubkey(), &native);
+    let sol_vault_lamports = ctx.lamports(&sol_vault).await;
+
+    let cranker = cranker(&mut ctx).await;
+    let lamports_before = ctx.lamports(&cranker.pubkey()).await;
+
+    let mut instruction = crank_release_ix(&ctx.program_id, &cranker.pubkey(), &DEVNET_MINT, &[owner.pubkey()]);
+    let sol_target = crank_release_ix(&ctx.program_id, &cranker.pubkey(), &native, &[sol_owner.pubkey()]);
+    instruction.accounts.extend(sol_target.accounts.into_iter().skip(4));
+    assert_eq!(instruction.accounts[10], AccountMeta::new(sol_vault, false));
+    ctx.process(&[instruction], &[&cranker]).await.unwrap();
+
+    // The KUZA vault is released and tipped for; the SOL vault is left alone
+    assert_eq!(ctx.vault(&owner.pubkey()).await.status(), Ok(VaultStatus::Released));
+    assert_eq!(ctx.lamports(&sol_vault).await, sol_vault_lamports);
+    let account = ctx.banks_client.get_account(sol_vault).await.unwrap().unwrap();
+    assert_eq!(Vault::unpack(&account.data).unwrap().status(), Ok(VaultStatus::Locked));
+    assert_eq!(ctx.lamports(&cranker.pubkey()).await, lamports_before + TIP);
+}
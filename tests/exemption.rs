@@ -0,0 +1,104 @@
+mod common;
+
+use common::*;
+use construct_vault_sol::{
+    error::VaultError,
+    state::{FeeExemptionReason, VaultKind},
+};
+use solana_program::clock::Clock;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+const DAY: i64 = 86_400;
+
+#[tokio::test]
+async fn test_exempt_wallet_pays_no_fees() {
+    let mut ctx = setup().await;
+    let payer = ctx.payer.pubkey();
+    let fee_receiver = Pubkey::new_unique();
+
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    ctx.fund_token_account(&payer, 1_000_000_000).await;
+
+    let instruction = set_fee_exemption_ix(
+        &ctx.program_id,
+        &payer,
+        payer,
+        None,
+        FeeExemptionReason::TeamMultisig,
+    );
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    let instruction = initialize_ix(&ctx.program_id, &payer, &DEVNET_MINT, 1, &[fee_receiver], None, false, VaultKind::Fixed);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    let instruction = withdraw_ix(&ctx.program_id, &payer, &DEVNET_MINT, &[fee_receiver], None);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    assert_eq!(ctx.lamports(&fee_receiver).await, 0);
+    assert_eq!(u64::from(ctx.vault(&payer).await.amount_locked), 0);
+}
+
+#[tokio::test]
+async fn test_expired_and_removed_exemptions_are_charged() {
+    let mut ctx = setup().await;
+    let payer = ctx.payer.pubkey();
+    let fee_receiver = Pubkey::new_unique();
+
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    ctx.fund_token_account(&payer, 1_000_000_000).await;
+
+    let clock: Clock = ctx.banks_client.get_sysvar().await.unwrap();
+    let instruction = set_fee_exemption_ix(
+        &ctx.program_id,
+        &payer,
+        payer,
+        Some(clock.unix_timestamp + DAY),
+        FeeExemptionReason::MarketMaker,
+    );
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    ctx.warp_forward(2 * DAY).await;
+
+    let instruction = initialize_ix(&ctx.program_id, &payer, &DEVNET_MINT, 1, &[fee_receiver], None, false, VaultKind::Fixed);
+    ctx.process(&[instruction], &[]).await.unwrap();
+    assert_eq!(ctx.lamports(&fee_receiver).await, 100_000_000);
+
+    // Renew the exemption, then revoke it before withdrawing
+    let instruction = set_fee_exemption_ix(
+        &ctx.program_id,
+        &payer,
+        payer,
+        None,
+        FeeExemptionReason::MarketMaker,
+    );
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    let instruction = remove_fee_exemption_ix(&ctx.program_id, &payer, payer);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    let instruction = withdraw_ix(&ctx.program_id, &payer, &DEVNET_MINT, &[fee_receiver], None);
+    ctx.process(&[instruction], &[]).await.unwrap();
+    assert_eq!(ctx.lamports(&fee_receiver).await, 100_000_000 + 3_750_000_000);
+}
+
+#[tokio::test]
+async fn test_only_admin_can_grant_exemptions() {
+    let mut ctx = setup().await;
+
+    ctx.initialize_config(single_recipient(Pubkey::new_unique()), 0).await;
+
+    let impostor = Keypair::new();
+    let instruction = set_fee_exemption_ix(
+        &ctx.program_id,
+        &impostor.pubkey(),
+        impostor.pubkey(),
+        None,
+        FeeExemptionReason::Other,
+    );
+    let error = ctx.process(&[instruction], &[&impostor]).await.unwrap_err();
+
+    assert_eq!(custom_error(error), Some(VaultError::Unauthorized as u32));
+}
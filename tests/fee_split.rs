@@ -0,0 +1,201 @@
+mod common;
+
+use common::*;
+use construct_vault_sol::{
+    error::VaultError,
+    fee::{early_withdrawal_fee, split_fee, validate_fee_shares},
+    state::{Config, FeeShare, VaultKind},
+    INITIALIZATION_FEE_LAMPORTS, MAX_EARLY_WITHDRAWAL_FEE_LAMPORTS,
+};
+use spl_associated_token_account::{get_associated_token_address, instruction::create_associated_token_account};
+use solana_sdk::{
+    instruction::InstructionError,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction,
+    transaction::TransactionError,
+};
+
+fn shares(weights: &[u16]) -> Vec<FeeShare> {
+    weights
+        .iter()
+        .map(|bps| FeeShare {
+            recipient: Pubkey::new_unique(),
+            bps: *bps,
+        })
+        .collect()
+}
+
+#[test]
+fn test_split_sends_dust_to_designated_recipient() {
+    let fee_shares = shares(&[3_333, 3_333, 3_334]);
+
+    assert_eq!(split_fee(100, &fee_shares, 0), vec![34, 33, 33]);
+    assert_eq!(split_fee(100, &fee_shares, 2), vec![33, 33, 34]);
+    assert_eq!(split_fee(1, &fee_shares, 1), vec![0, 1, 0]);
+    assert_eq!(split_fee(u64::MAX, &fee_shares, 0).iter().map(|p| *p as u128).sum::<u128>(), u64::MAX as u128);
+}
+
+#[test]
+fn test_validate_fee_shares() {
+    assert_eq!(validate_fee_shares(&shares(&[5_000, 5_000]), 1), Ok(()));
+    assert_eq!(validate_fee_shares(&shares(&[5_000, 4_999]), 0), Err(VaultError::InvalidFeeSplit));
+    assert_eq!(validate_fee_shares(&shares(&[10_000]), 1), Err(VaultError::InvalidFeeSplit));
+    assert_eq!(validate_fee_shares(&[], 0), Err(VaultError::InvalidFeeSplit));
+    assert_eq!(
+        validate_fee_shares(&shares(&[2_000; Config::MAX_FEE_RECIPIENTS + 1]), 0),
+        Err(VaultError::InvalidFeeSplit)
+    );
+}
+
+#[test]
+fn test_early_withdrawal_fee_falls_by_whole_days() {
+    let day = 86_400;
+    let lock = 30 * day;
+
+    assert_eq!(early_withdrawal_fee(0, lock, MAX_EARLY_WITHDRAWAL_FEE_LAMPORTS).unwrap(), 3_750_000_000);
+    // Partial days don't reduce the fee
+    assert_eq!(early_withdrawal_fee(day - 1, lock, MAX_EARLY_WITHDRAWAL_FEE_LAMPORTS).unwrap(), 3_750_000_000);
+    assert_eq!(early_withdrawal_fee(15 * day, lock, MAX_EARLY_WITHDRAWAL_FEE_LAMPORTS).unwrap(), 1_875_000_000);
+    assert_eq!(early_withdrawal_fee(30 * day, lock, MAX_EARLY_WITHDRAWAL_FEE_LAMPORTS).unwrap(), 0);
+    assert_eq!(early_withdrawal_fee(day, 0, MAX_EARLY_WITHDRAWAL_FEE_LAMPORTS).unwrap(), 0);
+}
+
+#[test]
+fn test_early_withdrawal_fee_is_exact_for_any_maximum() {
+    let day = 86_400;
+    let lock = 30 * day;
+
+    // A third of the way in is exactly half of the maximum
+    assert_eq!(early_withdrawal_fee(10 * day, lock, 5_000_000_000).unwrap(), 2_500_000_000);
+    assert_eq!(early_withdrawal_fee(10 * day, lock, 7).unwrap(), 3);
+    assert_eq!(early_withdrawal_fee(0, lock, u64::MAX).unwrap(), (u64::MAX as u128 * 3 / 4) as u64);
+    assert_eq!(early_withdrawal_fee(29 * day, lock, u64::MAX).unwrap(), u64::MAX / 40);
+}
+
+#[tokio::test]
+async fn test_initialize_splits_fee_across_recipients() {
+    let mut ctx = setup().await;
+    let payer = ctx.payer.pubkey();
+    let fee_shares = shares(&[6_000, 2_500, 1_500]);
+    let recipients: Vec<Pubkey> = fee_shares.iter().map(|share| share.recipient).collect();
+
+    ctx.initialize_config(fee_shares.clone(), 1).await;
+    ctx.fund_token_account(&payer, 0).await;
+
+    let instruction = initialize_ix(&ctx.program_id, &payer, &DEVNET_MINT, 0, &recipients, None, false, VaultKind::Fixed);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    let expected = split_fee(INITIALIZATION_FEE_LAMPORTS, &fee_shares, 1);
+    for (recipient, portion) in recipients.iter().zip(expected) {
+        assert_eq!(ctx.lamports(recipient).await, portion);
+    }
+}
+
+#[tokio::test]
+async fn test_early_withdrawal_splits_token_fee_across_recipients() {
+    let mut ctx = setup().await;
+    let payer = ctx.payer.pubkey();
+    let fee_shares = shares(&[3_333, 6_667]);
+    let recipients: Vec<Pubkey> = fee_shares.iter().map(|share| share.recipient).collect();
+    let lock_duration = 30 * 86_400;
+    let max_fee_tokens = 1_000_003;
+
+    ctx.initialize_config(fee_shares.clone(), 0).await;
+    let mint = ctx.create_funded_mint(6, 1_000_000_000).await;
+
+    let mut instructions = vec![set_mint_config_ix(
+        &ctx.program_id,
+        &payer,
+        &mint,
+        true,
+        lock_duration,
+        0,
+        0,
+        max_fee_tokens,
+        vec![],
+    )];
+    instructions.extend(
+        recipients
+            .iter()
+            .map(|recipient| create_associated_token_account(&payer, recipient, &mint, &spl_token::id())),
+    );
+    instructions.push(initialize_ix(&ctx.program_id, &payer, &mint, 100, &recipients, None, false, VaultKind::Fixed));
+    ctx.process(&instructions, &[]).await.unwrap();
+
+    let instruction = withdraw_ix(&ctx.program_id, &payer, &mint, &recipients, None);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    // Day 0 costs 75% of the maximum, with the dust going to the first recipient
+    let expected = split_fee(early_withdrawal_fee(0, lock_duration, max_fee_tokens).unwrap(), &fee_shares, 0);
+    assert_eq!(expected, vec![249_976, 500_026]);
+
+    for (recipient, portion) in recipients.iter().zip(&expected) {
+        assert_eq!(ctx.token_balance(&get_associated_token_address(recipient, &mint)).await, *portion);
+    }
+
+    let user_ata = get_associated_token_address(&payer, &mint);
+    assert_eq!(ctx.token_balance(&user_ata).await, 1_000_000_000 - expected.iter().sum::<u64>());
+}
+
+#[tokio::test]
+async fn test_initialize_rejects_recipients_out_of_order() {
+    let mut ctx = setup().await;
+    let payer = ctx.payer.pubkey();
+    let fee_shares = shares(&[5_000, 5_000]);
+
+    ctx.initialize_config(fee_shares.clone(), 0).await;
+    ctx.fund_token_account(&payer, 0).await;
+
+    let reversed = [fee_shares[1].recipient, fee_shares[0].recipient];
+    let instruction = initialize_ix(&ctx.program_id, &payer, &DEVNET_MINT, 0, &reversed, None, false, VaultKind::Fixed);
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+
+    assert_eq!(custom_error(error), Some(VaultError::FeeRecipientMismatch as u32));
+}
+
+#[tokio::test]
+async fn test_only_the_upgrade_authority_initializes_the_config() {
+    let mut ctx = setup().await;
+    let impostor = Keypair::new();
+    let fund = system_instruction::transfer(&ctx.payer.pubkey(), &impostor.pubkey(), 1_000_000_000);
+    ctx.process(&[fund], &[]).await.unwrap();
+
+    let instruction = initialize_config_ix(&ctx.program_id, &impostor.pubkey(), shares(&[10_000]), 0);
+    let error = ctx.process(&[instruction], &[&impostor]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::Unauthorized as u32));
+
+    // Nor can they bring a ProgramData account of their own
+    let spoofed = Pubkey::new_unique();
+    ctx.set_account(&spoofed, program_data(impostor.pubkey()));
+    let mut instruction = initialize_config_ix(&ctx.program_id, &impostor.pubkey(), shares(&[10_000]), 0);
+    instruction.accounts[3].pubkey = spoofed;
+    let error = ctx.process(&[instruction], &[&impostor]).await.unwrap_err();
+    assert_eq!(error.unwrap(), TransactionError::InstructionError(0, InstructionError::InvalidAccountData));
+
+    ctx.initialize_config(shares(&[10_000]), 0).await;
+    let account = ctx.banks_client.get_account(Config::find_address(&ctx.program_id).0).await.unwrap().unwrap();
+    let config: Config = borsh::BorshDeserialize::try_from_slice(&account.data).unwrap();
+    assert_eq!(config.admin, ctx.payer.pubkey());
+}
+
+#[tokio::test]
+async fn test_set_fee_shares_requires_admin() {
+    let mut ctx = setup().await;
+    let (config_pda, _) = Config::find_address(&ctx.program_id);
+    ctx.initialize_config(shares(&[10_000]), 0).await;
+
+    let impostor = Keypair::new();
+    let instruction = set_fee_shares_ix(&ctx.program_id, &impostor.pubkey(), shares(&[10_000]), 0);
+    let error = ctx.process(&[instruction], &[&impostor]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::Unauthorized as u32));
+
+    let new_shares = shares(&[7_000, 3_000]);
+    let instruction = set_fee_shares_ix(&ctx.program_id, &ctx.payer.pubkey(), new_shares.clone(), 1);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    let account = ctx.banks_client.get_account(config_pda).await.unwrap().unwrap();
+    let config: Config = borsh::BorshDeserialize::try_from_slice(&account.data).unwrap();
+    assert_eq!(config.active_fee_shares(), &new_shares[..]);
+    assert_eq!(config.dust_recipient, 1);
+}
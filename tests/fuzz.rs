@@ -0,0 +1,711 @@
+use construct_vault_sol::{
+    client::*,
+    processor::process_instruction,
+    state::{
+        AccessTier, ActivityLog, Config, FeeExemption, FeeExemptionReason, FeeShare, Loyalty, LoyaltyTier,
+        MintConfig, Referral, ReferrerStats, StatsShard, TimeBasis, Treasury, Vault, VaultKind,
+    },
+    DEVNET_MINT,
+};
+use solana_program::{
+    account_info::AccountInfo,
+    bpf_loader,
+    clock::Clock,
+    entrypoint::{deserialize, ProgramResult, MAX_PERMITTED_DATA_INCREASE, SUCCESS},
+    instruction::Instruction,
+    program_error::ProgramError,
+    program_option::COption,
+    program_pack::Pack,
+    program_stubs::{set_syscall_stubs, SyscallStubs},
+    pubkey::Pubkey,
+    rent::Rent,
+    system_program, sysvar,
+};
+use spl_associated_token_account::get_associated_token_address;
+use spl_token::{
+    native_mint,
+    state::{Account as TokenAccount, AccountState, Mint},
+};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    panic::{self, AssertUnwindSafe},
+    sync::Once,
+};
+
+/// Cases per test; raise it with `FUZZ_CASES` for a longer run.
+const DEFAULT_CASES: u64 = 2_000;
+
+thread_local! {
+    // The clock `Clock::get` reads, set per case so tests can run in parallel
+    static CLOCK: RefCell<Clock> = RefCell::new(Clock::default());
+}
+
+/// Serves the sysvars from the per-case clock and silences program logs.
+/// System program CPIs create accounts and move lamports so handlers can go
+/// on to use what they created; every other CPI does nothing and succeeds.
+struct FuzzStubs;
+
+impl SyscallStubs for FuzzStubs {
+    fn sol_log(&self, _message: &str) {}
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        _signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        if instruction.program_id != system_program::id() {
+            return Ok(());
+        }
+
+        let account = |index: usize| {
+            instruction
+                .accounts
+                .get(index)
+                .and_then(|meta| account_infos.iter().find(|info| *info.key == meta.pubkey))
+                .ok_or(ProgramError::NotEnoughAccountKeys)
+        };
+        let data = &instruction.data;
+        let word = |offset: usize| -> Result<u64, ProgramError> {
+            let bytes = data.get(offset..offset + 8).ok_or(ProgramError::InvalidInstructionData)?;
+            Ok(u64::from_le_bytes(bytes.try_into().unwrap()))
+        };
+
+        match data.first() {
+            // CreateAccount { lamports, space, owner }
+            Some(0) => {
+                let owner = data.get(20..52).ok_or(ProgramError::InvalidInstructionData)?;
+                let new_account = account(1)?;
+                move_lamports(account(0)?, new_account, word(4)?)?;
+                new_account.realloc(word(12)? as usize, true)?;
+                new_account.assign(&Pubkey::try_from(owner).unwrap());
+                Ok(())
+            }
+            // Transfer { lamports }
+            Some(2) => move_lamports(account(0)?, account(1)?, word(4)?),
+            _ => Ok(()),
+        }
+    }
+
+    fn sol_log_data(&self, _fields: &[&[u8]]) {}
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        CLOCK.with(|clock| unsafe { *(var_addr as *mut Clock) = clock.borrow().clone() });
+        SUCCESS
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        SUCCESS
+    }
+}
+
+fn move_lamports(from: &AccountInfo, to: &AccountInfo, lamports: u64) -> ProgramResult {
+    let from_lamports = from.lamports().checked_sub(lamports).ok_or(ProgramError::InsufficientFunds)?;
+    let to_lamports = to.lamports().checked_add(lamports).ok_or(ProgramError::ArithmeticOverflow)?;
+    **from.try_borrow_mut_lamports()? = from_lamports;
+    **to.try_borrow_mut_lamports()? = to_lamports;
+    Ok(())
+}
+
+fn install_stubs() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        set_syscall_stubs(Box::new(FuzzStubs));
+    });
+}
+
+fn env_u64(name: &str, default: u64) -> u64 {
+    std::env::var(name).ok().and_then(|value| value.parse().ok()).unwrap_or(default)
+}
+
+/// xorshift64*: deterministic, so a failing case replays from its seed.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Rng(seed.max(1))
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    fn chance(&mut self, percent: u64) -> bool {
+        self.below(100) < percent
+    }
+
+    fn pick<T: Copy>(&mut self, items: &[T]) -> T {
+        items[self.below(items.len() as u64) as usize]
+    }
+
+    fn bytes(&mut self, len: usize) -> Vec<u8> {
+        (0..len).map(|_| self.next() as u8).collect()
+    }
+
+    fn pubkey(&mut self) -> Pubkey {
+        Pubkey::new_from_array(self.bytes(32).try_into().unwrap())
+    }
+
+    /// An amount, duration or count, biased towards the edges.
+    fn amount(&mut self) -> u64 {
+        match self.below(8) {
+            0 => 0,
+            1 => 1,
+            2 => u64::MAX,
+            3 => u64::MAX - self.below(1_000),
+            4 => 1 << 63,
+            5 => self.below(1_000_000_000_000),
+            6 => self.below(100),
+            _ => self.next(),
+        }
+    }
+
+    /// A unix timestamp, negative ones included.
+    fn timestamp(&mut self) -> i64 {
+        match self.below(6) {
+            0 => i64::MIN,
+            1 => -(self.below(1_000_000_000) as i64),
+            2 => 0,
+            3 => i64::MAX,
+            4 => self.next() as i64,
+            _ => 1_700_000_000 + self.below(1_000_000_000) as i64,
+        }
+    }
+
+    fn clock(&mut self) -> Clock {
+        Clock {
+            slot: self.amount(),
+            epoch_start_timestamp: self.timestamp(),
+            epoch: self.amount(),
+            leader_schedule_epoch: self.amount(),
+            unix_timestamp: self.timestamp(),
+        }
+    }
+}
+
+/// The keys one case is built around.
+struct Scenario {
+    program_id: Pubkey,
+    owner: Pubkey,
+    admin: Pubkey,
+    registered_mint: Pubkey,
+}
+
+impl Scenario {
+    fn new(rng: &mut Rng) -> Self {
+        Scenario {
+            program_id: rng.pubkey(),
+            owner: rng.pubkey(),
+            admin: rng.pubkey(),
+            registered_mint: rng.pubkey(),
+        }
+    }
+
+    fn mints(&self) -> [Pubkey; 3] {
+        [DEVNET_MINT, native_mint::id(), self.registered_mint]
+    }
+
+    fn wallet(&self, rng: &mut Rng) -> Pubkey {
+        match rng.below(3) {
+            0 => self.owner,
+            1 => self.admin,
+            _ => rng.pubkey(),
+        }
+    }
+
+    fn fee_shares(&self, rng: &mut Rng) -> Vec<FeeShare> {
+        (0..rng.below(Config::MAX_FEE_RECIPIENTS as u64 + 2))
+            .map(|_| FeeShare {
+                recipient: self.wallet(rng),
+                bps: rng.below(12_000) as u16,
+            })
+            .collect()
+    }
+
+    fn recipients(&self, rng: &mut Rng) -> Vec<Pubkey> {
+        self.fee_shares(rng).iter().map(|share| share.recipient).collect()
+    }
+
+    fn referrer(&self, rng: &mut Rng) -> Option<Pubkey> {
+        rng.chance(50).then(|| self.wallet(rng))
+    }
+
+    fn kind(&self, rng: &mut Rng) -> VaultKind {
+        rng.pick(&[VaultKind::Fixed, VaultKind::Cooldown])
+    }
+
+    fn time_basis(&self, rng: &mut Rng) -> TimeBasis {
+        rng.pick(&[TimeBasis::UnixSeconds, TimeBasis::Slots])
+    }
+
+    fn access_tiers(&self, rng: &mut Rng) -> Vec<AccessTier> {
+        (0..rng.below(Config::MAX_ACCESS_TIERS as u64 + 2))
+            .map(|_| AccessTier { min_amount_locked: rng.amount() })
+            .collect()
+    }
+
+    /// A client-built instruction with random arguments.
+    fn instruction(&self, rng: &mut Rng) -> Instruction {
+        let (program_id, owner, admin) = (&self.program_id, &self.owner, &self.admin);
+        let mint = rng.pick(&self.mints());
+
+        match rng.below(26) {
+            0 if rng.chance(20) => initialize_with_receipts_ix(
+                program_id,
+                owner,
+                &mint,
+                rng.amount(),
+                &self.recipients(rng),
+                self.referrer(rng),
+                self.time_basis(rng),
+            ),
+            0 => initialize_with_time_basis_ix(
+                program_id,
+                owner,
+                &mint,
+                rng.amount(),
+                &self.recipients(rng),
+                self.referrer(rng),
+                rng.chance(50),
+                self.kind(rng),
+                self.time_basis(rng),
+            ),
+            1 if rng.chance(20) => deposit_with_receipts_ix(program_id, owner, &mint, rng.amount()),
+            1 => deposit_ix(program_id, owner, &mint, rng.amount()),
+            2 if rng.chance(20) => {
+                withdraw_with_receipts_ix(program_id, owner, &mint, &self.recipients(rng), self.referrer(rng))
+            }
+            2 => withdraw_ix(program_id, owner, &mint, &self.recipients(rng), self.referrer(rng)),
+            3 => initialize_config_ix(program_id, admin, self.fee_shares(rng), rng.next() as u8),
+            4 => set_fee_shares_ix(program_id, admin, self.fee_shares(rng), rng.next() as u8),
+            5 => set_referral_share_ix(program_id, admin, rng.next() as u16),
+            6 => {
+                let loyalty_tiers = (0..rng.below(Config::MAX_LOYALTY_TIERS as u64 + 2))
+                    .map(|_| LoyaltyTier {
+                        min_completed_locks: rng.amount() as u32,
+                        discount_bps: rng.next() as u16,
+                    })
+                    .collect();
+                set_loyalty_tiers_ix(program_id, admin, loyalty_tiers)
+            }
+            7 => set_fee_exemption_ix(
+                program_id,
+                admin,
+                self.wallet(rng),
+                rng.chance(50).then(|| rng.timestamp()),
+                FeeExemptionReason::Other,
+            ),
+            8 => remove_fee_exemption_ix(program_id, admin, self.wallet(rng)),
+            9 => migrate_vault_ix(program_id, admin, owner, &mint),
+            10 => claim_ix(program_id, owner, &mint),
+            11 => set_crank_tip_ix(program_id, admin, rng.amount()),
+            12 => {
+                let owners: Vec<Pubkey> = (0..rng.below(4)).map(|_| self.wallet(rng)).collect();
+                crank_release_ix(program_id, admin, &mint, &owners)
+            }
+            13 => set_auto_renew_ix(program_id, owner, &mint, rng.chance(50)),
+            14 => set_cooldown_ix(program_id, admin, rng.amount()),
+            15 => request_unlock_ix(program_id, owner, &mint),
+            16 => cancel_unlock_ix(program_id, owner, &mint),
+            17 => enable_receipts_ix(program_id, owner, &mint),
+            18 => redeem_ix(program_id, &self.wallet(rng), owner, &mint),
+            19 => set_access_tiers_ix(program_id, admin, self.access_tiers(rng)),
+            20 => sync_badges_ix(program_id, admin, owner, &mint),
+            21 => set_mint_config_ix(
+                program_id,
+                admin,
+                &mint,
+                rng.chance(80),
+                rng.amount(),
+                rng.amount(),
+                rng.amount(),
+                0,
+                self.access_tiers(rng),
+            ),
+            22 => initialize_sol_with_time_basis_ix(
+                program_id,
+                owner,
+                rng.amount(),
+                &self.recipients(rng),
+                self.referrer(rng),
+                rng.chance(50),
+                self.kind(rng),
+                self.time_basis(rng),
+            ),
+            23 => deposit_sol_ix(program_id, owner, rng.amount()),
+            24 => withdraw_sol_ix(program_id, owner, &self.recipients(rng), self.referrer(rng)),
+            _ => set_slots_per_day_ix(program_id, admin, rng.amount()),
+        }
+    }
+
+    /// What the handlers expect to find at the keys they derive themselves.
+    fn classes(&self) -> HashMap<Pubkey, Class> {
+        let mut classes = HashMap::new();
+
+        for program in [
+            system_program::id(),
+            spl_token::id(),
+            spl_token_2022::id(),
+            spl_associated_token_account::id(),
+            sysvar::rent::id(),
+            sysvar::clock::id(),
+            self.program_id,
+        ] {
+            classes.insert(program, Class::Program);
+        }
+
+        let (config, config_bump) = Config::find_address(&self.program_id);
+        classes.insert(config, Class::Config { bump: config_bump });
+
+        let vaults: Vec<Pubkey> = self
+            .mints()
+            .iter()
+            .map(|mint| find_vault_address(&self.program_id, &self.owner, mint).0)
+            .collect();
+
+        for (mint, vault) in self.mints().into_iter().zip(&vaults) {
+            let (vault, bump) = (*vault, find_vault_address(&self.program_id, &self.owner, &mint).1);
+            let registered = if mint == DEVNET_MINT { Pubkey::default() } else { mint };
+            classes.insert(vault, Class::Vault { bump, mint: registered });
+            classes.insert(mint, Class::Mint);
+            classes.insert(receipt_mint_address(&self.program_id, &self.owner, &mint).0, Class::Mint);
+
+            let (mint_config, bump) = MintConfig::find_address(&mint, &self.program_id);
+            classes.insert(mint_config, Class::MintConfig { mint, bump });
+
+            for holder in [self.owner, self.admin].iter().chain(&vaults) {
+                let token_account = get_associated_token_address(holder, &mint);
+                classes.insert(token_account, Class::Token { mint, owner: *holder });
+            }
+        }
+
+        classes
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Class {
+    Program,
+    Config { bump: u8 },
+    Vault { bump: u8, mint: Pubkey },
+    MintConfig { mint: Pubkey, bump: u8 },
+    Mint,
+    Token { mint: Pubkey, owner: Pubkey },
+}
+
+/// One account as the runtime would hand it to the program.
+struct Account {
+    key: Pubkey,
+    owner: Pubkey,
+    lamports: u64,
+    data: Vec<u8>,
+    is_signer: bool,
+    is_writable: bool,
+    executable: bool,
+    // Index of an earlier account this one repeats
+    duplicate_of: Option<usize>,
+}
+
+impl Account {
+    fn new(key: Pubkey, owner: Pubkey, lamports: u64, data: Vec<u8>) -> Self {
+        Account {
+            key,
+            owner,
+            lamports,
+            data,
+            is_signer: false,
+            is_writable: false,
+            executable: false,
+            duplicate_of: None,
+        }
+    }
+}
+
+/// A random state for `key`, usually the kind of account the handlers expect
+/// there and sometimes anything at all.
+fn account(rng: &mut Rng, scenario: &Scenario, classes: &HashMap<Pubkey, Class>, key: Pubkey) -> Account {
+    let class = classes.get(&key).copied().filter(|_| rng.chance(97));
+    let program_id = scenario.program_id;
+
+    match class {
+        Some(Class::Program) => {
+            let mut account = Account::new(key, bpf_loader::id(), 1, vec![]);
+            account.executable = true;
+            account
+        }
+        Some(Class::Config { bump }) => {
+            let mut data = rng.bytes(Config::LEN);
+            if rng.chance(70) {
+                data[..32].copy_from_slice(scenario.admin.as_ref());
+            }
+            if rng.chance(90) {
+                data[Config::LEN - 1] = bump;
+            }
+            Account::new(key, program_id, rng.amount(), data)
+        }
+        Some(Class::Vault { bump, mint }) => {
+            let mut data = rng.bytes(Vault::LEN);
+            let vault = Vault::init(&mut data).unwrap();
+            vault.owner = scenario.owner;
+            vault.bump = if rng.chance(90) { bump } else { rng.next() as u8 };
+            vault.mint = mint;
+            vault.lock_duration = rng.amount().into();
+            vault.amount_locked = rng.amount().into();
+            vault.deposit_timestamp = rng.amount().into();
+            vault.status = rng.below(6) as u8;
+            vault.status_timestamp = rng.amount().into();
+            vault.auto_renew = rng.below(2) as u8;
+            vault.kind = rng.below(3) as u8;
+            vault.receipts = rng.below(2) as u8;
+            vault.receipt_bump = rng.next() as u8;
+            vault.rent_lamports = rng.amount().into();
+            vault.counted = rng.below(2) as u8;
+            vault.time_basis = rng.below(3) as u8;
+            Account::new(key, program_id, rng.amount(), data)
+        }
+        Some(Class::MintConfig { mint, bump }) => {
+            let mint_config = MintConfig {
+                mint,
+                enabled: rng.chance(90),
+                lock_duration: rng.amount(),
+                initialization_fee_lamports: rng.amount(),
+                max_early_withdrawal_fee_lamports: rng.amount(),
+                max_early_withdrawal_fee_tokens: rng.amount(),
+                access_tiers: [AccessTier { min_amount_locked: rng.amount() }; Config::MAX_ACCESS_TIERS],
+                access_tier_count: rng.below(Config::MAX_ACCESS_TIERS as u64 + 2) as u8,
+                badge_bumps: [rng.next() as u8; Config::MAX_ACCESS_TIERS],
+                bump,
+            };
+            Account::new(key, program_id, rng.amount(), borsh::to_vec(&mint_config).unwrap())
+        }
+        Some(Class::Mint) => mint_account(rng, scenario, key),
+        Some(Class::Token { mint, owner }) => token_account(rng, key, mint, owner),
+        // Any other PDA is usually one the handler creates on first use
+        None if !key.is_on_curve() && rng.chance(85) => {
+            Account::new(key, system_program::id(), 0, vec![])
+        }
+        None => match rng.below(10) {
+            0..=3 => Account::new(key, system_program::id(), rng.amount(), vec![]),
+            4 => {
+                let mint = rng.pick(&scenario.mints());
+                let owner = scenario.wallet(rng);
+                token_account(rng, key, mint, owner)
+            }
+            5 => mint_account(rng, scenario, key),
+            6 | 7 => {
+                let len = rng.pick(&[
+                    ActivityLog::LEN,
+                    Config::LEN,
+                    FeeExemption::LEN,
+                    Loyalty::LEN,
+                    MintConfig::LEN,
+                    Referral::LEN,
+                    ReferrerStats::LEN,
+                    StatsShard::LEN,
+                    Treasury::LEN,
+                    Vault::LEN,
+                ]);
+                Account::new(key, program_id, rng.amount(), rng.bytes(len))
+            }
+            _ => {
+                let stranger = rng.pubkey();
+                let owner = rng.pick(&[program_id, system_program::id(), spl_token::id(), stranger]);
+                let len = rng.below(300) as usize;
+                Account::new(key, owner, rng.amount(), rng.bytes(len))
+            }
+        },
+    }
+}
+
+fn token_account(rng: &mut Rng, key: Pubkey, mint: Pubkey, owner: Pubkey) -> Account {
+    let state = TokenAccount {
+        mint,
+        owner,
+        amount: rng.amount(),
+        delegate: COption::None,
+        state: AccountState::Initialized,
+        is_native: COption::None,
+        delegated_amount: 0,
+        close_authority: COption::None,
+    };
+
+    let mut data = vec![0; TokenAccount::LEN];
+    state.pack_into_slice(&mut data);
+    let token_program = if rng.chance(95) { spl_token::id() } else { spl_token_2022::id() };
+    Account::new(key, token_program, rng.amount(), data)
+}
+
+fn mint_account(rng: &mut Rng, scenario: &Scenario, key: Pubkey) -> Account {
+    let state = Mint {
+        mint_authority: COption::Some(scenario.wallet(rng)),
+        supply: rng.amount(),
+        decimals: if rng.chance(50) { 9 } else { rng.next() as u8 },
+        is_initialized: true,
+        freeze_authority: COption::None,
+    };
+
+    let mut data = vec![0; Mint::LEN];
+    state.pack_into_slice(&mut data);
+    let token_program = if rng.chance(80) { spl_token::id() } else { spl_token_2022::id() };
+    Account::new(key, token_program, rng.amount(), data)
+}
+
+/// Lays the accounts and data out the way the runtime passes them to a
+/// program, so `realloc` finds the spare capacity it expects and duplicate
+/// accounts share one state.
+fn serialize(program_id: &Pubkey, accounts: &[Account], data: &[u8]) -> Vec<u64> {
+    let mut input = Vec::new();
+    input.extend((accounts.len() as u64).to_le_bytes());
+
+    for account in accounts {
+        if let Some(index) = account.duplicate_of {
+            input.push(index as u8);
+            input.extend([0; 7]);
+            continue;
+        }
+
+        input.push(u8::MAX);
+        input.extend([account.is_signer as u8, account.is_writable as u8, account.executable as u8]);
+        input.extend([0; 4]);
+        input.extend(account.key.as_ref());
+        input.extend(account.owner.as_ref());
+        input.extend(account.lamports.to_le_bytes());
+        input.extend((account.data.len() as u64).to_le_bytes());
+        input.extend(&account.data);
+        input.resize(input.len() + MAX_PERMITTED_DATA_INCREASE, 0);
+        input.resize(input.len().next_multiple_of(8), 0);
+        input.extend(u64::MAX.to_le_bytes());
+    }
+
+    input.extend((data.len() as u64).to_le_bytes());
+    input.extend(data);
+    input.extend(program_id.as_ref());
+
+    // Back the input with u64s so its fields are aligned
+    let mut words = vec![0u64; input.len().div_ceil(8)];
+    unsafe { std::ptr::copy_nonoverlapping(input.as_ptr(), words.as_mut_ptr() as *mut u8, input.len()) };
+    words
+}
+
+/// Runs one case, returning whether the processor panicked and, if not,
+/// whether it succeeded.
+fn run(program_id: &Pubkey, accounts: &[Account], data: &[u8]) -> Result<bool, ()> {
+    let mut input = serialize(program_id, accounts, data);
+    let (program_id, accounts, data) = unsafe { deserialize(input.as_mut_ptr() as *mut u8) };
+
+    panic::catch_unwind(AssertUnwindSafe(|| process_instruction(program_id, &accounts, data).is_ok()))
+        .map_err(|_| ())
+}
+
+/// Builds the accounts for `instruction`: its metas with random states, and
+/// now and then a signer or writable flag flipped, an account repeated or
+/// the list cut short.
+fn accounts_for(rng: &mut Rng, scenario: &Scenario, instruction: &Instruction) -> Vec<Account> {
+    let classes = scenario.classes();
+    let mut accounts: Vec<Account> = instruction
+        .accounts
+        .iter()
+        .map(|meta| {
+            let mut account = account(rng, scenario, &classes, meta.pubkey);
+            account.is_signer = meta.is_signer;
+            account.is_writable = meta.is_writable;
+            account
+        })
+        .collect();
+
+    if accounts.is_empty() {
+        return accounts;
+    }
+    let len = accounts.len() as u64;
+
+    if rng.chance(10) {
+        let index = rng.below(len) as usize;
+        accounts[index].is_signer ^= true;
+    }
+    if rng.chance(10) {
+        let index = rng.below(len) as usize;
+        accounts[index].is_writable ^= true;
+    }
+    if len > 1 && rng.chance(10) {
+        let index = 1 + rng.below(len - 1) as usize;
+        let original = rng.below(index as u64) as usize;
+        accounts[index] = Account::new(accounts[original].key, Pubkey::default(), 0, vec![]);
+        accounts[index].duplicate_of = Some(original);
+    }
+    if rng.chance(5) {
+        accounts.truncate(rng.below(len) as usize);
+    }
+
+    accounts
+}
+
+/// Runs `cases` cases from `seed`, each shaped by `case`, and returns how
+/// many succeeded.
+fn fuzz(seed: u64, case: impl Fn(&mut Rng, &Scenario) -> (Vec<Account>, Vec<u8>)) -> u64 {
+    install_stubs();
+    let cases = env_u64("FUZZ_CASES", DEFAULT_CASES);
+    let seed = env_u64("FUZZ_SEED", seed);
+    let mut succeeded = 0;
+
+    for index in 0..cases {
+        let case_seed = seed.wrapping_add(index);
+        let mut rng = Rng::new(case_seed);
+        let scenario = Scenario::new(&mut rng);
+        let (accounts, data) = case(&mut rng, &scenario);
+        CLOCK.with(|clock| *clock.borrow_mut() = rng.clock());
+
+        match run(&scenario.program_id, &accounts, &data) {
+            Ok(ok) => succeeded += ok as u64,
+            Err(()) => panic!("processor panicked; replay with FUZZ_SEED={case_seed} FUZZ_CASES=1"),
+        }
+    }
+
+    succeeded
+}
+
+#[test]
+fn test_processor_never_panics_on_client_instructions() {
+    let succeeded = fuzz(0x5eed_0001, |rng, scenario| {
+        let instruction = scenario.instruction(rng);
+        let accounts = accounts_for(rng, scenario, &instruction);
+        let mut data = instruction.data;
+
+        if !data.is_empty() && rng.chance(10) {
+            let index = rng.below(data.len() as u64) as usize;
+            data[index] = rng.next() as u8;
+        }
+        if rng.chance(5) {
+            let len = rng.below(data.len() as u64 + 1) as usize;
+            data.truncate(len);
+        }
+
+        (accounts, data)
+    });
+
+    // Enough cases get past the account checks that the arithmetic behind
+    // them is exercised too
+    assert!(succeeded > 0, "no case reached a successful handler");
+}
+
+#[test]
+fn test_processor_never_panics_on_random_data() {
+    fuzz(0x5eed_0002, |rng, scenario| {
+        let instruction = scenario.instruction(rng);
+        let accounts = accounts_for(rng, scenario, &instruction);
+
+        let mut data = vec![rng.below(32) as u8];
+        let len = rng.below(128) as usize;
+        data.extend(rng.bytes(len));
+
+        (accounts, data)
+    });
+}
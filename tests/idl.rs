@@ -0,0 +1,347 @@
+use borsh::BorshDeserialize;
+use construct_vault_sol::{
+    client::*,
+    error::VaultError,
+    idl,
+    processor::{process_instruction, VaultInstruction},
+    state::{
+        AccessTier, ActivityEntry, ActivityKind, ActivityLog, FeeExemptionReason, FeeShare, LoyaltyTier,
+        TimeBasis, Vault, VaultKind, VaultStatus,
+    },
+    DEVNET_MINT,
+};
+use solana_program::instruction::Instruction;
+use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+
+const IDL_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/idl/construct_vault_sol.json");
+
+// One value of every instruction, in discriminant order.
+fn samples() -> Vec<VaultInstruction> {
+    let fee_shares = vec![FeeShare {
+        recipient: Pubkey::new_unique(),
+        bps: 10_000,
+    }];
+
+    vec![
+        VaultInstruction::Initialize {
+            amount: 1,
+            referrer: None,
+            auto_renew: false,
+            kind: VaultKind::Fixed,
+            time_basis: TimeBasis::UnixSeconds,
+            receipts: false,
+        },
+        VaultInstruction::Deposit { amount: 1 },
+        VaultInstruction::Withdraw,
+        VaultInstruction::InitializeConfig { fee_shares: fee_shares.clone(), dust_recipient: 0 },
+        VaultInstruction::SetFeeShares { fee_shares, dust_recipient: 0 },
+        VaultInstruction::SetReferralShare { referral_bps: 0 },
+        VaultInstruction::SetLoyaltyTiers {
+            loyalty_tiers: vec![LoyaltyTier { min_completed_locks: 1, discount_bps: 100 }],
+        },
+        VaultInstruction::SetFeeExemption {
+            wallet: Pubkey::new_unique(),
+            expires_at: None,
+            reason: FeeExemptionReason::Other,
+        },
+        VaultInstruction::RemoveFeeExemption { wallet: Pubkey::new_unique() },
+        VaultInstruction::MigrateVault,
+        VaultInstruction::Claim,
+        VaultInstruction::SetCrankTip { crank_tip_lamports: 0 },
+        VaultInstruction::CrankRelease,
+        VaultInstruction::SetAutoRenew { auto_renew: true },
+        VaultInstruction::SetCooldown { cooldown_duration: 0 },
+        VaultInstruction::RequestUnlock,
+        VaultInstruction::CancelUnlock,
+        VaultInstruction::EnableReceipts,
+        VaultInstruction::Redeem,
+        VaultInstruction::SetAccessTiers {
+            access_tiers: vec![AccessTier { min_amount_locked: 1 }],
+        },
+        VaultInstruction::SyncBadges,
+        VaultInstruction::SetMintConfig {
+            enabled: true,
+            lock_duration: 1,
+            initialization_fee_lamports: 0,
+            max_early_withdrawal_fee_lamports: 0,
+            max_early_withdrawal_fee_tokens: 0,
+            access_tiers: vec![AccessTier { min_amount_locked: 1 }],
+        },
+        VaultInstruction::InitializeSol {
+            amount: 1,
+            referrer: None,
+            auto_renew: false,
+            kind: VaultKind::Fixed,
+            time_basis: TimeBasis::UnixSeconds,
+        },
+        VaultInstruction::DepositSol { amount: 1 },
+        VaultInstruction::WithdrawSol,
+        VaultInstruction::SetSlotsPerDay { slots_per_day: 1 },
+    ]
+}
+
+// One client-built instruction for every variant, in discriminant order.
+fn client_samples() -> Vec<Instruction> {
+    let program_id = Pubkey::new_unique();
+    let admin = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let fee_shares = vec![FeeShare {
+        recipient: Pubkey::new_unique(),
+        bps: 10_000,
+    }];
+    let recipients = [fee_shares[0].recipient];
+
+    vec![
+        initialize_ix(&program_id, &owner, &DEVNET_MINT, 1, &recipients, None, false, VaultKind::Fixed),
+        deposit_ix(&program_id, &owner, &DEVNET_MINT, 1),
+        withdraw_ix(&program_id, &owner, &DEVNET_MINT, &recipients, None),
+        initialize_config_ix(&program_id, &admin, fee_shares.clone(), 0),
+        set_fee_shares_ix(&program_id, &admin, fee_shares, 0),
+        set_referral_share_ix(&program_id, &admin, 0),
+        set_loyalty_tiers_ix(&program_id, &admin, vec![]),
+        set_fee_exemption_ix(&program_id, &admin, owner, None, FeeExemptionReason::Other),
+        remove_fee_exemption_ix(&program_id, &admin, owner),
+        migrate_vault_ix(&program_id, &admin, &owner, &DEVNET_MINT),
+        claim_ix(&program_id, &owner, &DEVNET_MINT),
+        set_crank_tip_ix(&program_id, &admin, 0),
+        crank_release_ix(&program_id, &admin, &DEVNET_MINT, &[owner]),
+        set_auto_renew_ix(&program_id, &owner, &DEVNET_MINT, true),
+        set_cooldown_ix(&program_id, &admin, 0),
+        request_unlock_ix(&program_id, &owner, &DEVNET_MINT),
+        cancel_unlock_ix(&program_id, &owner, &DEVNET_MINT),
+        enable_receipts_ix(&program_id, &owner, &DEVNET_MINT),
+        redeem_ix(&program_id, &admin, &owner, &DEVNET_MINT),
+        set_access_tiers_ix(&program_id, &admin, vec![]),
+        sync_badges_ix(&program_id, &admin, &owner, &DEVNET_MINT),
+        set_mint_config_ix(&program_id, &admin, &Pubkey::new_unique(), true, 1, 0, 0, 0, vec![]),
+        initialize_sol_ix(&program_id, &owner, 1, &recipients, None, false, VaultKind::Fixed),
+        deposit_sol_ix(&program_id, &owner, 1),
+        withdraw_sol_ix(&program_id, &owner, &recipients, None),
+        set_slots_per_day_ix(&program_id, &admin, 1),
+    ]
+}
+
+/// Runs the processor natively against `signers.len()` blank accounts.
+fn run(data: &[u8], signers: &[bool]) -> Result<(), ProgramError> {
+    let program_id = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let keys: Vec<Pubkey> = signers.iter().map(|_| Pubkey::new_unique()).collect();
+    let mut lamports = vec![0u64; signers.len()];
+    let mut account_data = vec![Vec::<u8>::new(); signers.len()];
+
+    let accounts: Vec<AccountInfo> = keys
+        .iter()
+        .zip(lamports.iter_mut())
+        .zip(account_data.iter_mut())
+        .zip(signers)
+        .map(|(((key, lamports), data), is_signer)| {
+            AccountInfo::new(key, *is_signer, true, lamports, data, &owner, false, 0)
+        })
+        .collect();
+
+    process_instruction(&program_id, &accounts, data)
+}
+
+#[test]
+fn test_idl_is_up_to_date() {
+    let generated = serde_json::to_string_pretty(&idl::generate()).unwrap() + "\n";
+
+    if std::env::var("UPDATE_IDL").is_ok() {
+        std::fs::write(IDL_PATH, &generated).unwrap();
+    }
+
+    let checked_in = std::fs::read_to_string(IDL_PATH).unwrap_or_default();
+    assert!(
+        checked_in == generated,
+        "idl/construct_vault_sol.json is stale; rerun with UPDATE_IDL=1 to regenerate"
+    );
+}
+
+#[test]
+fn test_idl_accounts_match_handlers() {
+    let instructions = VaultInstruction::idl_instructions();
+    let samples = samples();
+    assert_eq!(instructions.len(), samples.len());
+
+    for (discriminant, (instruction, sample)) in instructions.iter().zip(samples).enumerate() {
+        let data = borsh::to_vec(&sample).unwrap();
+        assert_eq!(data[0] as usize, discriminant);
+        assert!(format!("{:?}", sample).starts_with(instruction.name));
+
+        let signers: Vec<bool> = instruction.fixed_accounts().map(|account| account.signer).collect();
+
+        // The handler reads exactly the fixed accounts before anything else
+        assert_eq!(
+            run(&data, &signers[..signers.len() - 1]),
+            Err(ProgramError::NotEnoughAccountKeys),
+            "{} reads fewer accounts than its IDL lists",
+            instruction.name
+        );
+        assert_ne!(
+            run(&data, &signers),
+            Err(ProgramError::NotEnoughAccountKeys),
+            "{} reads more accounts than its IDL lists",
+            instruction.name
+        );
+
+        // Exactly the accounts marked as signers are required to sign
+        assert_ne!(
+            run(&data, &signers),
+            Err(ProgramError::MissingRequiredSignature),
+            "{} requires a signer its IDL doesn't mark",
+            instruction.name
+        );
+
+        for index in (0..signers.len()).filter(|index| signers[*index]) {
+            let mut unsigned = signers.clone();
+            unsigned[index] = false;
+
+            assert_eq!(
+                run(&data, &unsigned),
+                Err(ProgramError::MissingRequiredSignature),
+                "{} doesn't require account {} to sign",
+                instruction.name,
+                index
+            );
+        }
+    }
+}
+
+#[test]
+fn test_client_builders_match_idl() {
+    let instructions = VaultInstruction::idl_instructions();
+    let samples = client_samples();
+    assert_eq!(instructions.len(), samples.len());
+
+    for (instruction, sample) in instructions.iter().zip(samples) {
+        let decoded = VaultInstruction::try_from_slice(&sample.data).unwrap();
+        assert!(format!("{:?}", decoded).starts_with(instruction.name));
+
+        let fixed: Vec<_> = instruction.fixed_accounts().collect();
+        assert!(
+            sample.accounts.len() >= fixed.len(),
+            "{} builder passes too few accounts",
+            instruction.name
+        );
+
+        for (meta, account) in sample.accounts.iter().zip(fixed) {
+            assert_eq!(
+                (meta.is_signer, meta.is_writable),
+                (account.signer, account.writable),
+                "{} builder disagrees with the IDL on {}",
+                instruction.name,
+                account.name
+            );
+        }
+    }
+}
+
+#[test]
+fn test_idl_vault_layout_matches_account_data() {
+    let mut data = vec![0; Vault::LEN];
+    let vault = Vault::init(&mut data).unwrap();
+    vault.owner = Pubkey::new_from_array([7; 32]);
+    vault.lock_duration = 0x0101_0101_0101_0101.into();
+    vault.amount_locked = 0x0202_0202_0202_0202.into();
+    vault.deposit_timestamp = 0x0303_0303_0303_0303.into();
+    vault.status = VaultStatus::Locked as u8;
+    vault.bump = 0xfe;
+    vault.status_timestamp = 0x0404_0404_0404_0404.into();
+    vault.auto_renew = 1;
+    vault.kind = VaultKind::Cooldown as u8;
+    vault.receipts = 1;
+    vault.receipt_bump = 0xfd;
+    vault.mint = Pubkey::new_from_array([9; 32]);
+    vault.rent_lamports = 0x0505_0505_0505_0505.into();
+    vault.counted = 1;
+    vault.time_basis = TimeBasis::Slots as u8;
+    vault.exemption_bump = 0xfc;
+    vault.referral_bump = 0xfb;
+    vault.reserved = [0xaa; Vault::RESERVED_LEN];
+    let vault = *vault;
+
+    let layout = &idl::generate()["accounts"][0];
+    assert_eq!(layout["size"], Vault::LEN);
+
+    let mut end = 0;
+    for field in layout["fields"].as_array().unwrap() {
+        let offset = field["offset"].as_u64().unwrap() as usize;
+        let size = field["size"].as_u64().unwrap() as usize;
+        assert_eq!(offset, end, "gap before {}", field["name"]);
+        end = offset + size;
+
+        let expected: Vec<u8> = match field["name"].as_str().unwrap() {
+            "discriminator" => Vault::DISCRIMINATOR.to_vec(),
+            "version" => vec![Vault::VERSION],
+            "owner" => vault.owner.to_bytes().to_vec(),
+            "lock_duration" => 0x0101_0101_0101_0101u64.to_le_bytes().to_vec(),
+            "amount_locked" => 0x0202_0202_0202_0202u64.to_le_bytes().to_vec(),
+            "deposit_timestamp" => 0x0303_0303_0303_0303u64.to_le_bytes().to_vec(),
+            "status" => vec![VaultStatus::Locked as u8],
+            "bump" => vec![vault.bump],
+            "status_timestamp" => 0x0404_0404_0404_0404u64.to_le_bytes().to_vec(),
+            "auto_renew" => vec![1],
+            "kind" => vec![VaultKind::Cooldown as u8],
+            "receipts" => vec![1],
+            "receipt_bump" => vec![0xfd],
+            "mint" => vault.mint.to_bytes().to_vec(),
+            "rent_lamports" => 0x0505_0505_0505_0505u64.to_le_bytes().to_vec(),
+            "counted" => vec![1],
+            "time_basis" => vec![TimeBasis::Slots as u8],
+            "exemption_bump" => vec![0xfc],
+            "referral_bump" => vec![0xfb],
+            "reserved" => vec![0xaa; Vault::RESERVED_LEN],
+            name => panic!("unexpected vault field {}", name),
+        };
+
+        assert_eq!(&data[offset..end], &expected[..], "field {}", field["name"]);
+    }
+    assert_eq!(end, Vault::LEN);
+}
+
+#[test]
+fn test_idl_activity_log_layout_matches_account_data() {
+    let mut data = vec![0; ActivityLog::LEN];
+    let log = ActivityLog::load_mut(&mut data).unwrap();
+    log.vault = Pubkey::new_from_array([7; 32]);
+    log.bump = 0xfe;
+    log.push(ActivityEntry::new(ActivityKind::Withdraw, 0x0202_0202_0202_0202, 0x0303_0303_0303_0303, 0x0101));
+
+    let layout = &idl::generate()["accounts"][1];
+    assert_eq!(layout["name"], "ActivityLog");
+    assert_eq!(layout["size"], ActivityLog::LEN);
+
+    let mut end = 0;
+    for field in layout["fields"].as_array().unwrap() {
+        let offset = field["offset"].as_u64().unwrap() as usize;
+        let size = field["size"].as_u64().unwrap() as usize;
+        assert_eq!(offset, end, "gap before {}", field["name"]);
+        end = offset + size;
+
+        let expected: Vec<u8> = match field["name"].as_str().unwrap() {
+            "vault" => vec![7; 32],
+            "bump" => vec![0xfe],
+            "total" => 1u64.to_le_bytes().to_vec(),
+            "entries" => {
+                // The first entry is timestamp, kind, amount and fee, in that order
+                let mut entries = 0x0101u64.to_le_bytes().to_vec();
+                entries.push(ActivityKind::Withdraw as u8);
+                entries.extend(0x0202_0202_0202_0202u64.to_le_bytes());
+                entries.extend(0x0303_0303_0303_0303u64.to_le_bytes());
+                entries.resize(size, 0);
+                entries
+            }
+            name => panic!("unexpected activity log field {}", name),
+        };
+
+        assert_eq!(&data[offset..end], &expected[..], "field {}", field["name"]);
+    }
+    assert_eq!(end, ActivityLog::LEN);
+}
+
+#[test]
+fn test_error_codes_are_dense() {
+    for (code, error) in VaultError::ALL.iter().enumerate() {
+        assert_eq!(*error as usize, code);
+    }
+}
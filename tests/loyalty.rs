@@ -0,0 +1,110 @@
+mod common;
+
+use borsh::BorshDeserialize;
+use common::*;
+use construct_vault_sol::{
+    error::VaultError,
+    state::{Config, Loyalty, LoyaltyTier, VaultKind},
+};
+use solana_sdk::{pubkey::Pubkey, signature::Signer};
+
+const DAY: i64 = 86_400;
+
+async fn loyalty(ctx: &mut TestContext, owner: &Pubkey) -> Loyalty {
+    let (loyalty_pda, _) = Loyalty::find_address(owner, &ctx.program_id);
+    let account = ctx.banks_client.get_account(loyalty_pda).await.unwrap().unwrap();
+    Loyalty::try_from_slice(&account.data).unwrap()
+}
+
+fn tier(min_completed_locks: u32, discount_bps: u16) -> LoyaltyTier {
+    LoyaltyTier {
+        min_completed_locks,
+        discount_bps,
+    }
+}
+
+#[test]
+fn test_discount_uses_highest_reached_tier() {
+    let mut config = Config::new(Pubkey::new_unique(), 255);
+    config.loyalty_tiers[..3].copy_from_slice(&[tier(1, 1_000), tier(3, 2_500), tier(10, 5_000)]);
+    config.loyalty_tier_count = 3;
+
+    assert_eq!(config.loyalty_discount_bps(0), 0);
+    assert_eq!(config.loyalty_discount_bps(1), 1_000);
+    assert_eq!(config.loyalty_discount_bps(9), 2_500);
+    assert_eq!(config.loyalty_discount_bps(42), 5_000);
+}
+
+#[tokio::test]
+async fn test_completed_lock_discounts_next_early_withdrawal() {
+    let mut ctx = setup().await;
+    let payer = ctx.payer.pubkey();
+    let fee_receiver = Pubkey::new_unique();
+
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    let instruction = set_loyalty_tiers_ix(&ctx.program_id, &payer, vec![tier(1, 5_000)]);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    ctx.fund_token_account(&payer, 2_000_000_000).await;
+
+    let instruction = initialize_ix(&ctx.program_id, &payer, &DEVNET_MINT, 1, &[fee_receiver], None, false, VaultKind::Fixed);
+    ctx.process(&[instruction], &[]).await.unwrap();
+    assert_eq!(ctx.lamports(&fee_receiver).await, 100_000_000);
+
+    // Let the first lock run its full term, then exit without a fee
+    ctx.warp_forward(31 * DAY).await;
+    let instruction = withdraw_ix(&ctx.program_id, &payer, &DEVNET_MINT, &[fee_receiver], None);
+    ctx.process(&[instruction], &[]).await.unwrap();
+    assert_eq!(ctx.lamports(&fee_receiver).await, 100_000_000);
+
+    let record = loyalty(&mut ctx, &payer).await;
+    assert_eq!(record.completed_locks, 1);
+    assert_eq!(record.early_exits, 0);
+    assert!(record.total_time_locked >= 31 * DAY as u64);
+
+    // Relock and leave straight away: half of the 3.75 SOL fee
+    let instruction = deposit_ix(&ctx.program_id, &payer, &DEVNET_MINT, 1_000);
+    ctx.process(&[instruction], &[]).await.unwrap();
+    let instruction = withdraw_ix(&ctx.program_id, &payer, &DEVNET_MINT, &[fee_receiver], None);
+    ctx.process(&[instruction], &[]).await.unwrap();
+    assert_eq!(ctx.lamports(&fee_receiver).await, 100_000_000 + 1_875_000_000);
+
+    let record = loyalty(&mut ctx, &payer).await;
+    assert_eq!(record.completed_locks, 1);
+    assert_eq!(record.early_exits, 1);
+}
+
+#[tokio::test]
+async fn test_claim_credits_the_completed_lock() {
+    let mut ctx = setup().await;
+    let payer = ctx.payer.pubkey();
+    let fee_receiver = Pubkey::new_unique();
+
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    ctx.open_vault(None, fee_receiver, 1, false, VaultKind::Fixed).await;
+
+    ctx.warp_forward(31 * DAY).await;
+    let instruction = claim_ix(&ctx.program_id, &payer, &DEVNET_MINT);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    let record = loyalty(&mut ctx, &payer).await;
+    assert_eq!(record.completed_locks, 1);
+    assert_eq!(record.early_exits, 0);
+    assert!(record.total_time_locked >= 31 * DAY as u64);
+}
+
+#[tokio::test]
+async fn test_set_loyalty_tiers_rejects_unsorted_table() {
+    let mut ctx = setup().await;
+    let payer = ctx.payer.pubkey();
+
+    ctx.initialize_config(single_recipient(Pubkey::new_unique()), 0).await;
+
+    let instruction = set_loyalty_tiers_ix(&ctx.program_id, &payer, vec![tier(5, 1_000), tier(2, 2_000)]);
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::InvalidLoyaltyTiers as u32));
+
+    let instruction = set_loyalty_tiers_ix(&ctx.program_id, &payer, vec![tier(1, 10_001)]);
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::InvalidLoyaltyTiers as u32));
+}
@@ -0,0 +1,40 @@
+use construct_vault_sol::{
+    error::VaultError,
+    math::{elapsed, to_base_units, unix_seconds, CheckedMath},
+};
+use solana_program::program_error::ProgramError;
+
+fn overflow() -> ProgramError {
+    VaultError::ArithmeticOverflow.into()
+}
+
+#[test]
+fn test_checked_math_reports_overflow() {
+    assert_eq!(u64::MAX.try_add(1), Err(overflow()));
+    assert_eq!(0u64.try_sub(1), Err(overflow()));
+    assert_eq!(u64::MAX.try_mul(2), Err(overflow()));
+    assert_eq!(u32::MAX.try_add(1), Err(overflow()));
+    assert_eq!(u128::MAX.try_mul(2), Err(overflow()));
+
+    assert_eq!(u64::MAX.try_sub(1), Ok(u64::MAX - 1));
+    assert_eq!(7u32.try_mul(6), Ok(42));
+}
+
+#[test]
+fn test_to_base_units_scales_by_decimals() {
+    assert_eq!(to_base_units(3, 9), Ok(3_000_000_000));
+    assert_eq!(to_base_units(3, 0), Ok(3));
+    assert_eq!(to_base_units(u64::MAX / 1_000_000_000 + 1, 9), Err(overflow()));
+    // 10^20 doesn't fit in a u64, whatever the amount
+    assert_eq!(to_base_units(0, 20), Err(overflow()));
+}
+
+#[test]
+fn test_time_is_clamped_at_zero() {
+    assert_eq!(elapsed(100, 40), 60);
+    assert_eq!(elapsed(40, 100), 0);
+
+    assert_eq!(unix_seconds(1_700_000_000), 1_700_000_000);
+    assert_eq!(unix_seconds(-1), 0);
+    assert_eq!(unix_seconds(i64::MIN), 0);
+}
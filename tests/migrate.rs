@@ -0,0 +1,210 @@
+mod common;
+
+use borsh::BorshSerialize;
+use common::*;
+use construct_vault_sol::{error::VaultError, state::{LegacyVault, Vault, VaultStatus}};
+use solana_program::{program_error::ProgramError, rent::Rent};
+use solana_sdk::{
+    account::Account,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction,
+};
+use spl_associated_token_account::{get_associated_token_address, instruction::create_associated_token_account};
+
+fn legacy_vault(owner: Pubkey) -> LegacyVault {
+    LegacyVault {
+        owner,
+        lock_duration: Vault::LOCK_DURATION,
+        amount_locked: 42,
+        deposit_timestamp: 1_700_000_000,
+        is_locked: true,
+        bump: 0,
+    }
+}
+
+#[test]
+fn test_unpack_dispatches_on_version() {
+    let vault = legacy_vault(Pubkey::new_unique());
+    let legacy_data = borsh::to_vec(&vault).unwrap();
+    assert_eq!(legacy_data.len(), Vault::LEGACY_LEN);
+
+    assert_eq!(Vault::version(&legacy_data), Ok(0));
+    let vault = Vault::from(vault);
+    assert_eq!(Vault::unpack(&legacy_data).unwrap(), vault);
+    assert_eq!(vault.status(), Ok(VaultStatus::Locked));
+    assert_eq!(vault.status_timestamp, vault.deposit_timestamp);
+    assert_eq!(
+        Vault::load(&legacy_data).err(),
+        Some(ProgramError::from(VaultError::VaultNeedsMigration))
+    );
+
+    let mut data = vec![0; Vault::LEN];
+    *Vault::init(&mut data).unwrap() = vault;
+    assert_eq!(Vault::version(&data), Ok(Vault::VERSION));
+    assert_eq!(Vault::load(&data).unwrap(), &vault);
+
+    data[8] = Vault::VERSION + 1;
+    assert_eq!(
+        Vault::unpack(&data),
+        Err(ProgramError::from(VaultError::UnsupportedVaultVersion))
+    );
+
+    // A current header on data of the wrong size is caught up front
+    data[8] = Vault::VERSION;
+    assert_eq!(
+        Vault::load_mut(&mut data[..Vault::LEN - 1]).err(),
+        Some(ProgramError::InvalidAccountData)
+    );
+}
+
+#[tokio::test]
+async fn test_migrate_legacy_vault() {
+    let program_id = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let (vault_pda, bump) = vault_address(&program_id, &owner);
+
+    let mut legacy_data = Vec::new();
+    legacy_vault(owner).serialize(&mut legacy_data).unwrap();
+
+    let legacy_account = Account {
+        lamports: Rent::default().minimum_balance(Vault::LEGACY_LEN),
+        data: legacy_data,
+        owner: program_id,
+        executable: false,
+        rent_epoch: 0,
+    };
+
+    let vault_ata = (vault_token_address(&program_id, &owner), kuza_token_account(vault_pda, 42 * TOKEN));
+    let mut ctx = setup_with_accounts(program_id, vec![(vault_pda, legacy_account), vault_ata]).await;
+
+    let instruction = migrate_vault_ix(&program_id, &ctx.payer.pubkey(), &owner, &DEVNET_MINT);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    let account = ctx.banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    assert_eq!(account.data.len(), Vault::LEN);
+    assert_eq!(account.lamports, Rent::default().minimum_balance(Vault::LEN));
+    assert_eq!(Vault::version(&account.data), Ok(Vault::VERSION));
+
+    // The whole tokens the legacy vault recorded become base units
+    let migrated = Vault::load(&account.data).unwrap();
+    let amount_locked = (42 * TOKEN).into();
+    let (exemption_bump, referral_bump) = Vault::find_owner_bumps(&owner, &program_id);
+    let expected = Vault { bump, amount_locked, exemption_bump, referral_bump, ..legacy_vault(owner).into() };
+    assert_eq!(*migrated, expected);
+
+    // Running it again is a no-op
+    let instruction = migrate_vault_ix(&program_id, &ctx.payer.pubkey(), &owner, &DEVNET_MINT);
+    ctx.process(&[instruction], &[]).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_migrated_legacy_vault_releases_its_whole_balance() {
+    let program_id = Pubkey::new_unique();
+    let owner_keypair = Keypair::new();
+    let owner = owner_keypair.pubkey();
+    let (vault_pda, _) = vault_address(&program_id, &owner);
+
+    // Locked long enough ago to have matured
+    let legacy_account = Account {
+        lamports: Rent::default().minimum_balance(Vault::LEGACY_LEN),
+        data: borsh::to_vec(&legacy_vault(owner)).unwrap(),
+        owner: program_id,
+        executable: false,
+        rent_epoch: 0,
+    };
+    let vault_ata = vault_token_address(&program_id, &owner);
+
+    let accounts = vec![(vault_pda, legacy_account), (vault_ata, kuza_token_account(vault_pda, 42 * TOKEN))];
+    let mut ctx = setup_with_accounts(program_id, accounts).await;
+    ctx.initialize_config(single_recipient(Pubkey::new_unique()), 0).await;
+
+    // The owner pays for the loyalty record the claim credits
+    let instructions = [
+        migrate_vault_ix(&program_id, &ctx.payer.pubkey(), &owner, &DEVNET_MINT),
+        create_associated_token_account(&ctx.payer.pubkey(), &owner, &DEVNET_MINT, &spl_token::id()),
+        system_instruction::transfer(&ctx.payer.pubkey(), &owner, 1_000_000_000),
+        claim_ix(&program_id, &owner, &DEVNET_MINT),
+    ];
+    ctx.process(&instructions, &[&owner_keypair]).await.unwrap();
+
+    assert_eq!(ctx.token_balance(&vault_ata).await, 0);
+    assert_eq!(ctx.token_balance(&get_associated_token_address(&owner, &DEVNET_MINT)).await, 42 * TOKEN);
+}
+
+#[tokio::test]
+async fn test_migrate_records_missing_bump() {
+    let program_id = Pubkey::new_unique();
+    let owner_keypair = Keypair::new();
+    let owner = owner_keypair.pubkey();
+    let (vault_pda, bump) = vault_address(&program_id, &owner);
+
+    // A current-layout vault written before initialize stored the bump
+    let mut data = vec![0; Vault::LEN];
+    *Vault::init(&mut data).unwrap() = legacy_vault(owner).into();
+
+    let account = Account {
+        lamports: Rent::default().minimum_balance(Vault::LEN),
+        data,
+        owner: program_id,
+        executable: false,
+        rent_epoch: 0,
+    };
+
+    let vault_ata = (vault_token_address(&program_id, &owner), kuza_token_account(vault_pda, 42 * TOKEN));
+    let mut ctx = setup_with_accounts(program_id, vec![(vault_pda, account), vault_ata]).await;
+
+    // The stored bump of 0 doesn't derive the vault address
+    let instruction = deposit_ix(&program_id, &owner, &DEVNET_MINT, 1);
+    let error = ctx.process(&[instruction], &[&owner_keypair]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::VaultAddressMismatch as u32));
+
+    let instruction = migrate_vault_ix(&program_id, &ctx.payer.pubkey(), &owner, &DEVNET_MINT);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    let vault = ctx.vault(&owner).await;
+    let amount_locked = (42 * TOKEN).into();
+    let (exemption_bump, referral_bump) = Vault::find_owner_bumps(&owner, &program_id);
+    assert_eq!(vault, Vault { bump, amount_locked, exemption_bump, referral_bump, ..legacy_vault(owner).into() });
+}
+
+#[tokio::test]
+async fn test_migrate_version_1_vault() {
+    let program_id = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let (vault_pda, bump) = vault_address(&program_id, &owner);
+
+    // Version 1 kept an `is_locked` bool where the status now sits; this
+    // vault had already been released
+    let mut data = vec![0; Vault::LEN];
+    let vault = Vault::init(&mut data).unwrap();
+    vault.version = 1;
+    vault.owner = owner;
+    vault.status = 0;
+    vault.bump = bump;
+
+    assert_eq!(
+        Vault::load(&data).err(),
+        Some(ProgramError::from(VaultError::VaultNeedsMigration))
+    );
+
+    let account = Account {
+        lamports: Rent::default().minimum_balance(Vault::LEN),
+        data,
+        owner: program_id,
+        executable: false,
+        rent_epoch: 0,
+    };
+
+    let vault_ata = (vault_token_address(&program_id, &owner), kuza_token_account(vault_pda, 0));
+    let mut ctx = setup_with_accounts(program_id, vec![(vault_pda, account), vault_ata]).await;
+
+    let instruction = migrate_vault_ix(&program_id, &ctx.payer.pubkey(), &owner, &DEVNET_MINT);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    let account = ctx.banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let migrated = Vault::load(&account.data).unwrap();
+    assert_eq!(migrated.status(), Ok(VaultStatus::Released));
+    assert_eq!(u64::from(migrated.status_timestamp), 0);
+    assert_eq!(migrated.owner, owner);
+}
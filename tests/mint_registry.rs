@@ -0,0 +1,177 @@
+mod common;
+
+use common::*;
+use construct_vault_sol::{
+    error::VaultError,
+    state::{AccessTier, MintConfig, Vault, VaultKind},
+    INITIALIZATION_FEE_LAMPORTS,
+};
+use borsh::BorshDeserialize;
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+use spl_associated_token_account::get_associated_token_address;
+use spl_token_2022::{extension::StateWithExtensions, state::Account as BadgeAccount};
+
+const DAY: u64 = 86_400;
+const LOCK_DURATION: u64 = 7 * DAY;
+const INITIALIZATION_FEE: u64 = 20_000_000;
+const MAX_EARLY_WITHDRAWAL_FEE: u64 = 1_000_000_000;
+
+fn set_terms_ix(ctx: &TestContext, mint: &Pubkey, enabled: bool, access_tiers: Vec<AccessTier>) -> Instruction {
+    set_mint_config_ix(
+        &ctx.program_id,
+        &ctx.payer.pubkey(),
+        mint,
+        enabled,
+        LOCK_DURATION,
+        INITIALIZATION_FEE,
+        MAX_EARLY_WITHDRAWAL_FEE,
+        0,
+        access_tiers,
+    )
+}
+
+/// Config with a single fee recipient, and the partner mint registered.
+async fn setup_registry(ctx: &mut TestContext) -> (Pubkey, Pubkey) {
+    let fee_receiver = Pubkey::new_unique();
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+
+    let mint = ctx.create_funded_mint(6, 1_000_000_000).await;
+    let instruction = set_terms_ix(ctx, &mint, true, vec![]);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    (mint, fee_receiver)
+}
+
+#[tokio::test]
+async fn test_registered_mint_vaults_use_the_mint_terms() {
+    let mut ctx = setup().await;
+    let payer = ctx.payer.pubkey();
+    let (mint, fee_receiver) = setup_registry(&mut ctx).await;
+
+    let account = ctx.banks_client.get_account(MintConfig::find_address(&mint, &ctx.program_id).0).await.unwrap();
+    let mint_config = MintConfig::try_from_slice(&account.unwrap().data).unwrap();
+    assert_eq!((mint_config.mint, mint_config.enabled, mint_config.lock_duration), (mint, true, LOCK_DURATION));
+
+    // The owner's KUZA vault and partner vault live side by side
+    ctx.fund_token_account(&payer, 1_000_000_000_000).await;
+    let instructions = [
+        initialize_ix(&ctx.program_id, &payer, &DEVNET_MINT, 1, &[fee_receiver], None, false, VaultKind::Fixed),
+        initialize_ix(&ctx.program_id, &payer, &mint, 2, &[fee_receiver], None, false, VaultKind::Fixed),
+    ];
+    ctx.process(&instructions, &[]).await.unwrap();
+
+    let (vault_pda, _) = Vault::find_mint_address(&payer, &mint, &ctx.program_id);
+    assert_eq!(find_vault_address(&ctx.program_id, &payer, &mint).0, vault_pda);
+    assert_ne!(vault_pda, vault_address(&ctx.program_id, &payer).0);
+
+    let account = ctx.banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    let vault = Vault::unpack(&account.data).unwrap();
+    assert_eq!(vault.mint, mint);
+    assert_eq!(u64::from(vault.lock_duration), LOCK_DURATION);
+
+    // Whole tokens are scaled by the mint's own decimals
+    let vault_ata = get_associated_token_address(&vault_pda, &mint);
+    assert_eq!(ctx.token_balance(&vault_ata).await, 2_000_000);
+    assert_eq!(ctx.lamports(&fee_receiver).await, INITIALIZATION_FEE_LAMPORTS + INITIALIZATION_FEE);
+
+    // The term is the mint's, not the KUZA month
+    let user_ata = get_associated_token_address(&payer, &mint);
+    let balance_before = ctx.token_balance(&user_ata).await;
+    ctx.warp_forward(LOCK_DURATION as i64).await;
+    let instruction = claim_ix(&ctx.program_id, &payer, &mint);
+    ctx.process(&[instruction], &[]).await.unwrap();
+    assert_eq!(ctx.token_balance(&user_ata).await, balance_before + u64::from(vault.amount_locked));
+    assert_eq!(ctx.vault(&payer).await.mint, Pubkey::default());
+}
+
+#[tokio::test]
+async fn test_disabled_mint_refuses_new_locks_but_lets_vaults_out() {
+    let mut ctx = setup().await;
+    let payer = ctx.payer.pubkey();
+    let (mint, fee_receiver) = setup_registry(&mut ctx).await;
+
+    let instruction = initialize_ix(&ctx.program_id, &payer, &mint, 2, &[fee_receiver], None, false, VaultKind::Fixed);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    let instruction = set_terms_ix(&ctx, &mint, false, vec![]);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    let instruction = deposit_ix(&ctx.program_id, &payer, &mint, 1);
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::MintDisabled as u32));
+
+    // Leaving early costs the mint's fee: 75% of its maximum on day 0
+    let before = ctx.lamports(&fee_receiver).await;
+    let instruction = withdraw_ix(&ctx.program_id, &payer, &mint, &[fee_receiver], None);
+    ctx.process(&[instruction], &[]).await.unwrap();
+    assert_eq!(ctx.lamports(&fee_receiver).await - before, MAX_EARLY_WITHDRAWAL_FEE * 3 / 4);
+}
+
+#[tokio::test]
+async fn test_registered_mints_earn_their_own_badges() {
+    let mut ctx = setup().await;
+    let payer = ctx.payer.pubkey();
+    let (mint, fee_receiver) = setup_registry(&mut ctx).await;
+
+    let instruction = set_terms_ix(&ctx, &mint, true, vec![AccessTier { min_amount_locked: 2 }]);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    let instruction = initialize_ix(&ctx.program_id, &payer, &mint, 2, &[fee_receiver], None, false, VaultKind::Fixed);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    let (badge_mint, badge_bump) = AccessTier::find_badge_mint(Some(&mint), 0, &ctx.program_id);
+    let account = ctx.banks_client.get_account(badge_account_address(&payer, &badge_mint)).await.unwrap().unwrap();
+    assert_eq!(StateWithExtensions::<BadgeAccount>::unpack(&account.data).unwrap().base.amount, 1);
+
+    // The badge mint's bump is kept in the mint config and survives new terms
+    let instruction = set_terms_ix(&ctx, &mint, true, vec![AccessTier { min_amount_locked: 2 }]);
+    ctx.process(&[instruction], &[]).await.unwrap();
+    let account = ctx.banks_client.get_account(MintConfig::find_address(&mint, &ctx.program_id).0).await.unwrap();
+    let mint_config = MintConfig::try_from_slice(&account.unwrap().data).unwrap();
+    assert_eq!(mint_config.badge_bumps, [badge_bump, 0, 0, 0]);
+
+    // The KUZA badges are untouched
+    let (kuza_badge_mint, _) = AccessTier::find_badge_mint(None, 0, &ctx.program_id);
+    assert!(ctx.banks_client.get_account(kuza_badge_mint).await.unwrap().is_none());
+
+    let instruction = withdraw_ix(&ctx.program_id, &payer, &mint, &[fee_receiver], None);
+    ctx.process(&[instruction], &[]).await.unwrap();
+    let instruction = sync_badges_ix(&ctx.program_id, &payer, &payer, &mint);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    let account = ctx.banks_client.get_account(badge_account_address(&payer, &badge_mint)).await.unwrap().unwrap();
+    assert_eq!(StateWithExtensions::<BadgeAccount>::unpack(&account.data).unwrap().base.amount, 0);
+}
+
+#[tokio::test]
+async fn test_mint_registry_is_checked() {
+    let mut ctx = setup().await;
+    let payer = ctx.payer.pubkey();
+    let fee_receiver = Pubkey::new_unique();
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    let mint = ctx.create_funded_mint(6, 1_000_000_000).await;
+
+    // An unregistered mint can't be locked
+    let instruction = initialize_ix(&ctx.program_id, &payer, &mint, 1, &[fee_receiver], None, false, VaultKind::Fixed);
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::InvalidMint as u32));
+
+    // The KUZA terms are built in
+    let instruction = set_terms_ix(&ctx, &DEVNET_MINT, true, vec![]);
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::InvalidMint as u32));
+
+    let instruction = set_mint_config_ix(&ctx.program_id, &payer, &mint, true, 0, 0, 0, 0, vec![]);
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::InvalidMintConfig as u32));
+
+    let stranger = Keypair::new();
+    let mut instruction = set_terms_ix(&ctx, &mint, true, vec![]);
+    instruction.accounts[0].pubkey = stranger.pubkey();
+    let error = ctx.process(&[instruction], &[&stranger]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::Unauthorized as u32));
+}
@@ -0,0 +1,296 @@
+mod common;
+
+use common::*;
+use construct_vault_sol::{
+    error::VaultError,
+    processor::VaultInstruction,
+    state::{TimeBasis, Treasury, Vault, VaultKind, VaultStatus},
+    INITIALIZATION_FEE_LAMPORTS,
+};
+use solana_program::program_pack::Pack;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction,
+};
+use spl_associated_token_account::{get_associated_token_address, instruction::create_associated_token_account};
+use spl_token::state::Mint;
+
+const DAY: i64 = 86_400;
+
+/// Opens the payer's vault with one token and receipts for it. Returns the
+/// payer's receipt account and the fee recipient.
+async fn open_receipt_vault(ctx: &mut TestContext) -> (Pubkey, Pubkey) {
+    let payer = ctx.payer.pubkey();
+    let fee_receiver = Pubkey::new_unique();
+
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    ctx.fund_token_account(&payer, 1_000_000_000_000).await;
+
+    let (program_id, basis) = (ctx.program_id, TimeBasis::UnixSeconds);
+    let instruction = initialize_with_receipts_ix(&program_id, &payer, &DEVNET_MINT, 1, &[fee_receiver], None, basis);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    let (receipt_mint, _) = receipt_mint_address(&ctx.program_id, &payer, &DEVNET_MINT);
+    (get_associated_token_address(&payer, &receipt_mint), fee_receiver)
+}
+
+/// A wallet with SOL, an empty KUZA account and `receipts` of the payer's
+/// receipts. Returns the wallet and its receipt account.
+async fn buyer(ctx: &mut TestContext, receipts: u64) -> (Keypair, Pubkey) {
+    let payer = ctx.payer.pubkey();
+    let buyer = Keypair::new();
+    let (receipt_mint, _) = receipt_mint_address(&ctx.program_id, &payer, &DEVNET_MINT);
+    let receipt_account = get_associated_token_address(&buyer.pubkey(), &receipt_mint);
+
+    let instructions = [
+        system_instruction::transfer(&payer, &buyer.pubkey(), 1_000_000_000),
+        create_associated_token_account(&payer, &buyer.pubkey(), &receipt_mint, &spl_token::id()),
+        spl_token::instruction::transfer(
+            &spl_token::id(),
+            &get_associated_token_address(&payer, &receipt_mint),
+            &receipt_account,
+            &payer,
+            &[],
+            receipts,
+        )
+        .unwrap(),
+    ];
+    ctx.process(&instructions, &[]).await.unwrap();
+    ctx.fund_token_account(&buyer.pubkey(), 0).await;
+
+    (buyer, receipt_account)
+}
+
+async fn receipt_supply(ctx: &mut TestContext) -> u64 {
+    let (receipt_mint, _) = receipt_mint_address(&ctx.program_id, &ctx.payer.pubkey(), &DEVNET_MINT);
+    let account = ctx.banks_client.get_account(receipt_mint).await.unwrap().unwrap();
+    Mint::unpack(&account.data).unwrap().supply
+}
+
+#[tokio::test]
+async fn test_receipt_holder_redeems_the_matured_vault() {
+    let mut ctx = setup().await;
+    let payer = ctx.payer.pubkey();
+    let (receipt_account, _) = open_receipt_vault(&mut ctx).await;
+
+    let vault = ctx.vault(&payer).await;
+    let amount_locked = u64::from(vault.amount_locked);
+    let vault_ata = vault_token_address(&ctx.program_id, &payer);
+    let locked = ctx.token_balance(&vault_ata).await;
+    assert_eq!(locked, TOKEN);
+    assert_eq!(amount_locked, locked);
+    assert!(vault.has_receipts());
+    assert!(!vault.auto_renews());
+    assert!(!vault.counts_toward_tier(ctx.now().await));
+    assert_eq!(ctx.token_balance(&receipt_account).await, amount_locked);
+
+    let (buyer, _) = buyer(&mut ctx, amount_locked).await;
+    let buyer_ata = get_associated_token_address(&buyer.pubkey(), &DEVNET_MINT);
+
+    // Still locked, even for the holder
+    let instruction = redeem_ix(&ctx.program_id, &buyer.pubkey(), &payer, &DEVNET_MINT);
+    let error = ctx.process(&[instruction], &[&buyer]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::LockNotExpired as u32));
+
+    ctx.warp_forward(Vault::LOCK_DURATION as i64).await;
+
+    // The owner sold the position, so they can't claim it
+    let instruction = claim_ix(&ctx.program_id, &payer, &DEVNET_MINT);
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::ReceiptsIssued as u32));
+
+    let instruction = redeem_ix(&ctx.program_id, &buyer.pubkey(), &payer, &DEVNET_MINT);
+    ctx.process(&[instruction], &[&buyer]).await.unwrap();
+
+    assert_eq!(ctx.token_balance(&buyer_ata).await, locked);
+    assert_eq!(ctx.token_balance(&vault_ata).await, 0);
+    assert_eq!(receipt_supply(&mut ctx).await, 0);
+    assert_eq!(ctx.vault(&payer).await.status(), Ok(VaultStatus::Released));
+}
+
+#[tokio::test]
+async fn test_redeeming_needs_every_receipt() {
+    let mut ctx = setup().await;
+    let payer = ctx.payer.pubkey();
+    open_receipt_vault(&mut ctx).await;
+
+    let instruction = deposit_with_receipts_ix(&ctx.program_id, &payer, &DEVNET_MINT, 1_000);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    let (buyer, _) = buyer(&mut ctx, 1_000).await;
+    ctx.warp_forward(Vault::LOCK_DURATION as i64).await;
+
+    let instruction = redeem_ix(&ctx.program_id, &buyer.pubkey(), &payer, &DEVNET_MINT);
+    let error = ctx.process(&[instruction], &[&buyer]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(spl_token::error::TokenError::InsufficientFunds as u32));
+    assert_eq!(ctx.vault(&payer).await.status(), Ok(VaultStatus::Locked));
+}
+
+#[tokio::test]
+async fn test_deposits_mint_receipts_and_keep_the_term() {
+    let mut ctx = setup().await;
+    let payer = ctx.payer.pubkey();
+    let (receipt_account, _) = open_receipt_vault(&mut ctx).await;
+    let before = ctx.vault(&payer).await;
+
+    ctx.warp_forward(DAY).await;
+
+    // Without the receipt accounts there is nowhere to mint to
+    let instruction = deposit_ix(&ctx.program_id, &payer, &DEVNET_MINT, 1_000);
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+    assert!(custom_error(error).is_none());
+
+    let instruction = deposit_with_receipts_ix(&ctx.program_id, &payer, &DEVNET_MINT, 1_000);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    let vault = ctx.vault(&payer).await;
+    let amount_locked = u64::from(before.amount_locked) + 1_000;
+    assert_eq!(u64::from(vault.amount_locked), amount_locked);
+    assert_eq!(vault.deposit_timestamp, before.deposit_timestamp);
+    assert_eq!(ctx.token_balance(&receipt_account).await, amount_locked);
+    assert_eq!(receipt_supply(&mut ctx).await, amount_locked);
+
+    // Holders can redeem a matured vault, so it can't be relocked under them
+    ctx.warp_forward(Vault::LOCK_DURATION as i64).await;
+    let instruction = deposit_with_receipts_ix(&ctx.program_id, &payer, &DEVNET_MINT, 1_000);
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::ReceiptsIssued as u32));
+}
+
+#[tokio::test]
+async fn test_early_withdrawal_burns_the_receipts_and_charges_the_fee() {
+    let mut ctx = setup().await;
+    let payer = ctx.payer.pubkey();
+    let (receipt_account, fee_receiver) = open_receipt_vault(&mut ctx).await;
+    let user_ata = get_associated_token_address(&payer, &DEVNET_MINT);
+    let balance = ctx.token_balance(&user_ata).await;
+
+    // Initialize minted a receipt per base unit locked
+    assert_eq!(ctx.token_balance(&receipt_account).await, TOKEN);
+    assert_eq!(receipt_supply(&mut ctx).await, TOKEN);
+
+    let instruction = withdraw_with_receipts_ix(&ctx.program_id, &payer, &DEVNET_MINT, &[fee_receiver], None);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    // No time has passed, so the full 75% early-withdrawal fee applies
+    assert_eq!(ctx.lamports(&fee_receiver).await, INITIALIZATION_FEE_LAMPORTS + 3_750_000_000);
+    assert_eq!(ctx.token_balance(&user_ata).await, balance + TOKEN);
+    assert_eq!(ctx.token_balance(&receipt_account).await, 0);
+    assert_eq!(receipt_supply(&mut ctx).await, 0);
+    assert_eq!(ctx.vault(&payer).await.status(), Ok(VaultStatus::Released));
+}
+
+#[tokio::test]
+async fn test_owner_exits_need_every_receipt() {
+    let mut ctx = setup().await;
+    let payer = ctx.payer.pubkey();
+    let (_, fee_receiver) = open_receipt_vault(&mut ctx).await;
+
+    // Without the receipt accounts there is nothing to burn
+    let instruction = withdraw_ix(&ctx.program_id, &payer, &DEVNET_MINT, &[fee_receiver], None);
+    ctx.process(&[instruction], &[]).await.unwrap_err();
+
+    // Once part of the position is sold, the owner can't take it out
+    buyer(&mut ctx, 1).await;
+    let instruction = withdraw_with_receipts_ix(&ctx.program_id, &payer, &DEVNET_MINT, &[fee_receiver], None);
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(spl_token::error::TokenError::InsufficientFunds as u32));
+    assert_eq!(ctx.vault(&payer).await.status(), Ok(VaultStatus::Locked));
+
+    let instruction = set_auto_renew_ix(&ctx.program_id, &payer, &DEVNET_MINT, true);
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::ReceiptsIssued as u32));
+
+    let instruction = enable_receipts_ix(&ctx.program_id, &payer, &DEVNET_MINT);
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::ReceiptsIssued as u32));
+
+    // The crank can't know who holds the receipts, so it leaves the vault be
+    let instructions = [
+        set_crank_tip_ix(&ctx.program_id, &payer, 1_000_000),
+        system_instruction::transfer(&payer, &Treasury::find_address(&ctx.program_id).0, 10_000_000),
+    ];
+    ctx.process(&instructions, &[]).await.unwrap();
+    ctx.warp_forward(Vault::LOCK_DURATION as i64).await;
+
+    let instruction = crank_release_ix(&ctx.program_id, &payer, &DEVNET_MINT, &[payer]);
+    ctx.process(&[instruction], &[]).await.unwrap();
+    assert_eq!(ctx.vault(&payer).await.status(), Ok(VaultStatus::Locked));
+}
+
+#[tokio::test]
+async fn test_cooldown_vaults_cannot_take_receipts() {
+    let mut ctx = setup().await;
+    let payer = ctx.payer.pubkey();
+    let fee_receiver = Pubkey::new_unique();
+
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    ctx.fund_token_account(&payer, 1_000_000_000).await;
+
+    // Neither at initialization
+    let mut instruction = initialize_with_receipts_ix(
+        &ctx.program_id,
+        &payer,
+        &DEVNET_MINT,
+        1,
+        &[fee_receiver],
+        None,
+        TimeBasis::UnixSeconds,
+    );
+    instruction.data = borsh::to_vec(&VaultInstruction::Initialize {
+        amount: 1,
+        referrer: None,
+        auto_renew: false,
+        kind: VaultKind::Cooldown,
+        time_basis: TimeBasis::UnixSeconds,
+        receipts: true,
+    })
+    .unwrap();
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::WrongVaultKind as u32));
+
+    // Nor later
+    let instruction =
+        initialize_ix(&ctx.program_id, &payer, &DEVNET_MINT, 1, &[fee_receiver], None, false, VaultKind::Cooldown);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    let instruction = enable_receipts_ix(&ctx.program_id, &payer, &DEVNET_MINT);
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::WrongVaultKind as u32));
+}
+
+#[tokio::test]
+async fn test_receipts_take_the_locked_mints_decimals() {
+    let mut ctx = setup().await;
+    let payer = ctx.payer.pubkey();
+    let fee_receiver = Pubkey::new_unique();
+
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    let mint = ctx.create_funded_mint(6, 1_000_000_000).await;
+    let instruction = set_mint_config_ix(&ctx.program_id, &payer, &mint, true, 7 * DAY as u64, 0, 0, 0, vec![]);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    let instruction = initialize_ix(&ctx.program_id, &payer, &mint, 2, &[fee_receiver], None, true, VaultKind::Fixed);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    // The vault's mint has to come along, not a KUZA one
+    let mut instruction = enable_receipts_ix(&ctx.program_id, &payer, &mint);
+    instruction.accounts.last_mut().unwrap().pubkey = DEVNET_MINT;
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::InvalidMint as u32));
+
+    let instruction = enable_receipts_ix(&ctx.program_id, &payer, &mint);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    // A holder is owed a term that ends
+    let (vault_pda, _) = find_vault_address(&ctx.program_id, &payer, &mint);
+    let account = ctx.banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    assert!(!Vault::unpack(&account.data).unwrap().auto_renews());
+
+    let (receipt_mint, _) = receipt_mint_address(&ctx.program_id, &payer, &mint);
+    let account = ctx.banks_client.get_account(receipt_mint).await.unwrap().unwrap();
+    let receipt_mint_state = Mint::unpack(&account.data).unwrap();
+    assert_eq!(receipt_mint_state.decimals, 6);
+    assert_eq!(receipt_mint_state.supply, 2_000_000);
+}
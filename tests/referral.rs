@@ -0,0 +1,154 @@
+mod common;
+
+use borsh::BorshDeserialize;
+use common::*;
+use construct_vault_sol::{
+    error::VaultError,
+    state::{Referral, ReferrerStats, Vault, VaultKind},
+};
+use solana_program::{instruction::InstructionError, program_error::ProgramError};
+use solana_sdk::{pubkey::Pubkey, signature::Signer, transaction::TransactionError};
+
+async fn referrer_stats(ctx: &mut TestContext, referrer: &Pubkey) -> ReferrerStats {
+    let (stats_pda, _) = ReferrerStats::find_address(referrer, &ctx.program_id);
+    let account = ctx.banks_client.get_account(stats_pda).await.unwrap().unwrap();
+    ReferrerStats::try_from_slice(&account.data).unwrap()
+}
+
+async fn setup_with_referral_share(referral_bps: u16) -> (TestContext, Pubkey) {
+    let mut ctx = setup().await;
+    let fee_receiver = Pubkey::new_unique();
+
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    let instruction = set_referral_share_ix(&ctx.program_id, &ctx.payer.pubkey(), referral_bps);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    (ctx, fee_receiver)
+}
+
+#[tokio::test]
+async fn test_referrer_earns_share_of_initialization_and_early_withdrawal_fees() {
+    let (mut ctx, fee_receiver) = setup_with_referral_share(2_000).await;
+    let payer = ctx.payer.pubkey();
+    let referrer = Pubkey::new_unique();
+
+    ctx.fund_token_account(&payer, 1_000_000_000).await;
+
+    let instruction = initialize_ix(&ctx.program_id, &payer, &DEVNET_MINT, 1, &[fee_receiver], Some(referrer), false, VaultKind::Fixed);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    assert_eq!(ctx.lamports(&referrer).await, 20_000_000);
+    assert_eq!(ctx.lamports(&fee_receiver).await, 80_000_000);
+
+    let stats = referrer_stats(&mut ctx, &referrer).await;
+    assert_eq!(stats.referral_count, 1);
+    assert_eq!(stats.lamports_earned, 20_000_000);
+
+    let instruction = withdraw_ix(&ctx.program_id, &payer, &DEVNET_MINT, &[fee_receiver], Some(referrer));
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    // 20% of the 3.75 SOL early-withdrawal fee
+    assert_eq!(ctx.lamports(&referrer).await, 20_000_000 + 750_000_000);
+    assert_eq!(ctx.lamports(&fee_receiver).await, 80_000_000 + 3_000_000_000);
+    assert_eq!(referrer_stats(&mut ctx, &referrer).await.lamports_earned, 770_000_000);
+}
+
+#[tokio::test]
+async fn test_second_vault_reuses_the_referral() {
+    let (mut ctx, fee_receiver) = setup_with_referral_share(2_000).await;
+    let payer = ctx.payer.pubkey();
+    let referrer = Pubkey::new_unique();
+    let native = spl_token::native_mint::id();
+
+    ctx.fund_token_account(&payer, 1_000_000_000).await;
+
+    let instruction = initialize_ix(&ctx.program_id, &payer, &DEVNET_MINT, 1, &[fee_receiver], Some(referrer), false, VaultKind::Fixed);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    // A SOL vault charging the same 0.1 SOL initialization fee
+    let instruction =
+        set_mint_config_ix(&ctx.program_id, &payer, &native, true, Vault::LOCK_DURATION, 100_000_000, 0, 0, vec![]);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    // The owner was referred by someone else
+    let other = Pubkey::new_unique();
+    let instruction = initialize_sol_ix(&ctx.program_id, &payer, TOKEN, &[fee_receiver], Some(other), false, VaultKind::Fixed);
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::ReferrerMismatch as u32));
+
+    let instruction =
+        initialize_sol_ix(&ctx.program_id, &payer, TOKEN, &[fee_receiver], Some(referrer), false, VaultKind::Fixed);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    // Both fees pay the referrer, but it is still one referral
+    assert_eq!(ctx.lamports(&referrer).await, 2 * 20_000_000);
+    let stats = referrer_stats(&mut ctx, &referrer).await;
+    assert_eq!(stats.referral_count, 1);
+    assert_eq!(stats.lamports_earned, 2 * 20_000_000);
+}
+
+#[tokio::test]
+async fn test_self_referral_is_rejected() {
+    let (mut ctx, fee_receiver) = setup_with_referral_share(2_000).await;
+    let payer = ctx.payer.pubkey();
+
+    ctx.fund_token_account(&payer, 0).await;
+
+    let instruction = initialize_ix(&ctx.program_id, &payer, &DEVNET_MINT, 0, &[fee_receiver], Some(payer), false, VaultKind::Fixed);
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+
+    assert_eq!(custom_error(error), Some(VaultError::SelfReferral as u32));
+}
+
+#[tokio::test]
+async fn test_referred_withdraw_requires_referrer_accounts() {
+    let (mut ctx, fee_receiver) = setup_with_referral_share(2_000).await;
+    let payer = ctx.payer.pubkey();
+    let referrer = Pubkey::new_unique();
+
+    ctx.fund_token_account(&payer, 1_000_000_000).await;
+
+    let instruction = initialize_ix(&ctx.program_id, &payer, &DEVNET_MINT, 1, &[fee_receiver], Some(referrer), false, VaultKind::Fixed);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    let instruction = withdraw_ix(&ctx.program_id, &payer, &DEVNET_MINT, &[fee_receiver], None);
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+
+    assert_eq!(
+        error.unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::from(u64::from(ProgramError::NotEnoughAccountKeys))
+        )
+    );
+
+    let impostor = Pubkey::new_unique();
+    let instruction = withdraw_ix(&ctx.program_id, &payer, &DEVNET_MINT, &[fee_receiver], Some(impostor));
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+
+    assert_eq!(custom_error(error), Some(VaultError::ReferrerMismatch as u32));
+}
+
+#[tokio::test]
+async fn test_withdraw_checks_the_referral_record_against_the_stored_bump() {
+    let (mut ctx, fee_receiver) = setup_with_referral_share(2_000).await;
+    let payer = ctx.payer.pubkey();
+
+    ctx.open_vault(None, fee_receiver, 1, false, VaultKind::Fixed).await;
+
+    // The bumps of the owner's exemption and referral PDAs are found once, at creation
+    let vault = ctx.vault(&payer).await;
+    assert_eq!((vault.exemption_bump, vault.referral_bump), Vault::find_owner_bumps(&payer, &ctx.program_id));
+
+    // An unreferred owner still can't pass some other empty account
+    let mut instruction = withdraw_ix(&ctx.program_id, &payer, &DEVNET_MINT, &[fee_receiver], None);
+    let (referral_pda, _) = Referral::find_address(&payer, &ctx.program_id);
+    let referral_meta = instruction.accounts.iter_mut().find(|meta| meta.pubkey == referral_pda).unwrap();
+    referral_meta.pubkey = Pubkey::new_unique();
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+
+    assert_eq!(
+        error.unwrap(),
+        TransactionError::InstructionError(0, InstructionError::from(u64::from(ProgramError::InvalidSeeds)))
+    );
+}
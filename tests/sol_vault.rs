@@ -0,0 +1,193 @@
+mod common;
+
+use common::*;
+use construct_vault_sol::{
+    error::VaultError,
+    state::{AccessTier, Vault, VaultKind, VaultStatus},
+};
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction,
+};
+use spl_token::native_mint;
+use spl_token_2022::{extension::StateWithExtensions, state::Account as BadgeAccount};
+
+const DAY: u64 = 86_400;
+const LOCK_DURATION: u64 = 7 * DAY;
+const SOL: u64 = 1_000_000_000;
+const INITIALIZATION_FEE: u64 = 10_000_000;
+const MAX_EARLY_WITHDRAWAL_FEE: u64 = 100_000_000;
+
+/// Config with a single fee recipient, SOL registered on `access_tiers`, and
+/// a funded owner who isn't the fee payer, so their balance moves only by
+/// what the program does.
+async fn setup_sol(ctx: &mut TestContext, access_tiers: Vec<AccessTier>) -> (Keypair, Pubkey) {
+    let fee_receiver = Pubkey::new_unique();
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+
+    let owner = Keypair::new();
+    let instructions = [
+        set_mint_config_ix(
+            &ctx.program_id,
+            &ctx.payer.pubkey(),
+            &native_mint::id(),
+            true,
+            LOCK_DURATION,
+            INITIALIZATION_FEE,
+            MAX_EARLY_WITHDRAWAL_FEE,
+            0,
+            access_tiers,
+        ),
+        system_instruction::transfer(&ctx.payer.pubkey(), &owner.pubkey(), 10 * SOL),
+    ];
+    ctx.process(&instructions, &[]).await.unwrap();
+
+    (owner, fee_receiver)
+}
+
+fn lock_ix(ctx: &TestContext, owner: &Pubkey, amount: u64, fee_receiver: Pubkey) -> Instruction {
+    initialize_sol_ix(&ctx.program_id, owner, amount, &[fee_receiver], None, false, VaultKind::Fixed)
+}
+
+async fn sol_vault(ctx: &mut TestContext, owner: &Pubkey) -> (Pubkey, Vault, u64) {
+    let (vault_pda, _) = find_vault_address(&ctx.program_id, owner, &native_mint::id());
+    let account = ctx.banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    (vault_pda, Vault::unpack(&account.data).unwrap(), account.lamports)
+}
+
+#[tokio::test]
+async fn test_sol_vault_keeps_its_rent_apart_from_the_lock() {
+    let mut ctx = setup().await;
+    let (owner, fee_receiver) = setup_sol(&mut ctx, vec![]).await;
+    let owner_key = owner.pubkey();
+
+    let instruction = lock_ix(&ctx, &owner_key, 2 * SOL, fee_receiver);
+    ctx.process(&[instruction], &[&owner]).await.unwrap();
+
+    let rent = ctx.banks_client.get_rent().await.unwrap().minimum_balance(Vault::LEN);
+    let (vault_pda, vault, lamports) = sol_vault(&mut ctx, &owner_key).await;
+    assert_ne!(vault_pda, vault_address(&ctx.program_id, &owner_key).0);
+    assert!(vault.is_native());
+    assert_eq!(u64::from(vault.rent_lamports), rent);
+    assert_eq!(u64::from(vault.amount_locked), 2 * SOL);
+    assert_eq!(u64::from(vault.lock_duration), LOCK_DURATION);
+    assert_eq!(lamports, rent + 2 * SOL);
+    assert_eq!(ctx.lamports(&fee_receiver).await, INITIALIZATION_FEE);
+
+    let instruction = deposit_sol_ix(&ctx.program_id, &owner_key, SOL);
+    ctx.process(&[instruction], &[&owner]).await.unwrap();
+    assert_eq!(u64::from(sol_vault(&mut ctx, &owner_key).await.1.amount_locked), 3 * SOL);
+
+    // Lamports sent to the vault from outside aren't part of the lock
+    let instruction = system_instruction::transfer(&ctx.payer.pubkey(), &vault_pda, SOL);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    // Once the lock has run out the whole lock comes back, free of charge
+    let before = ctx.lamports(&owner_key).await;
+    ctx.warp_forward(LOCK_DURATION as i64).await;
+    let instruction = withdraw_sol_ix(&ctx.program_id, &owner_key, &[fee_receiver], None);
+    ctx.process(&[instruction], &[&owner]).await.unwrap();
+    assert_eq!(ctx.lamports(&owner_key).await - before, 3 * SOL);
+    assert_eq!(ctx.lamports(&fee_receiver).await, INITIALIZATION_FEE);
+
+    let (_, vault, lamports) = sol_vault(&mut ctx, &owner_key).await;
+    assert_eq!(vault.status(), Ok(VaultStatus::Released));
+    assert_eq!(u64::from(vault.amount_locked), 0);
+    assert_eq!(lamports, rent + SOL);
+}
+
+#[tokio::test]
+async fn test_early_sol_withdrawal_pays_the_fee_from_the_vault() {
+    let mut ctx = setup().await;
+    let (owner, fee_receiver) = setup_sol(&mut ctx, vec![]).await;
+    let owner_key = owner.pubkey();
+
+    let instruction = lock_ix(&ctx, &owner_key, 2 * SOL, fee_receiver);
+    ctx.process(&[instruction], &[&owner]).await.unwrap();
+
+    // 75% of the maximum on day 0, taken from the released lamports
+    let fee = MAX_EARLY_WITHDRAWAL_FEE * 3 / 4;
+    let (owner_before, receiver_before) = (ctx.lamports(&owner_key).await, ctx.lamports(&fee_receiver).await);
+    let instruction = withdraw_sol_ix(&ctx.program_id, &owner_key, &[fee_receiver], None);
+    ctx.process(&[instruction], &[&owner]).await.unwrap();
+    assert_eq!(ctx.lamports(&owner_key).await - owner_before, 2 * SOL - fee);
+    assert_eq!(ctx.lamports(&fee_receiver).await - receiver_before, fee);
+
+    // A lock smaller than the fee is all taken, and the vault keeps its rent
+    let instruction = deposit_sol_ix(&ctx.program_id, &owner_key, 1_000);
+    ctx.process(&[instruction], &[&owner]).await.unwrap();
+
+    let (owner_before, receiver_before) = (ctx.lamports(&owner_key).await, ctx.lamports(&fee_receiver).await);
+    let instruction = withdraw_sol_ix(&ctx.program_id, &owner_key, &[fee_receiver], None);
+    ctx.process(&[instruction], &[&owner]).await.unwrap();
+    assert_eq!(ctx.lamports(&owner_key).await, owner_before);
+    assert_eq!(ctx.lamports(&fee_receiver).await - receiver_before, 1_000);
+
+    let rent = ctx.banks_client.get_rent().await.unwrap().minimum_balance(Vault::LEN);
+    assert_eq!(sol_vault(&mut ctx, &owner_key).await.2, rent);
+}
+
+#[tokio::test]
+async fn test_sol_vaults_earn_sol_badges() {
+    let mut ctx = setup().await;
+    let (owner, fee_receiver) = setup_sol(&mut ctx, vec![AccessTier { min_amount_locked: SOL }]).await;
+    let owner_key = owner.pubkey();
+
+    let instruction = lock_ix(&ctx, &owner_key, 2 * SOL, fee_receiver);
+    ctx.process(&[instruction], &[&owner]).await.unwrap();
+
+    let (badge_mint, _) = AccessTier::find_badge_mint(Some(&native_mint::id()), 0, &ctx.program_id);
+    let badge_account = badge_account_address(&owner_key, &badge_mint);
+    let account = ctx.banks_client.get_account(badge_account).await.unwrap().unwrap();
+    assert_eq!(StateWithExtensions::<BadgeAccount>::unpack(&account.data).unwrap().base.amount, 1);
+
+    let instruction = withdraw_sol_ix(&ctx.program_id, &owner_key, &[fee_receiver], None);
+    ctx.process(&[instruction], &[&owner]).await.unwrap();
+
+    let account = ctx.banks_client.get_account(badge_account).await.unwrap().unwrap();
+    assert_eq!(StateWithExtensions::<BadgeAccount>::unpack(&account.data).unwrap().base.amount, 0);
+}
+
+#[tokio::test]
+async fn test_sol_and_token_instructions_do_not_mix() {
+    let mut ctx = setup().await;
+    let payer = ctx.payer.pubkey();
+    let fee_receiver = Pubkey::new_unique();
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+
+    // SOL has to be registered like any other mint
+    let instruction = lock_ix(&ctx, &payer, SOL, fee_receiver);
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::InvalidMint as u32));
+
+    let native = native_mint::id();
+    let instruction = set_mint_config_ix(&ctx.program_id, &payer, &native, true, LOCK_DURATION, 0, 0, 0, vec![]);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    // Wrapped SOL isn't locked through the token path
+    let instruction =
+        initialize_ix(&ctx.program_id, &payer, &native, 1, &[fee_receiver], None, false, VaultKind::Fixed);
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::WrongVaultAsset as u32));
+
+    ctx.fund_token_account(&payer, 1_000_000_000_000).await;
+    let instructions = [
+        lock_ix(&ctx, &payer, SOL, fee_receiver),
+        initialize_ix(&ctx.program_id, &payer, &DEVNET_MINT, 1, &[fee_receiver], None, false, VaultKind::Fixed),
+    ];
+    ctx.process(&instructions, &[]).await.unwrap();
+
+    // The token instructions find no token account behind a SOL vault
+    for instruction in [claim_ix(&ctx.program_id, &payer, &native), deposit_ix(&ctx.program_id, &payer, &native, 1)] {
+        let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+        assert_eq!(custom_error(error), Some(VaultError::WrongVaultAsset as u32));
+    }
+
+    // Nor does a token vault take the SOL instructions
+    let mut instruction = withdraw_sol_ix(&ctx.program_id, &payer, &[fee_receiver], None);
+    instruction.accounts[1].pubkey = vault_address(&ctx.program_id, &payer).0;
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::WrongVaultAsset as u32));
+}
@@ -0,0 +1,204 @@
+mod common;
+
+use borsh::BorshDeserialize;
+use common::*;
+use construct_vault_sol::{
+    error::VaultError,
+    state::{ProtocolStats, StatsShard, Vault, VaultKind},
+};
+use solana_program::{instruction::InstructionError, program_error::ProgramError, pubkey::Pubkey};
+use solana_sdk::{
+    account::Account,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+
+async fn protocol_stats(ctx: &mut TestContext) -> ProtocolStats {
+    let mut shards = Vec::new();
+    for address in StatsShard::all_addresses(&ctx.program_id) {
+        if let Some(account) = ctx.banks_client.get_account(address).await.unwrap() {
+            shards.push(StatsShard::try_from_slice(&account.data).unwrap());
+        }
+    }
+    ProtocolStats::sum(&shards)
+}
+
+/// Makes `owner`'s vault look like one locked before the stats shards
+/// existed, and removes their shard.
+async fn forget_stats(ctx: &mut TestContext, owner: &Pubkey) {
+    let (vault_pda, _) = vault_address(&ctx.program_id, owner);
+    let mut account = ctx.banks_client.get_account(vault_pda).await.unwrap().unwrap();
+    Vault::load_mut(&mut account.data).unwrap().counted = 0;
+    ctx.set_account(&vault_pda, account);
+    ctx.set_account(&stats_address(&ctx.program_id, owner), Account::default());
+}
+
+#[test]
+fn test_stats_overflow_is_an_error() {
+    let overflow = Err(ProgramError::from(VaultError::ArithmeticOverflow));
+    let mut data = vec![0; Vault::LEN];
+    let vault = Vault::init(&mut data).unwrap();
+
+    let mut shard = StatsShard {
+        kuza_locked: u64::MAX,
+        ..StatsShard::default()
+    };
+    assert_eq!(shard.count_lock(vault, 1), overflow);
+
+    let mut shard = StatsShard {
+        lifetime_fees_lamports: u64::MAX,
+        ..StatsShard::default()
+    };
+    assert_eq!(shard.count_fee(1), overflow);
+}
+
+#[tokio::test]
+async fn test_stats_follow_locks_fees_and_releases() {
+    let mut ctx = setup().await;
+    let fee_receiver = Pubkey::new_unique();
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    let instruction = set_crank_tip_ix(&ctx.program_id, &ctx.payer.pubkey(), 0);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    let first = Keypair::new();
+    ctx.open_vault(Some(&first), fee_receiver, 5, false, VaultKind::Fixed).await;
+    let instruction = deposit_ix(&ctx.program_id, &first.pubkey(), &DEVNET_MINT, 1_000);
+    ctx.process(&[instruction], &[&first]).await.unwrap();
+    let second = Keypair::new();
+    ctx.open_vault(Some(&second), fee_receiver, 7, false, VaultKind::Fixed).await;
+
+    let fees = ctx.lamports(&fee_receiver).await;
+    let expected = ProtocolStats {
+        kuza_locked: 12 * TOKEN + 1_000,
+        open_vaults: 2,
+        lifetime_deposits: 12 * TOKEN + 1_000,
+        lifetime_fees_lamports: fees,
+    };
+    assert_eq!(protocol_stats(&mut ctx).await, expected);
+
+    // An early exit adds its fee and takes the vault out
+    let instruction = withdraw_ix(&ctx.program_id, &second.pubkey(), &DEVNET_MINT, &[fee_receiver], None);
+    ctx.process(&[instruction], &[&second]).await.unwrap();
+
+    let fees = ctx.lamports(&fee_receiver).await;
+    assert!(fees > expected.lifetime_fees_lamports);
+    let expected = ProtocolStats {
+        kuza_locked: 5 * TOKEN + 1_000,
+        open_vaults: 1,
+        lifetime_fees_lamports: fees,
+        ..expected
+    };
+    assert_eq!(protocol_stats(&mut ctx).await, expected);
+
+    // So does a crank release, on the owner's behalf
+    ctx.warp_forward(Vault::LOCK_DURATION as i64).await;
+    let instruction = crank_release_ix(&ctx.program_id, &ctx.payer.pubkey(), &DEVNET_MINT, &[first.pubkey()]);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    let expected = ProtocolStats {
+        kuza_locked: 0,
+        open_vaults: 0,
+        ..expected
+    };
+    assert_eq!(protocol_stats(&mut ctx).await, expected);
+
+    // Releasing reopens the vault in the stats
+    let instruction = deposit_ix(&ctx.program_id, &second.pubkey(), &DEVNET_MINT, 3);
+    ctx.process(&[instruction], &[&second]).await.unwrap();
+
+    let expected = ProtocolStats {
+        kuza_locked: 3,
+        open_vaults: 1,
+        lifetime_deposits: 12 * TOKEN + 1_003,
+        ..expected
+    };
+    assert_eq!(protocol_stats(&mut ctx).await, expected);
+}
+
+#[tokio::test]
+async fn test_kuza_locked_is_in_base_units() {
+    let mut ctx = setup().await;
+    let fee_receiver = Pubkey::new_unique();
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+
+    // Initialize takes whole tokens and deposit base units; the stats see both in base units
+    let owner = Keypair::new();
+    ctx.open_vault(Some(&owner), fee_receiver, 2, false, VaultKind::Fixed).await;
+    let instruction = deposit_ix(&ctx.program_id, &owner.pubkey(), &DEVNET_MINT, 500);
+    ctx.process(&[instruction], &[&owner]).await.unwrap();
+
+    let stats = protocol_stats(&mut ctx).await;
+    assert_eq!(stats.kuza_locked, 2 * TOKEN + 500);
+    assert_eq!(stats.lifetime_deposits, 2 * TOKEN + 500);
+
+    let vault_ata = vault_token_address(&ctx.program_id, &owner.pubkey());
+    assert_eq!(ctx.token_balance(&vault_ata).await, stats.kuza_locked);
+}
+
+#[tokio::test]
+async fn test_vaults_from_before_the_stats_join_at_their_next_deposit() {
+    let mut ctx = setup().await;
+    let fee_receiver = Pubkey::new_unique();
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+
+    let owner = Keypair::new();
+    ctx.open_vault(Some(&owner), fee_receiver, 5, false, VaultKind::Fixed).await;
+    let owner_key = owner.pubkey();
+    forget_stats(&mut ctx, &owner_key).await;
+
+    // The deposit brings in what the vault already held
+    let instruction = deposit_ix(&ctx.program_id, &owner_key, &DEVNET_MINT, 10);
+    ctx.process(&[instruction], &[&owner]).await.unwrap();
+
+    let stats = protocol_stats(&mut ctx).await;
+    assert_eq!((stats.kuza_locked, stats.open_vaults, stats.lifetime_deposits), (5 * TOKEN + 10, 1, 10));
+    assert!(ctx.vault(&owner_key).await.is_counted());
+
+    // A vault the stats haven't seen is released without its shard
+    forget_stats(&mut ctx, &owner_key).await;
+    ctx.warp_forward(Vault::LOCK_DURATION as i64).await;
+    let instruction = claim_ix(&ctx.program_id, &owner_key, &DEVNET_MINT);
+    ctx.process(&[instruction], &[&owner]).await.unwrap();
+
+    assert_eq!(protocol_stats(&mut ctx).await, ProtocolStats::default());
+}
+
+#[tokio::test]
+async fn test_vaults_are_counted_only_in_their_owners_shard() {
+    let mut ctx = setup().await;
+    let fee_receiver = Pubkey::new_unique();
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+
+    let owner = Keypair::new();
+    ctx.open_vault(Some(&owner), fee_receiver, 5, false, VaultKind::Fixed).await;
+    let owner_key = owner.pubkey();
+    let shard = StatsShard::shard_for(&owner_key);
+    assert!(shard < StatsShard::SHARD_COUNT);
+
+    let owner_shard = stats_address(&ctx.program_id, &owner_key);
+    let account = ctx.banks_client.get_account(owner_shard).await.unwrap().unwrap();
+    assert_eq!(account.owner, ctx.program_id);
+    assert_eq!(StatsShard::try_from_slice(&account.data).unwrap().shard, shard);
+
+    // Another shard is refused whether or not it exists yet
+    let other_owner = Keypair::new();
+    ctx.open_vault(Some(&other_owner), fee_receiver, 1, false, VaultKind::Fixed).await;
+    let other_owner = other_owner.pubkey();
+    let existing = (StatsShard::shard_for(&other_owner) != shard).then(|| stats_address(&ctx.program_id, &other_owner));
+    let missing = (0..StatsShard::SHARD_COUNT)
+        .map(|index| StatsShard::find_address(index, &ctx.program_id).0)
+        .find(|address| *address != owner_shard && Some(*address) != existing)
+        .unwrap();
+
+    for wrong_shard in existing.into_iter().chain([missing]) {
+        let mut instruction = deposit_ix(&ctx.program_id, &owner_key, &DEVNET_MINT, 1);
+        let stats_meta = instruction.accounts.iter_mut().find(|meta| meta.pubkey == owner_shard);
+        stats_meta.unwrap().pubkey = wrong_shard;
+
+        let error = ctx.process(&[instruction], &[&owner]).await.unwrap_err();
+        assert_eq!(
+            error.unwrap(),
+            TransactionError::InstructionError(0, InstructionError::InvalidSeeds)
+        );
+    }
+}
@@ -0,0 +1,125 @@
+mod common;
+
+use common::*;
+use construct_vault_sol::{
+    error::VaultError,
+    state::{Vault, VaultKind, VaultStatus},
+};
+use solana_program::clock::Clock;
+use solana_sdk::{
+    instruction::InstructionError,
+    pubkey::Pubkey,
+    signature::Signer,
+    transaction::TransactionError,
+};
+
+const ALL: [VaultStatus; 5] = [
+    VaultStatus::Uninitialized,
+    VaultStatus::Locked,
+    VaultStatus::Matured,
+    VaultStatus::Unlocking,
+    VaultStatus::Released,
+];
+
+#[test]
+fn test_status_transitions() {
+    use VaultStatus::*;
+
+    let allowed = [
+        (Uninitialized, Locked),
+        (Locked, Locked),
+        (Matured, Locked),
+        (Released, Locked),
+        (Locked, Matured),
+        (Locked, Unlocking),
+        (Unlocking, Locked),
+        (Locked, Released),
+        (Matured, Released),
+        (Unlocking, Released),
+    ];
+
+    for from in ALL {
+        for to in ALL {
+            assert_eq!(
+                from.can_transition_to(to),
+                allowed.contains(&(from, to)),
+                "{:?} -> {:?}",
+                from,
+                to
+            );
+        }
+    }
+
+    for (value, status) in ALL.iter().enumerate() {
+        assert_eq!(VaultStatus::try_from(value as u8), Ok(*status));
+    }
+    assert!(VaultStatus::try_from(ALL.len() as u8).is_err());
+}
+
+#[test]
+fn test_mature_only_moves_expired_locks() {
+    let mut data = vec![0; Vault::LEN];
+    let vault = Vault::init(&mut data).unwrap();
+    vault.deposit_timestamp = 1_000.into();
+    vault.lock_duration = Vault::LOCK_DURATION.into();
+    vault.transition(VaultStatus::Locked, 1_000).unwrap();
+
+    let unlocks_at = 1_000 + Vault::LOCK_DURATION;
+    vault.mature(unlocks_at - 1).unwrap();
+    assert_eq!(vault.status(), Ok(VaultStatus::Locked));
+    assert_eq!(u64::from(vault.status_timestamp), 1_000);
+
+    vault.mature(unlocks_at).unwrap();
+    assert_eq!(vault.status(), Ok(VaultStatus::Matured));
+    assert_eq!(u64::from(vault.status_timestamp), unlocks_at);
+
+    assert_eq!(
+        vault.transition(VaultStatus::Unlocking, unlocks_at),
+        Err(VaultError::InvalidStatusTransition.into())
+    );
+}
+
+#[tokio::test]
+async fn test_lifecycle_records_each_transition() {
+    let mut ctx = setup().await;
+    let payer = ctx.payer.pubkey();
+    let fee_receiver = Pubkey::new_unique();
+
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    ctx.fund_token_account(&payer, 2_000_000_000).await;
+
+    let instruction = initialize_ix(&ctx.program_id, &payer, &DEVNET_MINT, 1, &[fee_receiver], None, false, VaultKind::Fixed);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    let clock: Clock = ctx.banks_client.get_sysvar().await.unwrap();
+    let vault = ctx.vault(&payer).await;
+    assert_eq!(vault.status(), Ok(VaultStatus::Locked));
+    assert_eq!(u64::from(vault.status_timestamp), clock.unix_timestamp as u64);
+
+    // Opening the vault a second time is refused outright
+    let instruction = initialize_ix(&ctx.program_id, &payer, &DEVNET_MINT, 1, &[fee_receiver], None, false, VaultKind::Fixed);
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+    assert_eq!(
+        error.unwrap(),
+        TransactionError::InstructionError(0, InstructionError::AccountAlreadyInitialized)
+    );
+
+    ctx.warp_forward(Vault::LOCK_DURATION as i64).await;
+    let instruction = withdraw_ix(&ctx.program_id, &payer, &DEVNET_MINT, &[fee_receiver], None);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    let clock: Clock = ctx.banks_client.get_sysvar().await.unwrap();
+    let vault = ctx.vault(&payer).await;
+    assert_eq!(vault.status(), Ok(VaultStatus::Released));
+    assert_eq!(u64::from(vault.status_timestamp), clock.unix_timestamp as u64);
+
+    // There is nothing left to withdraw
+    let instruction = withdraw_ix(&ctx.program_id, &payer, &DEVNET_MINT, &[fee_receiver], None);
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::InvalidStatusTransition as u32));
+
+    // A deposit locks a released vault again
+    let instruction = deposit_ix(&ctx.program_id, &payer, &DEVNET_MINT, 1);
+    ctx.process(&[instruction], &[]).await.unwrap();
+    assert_eq!(ctx.vault(&payer).await.status(), Ok(VaultStatus::Locked));
+}
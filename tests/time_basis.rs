@@ -0,0 +1,161 @@
+mod common;
+
+use common::*;
+use construct_vault_sol::{
+    error::VaultError,
+    fee::early_withdrawal_fee,
+    state::{Config, TimeBasis, Vault, VaultKind, VaultStatus},
+    MAX_EARLY_WITHDRAWAL_FEE_LAMPORTS,
+};
+use solana_program::clock::Clock;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+const DAY: u64 = 86_400;
+
+/// A short day, so a 30-day lock is a few thousand slots.
+const SLOTS_PER_DAY: u64 = 100;
+
+/// Sets up the config with `SLOTS_PER_DAY` and opens the payer's slot-based
+/// vault of `kind` with one token. Returns the fee recipient.
+async fn open_slot_vault(ctx: &mut TestContext, kind: VaultKind) -> Pubkey {
+    let payer = ctx.payer.pubkey();
+    let fee_receiver = Pubkey::new_unique();
+
+    ctx.initialize_config(single_recipient(fee_receiver), 0).await;
+    let instruction = set_slots_per_day_ix(&ctx.program_id, &payer, SLOTS_PER_DAY);
+    ctx.process(&[instruction], &[]).await.unwrap();
+    ctx.fund_token_account(&payer, 1_000_000_000).await;
+
+    let instruction = initialize_with_time_basis_ix(
+        &ctx.program_id,
+        &payer,
+        &DEVNET_MINT,
+        1,
+        &[fee_receiver],
+        None,
+        false,
+        kind,
+        TimeBasis::Slots,
+    );
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    fee_receiver
+}
+
+async fn clock(ctx: &mut TestContext) -> Clock {
+    ctx.banks_client.get_sysvar().await.unwrap()
+}
+
+#[test]
+fn test_time_basis_converts_durations_at_the_slot_rate() {
+    let slots_per_day = Config::DEFAULT_SLOTS_PER_DAY;
+
+    assert_eq!(TimeBasis::UnixSeconds.from_seconds(DAY, slots_per_day), DAY);
+    assert_eq!(TimeBasis::Slots.from_seconds(DAY, slots_per_day), slots_per_day);
+    assert_eq!(TimeBasis::Slots.from_seconds(Vault::LOCK_DURATION, slots_per_day), 30 * slots_per_day);
+    assert_eq!(TimeBasis::Slots.to_seconds(30 * slots_per_day, slots_per_day), Vault::LOCK_DURATION);
+
+    // Saturates rather than wrapping, and reads as nothing without a rate
+    assert_eq!(TimeBasis::Slots.from_seconds(u64::MAX, slots_per_day), u64::MAX);
+    assert_eq!(TimeBasis::Slots.to_seconds(u64::MAX, 1), u64::MAX);
+    assert_eq!(TimeBasis::Slots.to_seconds(DAY, 0), 0);
+}
+
+#[tokio::test]
+async fn test_slot_vault_matures_by_slot_not_by_time() {
+    let mut ctx = setup().await;
+    let payer = ctx.payer.pubkey();
+    open_slot_vault(&mut ctx, VaultKind::Fixed).await;
+    let opened_at = clock(&mut ctx).await.slot;
+
+    let vault = ctx.vault(&payer).await;
+    assert_eq!(vault.time_basis(), Ok(TimeBasis::Slots));
+    assert_eq!(u64::from(vault.deposit_timestamp), opened_at);
+    assert_eq!(u64::from(vault.lock_duration), 30 * SLOTS_PER_DAY);
+
+    // However far the unix clock runs, the lock holds until its slots pass
+    ctx.warp_forward(2 * Vault::LOCK_DURATION as i64).await;
+    let instruction = claim_ix(&ctx.program_id, &payer, &DEVNET_MINT);
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::LockNotExpired as u32));
+
+    ctx.warp_slots(30 * SLOTS_PER_DAY).await;
+    let instruction = claim_ix(&ctx.program_id, &payer, &DEVNET_MINT);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    let vault = ctx.vault(&payer).await;
+    assert_eq!(vault.status(), Ok(VaultStatus::Released));
+    assert_eq!(u64::from(vault.status_timestamp), opened_at + 30 * SLOTS_PER_DAY);
+}
+
+#[tokio::test]
+async fn test_slot_vault_prices_the_early_withdrawal_fee_on_slot_days() {
+    let mut ctx = setup().await;
+    let payer = ctx.payer.pubkey();
+    let fee_receiver = open_slot_vault(&mut ctx, VaultKind::Fixed).await;
+
+    // Ten slot days in, whatever the unix clock says
+    ctx.warp_slots(10 * SLOTS_PER_DAY).await;
+    ctx.warp_forward(DAY as i64 / 2).await;
+
+    let fees_before = ctx.lamports(&fee_receiver).await;
+    let instruction = withdraw_ix(&ctx.program_id, &payer, &DEVNET_MINT, &[fee_receiver], None);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    let expected = early_withdrawal_fee(10 * DAY, Vault::LOCK_DURATION, MAX_EARLY_WITHDRAWAL_FEE_LAMPORTS).unwrap();
+    assert_eq!(ctx.lamports(&fee_receiver).await - fees_before, expected);
+    assert_eq!(ctx.vault(&payer).await.status(), Ok(VaultStatus::Released));
+}
+
+#[tokio::test]
+async fn test_slot_cooldown_vault_waits_out_the_cooldown_in_slots() {
+    let mut ctx = setup().await;
+    let payer = ctx.payer.pubkey();
+    open_slot_vault(&mut ctx, VaultKind::Cooldown).await;
+
+    let instruction = set_cooldown_ix(&ctx.program_id, &payer, 3 * DAY);
+    ctx.process(&[instruction], &[]).await.unwrap();
+    let instruction = request_unlock_ix(&ctx.program_id, &payer, &DEVNET_MINT);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    let requested_at = clock(&mut ctx).await.slot;
+    let vault = ctx.vault(&payer).await;
+    assert_eq!(vault.cooldown_ends_at(), requested_at + 3 * SLOTS_PER_DAY);
+
+    ctx.warp_slots(3 * SLOTS_PER_DAY - 1).await;
+    let instruction = claim_ix(&ctx.program_id, &payer, &DEVNET_MINT);
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::CooldownActive as u32));
+
+    ctx.warp_slots(1).await;
+    let instruction = claim_ix(&ctx.program_id, &payer, &DEVNET_MINT);
+    ctx.process(&[instruction], &[]).await.unwrap();
+    assert_eq!(ctx.vault(&payer).await.status(), Ok(VaultStatus::Released));
+}
+
+#[tokio::test]
+async fn test_set_slots_per_day_is_admin_only_and_needs_a_rate() {
+    let mut ctx = setup().await;
+    let payer = ctx.payer.pubkey();
+    ctx.initialize_config(single_recipient(Pubkey::new_unique()), 0).await;
+
+    let instruction = set_slots_per_day_ix(&ctx.program_id, &payer, 0);
+    let error = ctx.process(&[instruction], &[]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::InvalidSlotsPerDay as u32));
+
+    let stranger = Keypair::new();
+    let instruction = set_slots_per_day_ix(&ctx.program_id, &stranger.pubkey(), SLOTS_PER_DAY);
+    let error = ctx.process(&[instruction], &[&stranger]).await.unwrap_err();
+    assert_eq!(custom_error(error), Some(VaultError::Unauthorized as u32));
+
+    let instruction = set_slots_per_day_ix(&ctx.program_id, &payer, SLOTS_PER_DAY);
+    ctx.process(&[instruction], &[]).await.unwrap();
+
+    let (config_pda, _) = Config::find_address(&ctx.program_id);
+    let account = ctx.banks_client.get_account(config_pda).await.unwrap().unwrap();
+    let config: Config = borsh::BorshDeserialize::try_from_slice(&account.data).unwrap();
+    assert_eq!(config.slots_per_day, SLOTS_PER_DAY);
+}